@@ -11,18 +11,14 @@ use ark_mnt4_298::{Fr, G1Projective as G1, MNT4_298 as MNT4};
 use ark_mnt6_298::{G1Projective as G2, MNT6_298 as MNT6};
 
 use ark_groth16::Groth16;
-use ark_r1cs_std::convert::ToConstraintFieldGadget;
-use ark_r1cs_std::R1CSVar;
-use ark_r1cs_std::{alloc::AllocVar, uint64::UInt64};
-use ark_relations::r1cs::ConstraintSystem;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
 use memmap2::Mmap;
 use rand::rngs::StdRng;
 use rand::SeedableRng;
 use sig::{
-    bc::block::gen_blockchain_with_params,
+    bc::{block::gen_blockchain_with_params, params::QuorumThreshold},
     bls::Parameters,
-    folding::{bc::CommitteeVar, circuit::BCCircuitNoMerkle},
+    folding::{circuit::BCCircuitNoMerkle, decider::initial_state},
 };
 use std::io::Read;
 
@@ -97,7 +93,10 @@ where
 const MAX_COMMITTEE_SIZE: usize = 25;
 
 fn main() -> Result<(), Error> {
-    let f_circuit = BCCircuitNoMerkle::<Fr, MAX_COMMITTEE_SIZE>::new(Parameters::setup())?;
+    let f_circuit = BCCircuitNoMerkle::<Fr, MAX_COMMITTEE_SIZE>::new((
+        Parameters::setup(),
+        QuorumThreshold::default(),
+    ))?;
 
     // use Nova as FoldingScheme
     type FC = BCCircuitNoMerkle<Fr, MAX_COMMITTEE_SIZE>;
@@ -122,7 +121,7 @@ fn main() -> Result<(), Error> {
     // prepare the Nova prover & verifier params
     // - can serialize this when the circuit is stable
     println!("nova folding preprocess");
-    let nova_preprocess_params = PreprocessorParam::new(poseidon_config, f_circuit);
+    let nova_preprocess_params = PreprocessorParam::new(poseidon_config, f_circuit.clone());
     let nova_params = load_or_generate(
         &data_path.join("nova_folding_params.dat"),
         || {
@@ -137,13 +136,13 @@ fn main() -> Result<(), Error> {
                     &mut *reader,
                     Compress::No,
                     Validate::No,
-                    <FC as FCircuit<Fr>>::Params::setup(),
+                    (Parameters::setup(), QuorumThreshold::default()),
                 )?,
                 N::vp_deserialize_with_mode(
                     reader,
                     Compress::No,
                     Validate::No,
-                    <FC as FCircuit<Fr>>::Params::setup(),
+                    (Parameters::setup(), QuorumThreshold::default()),
                 )?,
             ))
         },
@@ -180,27 +179,10 @@ fn main() -> Result<(), Error> {
     let mut nova = load_or_generate(
         &data_path.join("nova_folding_state.dat"),
         || {
-            let cs = ConstraintSystem::new_ref();
-            let z_0 = {
-                let mut z_0: Vec<_> = CommitteeVar::new_constant(
-                    cs,
-                    bc.get(n_steps_proven).unwrap().committee.clone(),
-                )?
-                .to_constraint_field()?
-                .iter()
-                .map(|fpvar| fpvar.value().unwrap())
-                .collect();
-                z_0.push(
-                    UInt64::constant(bc.get(0).unwrap().epoch)
-                        .to_fp()?
-                        .value()
-                        .unwrap(),
-                );
-                z_0
-            };
+            let z_0 = initial_state::<Fr, MAX_COMMITTEE_SIZE>(bc.get(n_steps_proven).unwrap())?;
 
             timeit!("nova folding init", {
-                N::init(&nova_params, f_circuit, z_0)
+                N::init(&nova_params, f_circuit.clone(), z_0)
             })
         },
         |_, _| Ok(()),
@@ -211,7 +193,7 @@ fn main() -> Result<(), Error> {
                     Compress::No,
                     Validate::No,
                 )?,
-                <FC as FCircuit<Fr>>::Params::setup(),
+                (Parameters::setup(), QuorumThreshold::default()),
                 nova_params.clone(), // unfortunately, `FoldingScheme` API requires us to `clone` here
             )
         },
@@ -0,0 +1,57 @@
+use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar, uint8::UInt8};
+use ark_relations::r1cs::ConstraintSystem;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use sig::{
+    bls::{
+        get_aggregate_bls_instance_n, BLSAggregateSignatureVerifyGadget, ParametersVar,
+        PublicKeyVar, Signature, SignatureVar,
+    },
+    params::BlsSigField,
+};
+
+type BlsSigConfig = ark_bls12_377::Config;
+type CF = BlsSigField<BlsSigConfig>;
+
+const COMMITTEE_SIZES: [usize; 4] = [10, 50, 100, 500];
+
+// The gadget is synthesized over the base field of BLS12-377, so that the
+// pairing is native. Field emulation would be too slow for Criterion.
+fn bls_verify_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("BLS aggregate verify");
+    group.sample_size(10);
+
+    for n in COMMITTEE_SIZES {
+        let (msg, params, _, pks, sig) = get_aggregate_bls_instance_n::<BlsSigConfig>(n);
+
+        group.bench_with_input(BenchmarkId::new("native", n), &pks, |b, pks| {
+            b.iter(|| {
+                assert!(Signature::aggregate_verify(msg.as_bytes(), &sig, pks, &params).unwrap())
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("gadget synthesis", n), &pks, |b, pks| {
+            b.iter(|| {
+                let cs = ConstraintSystem::<CF>::new_ref();
+                let params_var: ParametersVar<BlsSigConfig, FpVar<CF>, CF> =
+                    ParametersVar::new_constant(cs.clone(), &params).unwrap();
+                let msg_var =
+                    Vec::<UInt8<CF>>::new_witness(cs.clone(), || Ok(msg.as_bytes())).unwrap();
+                let pk_var = pks
+                    .iter()
+                    .map(|pk| PublicKeyVar::new_witness(cs.clone(), || Ok(pk)).unwrap())
+                    .reduce(|acc, pk| acc + pk)
+                    .unwrap();
+                let sig_var = SignatureVar::new_witness(cs.clone(), || Ok(&sig)).unwrap();
+
+                BLSAggregateSignatureVerifyGadget::verify(&params_var, &pk_var, &msg_var, &sig_var)
+                    .unwrap();
+                cs
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bls_verify_bench);
+criterion_main!(benches);
@@ -0,0 +1,61 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use rand::{rngs::StdRng, SeedableRng};
+use sig::bc::{
+    block::gen_blockchain_with_params,
+    params::{
+        AuthorityAggregatedSignature, AuthorityPublicKey, AuthoritySecretKey, AuthoritySigParams,
+    },
+};
+
+const COMMITTEE_SIZE: usize = 1000;
+
+// Run with `--features parallel` to compare the rayon thread pool against a
+// single thread. Without the feature, both variants are sequential.
+fn committee_aggregation_bench(c: &mut Criterion) {
+    let mut rng = StdRng::from_seed([42; 32]);
+    let single_thread = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()
+        .unwrap();
+
+    let bc = gen_blockchain_with_params::<_, COMMITTEE_SIZE>(2, COMMITTEE_SIZE, &mut rng);
+    let params = &AuthoritySigParams::setup();
+    let (prev, block) = (bc.get(0).unwrap(), bc.get(1).unwrap());
+    let pks: Vec<_> = prev.committee.signers.iter().map(|(pk, _)| *pk).collect();
+    let sks: Vec<_> = (0..COMMITTEE_SIZE)
+        .map(|_| AuthoritySecretKey::new(&mut rng))
+        .collect();
+    let msg = b"committee aggregation";
+
+    let mut group = c.benchmark_group("Committee aggregation");
+    group.sample_size(10);
+
+    group.bench_function("aggregate public keys (1 thread)", |b| {
+        b.iter(|| single_thread.install(|| AuthorityPublicKey::aggregate(&pks)))
+    });
+    group.bench_function("aggregate public keys", |b| {
+        b.iter(|| AuthorityPublicKey::aggregate(&pks))
+    });
+
+    group.bench_function("aggregate signatures (1 thread)", |b| {
+        b.iter(|| {
+            single_thread
+                .install(|| AuthorityAggregatedSignature::aggregate_sign(msg, &sks, params))
+        })
+    });
+    group.bench_function("aggregate signatures", |b| {
+        b.iter(|| AuthorityAggregatedSignature::aggregate_sign(msg, &sks, params))
+    });
+
+    group.bench_function("verify block (1 thread)", |b| {
+        b.iter(|| single_thread.install(|| block.verify(&prev.committee, prev.epoch, params)))
+    });
+    group.bench_function("verify block", |b| {
+        b.iter(|| block.verify(&prev.committee, prev.epoch, params))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, committee_aggregation_bench);
+criterion_main!(benches);
@@ -1,10 +1,12 @@
 use std::env::temp_dir;
 
 use sig::{
+    bc::params::QuorumThreshold,
+    bench_support::{measure_bc_circuit_constraints, measure_hashed_committee_circuit_constraints},
     bls::Parameters,
-    folding::circuit::{BCCircuitMerkleForest, BCCircuitNoMerkle},
+    folding::circuit::{BCCircuitHashedCommittee, BCCircuitMerkleForest, BCCircuitNoMerkle},
 };
-use utils::{ext::measure_bc_circuit_constraints, register_tracing};
+use utils::register_tracing;
 
 mod utils;
 
@@ -22,13 +24,38 @@ fn main() {
         MAX_COMMITTEE_SIZE,
         Fr,
         BCCircuitNoMerkle<Fr, MAX_COMMITTEE_SIZE>,
-    >(&dir, Parameters::setup(), true)
+    >(
+        &dir,
+        (Parameters::setup(), QuorumThreshold::default()),
+        true,
+    )
+    .unwrap();
+
+    // the state is a few field elements instead of thousands, at the cost of
+    // hashing the previous and the new committee in every step
+    measure_hashed_committee_circuit_constraints::<
+        MAX_COMMITTEE_SIZE,
+        Fr,
+        BCCircuitHashedCommittee<Fr, MAX_COMMITTEE_SIZE>,
+    >(
+        &dir,
+        (Parameters::setup(), QuorumThreshold::default()),
+        true,
+    )
     .unwrap();
 
     measure_bc_circuit_constraints::<
         MAX_COMMITTEE_SIZE,
         Fr,
         BCCircuitMerkleForest<Fr, MAX_COMMITTEE_SIZE>,
-    >(&dir, (Parameters::setup(), MAX_CHAIN_SIZE), true)
+    >(
+        &dir,
+        (
+            Parameters::setup(),
+            MAX_CHAIN_SIZE,
+            QuorumThreshold::default(),
+        ),
+        true,
+    )
     .unwrap();
 }
@@ -2,15 +2,13 @@
 /// - Measures actual BCCircuitMerkleForest constraints and saves to config
 /// - Uses a mock circuit with configurable constraints to collect timing data
 /// - Stores and prints results for extrapolation
-mod utils;
-
 use ark_crypto_primitives::crh::poseidon::constraints::CRHParametersVar;
 use ark_groth16::Groth16;
 use ark_mnt4_753::{Fr, G1Projective as G1, MNT4_753 as MNT4};
 use ark_mnt6_753::{G1Projective as G2, MNT6_753 as MNT6};
+use ark_r1cs_std::alloc::AllocVar;
 use ark_r1cs_std::convert::ToConstraintFieldGadget;
 use ark_r1cs_std::R1CSVar;
-use ark_r1cs_std::{alloc::AllocVar, uint64::UInt64};
 use ark_relations::r1cs::ConstraintSystem;
 use folding_schemes::{
     commitment::kzg::KZG,
@@ -24,20 +22,23 @@ use folding_schemes::{
 };
 use rand::rngs::StdRng;
 use rand::SeedableRng;
-use serde::{Deserialize, Serialize};
+use sig::bench_support::{
+    load_results, measure_bc_circuit_constraints, save_results, DummyBlockVar,
+    MemExperimentResult as ExperimentResult, MemRecorder, MockCircuit,
+};
 use sig::folding::circuit::BCCircuitMerkleForest;
 use sig::merkle::constraints::LeveledMerkleForestVar;
 use sig::merkle::Config;
 use sig::{
-    bc::block::{gen_blockchain_with_params, Block},
+    bc::{
+        block::{gen_blockchain_with_params, Block},
+        params::QuorumThreshold,
+    },
     bls::Parameters as BlsParameters,
-    folding::bc::CommitteeVar,
+    folding::decider::initial_block_state,
 };
-use std::fs::{self, File};
+use std::fs;
 use std::path::Path;
-use utils::ext::{
-    measure_bc_circuit_constraints, DummyBlockVar, MemRecorder, MockBCCircuitMerkleForest,
-};
 
 #[cfg(not(target_env = "msvc"))]
 use tikv_jemallocator::Jemalloc;
@@ -46,13 +47,6 @@ use tikv_jemallocator::Jemalloc;
 #[global_allocator]
 static GLOBAL: Jemalloc = Jemalloc;
 
-// Timing results for each experiment
-#[derive(Serialize, Deserialize, Clone)]
-struct ExperimentResult {
-    constraint_count: usize,
-    peak_mem: usize, // bytes
-}
-
 fn run_exp<const MAX_COMMITTEE_SIZE: usize, const STATE_SIZE: usize>(
     data_path: &Path,
 ) -> Result<(), Error> {
@@ -76,7 +70,15 @@ fn run_exp<const MAX_COMMITTEE_SIZE: usize, const STATE_SIZE: usize>(
         MAX_COMMITTEE_SIZE,
         Fr,
         BCCircuitMerkleForest<Fr, MAX_COMMITTEE_SIZE>,
-    >(&config_path, (BlsParameters::setup(), STATE_SIZE), false)?;
+    >(
+        &config_path,
+        (
+            BlsParameters::setup(),
+            STATE_SIZE,
+            QuorumThreshold::default(),
+        ),
+        false,
+    )?;
 
     // Define experiment parameters
     // - capped at 1 << 23 as it already requires roughly 900 GB memory
@@ -93,11 +95,7 @@ fn run_exp<const MAX_COMMITTEE_SIZE: usize, const STATE_SIZE: usize>(
     ));
 
     // Load existing results
-    let mut results: Vec<ExperimentResult> = if let Ok(file) = File::open(&results_path) {
-        serde_json::from_reader(file).unwrap_or_default()
-    } else {
-        vec![]
-    };
+    let mut results: Vec<ExperimentResult> = load_results(&results_path);
 
     for target_constraints in constraint_points {
         // Skip if already run
@@ -119,7 +117,7 @@ fn run_exp<const MAX_COMMITTEE_SIZE: usize, const STATE_SIZE: usize>(
 
         // Use MockBCCircuit
         type FC<const MAX_COMMITTEE_SIZE: usize> =
-            MockBCCircuitMerkleForest<Fr, MAX_COMMITTEE_SIZE>;
+            MockCircuit<Fr, Block<MAX_COMMITTEE_SIZE>, DummyBlockVar>;
         type N<const MAX_COMMITTEE_SIZE: usize> =
             Nova<G1, G2, FC<MAX_COMMITTEE_SIZE>, KZG<'static, MNT4>, KZG<'static, MNT6>, false>;
         type D<const MAX_COMMITTEE_SIZE: usize> = NovaDecider<
@@ -135,7 +133,7 @@ fn run_exp<const MAX_COMMITTEE_SIZE: usize, const STATE_SIZE: usize>(
 
         let mem = MemRecorder::start();
 
-        let f_circuit = FC::<MAX_COMMITTEE_SIZE>::new(STATE_SIZE, target_constraints)?;
+        let f_circuit = FC::<MAX_COMMITTEE_SIZE>::merkle_forest(STATE_SIZE, target_constraints);
 
         // Generate Nova parameters
         println!("Generating Nova parameters");
@@ -147,27 +145,18 @@ fn run_exp<const MAX_COMMITTEE_SIZE: usize, const STATE_SIZE: usize>(
         let bc = gen_blockchain_with_params(N_STEPS_TO_PROVE + 1, MAX_COMMITTEE_SIZE, &mut rng);
 
         // Prepare data to init Nova
-        let cs = ConstraintSystem::new_ref();
-        let z_0: Vec<_> =
-            CommitteeVar::new_constant(cs.clone(), bc.get(0).unwrap().committee.clone())?
-                .to_constraint_field()?
-                .into_iter()
-                .chain(std::iter::once(
-                    UInt64::constant(bc.get(0).unwrap().epoch).to_fp()?,
-                ))
-                .chain(
-                    LeveledMerkleForestVar::<Config<Fr>>::new_optimal(
-                        STATE_SIZE,
-                        &CRHParametersVar {
-                            parameters: poseidon_config.clone(),
-                        },
-                    )
-                    .expect("LMS should be constructed successfully")
-                    .to_constraint_field()?
-                    .into_iter(),
-                )
-                .map(|fpvar| fpvar.value().unwrap())
-                .collect();
+        let mut z_0 = initial_block_state::<Fr, MAX_COMMITTEE_SIZE>(bc.get(0).unwrap())?;
+        z_0.extend(
+            LeveledMerkleForestVar::<Config<Fr>>::new_optimal(
+                STATE_SIZE,
+                &CRHParametersVar {
+                    parameters: poseidon_config.clone(),
+                },
+            )
+            .expect("LMS should be constructed successfully")
+            .to_constraint_field()?
+            .value()?,
+        );
         assert_eq!(
             z_0.len(),
             f_circuit.state_len(),
@@ -218,9 +207,7 @@ fn run_exp<const MAX_COMMITTEE_SIZE: usize, const STATE_SIZE: usize>(
         results.push(result.clone());
 
         // Save results
-        let mut file = File::create(&results_path)?;
-        serde_json::to_writer_pretty(&mut file, &results)
-            .expect("serde_json pretty print should succeed");
+        save_results(&results_path, &results)?;
 
         // Print results
         println!("\nResults for {} constraints:", target_constraints);
@@ -2,14 +2,10 @@
 /// - Measures actual BCCircuitNoMerkle constraints and saves to config
 /// - Uses a mock circuit with configurable constraints to collect timing data
 /// - Stores and prints results for extrapolation
-mod utils;
-
 use ark_groth16::Groth16;
 use ark_mnt4_753::{Fr, G1Projective as G1, MNT4_753 as MNT4};
 use ark_mnt6_753::{G1Projective as G2, MNT6_753 as MNT6};
-use ark_r1cs_std::convert::ToConstraintFieldGadget;
-use ark_r1cs_std::R1CSVar;
-use ark_r1cs_std::{alloc::AllocVar, uint64::UInt64};
+use ark_r1cs_std::alloc::AllocVar;
 use ark_relations::r1cs::ConstraintSystem;
 use folding_schemes::{
     commitment::kzg::KZG,
@@ -23,15 +19,20 @@ use folding_schemes::{
 };
 use rand::rngs::StdRng;
 use rand::SeedableRng;
-use serde::{Deserialize, Serialize};
+use sig::bench_support::{
+    load_results, measure_bc_circuit_constraints, save_results, DummyBlockVar, MockCircuit,
+    TimeExperimentResult as ExperimentResult, Timer,
+};
 use sig::{
-    bc::block::{gen_blockchain_with_params, Block},
+    bc::{
+        block::{gen_blockchain_with_params, Block},
+        params::QuorumThreshold,
+    },
     bls::Parameters as BlsParameters,
-    folding::{bc::CommitteeVar, circuit::BCCircuitNoMerkle},
+    folding::{circuit::BCCircuitNoMerkle, decider::initial_state},
 };
-use std::fs::{self, File};
+use std::fs;
 use std::path::Path;
-use utils::ext::{measure_bc_circuit_constraints, DummyBlockVar, MockBCCircuitNoMerkle, Timer};
 
 #[cfg(not(target_env = "msvc"))]
 use tikv_jemallocator::Jemalloc;
@@ -40,18 +41,6 @@ use tikv_jemallocator::Jemalloc;
 #[global_allocator]
 static GLOBAL: Jemalloc = Jemalloc;
 
-// Timing results for each experiment
-#[derive(Serialize, Deserialize, Clone)]
-struct ExperimentResult {
-    constraint_count: usize,
-    nova_param_gen_time: f64,     // seconds
-    nova_init_time: f64,          // seconds
-    snark_param_gen_time: f64,    // seconds
-    folding_step_times: Vec<f64>, // seconds
-    snark_prove_time: f64,        // seconds
-    snark_verify_time: f64,       // seconds,
-}
-
 fn run_exp<const MAX_COMMITTEE_SIZE: usize>(data_path: &Path) -> Result<(), Error> {
     println!("Start exp with MAX_COMMITTEE_SIZE = {}", MAX_COMMITTEE_SIZE);
 
@@ -70,7 +59,11 @@ fn run_exp<const MAX_COMMITTEE_SIZE: usize>(data_path: &Path) -> Result<(), Erro
         MAX_COMMITTEE_SIZE,
         Fr,
         BCCircuitNoMerkle<Fr, MAX_COMMITTEE_SIZE>,
-    >(&config_path, BlsParameters::setup(), false)?;
+    >(
+        &config_path,
+        (BlsParameters::setup(), QuorumThreshold::default()),
+        false,
+    )?;
 
     // Define experiment parameters
     // - capped at 1 << 22 as 1 << 23 requires roughly 900 GB memory
@@ -88,11 +81,7 @@ fn run_exp<const MAX_COMMITTEE_SIZE: usize>(data_path: &Path) -> Result<(), Erro
     ));
 
     // Load existing results
-    let mut results: Vec<ExperimentResult> = if let Ok(file) = File::open(&results_path) {
-        serde_json::from_reader(file).unwrap_or_default()
-    } else {
-        vec![]
-    };
+    let mut results: Vec<ExperimentResult> = load_results(&results_path);
 
     for target_constraints in constraint_points {
         // Skip if already run
@@ -113,7 +102,8 @@ fn run_exp<const MAX_COMMITTEE_SIZE: usize>(data_path: &Path) -> Result<(), Erro
         );
 
         // Use MockBCCircuitNoMerkle
-        type FC<const MAX_COMMITTEE_SIZE: usize> = MockBCCircuitNoMerkle<Fr, MAX_COMMITTEE_SIZE>;
+        type FC<const MAX_COMMITTEE_SIZE: usize> =
+            MockCircuit<Fr, Block<MAX_COMMITTEE_SIZE>, DummyBlockVar>;
         type N<const MAX_COMMITTEE_SIZE: usize> =
             Nova<G1, G2, FC<MAX_COMMITTEE_SIZE>, KZG<'static, MNT4>, KZG<'static, MNT6>, false>;
         type D<const MAX_COMMITTEE_SIZE: usize> = NovaDecider<
@@ -127,7 +117,7 @@ fn run_exp<const MAX_COMMITTEE_SIZE: usize>(data_path: &Path) -> Result<(), Erro
             N<MAX_COMMITTEE_SIZE>,
         >;
 
-        let f_circuit = FC::<MAX_COMMITTEE_SIZE>::new(target_constraints)?;
+        let f_circuit = FC::<MAX_COMMITTEE_SIZE>::no_merkle(target_constraints);
 
         // Generate Nova parameters
         println!("Generating Nova parameters");
@@ -141,19 +131,7 @@ fn run_exp<const MAX_COMMITTEE_SIZE: usize>(data_path: &Path) -> Result<(), Erro
         let bc = gen_blockchain_with_params(N_STEPS_TO_PROVE + 1, MAX_COMMITTEE_SIZE, &mut rng);
 
         // Prepare data to init Nova
-        let cs = ConstraintSystem::new_ref();
-        let z_0: Vec<_> =
-            CommitteeVar::new_constant(cs.clone(), bc.get(0).unwrap().committee.clone())?
-                .to_constraint_field()?
-                .iter()
-                .map(|fpvar| fpvar.value().unwrap())
-                .chain(std::iter::once(
-                    UInt64::constant(bc.get(0).unwrap().epoch)
-                        .to_fp()?
-                        .value()
-                        .unwrap(),
-                ))
-                .collect();
+        let z_0 = initial_state::<Fr, MAX_COMMITTEE_SIZE>(bc.get(0).unwrap())?;
         assert_eq!(
             z_0.len(),
             f_circuit.state_len(),
@@ -219,9 +197,7 @@ fn run_exp<const MAX_COMMITTEE_SIZE: usize>(data_path: &Path) -> Result<(), Erro
         results.push(result.clone());
 
         // Save results
-        let mut file = File::create(&results_path)?;
-        serde_json::to_writer_pretty(&mut file, &results)
-            .expect("serde_json pretty print should succeed");
+        save_results(&results_path, &results)?;
 
         // Print results
         println!("\nResults for {} constraints:", target_constraints);
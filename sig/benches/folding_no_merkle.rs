@@ -2,14 +2,8 @@
 /// - Measures actual BCCircuitMerkleForest constraints and saves to config
 /// - Uses a mock circuit with configurable constraints to collect timing data
 /// - Stores and prints results for extrapolation
-mod utils;
-
 use ark_mnt4_753::{Fr, G1Projective as G1, MNT4_753 as MNT4};
 use ark_mnt6_753::{G1Projective as G2, MNT6_753 as MNT6};
-use ark_r1cs_std::convert::ToConstraintFieldGadget;
-use ark_r1cs_std::R1CSVar;
-use ark_r1cs_std::{alloc::AllocVar, uint64::UInt64};
-use ark_relations::r1cs::ConstraintSystem;
 use folding_schemes::FoldingScheme;
 use folding_schemes::{
     commitment::kzg::KZG,
@@ -21,14 +15,15 @@ use folding_schemes::{
 use rand::rngs::StdRng;
 use rand::SeedableRng;
 use serde::{Deserialize, Serialize};
+use sig::bench_support::Timer;
 use sig::folding::circuit::BCCircuitNoMerkle;
 use sig::{
-    bc::block::gen_blockchain_with_params, bls::Parameters as BlsParameters,
-    folding::bc::CommitteeVar,
+    bc::{block::gen_blockchain_with_params, params::QuorumThreshold},
+    bls::Parameters as BlsParameters,
+    folding::decider::initial_state,
 };
 use std::fs::{self, File};
 use std::path::Path;
-use utils::ext::Timer;
 
 #[cfg(not(target_env = "msvc"))]
 use tikv_jemallocator::Jemalloc;
@@ -87,7 +82,8 @@ fn run_exp<const MAX_COMMITTEE_SIZE: usize>(data_path: &Path) -> Result<(), Erro
     type N<const MAX_COMMITTEE_SIZE: usize> =
         Nova<G1, G2, FC<MAX_COMMITTEE_SIZE>, KZG<'static, MNT4>, KZG<'static, MNT6>, false>;
 
-    let f_circuit = FC::<MAX_COMMITTEE_SIZE>::new(BlsParameters::setup())?;
+    let f_circuit =
+        FC::<MAX_COMMITTEE_SIZE>::new((BlsParameters::setup(), QuorumThreshold::default()))?;
 
     // Generate Nova parameters
     println!("Generating Nova parameters");
@@ -100,15 +96,7 @@ fn run_exp<const MAX_COMMITTEE_SIZE: usize>(data_path: &Path) -> Result<(), Erro
     let bc = gen_blockchain_with_params(N_STEPS_TO_PROVE + 1, MAX_COMMITTEE_SIZE, &mut rng);
 
     // Prepare data to init Nova
-    let cs = ConstraintSystem::new_ref();
-    let z_0: Vec<_> = CommitteeVar::new_constant(cs.clone(), bc.get(0).unwrap().committee.clone())?
-        .to_constraint_field()?
-        .into_iter()
-        .chain(std::iter::once(
-            UInt64::constant(bc.get(0).unwrap().epoch).to_fp()?,
-        ))
-        .map(|fpvar| fpvar.value().unwrap())
-        .collect();
+    let z_0 = initial_state::<Fr, MAX_COMMITTEE_SIZE>(bc.get(0).unwrap())?;
     assert_eq!(
         z_0.len(),
         f_circuit.state_len(),
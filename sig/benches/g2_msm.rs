@@ -0,0 +1,68 @@
+use ark_ff::{BigInteger, PrimeField, UniformRand};
+use ark_r1cs_std::{
+    alloc::AllocVar,
+    fields::fp::FpVar,
+    groups::{bls12::G2Var, CurveVar},
+    prelude::Boolean,
+};
+use ark_relations::r1cs::ConstraintSystem;
+use rand::{rngs::StdRng, SeedableRng};
+use sig::{
+    bls::{g2_msm, g2_sum},
+    params::BlsSigField,
+};
+
+type BlsSigConfig = ark_bls12_377::Config;
+type CF = BlsSigField<BlsSigConfig>;
+type PointVar = G2Var<BlsSigConfig, FpVar<CF>, CF>;
+
+const NUM_POINTS: usize = 100;
+
+// Counts the constraints of aggregating `NUM_POINTS` G2 points, over the base
+// field of BLS12-377 so that the points are not emulated.
+fn main() {
+    let mut rng = StdRng::from_seed([42; 32]);
+    let cs = ConstraintSystem::<CF>::new_ref();
+
+    let points = Vec::<PointVar>::new_witness(cs.clone(), || {
+        Ok((0..NUM_POINTS)
+            .map(|_| ark_bls12_377::G2Projective::rand(&mut rng))
+            .collect::<Vec<_>>())
+    })
+    .unwrap();
+    let scalars: Vec<_> = (0..NUM_POINTS)
+        .map(|_| {
+            let bits = ark_bls12_377::Fr::rand(&mut rng).into_bigint().to_bits_le();
+            Vec::<Boolean<CF>>::new_witness(cs.clone(), || Ok(bits)).unwrap()
+        })
+        .collect();
+
+    let count = |f: &dyn Fn()| {
+        let before = cs.num_constraints();
+        f();
+        cs.num_constraints() - before
+    };
+
+    println!(
+        "sum of {NUM_POINTS} points, g2_sum: {}",
+        count(&|| {
+            g2_sum(&points).unwrap();
+        })
+    );
+    println!(
+        "msm of {NUM_POINTS} points, one scalar multiplication at a time: {}",
+        count(&|| {
+            points
+                .iter()
+                .zip(&scalars)
+                .map(|(point, bits)| point.scalar_mul_le(bits.iter()).unwrap())
+                .fold(PointVar::zero(), |acc, point| acc + point);
+        })
+    );
+    println!(
+        "msm of {NUM_POINTS} points, g2_msm: {}",
+        count(&|| {
+            g2_msm(&points, &scalars).unwrap();
+        })
+    );
+}
@@ -1,5 +1,3 @@
-mod utils;
-
 use either::Either;
 #[cfg(not(target_env = "msvc"))]
 use tikv_jemallocator::Jemalloc;
@@ -13,12 +11,12 @@ use ark_crypto_primitives::sponge::poseidon::PoseidonConfig;
 use ark_ff::UniformRand;
 use rand::thread_rng;
 use serde::Serialize;
+use sig::bench_support::MemRecorder;
 use sig::merkle::{forest::LeveledMerkleForest, tree::MerkleTree, Config};
 use std::{
     fs::{self, File},
     path::Path,
 };
-use utils::ext::MemRecorder;
 
 // Utility to get Poseidon parameters
 fn poseidon_params() -> PoseidonConfig<Fr> {
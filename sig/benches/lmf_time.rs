@@ -1,17 +1,15 @@
-mod utils;
-
 use ark_bls12_381::Fr;
 use ark_crypto_primitives::sponge::poseidon::PoseidonConfig;
 use ark_ff::UniformRand;
 use either::Either;
 use rand::{thread_rng, Rng};
 use serde::Serialize;
+use sig::bench_support::Timer;
 use sig::merkle::{forest::LeveledMerkleForest, tree::MerkleTree, Config};
 use std::{
     fs::{self, File},
     path::Path,
 };
-use utils::ext::Timer;
 
 // Utility to get Poseidon parameters
 fn poseidon_params() -> PoseidonConfig<Fr> {
@@ -61,14 +59,14 @@ fn run_experiment(n: usize, num_proofs: usize) -> ExperimentResult {
             .prove(idx)
             .expect("Failed to generate Merkle proof");
         merkle_proof_time += proof_start.end();
-        merkle_proof_size = proof.0.len();
+        merkle_proof_size = proof.siblings.len();
 
         // ensure the proof is correct
         let valid = MerkleTree::<Config<Fr>>::verify(
             &params,
             merkle_tree.root(),
             Either::Left(&leaves[idx]),
-            proof,
+            &proof,
         )
         .unwrap();
         assert!(valid);
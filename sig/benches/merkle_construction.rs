@@ -0,0 +1,69 @@
+use ark_bls12_381::Fr;
+use ark_ff::UniformRand;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use either::Either;
+use rand::{rngs::StdRng, SeedableRng};
+use sig::merkle::{forest::LeveledMerkleForest, tree::MerkleTree, Config};
+
+// Run with `--features parallel` to compare the rayon thread pool against a
+// single thread. Without the feature, both variants are sequential.
+fn merkle_construction_bench(c: &mut Criterion) {
+    let params = folding_schemes::transcript::poseidon::poseidon_canonical_config::<Fr>();
+    let mut rng = StdRng::from_seed([42; 32]);
+    let single_thread = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()
+        .unwrap();
+
+    let mut group = c.benchmark_group("Merkle construction");
+    group.sample_size(10);
+    for log_n in [12, 14] {
+        let leaves: Vec<_> = (0..1 << log_n).map(|_| Fr::rand(&mut rng)).collect();
+        let values: Vec<_> = leaves.iter().map(std::slice::from_ref).collect();
+
+        group.bench_with_input(
+            BenchmarkId::new("tree (1 thread)", log_n),
+            &leaves,
+            |b, l| {
+                b.iter(|| {
+                    single_thread.install(|| {
+                        MerkleTree::<Config<Fr>>::new_with_data(Either::Left(l), &params)
+                    })
+                })
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("tree", log_n), &leaves, |b, l| {
+            b.iter(|| MerkleTree::<Config<Fr>>::new_with_data(Either::Left(l), &params))
+        });
+
+        group.bench_with_input(
+            BenchmarkId::new("forest add_batch (1 thread)", log_n),
+            &values,
+            |b, v| {
+                b.iter(|| {
+                    single_thread.install(|| {
+                        let mut forest =
+                            LeveledMerkleForest::<Config<Fr>>::new_optimal(v.len(), &params)
+                                .unwrap();
+                        forest.add_batch(v).unwrap();
+                    })
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("forest add_batch", log_n),
+            &values,
+            |b, v| {
+                b.iter(|| {
+                    let mut forest =
+                        LeveledMerkleForest::<Config<Fr>>::new_optimal(v.len(), &params).unwrap();
+                    forest.add_batch(v).unwrap();
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, merkle_construction_bench);
+criterion_main!(benches);
@@ -1,5 +1,3 @@
-pub mod ext;
-
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
 use tracing_tree::HierarchicalLayer;
 
@@ -1,29 +1,49 @@
+use ark_crypto_primitives::sponge::{poseidon::PoseidonConfig, Absorb};
 use ark_ec::{
+    bls12::Bls12Config,
     short_weierstrass::{Affine, Projective, SWCurveConfig},
     CurveGroup,
 };
-use ark_serialize::CanonicalSerialize;
+use ark_ff::{PrimeField, Zero};
+use ark_r1cs_std::{alloc::AllocVar, convert::ToConstraintFieldGadget, R1CSVar};
+use ark_relations::r1cs::{ConstraintSystem, SynthesisError};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use bincode::Options;
 use blake2::Digest;
 use delegate::delegate;
 use rand::Rng;
-use serde::{ser::SerializeTuple, Serialize, Serializer};
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    ser::SerializeTuple,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 use serde_with::serde_as;
-
-use crate::{bc::params::AuthoritySecretKey, bls::Signature};
+use thiserror::Error;
+
+use crate::{
+    bc::params::AuthoritySecretKey,
+    bls::Signature,
+    folding::bc::SignerVar,
+    merkle::{
+        tree::{MerkleTree, MerkleTreeError},
+        Config,
+    },
+    params::BlsSigConfig,
+};
 
 use super::params::{
-    AuthorityAggregatedSignature, AuthorityPublicKey, AuthoritySigParams, HashFunc, Signers,
-    Weight, HASH_OUTPUT_SIZE, STRONG_THRESHOLD, TOTAL_VOTING_POWER,
+    AuthorityAggregatedSignature, AuthorityPublicKey, AuthoritySigParams, HashFunc, PointEncoding,
+    QuorumThreshold, Signers, Weight, HASH_OUTPUT_SIZE, POINT_ENCODING, STRONG_THRESHOLD,
+    TOTAL_VOTING_POWER,
 };
 
 // const MAX_COMMITTEE_SIZE: usize = 1;
 
-#[serde_as]
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct QuorumSignature<const MAX_COMMITTEE_SIZE: usize> {
     pub sig: AuthorityAggregatedSignature,
     // a roaring bitmap is a better alternative, but for easy impl of R1CS circuit, we use Vec<bool>
-    #[serde_as(as = "[_; MAX_COMMITTEE_SIZE]")]
+    #[serde(with = "packed_signers")]
     pub signers: [bool; MAX_COMMITTEE_SIZE],
 }
 
@@ -37,7 +57,7 @@ impl<const MAX_COMMITTEE_SIZE: usize> Default for QuorumSignature<MAX_COMMITTEE_
 }
 
 #[serde_as]
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Committee<const MAX_COMMITTEE_SIZE: usize> {
     #[serde_as(as = "[_; MAX_COMMITTEE_SIZE]")]
     pub signers: [(AuthorityPublicKey, Weight); MAX_COMMITTEE_SIZE],
@@ -51,7 +71,83 @@ impl<const MAX_COMMITTEE_SIZE: usize> Default for Committee<MAX_COMMITTEE_SIZE>
     }
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+impl<const MAX_COMMITTEE_SIZE: usize> Committee<MAX_COMMITTEE_SIZE> {
+    /// The root of a Merkle tree over the signers, where every leaf is the
+    /// Poseidon hash of a signer as represented in the folding circuits, i.e.
+    /// `SignerVar::to_constraint_field`. The leaves are padded to a power of 2.
+    ///
+    /// `CommitteeVar::merkle_root` computes the same root in-circuit.
+    pub fn merkle_root<CF: PrimeField + Absorb>(
+        &self,
+        params: &PoseidonConfig<CF>,
+    ) -> Result<CF, MerkleTreeError> {
+        let values = self
+            .signers
+            .iter()
+            .map(|signer| {
+                let cs = ConstraintSystem::<CF>::new_ref();
+                SignerVar::new_constant(cs, signer)?
+                    .to_constraint_field()?
+                    .value()
+            })
+            .collect::<Result<Vec<_>, SynthesisError>>()
+            .expect("constants can always be converted into field elements");
+        let values: Vec<_> = values.iter().map(Vec::as_slice).collect();
+
+        let tree = MerkleTree::<Config<CF>>::from_values(
+            &values,
+            committee_tree_capacity(MAX_COMMITTEE_SIZE),
+            params,
+        )?;
+        Ok(tree.root())
+    }
+
+    /// Whether the weights of the committee sum to at most
+    /// `TOTAL_VOTING_POWER`, and, with the `distinct-committee-keys` feature,
+    /// the members with a non-zero weight have pairwise distinct public keys.
+    ///
+    /// `CommitteeVar::enforce_valid` enforces the same in-circuit.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        // `MAX_COMMITTEE_SIZE` 64-bit weights cannot overflow a u128
+        let total: u128 = self
+            .signers
+            .iter()
+            .map(|(_, weight)| u128::from(*weight))
+            .sum();
+        if total > u128::from(TOTAL_VOTING_POWER) {
+            return false;
+        }
+
+        if cfg!(feature = "distinct-committee-keys") {
+            let members: Vec<_> = self
+                .signers
+                .iter()
+                .filter(|(_, weight)| *weight != 0)
+                .map(|(pk, _)| Into::<Projective<_>>::into(*pk))
+                .collect();
+            for (i, pk) in members.iter().enumerate() {
+                if members[i + 1..].contains(pk) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+/// The capacity of the tree built by `Committee::merkle_root`.
+pub(crate) const fn committee_tree_capacity(max_committee_size: usize) -> usize {
+    // a tree has at least 2 leaves
+    let num_leaves = if max_committee_size < 2 {
+        2
+    } else {
+        max_committee_size.next_power_of_two()
+    };
+    2 * num_leaves - 1
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct Block<const MAX_COMMITTEE_SIZE: usize> {
     pub epoch: u64,
 
@@ -69,32 +165,34 @@ pub struct Block<const MAX_COMMITTEE_SIZE: usize> {
 pub struct Blockchain<const MAX_COMMITTEE_SIZE: usize> {
     blocks: Vec<Block<MAX_COMMITTEE_SIZE>>,
     params: AuthoritySigParams,
+    threshold: QuorumThreshold,
+}
+
+/// Encode `affine` in the given format, see `PointEncoding`.
+pub(crate) fn encode_curve_point<Config: SWCurveConfig>(
+    affine: Affine<Config>,
+    encoding: PointEncoding,
+) -> Result<Vec<u8>, SerializationError> {
+    let mut bytes = vec![];
+    match encoding {
+        PointEncoding::Uncompressed => {
+            affine.x.serialize_uncompressed(&mut bytes)?;
+            affine.y.serialize_uncompressed(&mut bytes)?;
+            affine.infinity.serialize_uncompressed(&mut bytes)?;
+        }
+        PointEncoding::Compressed => affine.serialize_compressed(&mut bytes)?,
+    }
+    Ok(bytes)
 }
 
 fn serialize_curve_point<Config: SWCurveConfig, S: Serializer>(
     affine: Affine<Config>,
     serializer: S,
 ) -> Result<S::Ok, S::Error> {
-    let mut bytes = vec![];
-    affine
-        .x
-        .serialize_uncompressed(&mut bytes)
-        .map_err(serde::ser::Error::custom)?;
-    affine
-        .y
-        .serialize_uncompressed(&mut bytes)
-        .map_err(serde::ser::Error::custom)?;
-    affine
-        .infinity
-        .serialize_uncompressed(&mut bytes)
-        .map_err(serde::ser::Error::custom)?;
+    let bytes = encode_curve_point(affine, POINT_ENCODING).map_err(serde::ser::Error::custom)?;
 
     // The length of the struct is static, so it's safe to use this
-    let mut seq = serializer.serialize_tuple(bytes.len())?;
-    for b in bytes {
-        seq.serialize_element(&b)?;
-    }
-    seq.end()
+    serialize_bytes(&bytes, serializer)
 }
 
 /// Serialize is implemented manually because it's easy to match it with `SerializeGadget` implementation
@@ -119,7 +217,213 @@ impl Serialize for AuthorityPublicKey {
     }
 }
 
+/// The number of bytes of a point encoded by `encode_curve_point`.
+pub(crate) fn encoded_point_len<Config: SWCurveConfig>(encoding: PointEncoding) -> usize {
+    let identity = Affine::<Config>::identity();
+    match encoding {
+        PointEncoding::Uncompressed => 2 * identity.x.uncompressed_size() + 1,
+        PointEncoding::Compressed => identity.compressed_size(),
+    }
+}
+
+/// Inverse of `encode_curve_point`, which rejects points that are not on the
+/// curve or not in the prime order subgroup.
+pub(crate) fn decode_curve_point<Config: SWCurveConfig>(
+    mut bytes: &[u8],
+    encoding: PointEncoding,
+) -> Result<Affine<Config>, SerializationError> {
+    let affine = match encoding {
+        PointEncoding::Uncompressed => {
+            let x = Config::BaseField::deserialize_uncompressed(&mut bytes)?;
+            let y = Config::BaseField::deserialize_uncompressed(&mut bytes)?;
+            let infinity = bool::deserialize_uncompressed(&mut bytes)?;
+
+            // the point at infinity has a unique encoding
+            if infinity && (!x.is_zero() || !y.is_zero()) {
+                return Err(SerializationError::InvalidData);
+            }
+            let affine = if infinity {
+                Affine::identity()
+            } else {
+                Affine::new_unchecked(x, y)
+            };
+            if !affine.is_on_curve() || !affine.is_in_correct_subgroup_assuming_on_curve() {
+                return Err(SerializationError::InvalidData);
+            }
+            affine
+        }
+        // validates the point as well
+        PointEncoding::Compressed => Affine::deserialize_compressed(&mut bytes)?,
+    };
+
+    if !bytes.is_empty() {
+        return Err(SerializationError::InvalidData);
+    }
+    Ok(affine)
+}
+
+/// Serialize `bytes` as a tuple, i.e. without their length, which is fixed.
+fn serialize_bytes<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    let mut seq = serializer.serialize_tuple(bytes.len())?;
+    for b in bytes {
+        seq.serialize_element(b)?;
+    }
+    seq.end()
+}
+
+/// Inverse of `serialize_bytes` for `len` bytes.
+fn deserialize_bytes<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    len: usize,
+) -> Result<Vec<u8>, D::Error> {
+    struct FixedBytes(usize);
+
+    impl<'de> Visitor<'de> for FixedBytes {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "{} bytes", self.0)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+            let mut bytes = Vec::with_capacity(self.0);
+            for i in 0..self.0 {
+                bytes.push(
+                    seq.next_element()?
+                        .ok_or_else(|| de::Error::invalid_length(i, &self))?,
+                );
+            }
+            Ok(bytes)
+        }
+    }
+
+    deserializer.deserialize_tuple(len, FixedBytes(len))
+}
+
+/// The signer bitmap is serialized with 8 signers per byte, where the `i`-th
+/// signer is bit `i % 8` of byte `i / 8`, and the unused bits of the last
+/// byte are zero. `SerializeGadget for SignersVar` follows the same layout.
+mod packed_signers {
+    use serde::{de, Deserializer, Serializer};
+
+    use super::{deserialize_bytes, serialize_bytes};
+
+    pub fn serialize<S: Serializer, const N: usize>(
+        signers: &[bool; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut bytes = vec![0u8; N.div_ceil(8)];
+        for (i, signed) in signers.iter().enumerate() {
+            bytes[i / 8] |= u8::from(*signed) << (i % 8);
+        }
+        serialize_bytes(&bytes, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[bool; N], D::Error> {
+        let bytes = deserialize_bytes(deserializer, N.div_ceil(8))?;
+
+        // the bitmap has a unique encoding
+        if N % 8 != 0 && bytes[N / 8] >> (N % 8) != 0 {
+            return Err(de::Error::custom(
+                "the unused bits of the signers are not zero",
+            ));
+        }
+        Ok(core::array::from_fn(|i| bytes[i / 8] >> (i % 8) & 1 == 1))
+    }
+}
+
+fn deserialize_curve_point<'de, Config: SWCurveConfig, D: Deserializer<'de>>(
+    deserializer: D,
+    what: &'static str,
+) -> Result<Affine<Config>, D::Error> {
+    let bytes = deserialize_bytes(deserializer, encoded_point_len::<Config>(POINT_ENCODING))?;
+    decode_curve_point(&bytes, POINT_ENCODING)
+        .map_err(|e| de::Error::custom(format_args!("invalid {what}: {e}")))
+}
+
+/// Deserialize is the inverse of `Serialize`, and validates the point.
+impl<'de> Deserialize<'de> for AuthorityAggregatedSignature {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let affine = deserialize_curve_point::<<BlsSigConfig as Bls12Config>::G2Config, _>(
+            deserializer,
+            "signature",
+        )?;
+        Ok(Projective::from(affine).into())
+    }
+}
+
+/// Deserialize is the inverse of `Serialize`, and validates the point.
+impl<'de> Deserialize<'de> for AuthorityPublicKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let affine = deserialize_curve_point::<<BlsSigConfig as Bls12Config>::G1Config, _>(
+            deserializer,
+            "public key",
+        )?;
+        Ok(Projective::from(affine).into())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum BlockDecodingError {
+    #[error(
+        "Block of {found} bytes, expected {expected} bytes for a committee of {max_committee_size} members"
+    )]
+    InvalidLength {
+        found: usize,
+        expected: usize,
+        max_committee_size: usize,
+    },
+
+    #[error("Malformed block: {0}")]
+    Malformed(#[from] bincode::Error),
+}
+
 impl<const MAX_COMMITTEE_SIZE: usize> Block<MAX_COMMITTEE_SIZE> {
+    /// The number of bytes of a block encoded with `bincode::serialize`,
+    /// which does not depend on its content.
+    #[must_use]
+    pub fn encoded_len() -> usize {
+        let pk_len = encoded_point_len::<<BlsSigConfig as Bls12Config>::G1Config>(POINT_ENCODING);
+        let sig_len = encoded_point_len::<<BlsSigConfig as Bls12Config>::G2Config>(POINT_ENCODING);
+
+        // epoch || prev_digest || sig || signers || committee
+        std::mem::size_of::<u64>()
+            + HASH_OUTPUT_SIZE
+            + sig_len
+            + MAX_COMMITTEE_SIZE.div_ceil(8)
+            + MAX_COMMITTEE_SIZE * (pk_len + std::mem::size_of::<Weight>())
+    }
+
+    /// Decode a block encoded with `bincode::serialize`, e.g. received from
+    /// an untrusted peer.
+    ///
+    /// The committee has to have exactly `MAX_COMMITTEE_SIZE` members, and
+    /// the keys and the signature have to be on the curve and in the prime
+    /// order subgroup. This does not verify the signature, see `verify`.
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, BlockDecodingError> {
+        let expected = Self::encoded_len();
+        if bytes.len() != expected {
+            return Err(BlockDecodingError::InvalidLength {
+                found: bytes.len(),
+                expected,
+                max_committee_size: MAX_COMMITTEE_SIZE,
+            });
+        }
+
+        // the options of `bincode::serialize`
+        Ok(bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .deserialize(bytes)?)
+    }
+
     #[must_use]
     pub fn genesis(data: Committee<MAX_COMMITTEE_SIZE>) -> Self {
         Self {
@@ -130,7 +434,7 @@ impl<const MAX_COMMITTEE_SIZE: usize> Block<MAX_COMMITTEE_SIZE> {
         }
     }
 
-    fn new(
+    pub(crate) fn new(
         prev: &Self,
         data: Committee<MAX_COMMITTEE_SIZE>,
         signers: &Signers,
@@ -174,37 +478,67 @@ impl<const MAX_COMMITTEE_SIZE: usize> Block<MAX_COMMITTEE_SIZE> {
         committee: &Committee<MAX_COMMITTEE_SIZE>,
         epoch: u64,
         params: &AuthoritySigParams,
+    ) -> bool {
+        self.verify_with_threshold(committee, epoch, params, QuorumThreshold::default())
+    }
+
+    /// Same as `verify`, but the signers need a total weight that meets
+    /// `threshold` instead of at least `STRONG_THRESHOLD`.
+    #[must_use]
+    pub fn verify_with_threshold(
+        &self,
+        committee: &Committee<MAX_COMMITTEE_SIZE>,
+        epoch: u64,
+        params: &AuthoritySigParams,
+        threshold: impl Into<QuorumThreshold>,
     ) -> bool {
         assert!(
-            self.epoch == epoch + 1,
+            epoch.checked_add(1) == Some(self.epoch),
             "epoch mismatches: expect {} but get {}",
             self.epoch,
             epoch
         );
 
-        let aggregate_signer_info = committee
+        // the committee introduced by this block is checked as in the circuits
+        if !self.committee.is_valid() {
+            return false;
+        }
+
+        let (signers, weights): (Vec<_>, Vec<_>) = committee
             .signers
             .iter()
-            .enumerate()
-            .filter(|(i, _)| self.sig.signers[*i])
-            .map(|(_, signer_info)| signer_info)
-            .copied()
-            .reduce(|acc, e| (acc.0 + e.0, acc.1 + e.1));
+            .zip(&self.sig.signers)
+            .filter(|(_, signed)| **signed)
+            .map(|(signer_info, _)| *signer_info)
+            .unzip();
+
+        // no quorum signs this block, or the total weight overflows a u64,
+        // which is rejected as in the circuits
+        let Some(weight) = weights
+            .into_iter()
+            .try_fold(0, |acc: Weight, weight| acc.checked_add(weight))
+        else {
+            return false;
+        };
+        if signers.is_empty() || !threshold.into().is_met(weight) {
+            return false;
+        }
 
         // prepare the msg used in signing
         let mut self_clone = self.clone();
         self_clone.sig = QuorumSignature::default();
         let msg = bincode::serialize(&self_clone).expect("serialization should succeed");
 
-        if let Some((aggregate_pk, weights)) = aggregate_signer_info {
-            if weights < STRONG_THRESHOLD {
-                return false;
-            }
-            return Signature::verify(&msg, &self.sig.sig, &aggregate_pk, params);
-        }
+        let aggregate_pk = AuthorityPublicKey::aggregate(&signers);
+        Signature::verify(&msg, &self.sig.sig, &aggregate_pk, params)
+    }
+}
 
-        // weights == 0 => no quorum signs this block
-        false
+impl<const MAX_COMMITTEE_SIZE: usize> TryFrom<&[u8]> for Block<MAX_COMMITTEE_SIZE> {
+    type Error = BlockDecodingError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Self::try_from_bytes(bytes)
     }
 }
 
@@ -216,6 +550,17 @@ impl<const MAX_COMMITTEE_SIZE: usize> Blockchain<MAX_COMMITTEE_SIZE> {
         Self {
             blocks: vec![],
             params,
+            threshold: QuorumThreshold::at_least(STRONG_THRESHOLD),
+        }
+    }
+
+    /// Require the signers of every block to have a total weight that meets
+    /// `threshold` instead of at least `STRONG_THRESHOLD`.
+    #[must_use]
+    pub fn with_threshold(self, threshold: impl Into<QuorumThreshold>) -> Self {
+        Self {
+            threshold: threshold.into(),
+            ..self
         }
     }
 
@@ -244,6 +589,10 @@ impl<const MAX_COMMITTEE_SIZE: usize> Blockchain<MAX_COMMITTEE_SIZE> {
         if self.is_empty() {
             return true;
         }
+        // the later committees are checked by `Block::verify_with_threshold`
+        if !self.blocks[0].committee.is_valid() {
+            return false;
+        }
 
         let mut committee = &self.blocks[0].committee;
         let mut prev_digest = compute_digest(&self.blocks[0]);
@@ -251,7 +600,12 @@ impl<const MAX_COMMITTEE_SIZE: usize> Blockchain<MAX_COMMITTEE_SIZE> {
 
         for block in self.blocks.iter().skip(1) {
             if block.prev_digest != prev_digest
-                || !block.verify(committee, committee_epoch, &self.params)
+                || !block.verify_with_threshold(
+                    committee,
+                    committee_epoch,
+                    &self.params,
+                    self.threshold,
+                )
             {
                 return false;
             }
@@ -264,7 +618,9 @@ impl<const MAX_COMMITTEE_SIZE: usize> Blockchain<MAX_COMMITTEE_SIZE> {
     }
 }
 
-fn compute_digest<const MAX_COMMITTEE_SIZE: usize>(
+/// The digest of `block` that the next block stores as `prev_digest`. It
+/// covers the whole block, including the signature.
+pub fn compute_digest<const MAX_COMMITTEE_SIZE: usize>(
     block: &Block<MAX_COMMITTEE_SIZE>,
 ) -> [u8; HASH_OUTPUT_SIZE] {
     let bytes = bincode::serialize(&block).unwrap();
@@ -411,9 +767,21 @@ pub fn gen_blockchain_with_params<R: Rng, const MAX_COMMITTEE_SIZE: usize>(
 
 #[cfg(test)]
 mod test {
+    use ark_ec::{short_weierstrass::Affine, AffineRepr, CurveGroup};
+    use ark_serialize::CanonicalDeserialize;
+    use ark_std::UniformRand;
     use rand::thread_rng;
 
-    use super::gen_blockchain_with_params;
+    use crate::bc::params::PointEncoding;
+    use crate::bc::params::{
+        AuthorityPublicKey, AuthoritySecretKey, AuthoritySigParams, QuorumThreshold,
+        HASH_OUTPUT_SIZE, POINT_ENCODING, STRONG_THRESHOLD,
+    };
+
+    use super::{
+        encode_curve_point, encoded_point_len, gen_blockchain_with_params, Block,
+        BlockDecodingError, Blockchain, Committee, QuorumSignature,
+    };
 
     const MAX_COMMITTEE_SIZE: usize = 25;
 
@@ -421,4 +789,233 @@ mod test {
     fn test_gen_blockchain() {
         let _ = gen_blockchain_with_params::<_, MAX_COMMITTEE_SIZE>(100, 10, &mut thread_rng());
     }
+
+    #[test]
+    fn test_custom_threshold() {
+        let mut rng = thread_rng();
+        let params = AuthoritySigParams::setup();
+
+        let signers: Vec<_> = (0..4).map(|_| AuthoritySecretKey::new(&mut rng)).collect();
+        let committee = Committee::<4> {
+            signers: core::array::from_fn(|i| {
+                (AuthorityPublicKey::new(&signers[i], &params), 2_500)
+            }),
+        };
+
+        // half of the voting power signs the block
+        let genesis = Block::genesis(committee.clone());
+        let block = Block::new(
+            &genesis,
+            committee,
+            &signers,
+            &[true, true, false, false],
+            &params,
+        )
+        .unwrap();
+        assert!(!block.verify(&genesis.committee, 0, &params));
+        assert!(block.verify_with_threshold(&genesis.committee, 0, &params, 5_000));
+        assert!(!block.verify_with_threshold(&genesis.committee, 0, &params, 5_001));
+        assert!(!block.verify_with_threshold(
+            &genesis.committee,
+            0,
+            &params,
+            QuorumThreshold::more_than(5_000)
+        ));
+        assert!(block.verify_with_threshold(
+            &genesis.committee,
+            0,
+            &params,
+            QuorumThreshold::more_than(4_999)
+        ));
+
+        let mut bc = Blockchain::new(params);
+        bc.add_block(genesis);
+        bc.add_block(block);
+        assert!(!bc.verify());
+        let bc = bc.with_threshold(5_000);
+        assert!(bc.verify());
+        assert!(!bc.with_threshold(STRONG_THRESHOLD).verify());
+    }
+
+    #[test]
+    fn test_invalid_committee() {
+        let mut rng = thread_rng();
+        let params = AuthoritySigParams::setup();
+
+        let signers: Vec<_> = (0..4).map(|_| AuthoritySecretKey::new(&mut rng)).collect();
+        let committee = Committee::<4> {
+            signers: core::array::from_fn(|i| {
+                (AuthorityPublicKey::new(&signers[i], &params), 2_500)
+            }),
+        };
+        assert!(committee.is_valid());
+
+        // the weights sum to one more than the total voting power
+        let mut over_weight = committee.clone();
+        over_weight.signers[3].1 += 1;
+        assert!(!over_weight.is_valid());
+
+        // every signer signs, but the block introduces the over-weight committee
+        let genesis = Block::genesis(committee.clone());
+        let block =
+            Block::new(&genesis, over_weight.clone(), &signers, &[true; 4], &params).unwrap();
+        assert!(!block.verify(&genesis.committee, 0, &params));
+
+        let mut bc = Blockchain::new(params);
+        bc.add_block(genesis);
+        bc.add_block(block);
+        assert!(!bc.verify());
+
+        // the genesis committee is checked as well
+        let mut bc = Blockchain::new(params);
+        bc.add_block(Block::genesis(over_weight));
+        assert!(!bc.verify());
+
+        // a key that appears twice is only rejected with `distinct-committee-keys`
+        let mut duplicated = committee;
+        duplicated.signers[1].0 = duplicated.signers[0].0;
+        assert_eq!(
+            duplicated.is_valid(),
+            !cfg!(feature = "distinct-committee-keys")
+        );
+        duplicated.signers[1].1 = 0;
+        assert!(duplicated.is_valid());
+    }
+
+    #[test]
+    fn test_compressed_point_round_trip() {
+        fn check<P: ark_ec::short_weierstrass::SWCurveConfig>(points: &[Affine<P>], len: usize) {
+            for point in points {
+                let bytes = encode_curve_point(*point, PointEncoding::Compressed).unwrap();
+                assert_eq!(bytes.len(), len);
+                assert_eq!(
+                    Affine::<P>::deserialize_compressed(&bytes[..]).unwrap(),
+                    *point
+                );
+
+                // the compressed point is about half the size of the
+                // uncompressed coordinates
+                let uncompressed = encode_curve_point(*point, PointEncoding::Uncompressed).unwrap();
+                assert_eq!(uncompressed.len(), 2 * len + 1);
+            }
+        }
+
+        let mut rng = thread_rng();
+        let g1: Vec<_> = (0..8)
+            .map(|_| ark_bls12_381::G1Projective::rand(&mut rng).into_affine())
+            .chain([ark_bls12_381::G1Affine::zero()])
+            .collect();
+        let g2: Vec<_> = (0..8)
+            .map(|_| ark_bls12_381::G2Projective::rand(&mut rng).into_affine())
+            .chain([ark_bls12_381::G2Affine::zero()])
+            .collect();
+        check(&g1, 48);
+        check(&g2, 96);
+    }
+
+    #[test]
+    fn test_block_from_bytes() {
+        let bc: Blockchain<MAX_COMMITTEE_SIZE> =
+            gen_blockchain_with_params(3, 10, &mut thread_rng());
+
+        for block in bc.into_blocks() {
+            let bytes = bincode::serialize(&block).unwrap();
+            assert_eq!(bytes.len(), Block::<MAX_COMMITTEE_SIZE>::encoded_len());
+
+            let decoded = Block::<MAX_COMMITTEE_SIZE>::try_from(&bytes[..]).unwrap();
+            assert_eq!(bincode::serialize(&decoded).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn test_bincode_round_trip() {
+        fn check<const N: usize>(committee_size: usize) {
+            let bc: Blockchain<N> =
+                gen_blockchain_with_params(3, committee_size, &mut thread_rng());
+
+            for block in bc.into_blocks() {
+                let bytes = bincode::serialize(&block).unwrap();
+                let decoded: Block<N> = bincode::deserialize(&bytes).unwrap();
+                assert_eq!(decoded.epoch, block.epoch);
+                assert_eq!(decoded.prev_digest, block.prev_digest);
+                assert_eq!(decoded.sig.signers, block.sig.signers);
+                assert_eq!(bincode::serialize(&decoded).unwrap(), bytes);
+
+                // the parts of a block can be read back on their own
+                let sig = bincode::serialize(&block.sig).unwrap();
+                let decoded: QuorumSignature<N> = bincode::deserialize(&sig).unwrap();
+                assert_eq!(bincode::serialize(&decoded).unwrap(), sig);
+
+                let committee = bincode::serialize(&block.committee).unwrap();
+                let decoded: Committee<N> = bincode::deserialize(&committee).unwrap();
+                assert_eq!(bincode::serialize(&decoded).unwrap(), committee);
+            }
+        }
+
+        check::<1>(1);
+        check::<4>(3);
+        check::<8>(8);
+        check::<MAX_COMMITTEE_SIZE>(10);
+    }
+
+    #[test]
+    fn test_block_from_malformed_bytes() {
+        type G1Config = ark_bls12_381::g1::Config;
+        type G2Config = ark_bls12_381::g2::Config;
+
+        let bc: Blockchain<MAX_COMMITTEE_SIZE> =
+            gen_blockchain_with_params(2, 10, &mut thread_rng());
+        let bytes = bincode::serialize(bc.get(1).unwrap()).unwrap();
+        let decode = |bytes: &[u8]| Block::<MAX_COMMITTEE_SIZE>::try_from_bytes(bytes);
+
+        // truncated, extended, or for another committee size
+        assert!(matches!(
+            decode(&bytes[..bytes.len() - 1]),
+            Err(BlockDecodingError::InvalidLength { .. })
+        ));
+        assert!(matches!(
+            decode(&[&bytes[..], &[0]].concat()),
+            Err(BlockDecodingError::InvalidLength { .. })
+        ));
+        assert!(matches!(
+            Block::<{ MAX_COMMITTEE_SIZE + 1 }>::try_from_bytes(&bytes),
+            Err(BlockDecodingError::InvalidLength { max_committee_size, .. })
+                if max_committee_size == MAX_COMMITTEE_SIZE + 1
+        ));
+
+        // epoch || prev_digest || sig || signers || committee
+        let sig_offset = 8 + HASH_OUTPUT_SIZE;
+        let signers_offset = sig_offset + encoded_point_len::<G2Config>(POINT_ENCODING);
+        let pk_offset = signers_offset + MAX_COMMITTEE_SIZE.div_ceil(8);
+
+        let patched = |offset: usize, patch: &[u8]| {
+            let mut bytes = bytes.clone();
+            bytes[offset..offset + patch.len()].copy_from_slice(patch);
+            decode(&bytes).map_err(|e| e.to_string())
+        };
+        let flipped = |offset: usize| patched(offset + 1, &[bytes[offset + 1] ^ 1]);
+
+        // a signer past the end of the committee
+        assert!(patched(pk_offset - 1, &[0x80])
+            .unwrap_err()
+            .contains("unused bits"));
+
+        // points that are not on the curve
+        assert!(flipped(sig_offset)
+            .unwrap_err()
+            .contains("invalid signature"));
+        assert!(flipped(pk_offset)
+            .unwrap_err()
+            .contains("invalid public key"));
+
+        // a point on the curve that is not in the prime order subgroup
+        let point = (1u64..)
+            .filter_map(|x| Affine::<G1Config>::get_point_from_x_unchecked(x.into(), false))
+            .find(|p| !p.is_in_correct_subgroup_assuming_on_curve())
+            .unwrap();
+        let encoded = encode_curve_point(point, POINT_ENCODING).unwrap();
+        assert!(patched(pk_offset, &encoded)
+            .unwrap_err()
+            .contains("invalid public key"));
+    }
 }
@@ -16,10 +16,85 @@ pub type AuthorityAggregatedSignature = Signature<BlsSigConfig>;
 pub type AuthoritySigParams = Parameters<BlsSigConfig>;
 /* ====================Sig==================== */
 
+/* ====================Encoding==================== */
+/// How blocks encode curve points, which changes both the signed messages and
+/// `compute_digest`. `serialize_curve_point` and the `SerializeGadget` of keys
+/// and signatures both follow `POINT_ENCODING`, so the chain and the circuits
+/// always agree on the format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointEncoding {
+    /// Version 1: the uncompressed affine coordinates, followed by the
+    /// infinity flag as a byte.
+    Uncompressed,
+    /// Version 2: the compressed `CanonicalSerialize` encoding, i.e. the
+    /// big-endian x coordinate with the flags in its top 3 bits.
+    Compressed,
+}
+
+#[cfg(not(feature = "compressed-points"))]
+pub const POINT_ENCODING: PointEncoding = PointEncoding::Uncompressed;
+#[cfg(feature = "compressed-points")]
+pub const POINT_ENCODING: PointEncoding = PointEncoding::Compressed;
+/* ====================Encoding==================== */
+
 /* ====================Committee==================== */
 pub type Weight = u64;
 pub type Signers = Vec<AuthoritySecretKey>;
 
 pub const TOTAL_VOTING_POWER: u64 = 10_000;
+/// The default minimum weight of the signers of a block, i.e. 2/3 of
+/// `TOTAL_VOTING_POWER`. `Blockchain` and the folding circuits can be
+/// configured with a different `QuorumThreshold`.
 pub const STRONG_THRESHOLD: u64 = 6_667;
+
+/// The weight the signers of a block need for it to be accepted: either at
+/// least `weight`, or, if `strict`, more than `weight`.
+///
+/// A bare `Weight` converts to a non-strict threshold, and the default is at
+/// least `STRONG_THRESHOLD`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuorumThreshold {
+    pub weight: Weight,
+    pub strict: bool,
+}
+
+impl QuorumThreshold {
+    #[must_use]
+    pub const fn at_least(weight: Weight) -> Self {
+        Self {
+            weight,
+            strict: false,
+        }
+    }
+
+    #[must_use]
+    pub const fn more_than(weight: Weight) -> Self {
+        Self {
+            weight,
+            strict: true,
+        }
+    }
+
+    /// Whether signers with a total weight of `weight` form a quorum.
+    #[must_use]
+    pub const fn is_met(&self, weight: Weight) -> bool {
+        if self.strict {
+            weight > self.weight
+        } else {
+            weight >= self.weight
+        }
+    }
+}
+
+impl Default for QuorumThreshold {
+    fn default() -> Self {
+        Self::at_least(STRONG_THRESHOLD)
+    }
+}
+
+impl From<Weight> for QuorumThreshold {
+    fn from(weight: Weight) -> Self {
+        Self::at_least(weight)
+    }
+}
 /* ====================Committee==================== */
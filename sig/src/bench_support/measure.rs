@@ -0,0 +1,136 @@
+use std::{fs::File, path::Path};
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar};
+use ark_relations::r1cs::ConstraintSystem;
+use folding_schemes::{frontend::FCircuit, Error};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bc::block::{gen_blockchain_with_params, Block},
+    folding::bc::BlockWithCommittee,
+};
+
+/// Number of constraints of one step of `FC` on `external_inputs`, counting
+/// the allocation of the external inputs.
+pub fn measure_circuit_constraints<CF: PrimeField, FC: FCircuit<CF>>(
+    params: FC::Params,
+    external_inputs: FC::ExternalInputs,
+) -> Result<usize, Error> {
+    let f_circuit = FC::new(params)?;
+    let cs = ConstraintSystem::<CF>::new_ref();
+
+    let external_inputs = FC::ExternalInputsVar::new_witness(cs.clone(), || Ok(external_inputs))?;
+    // as long as the length matches, any state is fine
+    let z_i =
+        Vec::<FpVar<CF>>::new_witness(cs.clone(), || Ok(vec![CF::zero(); f_circuit.state_len()]))?;
+    f_circuit.generate_step_constraints(cs.clone(), 0, z_i, external_inputs)?;
+
+    Ok(cs.num_constraints())
+}
+
+/// Constraints of a circuit measured by a previous run, see `measure_bc_circuit_constraints`.
+#[derive(Serialize, Deserialize)]
+pub struct ExperimentConfig {
+    pub bc_circuit_constraints: usize,
+}
+
+/// Measure the constraints of a step of a blockchain circuit, e.g.
+/// `BCCircuitNoMerkle`, and cache them at `config_path`.
+///
+/// The cached value is returned unless `rerun` is set.
+pub fn measure_bc_circuit_constraints<
+    const MAX_COMMITTEE_SIZE: usize,
+    CF: PrimeField,
+    BCCircuit: FCircuit<CF, ExternalInputs = Block<MAX_COMMITTEE_SIZE>>,
+>(
+    config_path: &Path,
+    params: BCCircuit::Params,
+    rerun: bool,
+) -> Result<usize, Error> {
+    cached_step_constraints::<MAX_COMMITTEE_SIZE>(config_path, rerun, |_, block| {
+        measure_circuit_constraints::<CF, BCCircuit>(params, block.clone())
+    })
+}
+
+/// Same as `measure_bc_circuit_constraints`, for circuits that store the hash
+/// of the committee, e.g. `BCCircuitHashedCommittee`.
+pub fn measure_hashed_committee_circuit_constraints<
+    const MAX_COMMITTEE_SIZE: usize,
+    CF: PrimeField,
+    BCCircuit: FCircuit<CF, ExternalInputs = BlockWithCommittee<MAX_COMMITTEE_SIZE>>,
+>(
+    config_path: &Path,
+    params: BCCircuit::Params,
+    rerun: bool,
+) -> Result<usize, Error> {
+    cached_step_constraints::<MAX_COMMITTEE_SIZE>(config_path, rerun, |prev, block| {
+        measure_circuit_constraints::<CF, BCCircuit>(
+            params,
+            BlockWithCommittee {
+                committee: prev.committee.clone(),
+                block: block.clone(),
+            },
+        )
+    })
+}
+
+fn cached_step_constraints<const MAX_COMMITTEE_SIZE: usize>(
+    config_path: &Path,
+    rerun: bool,
+    measure: impl FnOnce(&Block<MAX_COMMITTEE_SIZE>, &Block<MAX_COMMITTEE_SIZE>) -> Result<usize, Error>,
+) -> Result<usize, Error> {
+    // Try to load existing config
+    if !rerun {
+        if let Ok(file) = File::open(config_path) {
+            let config: ExperimentConfig =
+                serde_json::from_reader(file).expect("serde_json should deserialize correctly");
+            println!(
+                "Loaded BCCircuit constraints: {}",
+                config.bc_circuit_constraints
+            );
+            return Ok(config.bc_circuit_constraints);
+        }
+    }
+
+    // Measure constraints on a valid transition, as verifying the signature
+    // of an arbitrary block fails
+    let mut rng = StdRng::from_seed([42; 32]);
+    let bc = gen_blockchain_with_params(2, MAX_COMMITTEE_SIZE, &mut rng);
+    let (prev, block) = (
+        bc.get(0).expect("there are 2 blocks"),
+        bc.get(1).expect("there are 2 blocks"),
+    );
+    let constraints = measure(prev, block)?;
+    println!("Measured BCCircuit constraints: {constraints}");
+
+    // Save to config
+    let config = ExperimentConfig {
+        bc_circuit_constraints: constraints,
+    };
+    let mut file = File::create(config_path)?;
+    serde_json::to_writer(&mut file, &config).expect("serde_json should serialize correctly");
+    Ok(constraints)
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Fr;
+    use ark_r1cs_std::fields::fp::FpVar;
+
+    use super::measure_circuit_constraints;
+    use crate::bench_support::MockCircuit;
+
+    #[test]
+    fn test_measure_mock_circuit() {
+        for target in [10, 1000] {
+            let constraints = measure_circuit_constraints::<Fr, MockCircuit<Fr, Fr, FpVar<Fr>>>(
+                (target, 2),
+                Fr::from(1u64),
+            )
+            .unwrap();
+            assert_eq!(constraints, target);
+        }
+    }
+}
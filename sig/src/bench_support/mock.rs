@@ -0,0 +1,325 @@
+use std::{borrow::Borrow, fmt::Debug, marker::PhantomData};
+
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    eq::EqGadget,
+    fields::{emulated_fp::EmulatedFpVar, fp::FpVar},
+    prelude::Boolean,
+    uint64::UInt64,
+    uint8::UInt8,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, OptimizationGoal, SynthesisError};
+use derivative::Derivative;
+use folding_schemes::{frontend::FCircuit, Error};
+
+use crate::{
+    bc::{
+        block::{Block, QuorumSignature},
+        params::HASH_OUTPUT_SIZE,
+    },
+    bls::SignatureVar,
+    folding::{
+        bc::CommitteeVar,
+        circuit::{block_state_len_for, BCCircuitNoMerkle},
+    },
+    merkle::{constraints::LeveledMerkleForestVar, forest::optimal_forest_params, Config},
+    params::BlsSigConfig,
+};
+
+/// A step circuit that pads every step with `x * y = z` constraints until the
+/// constraint system has `target_constraints` constraints, and returns the
+/// state unchanged.
+///
+/// The external inputs are allocated with `IV`, so that a mock of a real
+/// circuit takes the same inputs without checking them, e.g. `DummyBlockVar`
+/// for the blockchain circuits.
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""), Copy(bound = ""), Debug(bound = ""))]
+pub struct MockCircuit<CF, I, IV> {
+    target_constraints: usize,
+    state_len: usize,
+    #[derivative(Debug = "ignore")]
+    _inputs: PhantomData<fn() -> (CF, I, IV)>,
+}
+
+impl<CF, I, IV> MockCircuit<CF, I, IV> {
+    /// A mock with a state of a single element.
+    #[must_use]
+    pub fn with_target_constraints(target_constraints: usize) -> Self {
+        Self {
+            target_constraints,
+            state_len: 1,
+            _inputs: PhantomData,
+        }
+    }
+
+    #[must_use]
+    pub fn with_state_len(self, state_len: usize) -> Self {
+        Self { state_len, ..self }
+    }
+}
+
+impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize>
+    MockCircuit<CF, Block<MAX_COMMITTEE_SIZE>, DummyBlockVar>
+{
+    /// A mock with the state and external inputs of `BCCircuitNoMerkle`.
+    #[must_use]
+    pub fn no_merkle(target_constraints: usize) -> Self
+    where
+        CF: Absorb,
+    {
+        Self::with_target_constraints(target_constraints).with_state_len(BCCircuitNoMerkle::<
+            CF,
+            MAX_COMMITTEE_SIZE,
+        >::state_len_for(
+            OptimizationGoal::Constraints,
+        ))
+    }
+
+    /// A mock with the state and external inputs of `BCCircuitMerkleForest`
+    /// storing `max_num_blocks` blocks.
+    #[must_use]
+    pub fn merkle_forest(max_num_blocks: usize, target_constraints: usize) -> Self
+    where
+        CF: Absorb,
+    {
+        let (capacity_per_tree, num_tree) = optimal_forest_params(max_num_blocks);
+        Self::with_target_constraints(target_constraints).with_state_len(
+            block_state_len_for::<CF, MAX_COMMITTEE_SIZE>(OptimizationGoal::Constraints)
+                + LeveledMerkleForestVar::<Config<CF>>::num_constraint_var_needed(
+                    capacity_per_tree,
+                    num_tree,
+                ),
+        )
+    }
+}
+
+impl<CF, I, IV> FCircuit<CF> for MockCircuit<CF, I, IV>
+where
+    CF: PrimeField,
+    I: Clone + Debug + Default,
+    IV: Clone + Debug + AllocVar<I, CF>,
+{
+    /// The target number of constraints and the length of the state.
+    type Params = (usize, usize);
+    type ExternalInputs = I;
+    type ExternalInputsVar = IV;
+
+    fn new((target_constraints, state_len): Self::Params) -> Result<Self, Error> {
+        Ok(Self::with_target_constraints(target_constraints).with_state_len(state_len))
+    }
+
+    fn state_len(&self) -> usize {
+        self.state_len
+    }
+
+    fn generate_step_constraints(
+        &self,
+        cs: ConstraintSystemRef<CF>,
+        _: usize,
+        z_i: Vec<FpVar<CF>>,
+        _: Self::ExternalInputsVar,
+    ) -> Result<Vec<FpVar<CF>>, SynthesisError> {
+        let padding = self.target_constraints.saturating_sub(cs.num_constraints());
+
+        // j * (j + 1) = j * (j + 1), so that no constraint is trivially satisfied
+        let mut j = CF::one();
+        for _ in 0..padding {
+            let x = FpVar::new_witness(cs.clone(), || Ok(j))?;
+            let y = FpVar::new_witness(cs.clone(), || Ok(j + CF::one()))?;
+            let z = FpVar::new_witness(cs.clone(), || Ok(j * (j + CF::one())))?;
+            (x * y).enforce_equal(&z)?;
+            j += CF::one();
+        }
+
+        Ok(z_i)
+    }
+}
+
+/// Allocates the signature and the signers of a `QuorumSignature` without
+/// checking the signature is on the curve.
+#[derive(Clone, Debug)]
+struct DummyQuorumSignatureVar;
+
+/// Allocates every field of a `Block` like `BlockVar`, without any of the
+/// checks, so that a `MockCircuit` pays for the external inputs of the
+/// blockchain circuits only.
+#[derive(Clone, Debug)]
+pub struct DummyBlockVar;
+
+impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize>
+    AllocVar<QuorumSignature<MAX_COMMITTEE_SIZE>, CF> for DummyQuorumSignatureVar
+{
+    fn new_variable<T: Borrow<QuorumSignature<MAX_COMMITTEE_SIZE>>>(
+        cs: impl Into<Namespace<CF>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let cs = cs.into();
+
+        let quorum_signature = f();
+
+        let _ =
+            SignatureVar::<BlsSigConfig, EmulatedFpVar<_, _>, _>::new_variable_omit_on_curve_check(
+                cs.clone(),
+                || {
+                    quorum_signature
+                        .as_ref()
+                        .map(|qsig| qsig.borrow().sig)
+                        .map_err(SynthesisError::clone)
+                },
+                mode,
+            )?;
+
+        let _ = Vec::<Boolean<CF>>::new_variable(
+            cs,
+            || {
+                quorum_signature
+                    .as_ref()
+                    .map(|qsig| qsig.borrow().signers)
+                    .map_err(SynthesisError::clone)
+            },
+            mode,
+        )?;
+
+        Ok(Self)
+    }
+}
+
+impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize> AllocVar<Block<MAX_COMMITTEE_SIZE>, CF>
+    for DummyBlockVar
+{
+    fn new_variable<T: Borrow<Block<MAX_COMMITTEE_SIZE>>>(
+        cs: impl Into<Namespace<CF>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let cs = cs.into();
+
+        let block = f();
+
+        let _ = UInt64::new_variable(
+            cs.clone(),
+            || {
+                block
+                    .as_ref()
+                    .map(|block| block.borrow().epoch)
+                    .map_err(SynthesisError::clone)
+            },
+            mode,
+        )?;
+
+        let _ =
+            <[UInt8<CF>; HASH_OUTPUT_SIZE] as AllocVar<[u8; HASH_OUTPUT_SIZE], CF>>::new_variable(
+                cs.clone(),
+                || {
+                    block
+                        .as_ref()
+                        .map(|block| block.borrow().prev_digest)
+                        .map_err(SynthesisError::clone)
+                },
+                mode,
+            )?;
+
+        let _ = DummyQuorumSignatureVar::new_variable(
+            cs.clone(),
+            || {
+                block
+                    .as_ref()
+                    .map(|block| block.borrow().sig.clone())
+                    .map_err(SynthesisError::clone)
+            },
+            mode,
+        )?;
+
+        let _ = CommitteeVar::new_variable(
+            cs.clone(),
+            || {
+                block
+                    .as_ref()
+                    .map(|block| block.borrow().committee.clone())
+                    .map_err(SynthesisError::clone)
+            },
+            mode,
+        )?;
+
+        let _ = Boolean::new_variable(
+            cs,
+            || {
+                block
+                    .as_ref()
+                    .map(|block| block.borrow().epoch == 0)
+                    .map_err(SynthesisError::clone)
+            },
+            mode,
+        )?;
+
+        Ok(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Fr;
+    use ark_ff::Zero;
+    use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar};
+    use folding_schemes::frontend::FCircuit;
+
+    use super::{DummyBlockVar, MockCircuit};
+    use crate::{
+        bc::block::Block,
+        testing::{assert_constraints_within, count_constraints},
+    };
+
+    const MAX_COMMITTEE_SIZE: usize = 4;
+
+    #[test]
+    fn test_mock_circuit_target_constraints() {
+        for target in [1, 100, 1 << 12] {
+            let circuit =
+                MockCircuit::<Fr, Fr, FpVar<Fr>>::with_target_constraints(target).with_state_len(3);
+            let constraints = count_constraints(|cs| {
+                let z_i = Vec::new_witness(cs.clone(), || Ok(vec![Fr::zero(); 3]))?;
+                let x = FpVar::new_witness(cs.clone(), || Ok(Fr::zero()))?;
+                let z_i1 = circuit.generate_step_constraints(cs, 0, z_i, x)?;
+                assert_eq!(z_i1.len(), circuit.state_len());
+                Ok(())
+            });
+            assert_constraints_within(constraints, target, 0);
+        }
+    }
+
+    #[test]
+    fn test_mock_circuit_counts_external_inputs() {
+        type FC = MockCircuit<Fr, Block<MAX_COMMITTEE_SIZE>, DummyBlockVar>;
+
+        let base = count_constraints(|cs| {
+            DummyBlockVar::new_witness(cs, || Ok(Block::<MAX_COMMITTEE_SIZE>::default()))
+        });
+
+        for target in [base + 1, 2 * base] {
+            let circuit = FC::no_merkle(target);
+            let constraints = count_constraints(|cs| {
+                let block = DummyBlockVar::new_witness(cs.clone(), || {
+                    Ok(Block::<MAX_COMMITTEE_SIZE>::default())
+                })?;
+                let z_i =
+                    Vec::new_witness(cs.clone(), || Ok(vec![Fr::zero(); circuit.state_len()]))?;
+                circuit.generate_step_constraints(cs, 0, z_i, block)
+            });
+            assert_constraints_within(constraints, target, 0);
+        }
+
+        // a target below the cost of the external inputs adds nothing
+        let circuit = FC::no_merkle(base / 2);
+        let constraints = count_constraints(|cs| {
+            let block = DummyBlockVar::new_witness(cs.clone(), || {
+                Ok(Block::<MAX_COMMITTEE_SIZE>::default())
+            })?;
+            circuit.generate_step_constraints(cs, 0, vec![], block)
+        });
+        assert_eq!(constraints, base);
+    }
+}
@@ -0,0 +1,22 @@
+//! Support for the experiments in `benches/`, behind the `bench-utils` feature.
+//!
+//! Folding the blockchain circuits takes too long and too much memory for
+//! large committees, so the experiments fold a `MockCircuit` of increasing
+//! size instead, and extrapolate to the number of constraints measured with
+//! `measure_circuit_constraints`:
+//! - `MockCircuit` pads a step with multiplications up to a target number of
+//!   constraints, keeping the state and external inputs of the real circuit,
+//! - `measure_circuit_constraints` and its cached variants count the
+//!   constraints of one step of a circuit,
+//! - `Timer` and `MemRecorder` measure a run, whose results are stored as
+//!   `TimeExperimentResult` or `MemExperimentResult`.
+
+mod measure;
+mod mock;
+mod results;
+mod timer;
+
+pub use measure::*;
+pub use mock::*;
+pub use results::*;
+pub use timer::*;
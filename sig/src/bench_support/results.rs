@@ -0,0 +1,47 @@
+use std::{fs::File, path::Path};
+
+use folding_schemes::Error;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Time taken to fold and decide a circuit of `constraint_count` constraints,
+/// in seconds.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TimeExperimentResult {
+    pub constraint_count: usize,
+    pub nova_param_gen_time: f64,
+    pub nova_init_time: f64,
+    pub snark_param_gen_time: f64,
+    pub folding_step_times: Vec<f64>,
+    pub snark_prove_time: f64,
+    pub snark_verify_time: f64,
+}
+
+/// Peak memory used to fold and decide a circuit of `constraint_count`
+/// constraints, in bytes.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MemExperimentResult {
+    pub constraint_count: usize,
+    pub peak_mem: usize,
+}
+
+/// Load the results saved by `save_results`, or nothing if there are none yet,
+/// so that an interrupted experiment skips what it has already run.
+#[must_use]
+pub fn load_results<T: DeserializeOwned>(path: &Path) -> Vec<T> {
+    File::open(path)
+        .ok()
+        .and_then(|file| serde_json::from_reader(file).ok())
+        .unwrap_or_default()
+}
+
+/// Save `results` as pretty-printed JSON, replacing any existing file.
+///
+/// # Panics
+///
+/// Panics if `results` cannot be serialized.
+pub fn save_results<T: Serialize>(path: &Path, results: &[T]) -> Result<(), Error> {
+    let mut file = File::create(path)?;
+    serde_json::to_writer_pretty(&mut file, results)
+        .expect("serde_json pretty print should succeed");
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use std::time::Instant;
+#[cfg(not(target_env = "msvc"))]
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+#[cfg(not(target_env = "msvc"))]
+use tikv_jemalloc_ctl::{epoch, stats::resident};
+
+/// Wall-clock time of a section, see `Timer::end`.
+pub struct Timer(Instant);
+
+impl Timer {
+    #[must_use]
+    pub fn start() -> Timer {
+        Timer(Instant::now())
+    }
+
+    /// Seconds elapsed since `start`.
+    #[must_use]
+    pub fn end(self) -> f64 {
+        self.0.elapsed().as_secs_f64()
+    }
+}
+
+/// Peak resident memory of the process while a section runs, sampled every
+/// 5ms on a background thread.
+///
+/// The numbers come from jemalloc, so the benchmark has to use it as its
+/// global allocator.
+#[cfg(not(target_env = "msvc"))]
+pub struct MemRecorder {
+    stop_flag: Arc<AtomicBool>,
+    handle: Option<JoinHandle<usize>>,
+}
+
+#[cfg(not(target_env = "msvc"))]
+impl MemRecorder {
+    /// # Panics
+    ///
+    /// Panics if the jemalloc statistics are not available.
+    #[must_use]
+    pub fn start() -> MemRecorder {
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let flag_clone = Arc::clone(&stop_flag);
+
+        let e = epoch::mib().unwrap();
+        let resident_mib = resident::mib().unwrap();
+
+        let handle = thread::spawn(move || {
+            let mut peak = 0;
+            while !flag_clone.load(Ordering::Relaxed) {
+                e.advance().ok(); // Refresh stats
+                if let Ok(mem) = resident_mib.read() {
+                    peak = peak.max(mem);
+                }
+                thread::sleep(Duration::from_millis(5));
+            }
+            peak
+        });
+
+        MemRecorder {
+            stop_flag,
+            handle: Some(handle),
+        }
+    }
+
+    /// Peak resident memory in bytes since `start`.
+    #[must_use]
+    pub fn end(mut self) -> usize {
+        self.stop_flag.store(true, Ordering::Relaxed);
+        self.handle
+            .take()
+            // 0 in case the thread panicked
+            .map_or(0, |handle| handle.join().unwrap_or(0))
+    }
+}
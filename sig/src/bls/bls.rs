@@ -1,5 +1,6 @@
 use core::ops::Mul;
 
+use ark_crypto_primitives::sponge::Absorb;
 use ark_ec::{
     bls12::{self, Bls12Config},
     hashing::{
@@ -10,15 +11,23 @@ use ark_ec::{
     pairing::{Pairing, PairingOutput},
     short_weierstrass::SWCurveConfig,
 };
-use ark_ff::{field_hashers::DefaultFieldHasher, AdditiveGroup, UniformRand};
+use ark_ff::{
+    field_hashers::DefaultFieldHasher, AdditiveGroup, PrimeField, ToConstraintField, UniformRand,
+    Zero,
+};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::cfg_iter;
 use blake2::Blake2s256;
 use derivative::Derivative;
 use derive_more::{AsRef, From, Into};
 use gen_ops::gen_ops_ex;
 use rand::Rng;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 use crate::bls::params::{HashCurveConfig, HashCurveGroup};
+#[cfg(feature = "poseidon-hash-to-field")]
+use crate::{hash::hash_to_field::poseidon::PoseidonFieldHasher, params::BlsSigField};
 
 use super::params::{SecretKeyScalarField, G1, G2};
 
@@ -27,7 +36,9 @@ use super::params::{SecretKeyScalarField, G1, G2};
     Clone(bound = ""),
     Copy(bound = ""),
     Debug(bound = ""),
-    Default(bound = "")
+    Default(bound = ""),
+    PartialEq(bound = ""),
+    Eq(bound = "")
 )]
 pub struct Parameters<SigCurveConfig: Bls12Config> {
     pub g1_generator: G1<SigCurveConfig>,
@@ -96,7 +107,69 @@ gen_ops_ex!(
     where SigCurveConfig: Bls12Config
 );
 
+/// Selects how a message is hashed to the field before being mapped onto G2.
+///
+/// `Blake2s` follows the `expand_message_xmd` construction of RFC 9380 and is
+/// what [`Signature::sign`] and [`Signature::verify`] use. `Poseidon` replaces
+/// it with a Poseidon sponge over the base field of the signature curve, which
+/// is much cheaper to verify in a circuit defined over that field. It is not
+/// part of RFC 9380 and its parameters are not analysed for every field; see
+/// [`PoseidonFieldHasher`] before opting in.
+#[cfg(feature = "poseidon-hash-to-field")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HashStrategy {
+    #[default]
+    Blake2s,
+    Poseidon,
+}
+
+/// The schemes of the IETF BLS signature draft, which differ in how they
+/// prevent rogue key attacks on aggregate signatures.
+///
+/// Each scheme hashes under its own domain separation tag, so a signature is
+/// only valid under the scheme it was produced with. The tags follow the
+/// ciphersuite naming of the draft, except that the hash to field is
+/// `expand_message_xmd` over Blake2s (see [`Parameters::hash_to_g2`]) rather
+/// than SHA-256, so the signatures do not interoperate with the standard
+/// ciphersuites.
+///
+/// [`Signature::sign`] and [`Signature::verify`] predate the schemes and hash
+/// under an empty tag, which is also what the signature gadgets expect.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SigningScheme {
+    /// `_NUL_`: messages are signed as is, so an aggregate signature is only
+    /// secure over distinct messages.
+    #[default]
+    Basic,
+    /// `_AUG_`: the public key of the signer is prepended to the message.
+    MessageAugmentation,
+    /// `_POP_`: messages are signed as is, and signers publish a proof of
+    /// possession of their secret key (see [`Signature::prove_possession`]),
+    /// which allows aggregating signatures over the same message.
+    ProofOfPossession,
+}
+
+impl SigningScheme {
+    /// The domain separation tag used to hash messages.
+    #[must_use]
+    pub const fn dst(self) -> &'static [u8] {
+        match self {
+            Self::Basic => b"BLS_SIG_BLS12G2_XMD:BLAKE2S_SSWU_RO_NUL_",
+            Self::MessageAugmentation => b"BLS_SIG_BLS12G2_XMD:BLAKE2S_SSWU_RO_AUG_",
+            Self::ProofOfPossession => b"BLS_SIG_BLS12G2_XMD:BLAKE2S_SSWU_RO_POP_",
+        }
+    }
+
+    /// The domain separation tag used to hash public keys into proofs of
+    /// possession.
+    const POP_DST: &'static [u8] = b"BLS_POP_BLS12G2_XMD:BLAKE2S_SSWU_RO_POP_";
+}
+
 impl<SigCurveConfig: Bls12Config> Parameters<SigCurveConfig> {
+    /// The parameters with the standard generators of G1 and G2.
+    ///
+    /// They are constants of the curve, so every call returns the same
+    /// parameters, on every machine.
     #[must_use]
     pub fn setup() -> Self {
         Self {
@@ -106,6 +179,55 @@ impl<SigCurveConfig: Bls12Config> Parameters<SigCurveConfig> {
                 .into(),
         }
     }
+
+    /// Parameters with generators of G1 and G2 sampled from `rng`, e.g. to
+    /// check that nothing depends on the standard generators. The same seed
+    /// gives the same parameters.
+    #[must_use]
+    pub fn setup_with_rng<R: Rng>(rng: &mut R) -> Self {
+        // a random point of the prime order subgroup generates it, unless it
+        // is the identity
+        let g1_generator = loop {
+            let g = G1::<SigCurveConfig>::rand(rng);
+            if !g.is_zero() {
+                break g;
+            }
+        };
+        let g2_generator = loop {
+            let g = G2::<SigCurveConfig>::rand(rng);
+            if !g.is_zero() {
+                break g;
+            }
+        };
+        Self {
+            g1_generator,
+            g2_generator,
+        }
+    }
+}
+
+impl<SigCurveConfig: Bls12Config> Parameters<SigCurveConfig>
+where
+    <SigCurveConfig as Bls12Config>::G2Config: WBConfig,
+{
+    /// Hash `msg` onto G2 under the domain separation tag `dst`.
+    ///
+    /// This is the native counterpart of [`crate::hash::hash_to_g2`], and
+    /// [`Signature::sign`] uses it with an empty `dst`.
+    #[must_use]
+    pub fn hash_to_g2(dst: &[u8], msg: &[u8]) -> G2<SigCurveConfig> {
+        // safety
+        type FieldHasher = DefaultFieldHasher<Blake2s256, 128>;
+        type CurveMap<SigCurveConfig> = WBMap<HashCurveConfig<SigCurveConfig>>;
+        let hasher: MapToCurveBasedHasher<
+            HashCurveGroup<SigCurveConfig>,
+            FieldHasher,
+            CurveMap<SigCurveConfig>,
+        > = MapToCurveBasedHasher::new(dst).expect("BLS12 curve supports hash to curve");
+        let hashed_message = hasher.hash(msg).unwrap();
+
+        hashed_message.into()
+    }
 }
 
 impl<SigCurveConfig: Bls12Config> PublicKey<SigCurveConfig> {
@@ -117,6 +239,66 @@ impl<SigCurveConfig: Bls12Config> PublicKey<SigCurveConfig> {
         let pub_key = params.g1_generator.mul(secret_key.secret_key);
         pub_key.into()
     }
+
+    /// The sum of `public_keys`, against which their aggregate signature is
+    /// verified. The keys are summed on several threads with the `parallel`
+    /// feature.
+    #[must_use]
+    pub fn aggregate(public_keys: &[Self]) -> Self {
+        cfg_iter!(public_keys)
+            .map(|pk| pk.pub_key)
+            .sum::<G1<SigCurveConfig>>()
+            .into()
+    }
+}
+
+impl<SigCurveConfig: Bls12Config> PublicKey<SigCurveConfig> {
+    /// The compressed encoding of the key, which is what message augmentation
+    /// prepends and what a proof of possession signs.
+    ///
+    /// The gadgets compute the same bytes with `compress_g1`.
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.serialize_compressed(&mut bytes)
+            .expect("serializing into a Vec cannot fail");
+        bytes
+    }
+}
+
+/// Absorbs the compressed encoding of the key, i.e. its `x` coordinate and
+/// flags. As a field element, the encoding is packed into as many bytes as fit
+/// in `F`.
+impl<SigCurveConfig: Bls12Config> Absorb for PublicKey<SigCurveConfig> {
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.pub_key
+            .serialize_compressed(dest)
+            .expect("serializing into a Vec cannot fail");
+    }
+
+    fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
+        absorb_bytes_as_field_elements(&self.to_sponge_bytes_as_vec(), dest);
+    }
+}
+
+/// Same as for `PublicKey`, with the compressed encoding of the point on G2.
+impl<SigCurveConfig: Bls12Config> Absorb for Signature<SigCurveConfig> {
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.signature
+            .serialize_compressed(dest)
+            .expect("serializing into a Vec cannot fail");
+    }
+
+    fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
+        absorb_bytes_as_field_elements(&self.to_sponge_bytes_as_vec(), dest);
+    }
+}
+
+fn absorb_bytes_as_field_elements<F: PrimeField>(bytes: &[u8], dest: &mut Vec<F>) {
+    dest.extend(
+        bytes
+            .to_field_elements()
+            .expect("bytes can always be packed into field elements"),
+    );
 }
 
 impl<SigCurveConfig: Bls12Config> SecretKey<SigCurveConfig> {
@@ -131,19 +313,32 @@ where
     <SigCurveConfig as Bls12Config>::G2Config: WBConfig,
 {
     fn hash_to_curve(message: &[u8]) -> G2<SigCurveConfig> {
-        // safety
-        type FieldHasher = DefaultFieldHasher<Blake2s256, 128>;
-        type CurveMap<SigCurveConfig> = WBMap<HashCurveConfig<SigCurveConfig>>;
-        let hasher: MapToCurveBasedHasher<
-            HashCurveGroup<SigCurveConfig>,
-            FieldHasher,
-            CurveMap<SigCurveConfig>,
-        > = MapToCurveBasedHasher::new(&[]).expect("BLS12 curve supports hash to curve");
-        let hashed_message = hasher.hash(message).unwrap();
+        Parameters::<SigCurveConfig>::hash_to_g2(&[], message)
+    }
 
-        hashed_message.into()
+    #[cfg(feature = "poseidon-hash-to-field")]
+    fn hash_to_curve_with_strategy(message: &[u8], strategy: HashStrategy) -> G2<SigCurveConfig> {
+        match strategy {
+            HashStrategy::Blake2s => Self::hash_to_curve(message),
+            HashStrategy::Poseidon => {
+                type FieldHasher<SigCurveConfig> =
+                    PoseidonFieldHasher<BlsSigField<SigCurveConfig>, 128>;
+                type CurveMap<SigCurveConfig> = WBMap<HashCurveConfig<SigCurveConfig>>;
+                let hasher: MapToCurveBasedHasher<
+                    HashCurveGroup<SigCurveConfig>,
+                    FieldHasher<SigCurveConfig>,
+                    CurveMap<SigCurveConfig>,
+                > = MapToCurveBasedHasher::new(&[]).expect("BLS12 curve supports hash to curve");
+                let hashed_message = hasher.hash(message).unwrap();
+
+                hashed_message.into()
+            }
+        }
     }
 
+    /// Sign `message` as is, i.e. under [`SigningScheme::Basic`], but with an
+    /// empty domain separation tag instead of [`SigningScheme::dst`], which is
+    /// what the signature gadgets expect.
     #[must_use]
     pub fn sign(
         message: &[u8],
@@ -155,6 +350,19 @@ where
         signature.into()
     }
 
+    /// Same as [`Self::sign`], but hashes `message` with the given `strategy`.
+    #[cfg(feature = "poseidon-hash-to-field")]
+    #[must_use]
+    pub fn sign_with_strategy(
+        message: &[u8],
+        secret_key: &SecretKey<SigCurveConfig>,
+        strategy: HashStrategy,
+    ) -> Self {
+        let hashed_message = Self::hash_to_curve_with_strategy(message, strategy);
+        let signature = hashed_message.mul(secret_key.secret_key);
+        signature.into()
+    }
+
     #[must_use]
     pub fn aggregate_sign(
         message: &[u8],
@@ -179,10 +387,201 @@ where
         Some(Signature::sign(message, &sk, params))
         */
 
-        let mut sigs = secret_keys.iter().map(|sk| Self::sign(message, sk, params));
-        let first_sig = sigs.next()?;
+        if secret_keys.is_empty() {
+            return None;
+        }
+
+        let signature = cfg_iter!(secret_keys)
+            .map(|sk| Self::sign(message, sk, params).signature)
+            .sum::<G2<SigCurveConfig>>();
+        Some(signature.into())
+    }
+
+    /// Sign `message` under `scheme`.
+    #[must_use]
+    pub fn sign_with_scheme(
+        message: &[u8],
+        secret_key: &SecretKey<SigCurveConfig>,
+        params: &Parameters<SigCurveConfig>,
+        scheme: SigningScheme,
+    ) -> Self {
+        let public_key = PublicKey::new(secret_key, params);
+        let hashed_message = Self::hash_to_curve_with_scheme(message, &public_key, scheme);
+        hashed_message.mul(secret_key.secret_key).into()
+    }
+
+    /// Same as [`Self::aggregate_sign`], but each key signs under `scheme`.
+    #[must_use]
+    pub fn aggregate_sign_with_scheme(
+        message: &[u8],
+        secret_keys: &[SecretKey<SigCurveConfig>],
+        params: &Parameters<SigCurveConfig>,
+        scheme: SigningScheme,
+    ) -> Option<Self> {
+        secret_keys
+            .iter()
+            .map(|sk| Self::sign_with_scheme(message, sk, params, scheme))
+            .reduce(|acc, new_sig| acc + new_sig)
+    }
 
-        Some(sigs.fold(first_sig, |acc, new_sig| acc + new_sig))
+    /// Verify a signature produced by [`Self::sign_with_scheme`].
+    #[must_use]
+    pub fn verify_with_scheme(
+        message: &[u8],
+        signature: &Self,
+        public_key: &PublicKey<SigCurveConfig>,
+        params: &Parameters<SigCurveConfig>,
+        scheme: SigningScheme,
+    ) -> bool {
+        let hashed_message = Self::hash_to_curve_with_scheme(message, public_key, scheme);
+        Self::verify_hashed(hashed_message, signature, public_key, params)
+    }
+
+    /// Verify a signature produced by [`Self::aggregate_sign_with_scheme`].
+    ///
+    /// Returns `None` if `public_keys` is empty. As all keys sign the same
+    /// message, the aggregate is rejected under [`SigningScheme::Basic`] unless
+    /// there is a single key. Under [`SigningScheme::ProofOfPossession`], the
+    /// caller should have checked the proofs of possession of all keys.
+    #[must_use]
+    pub fn aggregate_verify_with_scheme(
+        message: &[u8],
+        aggregate_signature: &Self,
+        public_keys: &[PublicKey<SigCurveConfig>],
+        params: &Parameters<SigCurveConfig>,
+        scheme: SigningScheme,
+    ) -> Option<bool> {
+        match (scheme, public_keys) {
+            (_, []) => None,
+            (SigningScheme::Basic, [public_key]) => Some(Self::verify_with_scheme(
+                message,
+                aggregate_signature,
+                public_key,
+                params,
+                scheme,
+            )),
+            (SigningScheme::Basic, _) => Some(false),
+            (SigningScheme::MessageAugmentation, _) => Self::aggregate_verify_augmented(
+                &vec![message; public_keys.len()],
+                aggregate_signature,
+                public_keys,
+                params,
+            ),
+            (SigningScheme::ProofOfPossession, _) => {
+                let pk = public_keys
+                    .iter()
+                    .copied()
+                    .reduce(|acc, new_pk| acc + new_pk)?;
+                Some(Self::verify_with_scheme(
+                    message,
+                    aggregate_signature,
+                    &pk,
+                    params,
+                    scheme,
+                ))
+            }
+        }
+    }
+
+    /// Aggregate the signatures of `secret_keys[i]` over `messages[i]` under
+    /// [`SigningScheme::MessageAugmentation`].
+    ///
+    /// Returns `None` if there is no key or the lengths mismatch.
+    #[must_use]
+    pub fn aggregate_sign_augmented(
+        messages: &[&[u8]],
+        secret_keys: &[SecretKey<SigCurveConfig>],
+        params: &Parameters<SigCurveConfig>,
+    ) -> Option<Self> {
+        if messages.len() != secret_keys.len() {
+            return None;
+        }
+
+        messages
+            .iter()
+            .zip(secret_keys)
+            .map(|(message, sk)| {
+                Self::sign_with_scheme(message, sk, params, SigningScheme::MessageAugmentation)
+            })
+            .reduce(|acc, new_sig| acc + new_sig)
+    }
+
+    /// Verify a signature produced by [`Self::aggregate_sign_augmented`].
+    ///
+    /// As every message is prefixed by the key that signs it, the messages do
+    /// not need to be distinct and no proof of possession is needed. Returns
+    /// `None` if there is no key or the lengths mismatch.
+    #[must_use]
+    pub fn aggregate_verify_augmented(
+        messages: &[&[u8]],
+        aggregate_signature: &Self,
+        public_keys: &[PublicKey<SigCurveConfig>],
+        params: &Parameters<SigCurveConfig>,
+    ) -> Option<bool> {
+        if public_keys.is_empty() || messages.len() != public_keys.len() {
+            return None;
+        }
+
+        // e(g1, sig) == prod_i e(pk_i, H(pk_i || msg_i))
+        let (g1s, g2s): (Vec<_>, Vec<_>) = messages
+            .iter()
+            .zip(public_keys)
+            .map(|(message, pk)| {
+                let hashed_message = Self::hash_to_curve_with_scheme(
+                    message,
+                    pk,
+                    SigningScheme::MessageAugmentation,
+                );
+                (pk.pub_key, hashed_message)
+            })
+            .chain([(-params.g1_generator, aggregate_signature.signature)])
+            .unzip();
+        Some(bls12::Bls12::<SigCurveConfig>::multi_pairing(g1s, g2s) == PairingOutput::ZERO)
+    }
+
+    /// Prove the possession of `secret_key` for [`SigningScheme::ProofOfPossession`]
+    /// by signing its public key under a dedicated tag.
+    #[must_use]
+    pub fn prove_possession(
+        secret_key: &SecretKey<SigCurveConfig>,
+        params: &Parameters<SigCurveConfig>,
+    ) -> Self {
+        let public_key = PublicKey::new(secret_key, params);
+        let hashed_key = Parameters::<SigCurveConfig>::hash_to_g2(
+            SigningScheme::POP_DST,
+            &public_key.to_bytes(),
+        );
+        hashed_key.mul(secret_key.secret_key).into()
+    }
+
+    /// Verify a proof produced by [`Self::prove_possession`].
+    #[must_use]
+    pub fn verify_possession(
+        proof: &Self,
+        public_key: &PublicKey<SigCurveConfig>,
+        params: &Parameters<SigCurveConfig>,
+    ) -> bool {
+        let hashed_key = Parameters::<SigCurveConfig>::hash_to_g2(
+            SigningScheme::POP_DST,
+            &public_key.to_bytes(),
+        );
+        Self::verify_hashed(hashed_key, proof, public_key, params)
+    }
+
+    fn hash_to_curve_with_scheme(
+        message: &[u8],
+        public_key: &PublicKey<SigCurveConfig>,
+        scheme: SigningScheme,
+    ) -> G2<SigCurveConfig> {
+        match scheme {
+            SigningScheme::Basic | SigningScheme::ProofOfPossession => {
+                Parameters::<SigCurveConfig>::hash_to_g2(scheme.dst(), message)
+            }
+            SigningScheme::MessageAugmentation => Parameters::<SigCurveConfig>::hash_to_g2(
+                scheme.dst(),
+                &[public_key.to_bytes(), message.to_vec()].concat(),
+            ),
+        }
     }
 
     #[must_use]
@@ -203,6 +602,8 @@ where
         pairing_1 == pairing_2
     }
 
+    /// Verify a signature produced by [`Self::sign`], i.e. under
+    /// [`SigningScheme::Basic`] with an empty domain separation tag.
     #[must_use]
     pub fn verify(
         message: &[u8],
@@ -211,7 +612,29 @@ where
         params: &Parameters<SigCurveConfig>,
     ) -> bool {
         let hashed_message = Self::hash_to_curve(message);
+        Self::verify_hashed(hashed_message, signature, public_key, params)
+    }
 
+    /// Same as [`Self::verify`], but hashes `message` with the given `strategy`.
+    #[cfg(feature = "poseidon-hash-to-field")]
+    #[must_use]
+    pub fn verify_with_strategy(
+        message: &[u8],
+        signature: &Self,
+        public_key: &PublicKey<SigCurveConfig>,
+        params: &Parameters<SigCurveConfig>,
+        strategy: HashStrategy,
+    ) -> bool {
+        let hashed_message = Self::hash_to_curve_with_strategy(message, strategy);
+        Self::verify_hashed(hashed_message, signature, public_key, params)
+    }
+
+    fn verify_hashed(
+        hashed_message: G2<SigCurveConfig>,
+        signature: &Self,
+        public_key: &PublicKey<SigCurveConfig>,
+        params: &Parameters<SigCurveConfig>,
+    ) -> bool {
         // an optimized way to check pairing equation: e(g1, sig) == e(pk, H(msg))
         //
         // e'(g1, sig)^x == e'(pk, H(msg))^x (do miller loop for two sides without final exponentiation)
@@ -236,14 +659,7 @@ where
             return None;
         }
 
-        let public_key_0 = *public_keys.first()?;
-        let pk = public_keys
-            .iter()
-            .skip(1)
-            .fold(public_key_0, |acc, new_pk| PublicKey {
-                pub_key: acc.pub_key + new_pk.pub_key,
-            });
-
+        let pk = PublicKey::aggregate(public_keys);
         Some(Self::verify_slow(message, aggregate_signature, &pk, params))
     }
 }
@@ -278,10 +694,299 @@ mod test {
         ));
     }
 
+    #[test]
+    fn check_absorb() {
+        use ark_crypto_primitives::sponge::{poseidon::PoseidonSponge, CryptographicSponge};
+
+        type F = ark_bls12_381::Fr;
+
+        let (_, _, _, pk, sig) = get_bls_instance::<ark_bls12_381::Config>();
+        let (_, _, _, other_pk, _) = get_bls_instance::<ark_bls12_381::Config>();
+        let squeeze = |pk: &PublicKey<ark_bls12_381::Config>| {
+            let mut sponge = PoseidonSponge::<F>::new(&crate::params::poseidon_canonical_config());
+            sponge.absorb(pk);
+            sponge.absorb(&sig);
+            sponge.squeeze_field_elements::<F>(1)[0]
+        };
+
+        assert_eq!(squeeze(&pk), squeeze(&pk));
+        assert_ne!(squeeze(&pk), squeeze(&other_pk));
+
+        // a compressed point on G1 takes 48 bytes, which fit in 2 elements of
+        // a 255-bit field
+        assert_eq!(pk.to_sponge_bytes_as_vec().len(), 48);
+        assert_eq!(pk.to_sponge_field_elements_as_vec::<F>().len(), 2);
+    }
+
+    #[test]
+    fn check_setup_is_deterministic() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        assert_eq!(
+            Parameters::<ark_bls12_381::Config>::setup(),
+            Parameters::<ark_bls12_381::Config>::setup()
+        );
+
+        let seeded = |seed| {
+            Parameters::<ark_bls12_381::Config>::setup_with_rng(&mut StdRng::from_seed([seed; 32]))
+        };
+        assert_eq!(seeded(42), seeded(42));
+        assert_ne!(seeded(42), seeded(43));
+        assert_ne!(seeded(42), Parameters::setup());
+
+        // signatures do not depend on the generators
+        let params = seeded(42);
+        let sk = SecretKey::new(&mut StdRng::from_seed([1; 32]));
+        let pk = PublicKey::new(&sk, &params);
+        let sig = Signature::sign(b"message", &sk, &params);
+        assert!(Signature::verify(b"message", &sig, &pk, &params));
+        assert!(!Signature::verify(b"message", &sig, &pk, &seeded(43)));
+    }
+
     #[test]
     fn check_aggregate_signature() {
         let (msg, params, _, public_keys, sig) =
             get_aggregate_bls_instance::<ark_bls12_381::Config>();
         assert!(Signature::aggregate_verify(msg.as_bytes(), &sig, &public_keys, &params).unwrap());
     }
+
+    type Config = ark_bls12_381::Config;
+    const SCHEMES: [SigningScheme; 3] = [
+        SigningScheme::Basic,
+        SigningScheme::MessageAugmentation,
+        SigningScheme::ProofOfPossession,
+    ];
+
+    /// A fixed key and message, so that the expected signatures below can be
+    /// recomputed from their definition in the draft.
+    fn fixed_instance() -> (
+        &'static [u8],
+        Parameters<Config>,
+        SecretKey<Config>,
+        PublicKey<Config>,
+    ) {
+        let params = Parameters::setup();
+        let sk = SecretKey {
+            secret_key: SecretKeyScalarField::<Config>::from(0x6d69_6du64),
+        };
+        let pk = PublicKey::new(&sk, &params);
+        (b"sample message", params, sk, pk)
+    }
+
+    #[test]
+    fn check_signature_with_scheme_known_answers() {
+        // computed with an independent implementation of the draft and of
+        // expand_message_xmd over Blake2s, in the compressed encoding
+        const PUBLIC_KEY: &str = "a4a2b40818a6d11b0b6377e74329840b6379c1c08126383ee53a9c5d7cb0098899df9bf3fc712bbbf5faafc60f7c3fb9";
+        const BASIC: &str = "b630df7a6d2390d520893356096ded8d2c92bf2b636a2fb10e925a254d539a1f990ad407cd29ba8a8cc7882b58685e9317ef53a825f4c8aa09921c3794a3865c5e5c6676ced4c0581cf03172e4713f93ba660fc050b0af772dd624d93031a412";
+        const AUGMENTED: &str = "b9b6e39423319a1f16bc2bb82738a886be1718a869413ede43fb01cde8e6baab6855e09e60c6b2d831942408d78c69a80302e5655e5f7948c8bdc2d17acd251566aaabf8a25423f42a3957e3888d6370ca53aebb45cc9f3bf5ea48b0d56c2e68";
+
+        fn to_hex(value: &impl CanonicalSerialize) -> String {
+            let mut bytes = Vec::new();
+            value.serialize_compressed(&mut bytes).unwrap();
+            bytes.iter().map(|b| format!("{b:02x}")).collect()
+        }
+
+        let (msg, params, sk, pk) = fixed_instance();
+        assert_eq!(to_hex(&pk), PUBLIC_KEY);
+
+        let basic = Signature::sign_with_scheme(msg, &sk, &params, SigningScheme::Basic);
+        assert_eq!(to_hex(&basic), BASIC);
+
+        let aug =
+            Signature::sign_with_scheme(msg, &sk, &params, SigningScheme::MessageAugmentation);
+        assert_eq!(to_hex(&aug), AUGMENTED);
+    }
+
+    #[test]
+    fn check_signature_with_scheme() {
+        let (msg, params, sk, pk) = fixed_instance();
+
+        for scheme in SCHEMES {
+            let sig = Signature::sign_with_scheme(msg, &sk, &params, scheme);
+            for other in SCHEMES {
+                assert_eq!(
+                    Signature::verify_with_scheme(msg, &sig, &pk, &params, other),
+                    scheme == other
+                );
+            }
+            assert!(!Signature::verify_with_scheme(
+                &[msg, &[1]].concat(),
+                &sig,
+                &pk,
+                &params,
+                scheme
+            ));
+            // the legacy signature uses an empty tag
+            assert!(!Signature::verify(msg, &sig, &pk, &params));
+        }
+    }
+
+    #[test]
+    fn check_aggregate_signature_with_scheme() {
+        let mut rng = rand::thread_rng();
+        let msg = b"sample message";
+        let params = Parameters::<Config>::setup();
+        let secret_keys: Vec<_> = (0..5).map(|_| SecretKey::new(&mut rng)).collect();
+        let public_keys: Vec<_> = secret_keys
+            .iter()
+            .map(|sk| PublicKey::new(sk, &params))
+            .collect();
+
+        for scheme in SCHEMES {
+            let sig =
+                Signature::aggregate_sign_with_scheme(msg, &secret_keys, &params, scheme).unwrap();
+            let valid =
+                Signature::aggregate_verify_with_scheme(msg, &sig, &public_keys, &params, scheme)
+                    .unwrap();
+            // basic aggregates are only secure over distinct messages
+            assert_eq!(valid, scheme != SigningScheme::Basic);
+
+            let single =
+                Signature::aggregate_sign_with_scheme(msg, &secret_keys[..1], &params, scheme)
+                    .unwrap();
+            assert!(Signature::aggregate_verify_with_scheme(
+                msg,
+                &single,
+                &public_keys[..1],
+                &params,
+                scheme
+            )
+            .unwrap());
+
+            assert!(!Signature::aggregate_verify_with_scheme(
+                msg,
+                &sig,
+                &public_keys[1..],
+                &params,
+                scheme
+            )
+            .unwrap_or(false));
+            assert!(
+                Signature::aggregate_verify_with_scheme(msg, &sig, &[], &params, scheme).is_none()
+            );
+        }
+    }
+
+    #[test]
+    fn check_proof_of_possession() {
+        let mut rng = rand::thread_rng();
+        let params = Parameters::<Config>::setup();
+        let victim = PublicKey::new(&SecretKey::new(&mut rng), &params);
+        let attacker_sk = SecretKey::new(&mut rng);
+        let attacker = PublicKey::new(&attacker_sk, &params);
+
+        let proof = Signature::prove_possession(&attacker_sk, &params);
+        assert!(Signature::verify_possession(&proof, &attacker, &params));
+        assert!(!Signature::verify_possession(&proof, &victim, &params));
+
+        // a rogue key `attacker - victim` lets the attacker forge an aggregate
+        // over the same message, which is why its proof of possession is needed
+        let rogue = PublicKey::from(attacker.pub_key - victim.pub_key);
+        let msg = b"sample message";
+        let forged = Signature::sign_with_scheme(
+            msg,
+            &attacker_sk,
+            &params,
+            SigningScheme::ProofOfPossession,
+        );
+        assert!(Signature::aggregate_verify_with_scheme(
+            msg,
+            &forged,
+            &[victim, rogue],
+            &params,
+            SigningScheme::ProofOfPossession
+        )
+        .unwrap());
+        assert!(!Signature::verify_possession(&proof, &rogue, &params));
+        assert!(!Signature::aggregate_verify_with_scheme(
+            msg,
+            &forged,
+            &[victim, rogue],
+            &params,
+            SigningScheme::MessageAugmentation
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn check_aggregate_signature_augmented() {
+        let mut rng = rand::thread_rng();
+        let params = Parameters::<Config>::setup();
+        let secret_keys: Vec<_> = (0..4).map(|_| SecretKey::new(&mut rng)).collect();
+        let public_keys: Vec<_> = secret_keys
+            .iter()
+            .map(|sk| PublicKey::new(sk, &params))
+            .collect();
+        // the same message may be signed by several keys
+        let messages: Vec<&[u8]> = vec![b"message 0", b"message 1", b"message 1", b"message 3"];
+
+        let sig = Signature::aggregate_sign_augmented(&messages, &secret_keys, &params).unwrap();
+        assert!(
+            Signature::aggregate_verify_augmented(&messages, &sig, &public_keys, &params).unwrap()
+        );
+
+        let mut reordered = messages.clone();
+        reordered.swap(0, 3);
+        assert!(
+            !Signature::aggregate_verify_augmented(&reordered, &sig, &public_keys, &params)
+                .unwrap()
+        );
+        assert!(
+            Signature::aggregate_verify_augmented(&messages[1..], &sig, &public_keys, &params)
+                .is_none()
+        );
+
+        // a signer that signs under the pk prefix of another signer is rejected
+        let mut swapped_prefix = Vec::new();
+        public_keys[1]
+            .serialize_compressed(&mut swapped_prefix)
+            .unwrap();
+        swapped_prefix.extend_from_slice(messages[0]);
+        let swapped = Signature::from(
+            Parameters::<Config>::hash_to_g2(
+                SigningScheme::MessageAugmentation.dst(),
+                &swapped_prefix,
+            ) * secret_keys[0].secret_key,
+        );
+        let sig = secret_keys[1..]
+            .iter()
+            .zip(&messages[1..])
+            .map(|(sk, msg)| {
+                Signature::sign_with_scheme(msg, sk, &params, SigningScheme::MessageAugmentation)
+            })
+            .fold(swapped, |acc, sig| acc + sig);
+        assert!(
+            !Signature::aggregate_verify_augmented(&messages, &sig, &public_keys, &params).unwrap()
+        );
+    }
+
+    #[cfg(feature = "poseidon-hash-to-field")]
+    #[test]
+    fn check_signature_with_strategy() {
+        let (msg, params, sk, pk, sig) = get_bls_instance::<ark_bls12_381::Config>();
+        let poseidon_sig =
+            Signature::sign_with_strategy(msg.as_bytes(), &sk, HashStrategy::Poseidon);
+
+        assert!(Signature::verify_with_strategy(
+            msg.as_bytes(),
+            &poseidon_sig,
+            &pk,
+            &params,
+            HashStrategy::Poseidon
+        ));
+        assert!(Signature::verify_with_strategy(
+            msg.as_bytes(),
+            &sig,
+            &pk,
+            &params,
+            HashStrategy::Blake2s
+        ));
+        assert!(!Signature::verify(
+            msg.as_bytes(),
+            &poseidon_sig,
+            &pk,
+            &params
+        ));
+    }
 }
@@ -2,7 +2,8 @@ mod bls;
 use ark_ec::{bls12::Bls12Config, hashing::curve_maps::wb::WBConfig};
 pub use bls::*;
 
-mod params;
+pub(crate) mod params;
+pub use params::GTVar;
 
 mod r1cs;
 pub use r1cs::*;
@@ -10,6 +11,9 @@ pub use r1cs::*;
 mod circuit;
 pub use circuit::*;
 
+mod msm;
+pub use msm::*;
+
 use rand::thread_rng;
 
 #[must_use]
@@ -46,13 +50,32 @@ pub fn get_aggregate_bls_instance<SigCurveConfig: Bls12Config>() -> (
 where
     <SigCurveConfig as Bls12Config>::G2Config: WBConfig,
 {
-    const N: usize = 1000;
+    get_aggregate_bls_instance_n(1000)
+}
 
+/// Same as [`get_aggregate_bls_instance`], but the message is signed by `n` signers.
+///
+/// # Panics
+///
+/// Panics if `n` is zero.
+#[must_use]
+pub fn get_aggregate_bls_instance_n<SigCurveConfig: Bls12Config>(
+    n: usize,
+) -> (
+    &'static str,
+    Parameters<SigCurveConfig>,
+    Vec<SecretKey<SigCurveConfig>>,
+    Vec<PublicKey<SigCurveConfig>>,
+    Signature<SigCurveConfig>,
+)
+where
+    <SigCurveConfig as Bls12Config>::G2Config: WBConfig,
+{
     let msg = "Hello World";
     let mut rng = thread_rng();
 
     let params = Parameters::setup();
-    let secret_keys: Vec<_> = (0..N).map(|_| SecretKey::new(&mut rng)).collect();
+    let secret_keys: Vec<_> = (0..n).map(|_| SecretKey::new(&mut rng)).collect();
     let public_keys: Vec<_> = secret_keys
         .iter()
         .map(|sk| PublicKey::new(sk, &params))
@@ -0,0 +1,299 @@
+//! Multi-scalar multiplication in G2, to aggregate signatures in a circuit.
+
+use ark_ec::{bls12::Bls12Config, short_weierstrass::SWCurveConfig};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    fields::{FieldOpsBounds, FieldVar},
+    groups::{bls12::G2Var, curves::short_weierstrass::ProjectiveVar, CurveVar},
+    prelude::Boolean,
+    select::CondSelectGadget,
+};
+use ark_relations::r1cs::SynthesisError;
+
+use crate::params::BlsSigField;
+
+/// Number of scalar bits processed per window by [`g2_msm`].
+///
+/// Every base needs a table of its `2^MSM_WINDOW_SIZE` first multiples, and
+/// one addition and one lookup in the table per window, so 4 bits balances
+/// the size of the tables against the number of windows for scalars of about
+/// 256 bits.
+pub const MSM_WINDOW_SIZE: usize = 4;
+
+/// `Σ scalars[i] * bases[i]`, where every scalar is given by its bits in
+/// little-endian order, as in `CurveVar::scalar_mul_le`. Scalars can have
+/// different lengths.
+///
+/// Instead of multiplying each base on its own, the bases share the doublings
+/// (Straus' method with fixed windows): the multiples `0, P, ..., (2^w - 1) P`
+/// of every base `P` are computed once, and for every window of `w` bits, from
+/// the most significant one, the sum is doubled `w` times and the multiple
+/// selected by the window of each scalar is added. For `n` bases and `b`-bit
+/// scalars, this takes about `n * (2^w + b / w)` additions and `b` doublings,
+/// instead of `n * b` of each.
+pub fn g2_msm<SigCurveConfig, FV, CF>(
+    bases: &[G2Var<SigCurveConfig, FV, CF>],
+    scalars: &[Vec<Boolean<CF>>],
+) -> Result<G2Var<SigCurveConfig, FV, CF>, SynthesisError>
+where
+    SigCurveConfig: Bls12Config,
+    FV: FieldVar<BlsSigField<SigCurveConfig>, CF>,
+    CF: PrimeField,
+    for<'a> &'a FV: FieldOpsBounds<'a, BlsSigField<SigCurveConfig>, FV>,
+{
+    windowed_msm(bases, scalars)
+}
+
+/// `Σ points[i]`, i.e. [`g2_msm`] where every scalar is one, as when
+/// aggregating signatures of the same message.
+///
+/// This needs no window: `n - 1` additions is already the minimum.
+pub fn g2_sum<SigCurveConfig, FV, CF>(
+    points: &[G2Var<SigCurveConfig, FV, CF>],
+) -> Result<G2Var<SigCurveConfig, FV, CF>, SynthesisError>
+where
+    SigCurveConfig: Bls12Config,
+    FV: FieldVar<BlsSigField<SigCurveConfig>, CF>,
+    CF: PrimeField,
+    for<'a> &'a FV: FieldOpsBounds<'a, BlsSigField<SigCurveConfig>, FV>,
+{
+    Ok(points
+        .iter()
+        .cloned()
+        .reduce(|acc, point| acc + point)
+        .unwrap_or_else(G2Var::zero))
+}
+
+fn windowed_msm<P, F, CF>(
+    bases: &[ProjectiveVar<P, F, CF>],
+    scalars: &[Vec<Boolean<CF>>],
+) -> Result<ProjectiveVar<P, F, CF>, SynthesisError>
+where
+    P: SWCurveConfig,
+    F: FieldVar<P::BaseField, CF>,
+    CF: PrimeField,
+    for<'a> &'a F: FieldOpsBounds<'a, P::BaseField, F>,
+{
+    if bases.len() != scalars.len() {
+        tracing::error!(
+            num_bases = bases.len(),
+            num_scalars = scalars.len(),
+            "the number of bases and scalars differ"
+        );
+        return Err(SynthesisError::Unsatisfiable);
+    }
+
+    // tables[i][k] = k * bases[i]
+    let tables = bases
+        .iter()
+        .map(|base| {
+            let mut table = vec![ProjectiveVar::zero(), base.clone()];
+            for k in 2..1 << MSM_WINDOW_SIZE {
+                let multiple = if k % 2 == 0 {
+                    table[k / 2].double()?
+                } else {
+                    &table[k - 1] + base
+                };
+                table.push(multiple);
+            }
+            Ok(table)
+        })
+        .collect::<Result<Vec<_>, SynthesisError>>()?;
+
+    let num_windows = scalars
+        .iter()
+        .map(Vec::len)
+        .max()
+        .unwrap_or(0)
+        .div_ceil(MSM_WINDOW_SIZE);
+
+    let mut sum = ProjectiveVar::zero();
+    for window in (0..num_windows).rev() {
+        for _ in 0..MSM_WINDOW_SIZE {
+            sum.double_in_place()?;
+        }
+
+        for (table, scalar) in tables.iter().zip(scalars) {
+            // the multiplexer takes the bits of the window in big-endian order
+            let position: Vec<_> = (0..MSM_WINDOW_SIZE)
+                .rev()
+                .map(|j| {
+                    scalar
+                        .get(window * MSM_WINDOW_SIZE + j)
+                        .cloned()
+                        .unwrap_or(Boolean::FALSE)
+                })
+                .collect();
+            sum += ProjectiveVar::conditionally_select_power_of_two_vector(&position, table)?;
+        }
+    }
+
+    Ok(sum)
+}
+
+#[cfg(test)]
+mod test {
+    use ark_ec::CurveGroup;
+    use ark_ff::{AdditiveGroup, BigInteger, PrimeField, UniformRand, Zero};
+    use ark_r1cs_std::{
+        alloc::AllocVar,
+        fields::fp::FpVar,
+        groups::{bls12::G2Var, CurveVar},
+        prelude::Boolean,
+        R1CSVar,
+    };
+    use ark_relations::r1cs::{ConstraintSystem, SynthesisError};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::{g2_msm, g2_sum};
+    use crate::{
+        bls::params::{SecretKeyScalarField, G2},
+        params::BlsSigField,
+    };
+
+    type BlsSigConfig = ark_bls12_377::Config;
+    type CF = BlsSigField<BlsSigConfig>;
+    type Scalar = SecretKeyScalarField<BlsSigConfig>;
+    type PointVar = G2Var<BlsSigConfig, FpVar<CF>, CF>;
+
+    /// Random points with full scalars, or with scalars of every length up to
+    /// the full scalar field, including empty ones, unless `full` is set.
+    fn sample(rng: &mut StdRng, n: usize, full: bool) -> (Vec<G2<BlsSigConfig>>, Vec<Vec<bool>>) {
+        let points = (0..n).map(|_| G2::<BlsSigConfig>::rand(rng)).collect();
+        let scalars = (0..n)
+            .map(|i| {
+                let bits = Scalar::rand(rng).into_bigint().to_bits_le();
+                let len = match i % 3 {
+                    _ if full => bits.len(),
+                    1 => rng.gen_range(0..bits.len()),
+                    2 => 0,
+                    _ => bits.len(),
+                };
+                bits[..len].to_vec()
+            })
+            .collect();
+        (points, scalars)
+    }
+
+    fn alloc(
+        points: &[G2<BlsSigConfig>],
+        scalars: &[Vec<bool>],
+    ) -> (Vec<PointVar>, Vec<Vec<Boolean<CF>>>) {
+        let cs = ConstraintSystem::<CF>::new_ref();
+        let points_var = Vec::<PointVar>::new_witness(cs.clone(), || Ok(points)).unwrap();
+        let scalars_var = scalars
+            .iter()
+            .map(|bits| {
+                Vec::<Boolean<CF>>::new_witness(cs.clone(), || Ok(bits.as_slice())).unwrap()
+            })
+            .collect();
+        (points_var, scalars_var)
+    }
+
+    fn naive_msm(points: &[G2<BlsSigConfig>], scalars: &[Vec<bool>]) -> G2<BlsSigConfig> {
+        points
+            .iter()
+            .zip(scalars)
+            .map(|(point, bits)| {
+                bits.iter()
+                    .rev()
+                    .fold(G2::<BlsSigConfig>::default(), |acc, bit| {
+                        let acc = acc.double();
+                        if *bit {
+                            acc + point
+                        } else {
+                            acc
+                        }
+                    })
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_g2_msm() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let (points, scalars) = sample(&mut rng, 6, false);
+        let (points_var, scalars_var) = alloc(&points, &scalars);
+
+        let msm = g2_msm(&points_var, &scalars_var).unwrap();
+        assert_eq!(
+            msm.value().unwrap().into_affine(),
+            naive_msm(&points, &scalars).into_affine()
+        );
+        assert!(msm.cs().is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_g2_msm_constraints() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let (points, scalars) = sample(&mut rng, 4, true);
+        let (points_var, scalars_var) = alloc(&points, &scalars);
+        let cs = points_var[0].cs();
+
+        let before = cs.num_constraints();
+        let msm = g2_msm(&points_var, &scalars_var).unwrap();
+        let msm_constraints = cs.num_constraints() - before;
+
+        // multiplying every point on its own takes more constraints
+        let before = cs.num_constraints();
+        let naive = points_var
+            .iter()
+            .zip(&scalars_var)
+            .map(|(point, bits)| point.scalar_mul_le(bits.iter()).unwrap())
+            .fold(PointVar::zero(), |acc, point| acc + point);
+        assert!(msm_constraints < cs.num_constraints() - before);
+
+        assert_eq!(msm.value().unwrap(), naive.value().unwrap());
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_g2_msm_edge_cases() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let cs = ConstraintSystem::<CF>::new_ref();
+        let point =
+            PointVar::new_witness(cs.clone(), || Ok(G2::<BlsSigConfig>::rand(&mut rng))).unwrap();
+
+        // no base
+        assert!(g2_msm::<BlsSigConfig, FpVar<CF>, CF>(&[], &[])
+            .unwrap()
+            .value()
+            .unwrap()
+            .is_zero());
+
+        // a scalar of one is the base itself
+        let one = vec![Boolean::TRUE];
+        assert_eq!(
+            g2_msm(std::slice::from_ref(&point), &[one.clone()])
+                .unwrap()
+                .value()
+                .unwrap(),
+            point.value().unwrap()
+        );
+
+        // every base needs a scalar
+        assert!(matches!(
+            g2_msm(&[point.clone(), point], &[one]),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_g2_sum() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let (points, _) = sample(&mut rng, 5, true);
+        let (points_var, _) = alloc(&points, &[]);
+        let ones = vec![vec![Boolean::TRUE]; points.len()];
+
+        let sum = g2_sum(&points_var).unwrap().value().unwrap();
+        assert_eq!(sum, points.iter().sum::<G2<BlsSigConfig>>());
+        assert_eq!(sum, g2_msm(&points_var, &ones).unwrap().value().unwrap());
+        assert!(g2_sum::<BlsSigConfig, FpVar<CF>, CF>(&[])
+            .unwrap()
+            .value()
+            .unwrap()
+            .is_zero());
+        assert!(points_var[0].cs().is_satisfied().unwrap());
+    }
+}
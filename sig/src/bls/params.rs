@@ -1,5 +1,5 @@
 use ark_ec::{bls12::Bls12Config, short_weierstrass::Projective, CurveConfig, CurveGroup};
-use ark_r1cs_std::fields::fp2::Fp2Var;
+use ark_r1cs_std::fields::{fp12::Fp12Var, fp2::Fp2Var};
 
 pub type G1<SigCurveConfig> = Projective<<SigCurveConfig as Bls12Config>::G1Config>;
 pub type G2<SigCurveConfig> = Projective<<SigCurveConfig as Bls12Config>::G2Config>;
@@ -12,3 +12,9 @@ pub type HashCurveConfig<SigCurveConfig> = <HashCurveGroup<SigCurveConfig> as Cu
 // R1CS
 pub type HashCurveVar<SigCurveConfig, F, CF> =
     Fp2Var<<SigCurveConfig as Bls12Config>::Fp2Config, F, CF>;
+
+/// The target group of the pairing gadget. Its `ToBytesGadget` encoding matches
+/// the uncompressed `CanonicalSerialize` encoding of the native `TargetField`,
+/// and `ToConstraintFieldGadget` emits the 12 base field coefficients (in their
+/// limb representation if the base field is emulated).
+pub type GTVar<SigCurveConfig, F, CF> = Fp12Var<<SigCurveConfig as Bls12Config>::Fp12Config, F, CF>;
@@ -5,14 +5,17 @@ use ark_ec::bls12::{Bls12, Bls12Config};
 use ark_ec::hashing::curve_maps::wb::WBConfig;
 use ark_ec::pairing::Pairing;
 use ark_ec::short_weierstrass::{Projective, SWCurveConfig};
-use ark_ec::{CurveConfig, CurveGroup};
-use ark_ff::{Field, PrimeField};
+use ark_ec::CurveGroup;
+use ark_ff::{BitIteratorLE, Field, PrimeField};
 use ark_r1cs_std::alloc::{AllocVar, AllocationMode};
+use ark_r1cs_std::convert::ToBytesGadget;
 use ark_r1cs_std::eq::EqGadget;
 use ark_r1cs_std::fields::{FieldOpsBounds, FieldVar};
+use ark_r1cs_std::groups::curves::short_weierstrass::ProjectiveVar;
 use ark_r1cs_std::groups::CurveVar;
 use ark_r1cs_std::pairing::bls12;
 use ark_r1cs_std::prelude::{Boolean, PairingVar};
+use ark_r1cs_std::select::CondSelectGadget;
 use ark_r1cs_std::uint8::UInt8;
 use ark_r1cs_std::R1CSVar;
 use ark_relations::r1cs::{Namespace, SynthesisError};
@@ -23,19 +26,32 @@ use ark_r1cs_std::groups::bls12::{G1PreparedVar, G1Var, G2PreparedVar, G2Var};
 use derivative::Derivative;
 use derive_more::{AsRef, From, Into};
 
+use crate::bc::params::{PointEncoding, POINT_ENCODING};
+use crate::folding::serialize::{compress_g1, compress_g2};
 use crate::hash::hash_to_curve::cofactor::CofactorGadget;
-use crate::hash::hash_to_curve::MapToCurveBasedHasherGadget;
-use crate::hash::hash_to_field::default_hasher::DefaultFieldHasherGadget;
-use crate::hash::prf::blake2s::constraints::StatefulBlake2sGadget;
 use crate::hash::{
     hash_to_field::from_base_field::FromBaseFieldVarGadget,
-    map_to_curve::{sqrt::SqrtGadget, to_base_field::ToBaseFieldVarGadget, wb::WBMapGadget},
+    hash_to_g2,
+    map_to_curve::{sqrt::SqrtGadget, to_base_field::ToBaseFieldVarGadget},
 };
 use crate::params::BlsSigField;
+#[cfg(feature = "poseidon-hash-to-field")]
+use crate::{
+    bls::HashStrategy,
+    hash::{
+        hash_to_curve::MapToCurveBasedHasherGadget,
+        hash_to_field::poseidon::constraints::PoseidonFieldHasherGadget,
+        map_to_curve::wb::WBMapGadget,
+    },
+};
+#[cfg(feature = "poseidon-hash-to-field")]
+use ark_ec::CurveConfig;
 
 use super::params::{HashCurveConfig, HashCurveGroup, HashCurveVar, G1, G2};
-use super::{Parameters, PublicKey, Signature};
+use super::{Parameters, PublicKey, Signature, SigningScheme};
 
+/// The generators are fixed in practice, so they should be allocated with `new_constant`. Then
+/// negating and preparing them in `verify` is done at synthesis time and costs no constraints.
 #[derive(Derivative)]
 #[derivative(Clone(bound = ""), Debug(bound = ""))]
 pub struct ParametersVar<
@@ -127,19 +143,120 @@ where
         signature: &SignatureVar<SigCurveConfig, FV, CF>,
     ) -> Result<(), SynthesisError> {
         let hash_to_curve = Self::hash_to_curve(message)?;
+        Self::verify_hashed(parameters, pk, &hash_to_curve, signature)
+    }
+
+    /// Same as [`Self::verify`], but hashes `message` with the given `strategy`.
+    ///
+    /// `HashStrategy::Poseidon` only matches the native signature if `CF` is the
+    /// base field of the signature curve, as the sponge runs over `CF`.
+    #[cfg(feature = "poseidon-hash-to-field")]
+    #[tracing::instrument(skip_all)]
+    pub fn verify_with_strategy(
+        parameters: &ParametersVar<SigCurveConfig, FV, CF>,
+        pk: &PublicKeyVar<SigCurveConfig, FV, CF>,
+        message: &[UInt8<CF>],
+        signature: &SignatureVar<SigCurveConfig, FV, CF>,
+        strategy: HashStrategy,
+    ) -> Result<(), SynthesisError> {
+        let hash_to_curve = Self::hash_to_curve_with_strategy(message, strategy)?;
+        Self::verify_hashed(parameters, pk, &hash_to_curve, signature)
+    }
+
+    /// Same as [`Self::verify`], but the signature is only checked if
+    /// `should_enforce` is true.
+    ///
+    /// Otherwise, `pk` and `signature` are replaced by the generators before
+    /// pairing, so that any witness, including points at infinity that the
+    /// pairing gadget cannot prepare, keeps the constraints satisfiable.
+    #[tracing::instrument(skip_all)]
+    pub fn conditional_verify(
+        parameters: &ParametersVar<SigCurveConfig, FV, CF>,
+        pk: &PublicKeyVar<SigCurveConfig, FV, CF>,
+        message: &[UInt8<CF>],
+        signature: &SignatureVar<SigCurveConfig, FV, CF>,
+        should_enforce: &Boolean<CF>,
+    ) -> Result<(), SynthesisError> {
+        let hash_to_curve = Self::hash_to_curve(message)?;
+        let pk = should_enforce.select(&pk.pub_key, &parameters.g1_generator)?;
+        let signature = should_enforce.select(&signature.signature, &parameters.g2_generator)?;
+        Self::enforce_product_of_pairings_is_one(
+            &[parameters.g1_generator.negate()?, pk],
+            &[signature, hash_to_curve],
+            should_enforce,
+        )
+    }
+
+    fn verify_hashed(
+        parameters: &ParametersVar<SigCurveConfig, FV, CF>,
+        pk: &PublicKeyVar<SigCurveConfig, FV, CF>,
+        hash_to_curve: &G2Var<SigCurveConfig, FV, CF>,
+        signature: &SignatureVar<SigCurveConfig, FV, CF>,
+    ) -> Result<(), SynthesisError> {
+        Self::enforce_product_of_pairings_is_one(
+            &[parameters.g1_generator.negate()?, pk.pub_key.clone()],
+            &[signature.signature.clone(), hash_to_curve.clone()],
+            &Boolean::TRUE,
+        )
+    }
+
+    /// Verify several signatures on possibly different messages, sharing a
+    /// single final exponentiation between them.
+    ///
+    /// Instead of checking `e(g1, sig_i) == e(pk_i, H(m_i))` for every `i`,
+    /// this checks `e(g1, sum_i sig_i) == prod_i e(pk_i, H(m_i))`, i.e. the
+    /// aggregate of the signatures. It accepts whenever all signatures are
+    /// valid, and otherwise gives the security of BLS aggregate signatures:
+    /// signatures that are invalid on their own but sum up to a valid
+    /// aggregate, e.g. two swapped signatures, are also accepted.
+    ///
+    /// `n` relations cost `n + 1` Miller loops and one final exponentiation,
+    /// where calling `verify` for each of them costs `2n` Miller loops and
+    /// `n` final exponentiations.
+    #[tracing::instrument(skip_all)]
+    pub fn verify_many(
+        parameters: &ParametersVar<SigCurveConfig, FV, CF>,
+        relations: &[(
+            &PublicKeyVar<SigCurveConfig, FV, CF>,
+            &[UInt8<CF>],
+            &SignatureVar<SigCurveConfig, FV, CF>,
+        )],
+    ) -> Result<(), SynthesisError> {
+        let Some(((_, _, first), rest)) = relations.split_first() else {
+            return Ok(());
+        };
+
+        let signature = rest
+            .iter()
+            .fold(first.signature.clone(), |acc, (_, _, sig)| {
+                acc + &sig.signature
+            });
 
-        // an optimised way to check two pairings are equal
+        let mut g1s = vec![parameters.g1_generator.negate()?];
+        let mut g2s = vec![signature];
+        for (pk, message, _) in relations {
+            g1s.push(pk.pub_key.clone());
+            g2s.push(Self::hash_to_curve(message)?);
+        }
+
+        Self::enforce_product_of_pairings_is_one(&g1s, &g2s, &Boolean::TRUE)
+    }
+
+    /// Enforce `prod_i e(g1s[i], g2s[i]) == 1` if `should_enforce` is true,
+    /// with a single final exponentiation.
+    fn enforce_product_of_pairings_is_one(
+        g1s: &[G1Var<SigCurveConfig, FV, CF>],
+        g2s: &[G2Var<SigCurveConfig, FV, CF>],
+        should_enforce: &Boolean<CF>,
+    ) -> Result<(), SynthesisError> {
+        // an optimised way to check pairings are equal
         let prod = bls12::PairingVar::product_of_pairings(
-            &[
-                G1PreparedVar::<SigCurveConfig, FV, CF>::from_group_var(
-                    &parameters.g1_generator.negate()?,
-                )?,
-                G1PreparedVar::<SigCurveConfig, FV, CF>::from_group_var(&pk.pub_key)?,
-            ],
-            &[
-                G2PreparedVar::<SigCurveConfig, FV, CF>::from_group_var(&signature.signature)?,
-                G2PreparedVar::<SigCurveConfig, FV, CF>::from_group_var(&hash_to_curve)?,
-            ],
+            &g1s.iter()
+                .map(G1PreparedVar::<SigCurveConfig, FV, CF>::from_group_var)
+                .collect::<Result<Vec<_>, _>>()?,
+            &g2s.iter()
+                .map(G2PreparedVar::<SigCurveConfig, FV, CF>::from_group_var)
+                .collect::<Result<Vec<_>, _>>()?,
         )?;
 
         let cs = prod.cs();
@@ -153,7 +270,7 @@ where
                 <<Bls12<SigCurveConfig> as Pairing>::TargetField as Field>::ONE,
             )?,
         )?
-        .enforce_equal(&Boolean::TRUE)?;
+        .conditional_enforce_equal(&Boolean::TRUE, should_enforce)?;
 
         tracing::info!(num_constraints = cs.num_constraints());
 
@@ -195,32 +312,113 @@ where
         message: &[UInt8<CF>],
         signature: &SignatureVar<SigCurveConfig, FV, CF>,
     ) -> Result<(), SynthesisError> {
-        // Aggregate all public keys
-        let aggregated_pk = public_keys
+        Self::aggregate_verify_many(parameters, &[(public_keys, message, signature)])
+    }
+
+    /// Same as [`Self::aggregate_verify`] for several relations, which share a
+    /// single final exponentiation as in [`Self::verify_many`].
+    pub fn aggregate_verify_many(
+        parameters: &ParametersVar<SigCurveConfig, FV, CF>,
+        relations: &[(
+            &[PublicKeyVar<SigCurveConfig, FV, CF>],
+            &[UInt8<CF>],
+            &SignatureVar<SigCurveConfig, FV, CF>,
+        )],
+    ) -> Result<(), SynthesisError> {
+        // Aggregate all public keys of each relation
+        let aggregated_pks = relations
             .iter()
-            .skip(1)
-            .fold(public_keys[0].clone(), |acc, new_pk| acc + new_pk);
+            .map(|(public_keys, _, _)| {
+                public_keys
+                    .iter()
+                    .skip(1)
+                    .fold(public_keys[0].clone(), |acc, new_pk| acc + new_pk)
+            })
+            .collect::<Vec<_>>();
 
-        // Verify e(signature, G) == e(aggregated_pk, H(m))
-        Self::verify(parameters, &aggregated_pk, message, signature)
+        // Verify e(signature, G) == e(aggregated_pk, H(m)) for each relation
+        let relations = relations
+            .iter()
+            .zip(&aggregated_pks)
+            .map(|((_, message, signature), pk)| (pk, *message, *signature))
+            .collect::<Vec<_>>();
+        Self::verify_many(parameters, &relations)
     }
 
+    /// Same as [`Self::verify`], but for a signature produced by
+    /// [`Signature::sign_with_scheme`].
+    #[tracing::instrument(skip_all)]
+    pub fn verify_with_scheme(
+        parameters: &ParametersVar<SigCurveConfig, FV, CF>,
+        pk: &PublicKeyVar<SigCurveConfig, FV, CF>,
+        message: &[UInt8<CF>],
+        signature: &SignatureVar<SigCurveConfig, FV, CF>,
+        scheme: SigningScheme,
+    ) -> Result<(), SynthesisError> {
+        let hash_to_curve = Self::hash_to_curve_with_scheme(pk, message, scheme)?;
+        Self::verify_hashed(parameters, pk, &hash_to_curve, signature)
+    }
+
+    /// Hash `msg` under the domain separation tag of `scheme`. With
+    /// [`SigningScheme::MessageAugmentation`], the compressed encoding of `pk`
+    /// is prepended first, which costs converting it to affine coordinates and
+    /// decomposing them into bits. The encoding is the one of BLS12-381, so it
+    /// only matches the native signer for that curve.
+    #[tracing::instrument(skip_all)]
+    pub fn hash_to_curve_with_scheme(
+        pk: &PublicKeyVar<SigCurveConfig, FV, CF>,
+        msg: &[UInt8<CF>],
+        scheme: SigningScheme,
+    ) -> Result<G2Var<SigCurveConfig, FV, CF>, SynthesisError> {
+        let cs = msg.cs().or(pk.pub_key.cs());
+        tracing::info!(num_constraints = cs.num_constraints());
+
+        let dst = UInt8::constant_vec(scheme.dst());
+        let hash = match scheme {
+            SigningScheme::Basic | SigningScheme::ProofOfPossession => hash_to_g2(&dst, msg),
+            SigningScheme::MessageAugmentation => {
+                let mut augmented_msg = compress_g1(&pk.pub_key)?;
+                augmented_msg.extend_from_slice(msg);
+                hash_to_g2(&dst, &augmented_msg)
+            }
+        };
+
+        tracing::info!(num_constraints = cs.num_constraints());
+
+        hash
+    }
+
+    /// Same as [`hash_to_g2`] with an empty domain separation tag.
     #[tracing::instrument(skip_all)]
     pub fn hash_to_curve(
         msg: &[UInt8<CF>],
+    ) -> Result<G2Var<SigCurveConfig, FV, CF>, SynthesisError> {
+        let cs = msg.cs();
+        tracing::info!(num_constraints = cs.num_constraints());
+
+        let hash = hash_to_g2(&[], msg);
+
+        tracing::info!(num_constraints = cs.num_constraints());
+
+        hash
+    }
+
+    #[cfg(feature = "poseidon-hash-to-field")]
+    #[tracing::instrument(skip_all)]
+    pub fn hash_to_curve_with_strategy(
+        msg: &[UInt8<CF>],
+        strategy: HashStrategy,
     ) -> Result<G2Var<SigCurveConfig, FV, CF>, SynthesisError> {
         type HashGroupBaseField<SigCurveConfig> =
             <HashCurveConfig<SigCurveConfig> as CurveConfig>::BaseField;
 
-        type FieldHasherGadget<SigCurveConfig, FV, CF> = DefaultFieldHasherGadget<
-            StatefulBlake2sGadget<CF>,
+        type FieldHasherGadget<SigCurveConfig, FV, CF> = PoseidonFieldHasherGadget<
             HashGroupBaseField<SigCurveConfig>,
             CF,
             HashCurveVar<SigCurveConfig, FV, CF>,
             128,
         >;
 
-        // this is slightly different from its counterpart in `bls.rs` because of how WBMapGadget is defined
         type CurveMapGadget<SigCurveConfig> =
             WBMapGadget<<SigCurveConfig as Bls12Config>::G2Config>;
 
@@ -232,15 +430,20 @@ where
             HashCurveVar<SigCurveConfig, FV, CF>,
         >;
 
-        let cs = msg.cs();
-        tracing::info!(num_constraints = cs.num_constraints());
+        match strategy {
+            HashStrategy::Blake2s => Self::hash_to_curve(msg),
+            HashStrategy::Poseidon => {
+                let cs = msg.cs();
+                tracing::info!(num_constraints = cs.num_constraints());
 
-        let hasher_gadget = HasherGadget::<SigCurveConfig, FV, CF>::new(&[]);
-        let hash = hasher_gadget.hash(msg);
+                let hasher_gadget = HasherGadget::<SigCurveConfig, FV, CF>::new(&[]);
+                let hash = hasher_gadget.hash(msg);
 
-        tracing::info!(num_constraints = cs.num_constraints());
+                tracing::info!(num_constraints = cs.num_constraints());
 
-        hash.map(|h| G2Var::<SigCurveConfig, FV, CF>::new(h.x, h.y, h.z))
+                hash.map(|h| G2Var::<SigCurveConfig, FV, CF>::new(h.x, h.y, h.z))
+            }
+        }
     }
 }
 
@@ -313,6 +516,68 @@ where
             )?,
         })
     }
+
+    /// Enforce that the public key is in the prime-order subgroup of G1.
+    ///
+    /// Witnesses allocated through `AllocVar` are already checked, but inputs, constants and keys
+    /// allocated with `new_variable_omit_on_curve_check` are not. It is safe to skip this check when
+    /// the key comes from a trusted source, e.g. the committee keys of `BlockVar`, which are either
+    /// part of the initial state or signed by a quorum of the previous committee.
+    pub fn enforce_in_subgroup(&self) -> Result<(), SynthesisError> {
+        enforce_in_subgroup(&self.pub_key)
+    }
+}
+
+/// The encoding of the point under `POINT_ENCODING`, which matches
+/// `serialize_curve_point` in `bc/block.rs`. The compressed form is the one of
+/// BLS12-381, so it only matches the native encoding on that curve.
+impl<
+        SigCurveConfig: Bls12Config,
+        FV: FieldVar<BlsSigField<SigCurveConfig>, SNARKField>,
+        SNARKField: PrimeField,
+    > ToBytesGadget<SNARKField> for PublicKeyVar<SigCurveConfig, FV, SNARKField>
+where
+    for<'a> &'a FV: FieldOpsBounds<'a, BlsSigField<SigCurveConfig>, FV>,
+{
+    fn to_bytes_le(&self) -> Result<Vec<UInt8<SNARKField>>, SynthesisError> {
+        match POINT_ENCODING {
+            PointEncoding::Uncompressed => self.pub_key.to_bytes_le(),
+            PointEncoding::Compressed => compress_g1(&self.pub_key),
+        }
+    }
+
+    fn to_non_unique_bytes_le(&self) -> Result<Vec<UInt8<SNARKField>>, SynthesisError> {
+        match POINT_ENCODING {
+            PointEncoding::Uncompressed => self.pub_key.to_non_unique_bytes_le(),
+            PointEncoding::Compressed => compress_g1(&self.pub_key),
+        }
+    }
+}
+
+/// The encoding of the point under `POINT_ENCODING`, which matches
+/// `serialize_curve_point` in `bc/block.rs`. The compressed form is the one of
+/// BLS12-381, so it only matches the native encoding on that curve.
+impl<
+        SigCurveConfig: Bls12Config,
+        FV: FieldVar<BlsSigField<SigCurveConfig>, SNARKField>,
+        SNARKField: PrimeField,
+    > ToBytesGadget<SNARKField> for SignatureVar<SigCurveConfig, FV, SNARKField>
+where
+    for<'a> &'a FV: FieldOpsBounds<'a, BlsSigField<SigCurveConfig>, FV>,
+{
+    fn to_bytes_le(&self) -> Result<Vec<UInt8<SNARKField>>, SynthesisError> {
+        match POINT_ENCODING {
+            PointEncoding::Uncompressed => self.signature.to_bytes_le(),
+            PointEncoding::Compressed => compress_g2(&self.signature),
+        }
+    }
+
+    fn to_non_unique_bytes_le(&self) -> Result<Vec<UInt8<SNARKField>>, SynthesisError> {
+        match POINT_ENCODING {
+            PointEncoding::Uncompressed => self.signature.to_non_unique_bytes_le(),
+            PointEncoding::Compressed => compress_g2(&self.signature),
+        }
+    }
 }
 
 impl<
@@ -336,6 +601,51 @@ where
             )?,
         })
     }
+
+    /// Enforce that the signature is in the prime-order subgroup of G2.
+    ///
+    /// Same as [`PublicKeyVar::enforce_in_subgroup`]. Skipping it is only safe if the signature was
+    /// checked outside the circuit, as the pairing gadget assumes its inputs are in the subgroup.
+    pub fn enforce_in_subgroup(&self) -> Result<(), SynthesisError> {
+        enforce_in_subgroup(&self.signature)
+    }
+}
+
+/// Enforce that `point` is in the prime-order subgroup by exhibiting `q` such that `[h]q == point`,
+/// where `h` is the cofactor. Since `r^2` does not divide the order of the curve, the image of `[h]`
+/// is exactly the subgroup of order `r`.
+fn enforce_in_subgroup<P: SWCurveConfig, FV: FieldVar<P::BaseField, CF>, CF: PrimeField>(
+    point: &ProjectiveVar<P, FV, CF>,
+) -> Result<(), SynthesisError>
+where
+    for<'a> &'a FV: FieldOpsBounds<'a, P::BaseField, FV>,
+{
+    if point.is_constant() {
+        return if point
+            .value()?
+            .into_affine()
+            .is_in_correct_subgroup_assuming_on_curve()
+        {
+            Ok(())
+        } else {
+            Err(SynthesisError::Unsatisfiable)
+        };
+    }
+
+    let q = ProjectiveVar::<P, FV, CF>::new_variable_omit_prime_order_check(
+        ark_relations::ns!(point.cs(), "cofactor preimage"),
+        || {
+            point
+                .value()
+                .map(|p| p.into_affine().mul_by_cofactor_inv().into())
+        },
+        AllocationMode::Witness,
+    )?;
+    let cofactor_bits: Vec<_> = BitIteratorLE::new(P::COFACTOR)
+        .map(Boolean::constant)
+        .collect();
+
+    q.scalar_mul_le(cofactor_bits.iter())?.enforce_equal(point)
 }
 
 impl<
@@ -425,6 +735,195 @@ mod test {
         println!("RC1S is satisfied!");
     }
 
+    #[cfg(feature = "poseidon-hash-to-field")]
+    #[test]
+    fn check_r1cs_native_poseidon() {
+        use crate::bls::{HashStrategy, Signature};
+
+        type BlsSigConfig = ark_bls12_377::Config;
+        type BaseSigCurveField = BlsSigField<BlsSigConfig>;
+        type BaseSNARKField = BaseSigCurveField;
+
+        let cs = ConstraintSystem::new_ref();
+        let (msg, params, sk, pk, _) = get_bls_instance::<BlsSigConfig>();
+        let sig = Signature::sign_with_strategy(msg.as_bytes(), &sk, HashStrategy::Poseidon);
+
+        let msg_var: Vec<UInt8<BaseSNARKField>> = msg
+            .as_bytes()
+            .iter()
+            .map(|b| UInt8::new_input(cs.clone(), || Ok(b)).unwrap())
+            .collect();
+        let params_var: ParametersVar<BlsSigConfig, FpVar<BaseSigCurveField>, BaseSNARKField> =
+            ParametersVar::new_input(cs.clone(), || Ok(params)).unwrap();
+        let pk_var = PublicKeyVar::new_input(cs.clone(), || Ok(pk)).unwrap();
+        let sig_var = SignatureVar::new_input(cs.clone(), || Ok(sig)).unwrap();
+
+        BLSAggregateSignatureVerifyGadget::verify_with_strategy(
+            &params_var,
+            &pk_var,
+            &msg_var,
+            &sig_var,
+            HashStrategy::Poseidon,
+        )
+        .unwrap();
+
+        println!("Number of constraints: {}", cs.num_constraints());
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn check_r1cs_native_augmented() {
+        use ark_r1cs_std::R1CSVar;
+
+        use crate::bls::{Signature, SigningScheme};
+        use crate::folding::serialize::compress_g1;
+
+        // the key is prepended in the compressed encoding of BLS12-381
+        type BlsSigConfig = crate::params::BlsSigConfig;
+        type BaseSigCurveField = BlsSigField<BlsSigConfig>;
+        type BaseSNARKField = BaseSigCurveField;
+        type Gadget = BLSAggregateSignatureVerifyGadget<
+            BlsSigConfig,
+            FpVar<BaseSigCurveField>,
+            BaseSNARKField,
+        >;
+
+        let (msg, params, sk, pk, _) = get_bls_instance::<BlsSigConfig>();
+        let aug_sig = Signature::sign_with_scheme(
+            msg.as_bytes(),
+            &sk,
+            &params,
+            SigningScheme::MessageAugmentation,
+        );
+
+        let synthesize = |sig: Signature<BlsSigConfig>| {
+            let cs = ConstraintSystem::new_ref();
+            let msg_var = UInt8::new_input_vec(cs.clone(), msg.as_bytes()).unwrap();
+            let params_var = ParametersVar::new_constant(cs.clone(), params).unwrap();
+            let pk_var = PublicKeyVar::new_input(cs.clone(), || Ok(pk)).unwrap();
+            let sig_var = SignatureVar::new_input(cs.clone(), || Ok(sig)).unwrap();
+
+            // the key is encoded as the native signer prepends it
+            assert_eq!(
+                compress_g1(&pk_var.pub_key).unwrap().value().unwrap(),
+                pk.to_bytes()
+            );
+
+            Gadget::verify_with_scheme(
+                &params_var,
+                &pk_var,
+                &msg_var,
+                &sig_var,
+                SigningScheme::MessageAugmentation,
+            )
+            .unwrap();
+            cs.is_satisfied().unwrap()
+        };
+
+        assert!(synthesize(aug_sig));
+        // a signature over the message without the key prefix is rejected
+        assert!(!synthesize(Signature::sign_with_scheme(
+            msg.as_bytes(),
+            &sk,
+            &params,
+            SigningScheme::Basic,
+        )));
+    }
+
+    #[test]
+    fn check_point_var_encoding() {
+        use ark_ec::PrimeGroup;
+        use ark_r1cs_std::{R1CSVar, ToBytesGadget};
+        use ark_std::{test_rng, UniformRand, Zero};
+
+        use crate::bc::params::{AuthorityAggregatedSignature, AuthorityPublicKey};
+        use crate::params::BlsSigConfig;
+
+        type CF = BlsSigField<BlsSigConfig>;
+        type FV = EmulatedFpVar<BlsSigField<BlsSigConfig>, CF>;
+
+        let mut rng = test_rng();
+        let mut pks = vec![ark_bls12_381::G1Projective::zero()];
+        let mut sigs = vec![ark_bls12_381::G2Projective::zero()];
+        pks.push(ark_bls12_381::G1Projective::generator());
+        sigs.push(ark_bls12_381::G2Projective::generator());
+        for _ in 0..4 {
+            pks.push(ark_bls12_381::G1Projective::rand(&mut rng));
+            sigs.push(ark_bls12_381::G2Projective::rand(&mut rng));
+        }
+
+        // the bytes must match the bincode encoding of blocks, which goes
+        // through `serialize_curve_point`
+        let cs = ConstraintSystem::new_ref();
+        for pk in pks {
+            let pk = AuthorityPublicKey::from(pk);
+            let pk_var =
+                PublicKeyVar::<BlsSigConfig, FV, CF>::new_witness(cs.clone(), || Ok(pk)).unwrap();
+            assert_eq!(
+                pk_var.to_bytes_le().unwrap().value().unwrap(),
+                bincode::serialize(&pk).unwrap()
+            );
+        }
+        for sig in sigs {
+            let sig = AuthorityAggregatedSignature::from(sig);
+            let sig_var =
+                SignatureVar::<BlsSigConfig, FV, CF>::new_witness(cs.clone(), || Ok(sig)).unwrap();
+            assert_eq!(
+                sig_var.to_bytes_le().unwrap().value().unwrap(),
+                bincode::serialize(&sig).unwrap()
+            );
+        }
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn check_gt_var_encoding() {
+        use ark_ec::pairing::Pairing;
+        use ark_ff::Field;
+        use ark_r1cs_std::{R1CSVar, ToBytesGadget, ToConstraintFieldGadget};
+        use ark_serialize::CanonicalSerialize;
+
+        use crate::bls::GTVar;
+
+        type BlsSigConfig = ark_bls12_377::Config;
+        type BaseSigCurveField = BlsSigField<BlsSigConfig>;
+        type EmulatedSNARKField = BlsSigField<ark_bls12_381::Config>;
+
+        let (_, params, _, _, _) = get_bls_instance::<BlsSigConfig>();
+        let gt = ark_bls12_377::Bls12_377::pairing(params.g1_generator, params.g2_generator).0;
+        let mut expected = vec![];
+        gt.serialize_uncompressed(&mut expected).unwrap();
+
+        // native
+        let cs = ConstraintSystem::new_ref();
+        let gt_var =
+            GTVar::<BlsSigConfig, FpVar<BaseSigCurveField>, BaseSigCurveField>::new_witness(
+                cs.clone(),
+                || Ok(gt),
+            )
+            .unwrap();
+        assert_eq!(gt_var.to_bytes_le().unwrap().value().unwrap(), expected);
+
+        let elems = gt_var.to_constraint_field().unwrap();
+        assert_eq!(elems.len(), 12);
+        assert_eq!(
+            elems.value().unwrap(),
+            gt.to_base_prime_field_elements().collect::<Vec<_>>()
+        );
+        assert!(cs.is_satisfied().unwrap());
+
+        // emulated
+        let cs = ConstraintSystem::new_ref();
+        let gt_var = GTVar::<
+            BlsSigConfig,
+            EmulatedFpVar<BaseSigCurveField, EmulatedSNARKField>,
+            EmulatedSNARKField,
+        >::new_witness(cs.clone(), || Ok(gt))
+        .unwrap();
+        assert_eq!(gt_var.to_bytes_le().unwrap().value().unwrap(), expected);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
     #[test]
     #[ignore = "field emulation takes a long time to finish running"]
     fn check_r1cs_emulated() {
@@ -456,4 +955,252 @@ mod test {
 
         println!("RC1S is satisfied!");
     }
+
+    #[test]
+    fn check_r1cs_native_verify_many() {
+        use crate::bls::{Parameters, PublicKey, SecretKey, Signature};
+
+        type BlsSigConfig = ark_bls12_377::Config;
+        type BaseSNARKField = BlsSigField<BlsSigConfig>;
+        type Instance<'a> = (PublicKey<BlsSigConfig>, &'a [u8], Signature<BlsSigConfig>);
+
+        /// Returns whether the constraint system is satisfied and its size.
+        fn check(
+            params: Parameters<BlsSigConfig>,
+            instances: &[Instance],
+            many: bool,
+        ) -> (bool, usize) {
+            let cs = ConstraintSystem::<BaseSNARKField>::new_ref();
+            let params_var: ParametersVar<BlsSigConfig, FpVar<BaseSNARKField>, BaseSNARKField> =
+                ParametersVar::new_constant(cs.clone(), params).unwrap();
+            let vars: Vec<_> = instances
+                .iter()
+                .map(|(pk, msg, sig)| {
+                    (
+                        PublicKeyVar::new_witness(cs.clone(), || Ok(*pk)).unwrap(),
+                        UInt8::new_witness_vec(cs.clone(), *msg).unwrap(),
+                        SignatureVar::new_witness(cs.clone(), || Ok(*sig)).unwrap(),
+                    )
+                })
+                .collect();
+
+            if many {
+                let relations: Vec<_> = vars
+                    .iter()
+                    .map(|(pk, msg, sig)| (pk, &msg[..], sig))
+                    .collect();
+                BLSAggregateSignatureVerifyGadget::verify_many(&params_var, &relations).unwrap();
+            } else {
+                for (pk, msg, sig) in &vars {
+                    BLSAggregateSignatureVerifyGadget::verify(&params_var, pk, msg, sig).unwrap();
+                }
+            }
+            (cs.is_satisfied().unwrap(), cs.num_constraints())
+        }
+
+        let mut rng = rand::thread_rng();
+        let params = Parameters::<BlsSigConfig>::setup();
+        let msgs = ["Hello World", "Hello", "World"];
+        let instances: Vec<Instance> = msgs
+            .iter()
+            .map(|msg| {
+                let sk = SecretKey::new(&mut rng);
+                let pk = PublicKey::new(&sk, &params);
+                (
+                    pk,
+                    msg.as_bytes(),
+                    Signature::sign(msg.as_bytes(), &sk, &params),
+                )
+            })
+            .collect();
+
+        let (separate, separate_constraints) = check(params, &instances, false);
+        let (shared, shared_constraints) = check(params, &instances, true);
+        assert!(separate && shared);
+        assert!(shared_constraints < separate_constraints);
+
+        // a signature on another message
+        let mut wrong = instances.clone();
+        wrong[1].1 = msgs[2].as_bytes();
+        assert!(!check(params, &wrong, false).0);
+        assert!(!check(params, &wrong, true).0);
+    }
+
+    #[test]
+    fn check_r1cs_native_conditional_verify() {
+        use crate::bls::{PublicKey, Signature};
+        use ark_r1cs_std::prelude::Boolean;
+
+        type BlsSigConfig = ark_bls12_377::Config;
+        type BaseSNARKField = BlsSigField<BlsSigConfig>;
+
+        let (msg, params, _, pk, sig) = get_bls_instance::<BlsSigConfig>();
+        let check = |pk: PublicKey<BlsSigConfig>, sig: Signature<BlsSigConfig>, enforce: bool| {
+            let cs = ConstraintSystem::<BaseSNARKField>::new_ref();
+            let params_var: ParametersVar<BlsSigConfig, FpVar<BaseSNARKField>, BaseSNARKField> =
+                ParametersVar::new_constant(cs.clone(), params).unwrap();
+            let msg_var = UInt8::new_witness_vec(cs.clone(), msg.as_bytes()).unwrap();
+            let pk_var = PublicKeyVar::new_witness(cs.clone(), || Ok(pk)).unwrap();
+            let sig_var = SignatureVar::new_witness(cs.clone(), || Ok(sig)).unwrap();
+            let enforce = Boolean::new_witness(cs.clone(), || Ok(enforce)).unwrap();
+
+            BLSAggregateSignatureVerifyGadget::conditional_verify(
+                &params_var,
+                &pk_var,
+                &msg_var,
+                &sig_var,
+                &enforce,
+            )
+            .unwrap();
+            cs.is_satisfied().unwrap()
+        };
+
+        assert!(check(pk, sig, true));
+        assert!(check(pk, sig, false));
+
+        // a signature by another key is only rejected when enforced
+        let other = PublicKey::from(params.g1_generator);
+        assert!(!check(other, sig, true));
+        assert!(check(other, sig, false));
+
+        // points at infinity are fine when the check is disabled
+        assert!(check(PublicKey::default(), Signature::default(), false));
+    }
+
+    #[test]
+    fn check_constant_parameters() {
+        use ark_r1cs_std::{
+            groups::{
+                bls12::{G1PreparedVar, G2PreparedVar},
+                CurveVar,
+            },
+            R1CSVar,
+        };
+        use ark_relations::r1cs::ConstraintSystemRef;
+
+        use crate::bls::Parameters;
+
+        type BlsSigConfig = ark_bls12_377::Config;
+        type BaseSigCurveField = BlsSigField<BlsSigConfig>;
+        type BaseSNARKField = BaseSigCurveField;
+        type ParamsVar = ParametersVar<BlsSigConfig, FpVar<BaseSigCurveField>, BaseSNARKField>;
+
+        let (msg, params, _, pk, sig) = get_bls_instance::<BlsSigConfig>();
+
+        // negating and preparing constant generators is done at synthesis time
+        let cs = ConstraintSystem::new_ref();
+        let params_var = ParamsVar::new_constant(cs.clone(), params).unwrap();
+        let g1_neg = params_var.g1_generator.negate().unwrap();
+        assert!(g1_neg.is_constant());
+        G1PreparedVar::from_group_var(&g1_neg).unwrap();
+        G2PreparedVar::from_group_var(&params_var.g2_generator).unwrap();
+        assert_eq!(cs.num_constraints(), 0);
+
+        let num_constraints = |params_var: fn(
+            ConstraintSystemRef<BaseSNARKField>,
+            Parameters<BlsSigConfig>,
+        ) -> ParamsVar| {
+            crate::testing::count_constraints(|cs| {
+                let msg_var = UInt8::new_input_vec(cs.clone(), msg.as_bytes())?;
+                let params_var = params_var(cs.clone(), params);
+                let pk_var = PublicKeyVar::new_input(cs.clone(), || Ok(pk))?;
+                let sig_var = SignatureVar::new_input(cs.clone(), || Ok(sig))?;
+
+                BLSAggregateSignatureVerifyGadget::verify(&params_var, &pk_var, &msg_var, &sig_var)
+            })
+        };
+
+        let constant = num_constraints(|cs, params| ParamsVar::new_constant(cs, params).unwrap());
+        let input = num_constraints(|cs, params| ParamsVar::new_input(cs, || Ok(params)).unwrap());
+        println!("Number of constraints: constant = {constant}, input = {input}");
+        assert!(constant < input);
+    }
+
+    #[test]
+    fn check_enforce_in_subgroup() {
+        use crate::bls::{PublicKey, Signature};
+        use ark_bls12_377::{Fq, Fq2, Fr, G1Affine, G2Affine};
+        use ark_ec::{AffineRepr, CurveGroup};
+        use ark_ff::{PrimeField, UniformRand};
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        type BlsSigConfig = ark_bls12_377::Config;
+        type BaseSigCurveField = BlsSigField<BlsSigConfig>;
+        type BaseSNARKField = BaseSigCurveField;
+        type PkVar = PublicKeyVar<BlsSigConfig, FpVar<BaseSigCurveField>, BaseSNARKField>;
+        type SigVar = SignatureVar<BlsSigConfig, FpVar<BaseSigCurveField>, BaseSNARKField>;
+
+        let mut rng = StdRng::from_seed([42; 32]);
+
+        // multiplying a random point on the curve by `r` leaves only its torsion component
+        let g1_torsion = loop {
+            let (x, greatest) = (Fq::rand(&mut rng), rng.gen());
+            if let Some(p) = G1Affine::get_point_from_x_unchecked(x, greatest) {
+                let t = p.mul_bigint(Fr::MODULUS).into_affine();
+                if !t.is_zero() {
+                    break t;
+                }
+            }
+        };
+        let g2_torsion = loop {
+            let (x, greatest) = (Fq2::rand(&mut rng), rng.gen());
+            if let Some(p) = G2Affine::get_point_from_x_unchecked(x, greatest) {
+                let t = p.mul_bigint(Fr::MODULUS).into_affine();
+                if !t.is_zero() {
+                    break t;
+                }
+            }
+        };
+
+        let (_, _, _, pk, sig) = get_bls_instance::<BlsSigConfig>();
+
+        // valid public key and signature pass the check
+        let cs = ConstraintSystem::new_ref();
+        let pk_var = PkVar::new_input(cs.clone(), || Ok(pk)).unwrap();
+        let sig_var = SigVar::new_input(cs.clone(), || Ok(sig)).unwrap();
+        pk_var.enforce_in_subgroup().unwrap();
+        sig_var.enforce_in_subgroup().unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        // torsion points fail the check
+        let cs = ConstraintSystem::new_ref();
+        let pk_var =
+            PkVar::new_input(cs.clone(), || Ok(PublicKey::from(g1_torsion.into_group()))).unwrap();
+        pk_var.enforce_in_subgroup().unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+
+        let cs = ConstraintSystem::new_ref();
+        let sig_var =
+            SigVar::new_input(cs.clone(), || Ok(Signature::from(g2_torsion.into_group()))).unwrap();
+        sig_var.enforce_in_subgroup().unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn check_hash_to_g2_native() {
+        use crate::{bls::Parameters, hash::hash_to_g2};
+        use ark_r1cs_std::R1CSVar;
+
+        type BlsSigConfig = ark_bls12_377::Config;
+        type BaseSNARKField = BlsSigField<BlsSigConfig>;
+
+        for dst in ["".as_bytes(), b"BLS_SIG_BLS12377G2_XMD:BLAKE2S_SSWU_RO_"] {
+            for msg in ["", "abc", "Hello World", "abcdef0123456789"] {
+                let cs = ConstraintSystem::<BaseSNARKField>::new_ref();
+                let dst_var: Vec<_> = dst.iter().map(|b| UInt8::constant(*b)).collect();
+                let msg_var = UInt8::new_input_vec(cs.clone(), msg.as_bytes()).unwrap();
+
+                let p = hash_to_g2::<BlsSigConfig, FpVar<BaseSNARKField>, BaseSNARKField>(
+                    &dst_var, &msg_var,
+                )
+                .unwrap();
+
+                assert_eq!(
+                    p.value().unwrap(),
+                    Parameters::<BlsSigConfig>::hash_to_g2(dst, msg.as_bytes())
+                );
+                assert!(cs.is_satisfied().unwrap());
+            }
+        }
+    }
 }
@@ -1,6 +1,13 @@
+use ark_crypto_primitives::{crh::poseidon::constraints::CRHParametersVar, sponge::Absorb};
 use ark_ff::PrimeField;
 use ark_r1cs_std::{
-    alloc::AllocVar, fields::emulated_fp::EmulatedFpVar, prelude::Boolean, uint64::UInt64,
+    alloc::AllocVar,
+    convert::ToConstraintFieldGadget,
+    eq::EqGadget,
+    fields::{emulated_fp::EmulatedFpVar, fp::FpVar, FieldVar},
+    groups::bls12::G1Var,
+    prelude::Boolean,
+    uint64::UInt64,
     uint8::UInt8,
 };
 use ark_relations::r1cs::SynthesisError;
@@ -8,13 +15,17 @@ use derivative::Derivative;
 
 use crate::{
     bc::{
-        block::{Block, Committee, QuorumSignature},
+        block::{committee_tree_capacity, Block, Committee, QuorumSignature},
         params::HASH_OUTPUT_SIZE,
     },
     bls::{PublicKey, PublicKeyVar, SignatureVar},
+    hash::prf::{blake2s::constraints::StatefulBlake2sGadget, constraints::PRFGadget},
+    merkle::{constraints::MerkleTreeVar, Config},
     params::{BlsSigConfig, BlsSigField},
 };
 
+use super::serialize::SerializeGadget;
+
 #[derive(Derivative)]
 #[derivative(Clone(bound = ""), Debug(bound = ""))]
 pub struct SignerVar<CF: PrimeField> {
@@ -32,13 +43,24 @@ pub struct CommitteeVar<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize> {
     pub committee: Vec<SignerVar<CF>>,
 }
 
+/// The signer bitmap of a block, where the `i`-th bit tells whether the
+/// `i`-th member of the committee signed the block.
+///
+/// It is serialized with 8 signers per byte, as `QuorumSignature::signers`.
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""), Debug(bound = ""))]
+pub struct SignersVar<CF: PrimeField>(pub Vec<Boolean<CF>>);
+
 #[derive(Derivative)]
 #[derivative(Clone(bound = ""), Debug(bound = ""))]
 pub struct QuorumSignatureVar<CF: PrimeField> {
     pub sig: SignatureVar<BlsSigConfig, EmulatedFpVar<BlsSigField<BlsSigConfig>, CF>, CF>,
-    pub signers: Vec<Boolean<CF>>,
+    pub signers: SignersVar<CF>,
 }
 
+/// In-circuit digest of a block, see `BlockVar::digest`.
+pub type DigestVar<CF> = [UInt8<CF>; HASH_OUTPUT_SIZE];
+
 /// Copied from `sig/src/bc/block.rs`
 #[derive(Derivative)]
 #[derivative(Clone(bound = ""), Debug(bound = ""))]
@@ -54,6 +76,12 @@ pub struct BlockVar<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize> {
     /// (pks reside on the curve and the prime order subgroup) of the first committee and new blocks signed
     /// by the majority of the committee.
     pub committee: CommitteeVar<CF, MAX_COMMITTEE_SIZE>,
+
+    /// Whether the block starts the chain, which is not part of `Block` and
+    /// is allocated as `epoch == 0`. A genesis block is not signed, so only
+    /// `BCCircuitNoMerkle::with_genesis` accepts it, in the first step and
+    /// with the genesis committee it is built with.
+    pub is_genesis: Boolean<CF>,
 }
 
 impl<CF: PrimeField> AllocVar<(PublicKey<BlsSigConfig>, u64), CF> for SignerVar<CF> {
@@ -142,6 +170,88 @@ impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize> AllocVar<Committee<MAX_COM
     }
 }
 
+impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize> CommitteeVar<CF, MAX_COMMITTEE_SIZE> {
+    /// Enforce that the weights of the committee sum to at most
+    /// `total_power`, and, with the `distinct-committee-keys` feature, that
+    /// no public key appears twice, see `enforce_distinct_keys`. This is the
+    /// in-circuit counterpart of `Committee::is_valid`.
+    ///
+    /// The weights are summed in `CF`, where `MAX_COMMITTEE_SIZE` 64-bit
+    /// weights cannot wrap around, so `total_power - sum` fits in 64 bits iff
+    /// `sum <= total_power`.
+    pub fn enforce_valid(&self, total_power: &UInt64<CF>) -> Result<(), SynthesisError> {
+        let mut sum = FpVar::zero();
+        for signer in &self.committee {
+            sum += signer.weight.to_fp()?;
+        }
+        (total_power.to_fp()? - sum).to_bits_le_with_top_bits_zero(64)?;
+
+        if cfg!(feature = "distinct-committee-keys") {
+            self.enforce_distinct_keys()?;
+        }
+        Ok(())
+    }
+
+    /// Enforce that the members with a non-zero weight have pairwise distinct
+    /// public keys, so that no key counts twice towards a quorum.
+    ///
+    /// Every pair of members is compared as curve points, which costs
+    /// `O(MAX_COMMITTEE_SIZE^2)` emulated multiplications.
+    pub fn enforce_distinct_keys(&self) -> Result<(), SynthesisError> {
+        let members = self
+            .committee
+            .iter()
+            .map(|signer| {
+                let pk: &G1Var<BlsSigConfig, EmulatedFpVar<BlsSigField<BlsSigConfig>, CF>, CF> =
+                    signer.pk.as_ref();
+                Ok((pk, !signer.weight.to_fp()?.is_zero()?))
+            })
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+
+        for (i, (pk, active)) in members.iter().enumerate() {
+            for (other, other_active) in &members[i + 1..] {
+                pk.conditional_enforce_not_equal(other, &(active & other_active))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize>
+    CommitteeVar<CF, MAX_COMMITTEE_SIZE>
+{
+    /// In-circuit counterpart of `Committee::merkle_root`.
+    pub fn merkle_root(&self, params: &CRHParametersVar<CF>) -> Result<FpVar<CF>, SynthesisError> {
+        let values = self
+            .committee
+            .iter()
+            .map(ToConstraintFieldGadget::to_constraint_field)
+            .collect::<Result<Vec<_>, _>>()?;
+        let values: Vec<_> = values.iter().map(Vec::as_slice).collect();
+
+        let tree = MerkleTreeVar::<Config<CF>>::from_values(
+            &values,
+            committee_tree_capacity(MAX_COMMITTEE_SIZE),
+            params,
+        )?;
+        Ok(tree.root())
+    }
+}
+
+impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize> BlockVar<CF, MAX_COMMITTEE_SIZE> {
+    /// In-circuit counterpart of `compute_digest`: the Blake2s digest of the
+    /// serialized block, including the signature.
+    pub fn digest(&self) -> Result<DigestVar<CF>, SynthesisError> {
+        let mut hasher = StatefulBlake2sGadget::<CF, HASH_OUTPUT_SIZE>::default();
+        hasher.update(&self.serialize()?)?;
+        hasher
+            .finalize()?
+            .0
+            .try_into()
+            .map_err(|_| SynthesisError::Unsatisfiable)
+    }
+}
+
 impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize>
     AllocVar<QuorumSignature<MAX_COMMITTEE_SIZE>, CF> for QuorumSignatureVar<CF>
 {
@@ -195,7 +305,10 @@ impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize>
         // Update: It's not correct to extend it here. Rather, we need to enforce all the state outside the circuit has
         // fixed size. Otherwise, the hash of those states will never match their circuit counterpart.
 
-        Ok(Self { sig, signers })
+        Ok(Self {
+            sig,
+            signers: SignersVar(signers),
+        })
     }
 }
 
@@ -245,7 +358,7 @@ impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize> AllocVar<Block<MAX_COMMITT
         )?;
 
         let committee = CommitteeVar::new_variable(
-            cs,
+            cs.clone(),
             || {
                 block
                     .as_ref()
@@ -258,11 +371,295 @@ impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize> AllocVar<Block<MAX_COMMITT
             mode,
         )?;
 
+        let is_genesis = Boolean::new_variable(
+            cs,
+            || {
+                block
+                    .as_ref()
+                    .map(|block| block.borrow().epoch == 0)
+                    .map_err(SynthesisError::clone)
+            },
+            mode,
+        )?;
+
         Ok(Self {
             epoch,
             prev_digest,
             sig,
             committee,
+            is_genesis,
         })
     }
 }
+
+/// The external inputs of `BCCircuitHashedCommittee`: the new block together
+/// with the committee of the previous block, which the folding state only
+/// stores as a hash.
+#[derive(Debug, Default, Clone)]
+pub struct BlockWithCommittee<const MAX_COMMITTEE_SIZE: usize> {
+    pub committee: Committee<MAX_COMMITTEE_SIZE>,
+    pub block: Block<MAX_COMMITTEE_SIZE>,
+}
+
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""), Debug(bound = ""))]
+pub struct BlockWithCommitteeVar<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize> {
+    pub committee: CommitteeVar<CF, MAX_COMMITTEE_SIZE>,
+    pub block: BlockVar<CF, MAX_COMMITTEE_SIZE>,
+}
+
+impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize>
+    AllocVar<BlockWithCommittee<MAX_COMMITTEE_SIZE>, CF>
+    for BlockWithCommitteeVar<CF, MAX_COMMITTEE_SIZE>
+{
+    fn new_variable<T: std::borrow::Borrow<BlockWithCommittee<MAX_COMMITTEE_SIZE>>>(
+        cs: impl Into<ark_relations::r1cs::Namespace<CF>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: ark_r1cs_std::prelude::AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let cs = cs.into();
+
+        let input = f();
+
+        let committee = CommitteeVar::new_variable(
+            cs.clone(),
+            || {
+                input
+                    .as_ref()
+                    .map(|input| input.borrow().committee.clone())
+                    .map_err(SynthesisError::clone)
+            },
+            mode,
+        )?;
+
+        let block = BlockVar::new_variable(
+            cs,
+            || {
+                input
+                    .as_ref()
+                    .map(|input| input.borrow().block.clone())
+                    .map_err(SynthesisError::clone)
+            },
+            mode,
+        )?;
+
+        Ok(Self { committee, block })
+    }
+}
+
+/// The external inputs of `BCCircuitNoMerkleBatched`: `K` consecutive blocks,
+/// of which only those in `enabled` are new.
+///
+/// A disabled slot is a no-op, and must repeat the block before it, i.e. the
+/// committee and epoch of the current state. `new` pads a batch that way when
+/// fewer than `K` blocks remain.
+#[derive(Debug, Clone)]
+pub struct BlockBatch<const MAX_COMMITTEE_SIZE: usize, const K: usize> {
+    pub blocks: [Block<MAX_COMMITTEE_SIZE>; K],
+    pub enabled: [bool; K],
+}
+
+impl<const MAX_COMMITTEE_SIZE: usize, const K: usize> BlockBatch<MAX_COMMITTEE_SIZE, K> {
+    /// Batch `blocks`, which follow `prev`, padding the batch with copies of
+    /// the last block (or `prev` if `blocks` is empty).
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than `K` blocks.
+    #[must_use]
+    pub fn new(prev: &Block<MAX_COMMITTEE_SIZE>, blocks: &[Block<MAX_COMMITTEE_SIZE>]) -> Self {
+        assert!(
+            blocks.len() <= K,
+            "a batch holds at most {K} blocks, but got {}",
+            blocks.len()
+        );
+
+        let padding = blocks.last().unwrap_or(prev);
+        Self {
+            blocks: core::array::from_fn(|i| blocks.get(i).unwrap_or(padding).clone()),
+            enabled: core::array::from_fn(|i| i < blocks.len()),
+        }
+    }
+}
+
+impl<const MAX_COMMITTEE_SIZE: usize, const K: usize> Default
+    for BlockBatch<MAX_COMMITTEE_SIZE, K>
+{
+    fn default() -> Self {
+        Self {
+            blocks: core::array::from_fn(|_| Block::default()),
+            enabled: [false; K],
+        }
+    }
+}
+
+/// Both vectors have length `K`.
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""), Debug(bound = ""))]
+pub struct BlockBatchVar<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize, const K: usize> {
+    pub blocks: Vec<BlockVar<CF, MAX_COMMITTEE_SIZE>>,
+    pub enabled: Vec<Boolean<CF>>,
+}
+
+impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize, const K: usize>
+    AllocVar<BlockBatch<MAX_COMMITTEE_SIZE, K>, CF> for BlockBatchVar<CF, MAX_COMMITTEE_SIZE, K>
+{
+    fn new_variable<T: std::borrow::Borrow<BlockBatch<MAX_COMMITTEE_SIZE, K>>>(
+        cs: impl Into<ark_relations::r1cs::Namespace<CF>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: ark_r1cs_std::prelude::AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let cs = cs.into();
+
+        let input = f();
+
+        let mut blocks = Vec::with_capacity(K);
+        let mut enabled = Vec::with_capacity(K);
+        for i in 0..K {
+            blocks.push(BlockVar::new_variable(
+                cs.clone(),
+                || {
+                    input
+                        .as_ref()
+                        .map(|input| input.borrow().blocks[i].clone())
+                        .map_err(SynthesisError::clone)
+                },
+                mode,
+            )?);
+            enabled.push(Boolean::new_variable(
+                cs.clone(),
+                || {
+                    input
+                        .as_ref()
+                        .map(|input| input.borrow().enabled[i])
+                        .map_err(SynthesisError::clone)
+                },
+                mode,
+            )?);
+        }
+
+        Ok(Self { blocks, enabled })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Fr;
+    use ark_crypto_primitives::crh::poseidon::constraints::CRHParametersVar;
+    use ark_r1cs_std::{alloc::AllocVar, uint64::UInt64, R1CSVar};
+    use ark_relations::r1cs::ConstraintSystem;
+    use folding_schemes::transcript::poseidon::poseidon_canonical_config;
+    use rand::thread_rng;
+
+    use crate::bc::{
+        block::{compute_digest, gen_blockchain_with_params, Blockchain, Committee},
+        params::TOTAL_VOTING_POWER,
+    };
+
+    use super::{BlockVar, CommitteeVar};
+
+    const MAX_COMMITTEE_SIZE: usize = 4;
+
+    #[test]
+    fn test_committee_merkle_root() {
+        let params = poseidon_canonical_config::<Fr>();
+        let bc: Blockchain<MAX_COMMITTEE_SIZE> =
+            gen_blockchain_with_params(3, MAX_COMMITTEE_SIZE, &mut thread_rng());
+
+        let roots = bc
+            .into_blocks()
+            .map(|block| {
+                let expected = block.committee.merkle_root(&params).unwrap();
+
+                let cs = ConstraintSystem::<Fr>::new_ref();
+                let params_var = CRHParametersVar::new_constant(cs.clone(), &params).unwrap();
+                let committee_var =
+                    CommitteeVar::<_, MAX_COMMITTEE_SIZE>::new_witness(cs.clone(), || {
+                        Ok(&block.committee)
+                    })
+                    .unwrap();
+                let root = committee_var.merkle_root(&params_var).unwrap();
+
+                assert!(cs.is_satisfied().unwrap());
+                assert_eq!(root.value().unwrap(), expected);
+                expected
+            })
+            .collect::<Vec<_>>();
+
+        // every epoch elects a freshly generated committee
+        assert!(roots.windows(2).all(|w| w[0] != w[1]));
+    }
+
+    fn committee_is_valid(committee: &Committee<MAX_COMMITTEE_SIZE>, distinct: bool) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let committee_var =
+            CommitteeVar::<_, MAX_COMMITTEE_SIZE>::new_witness(cs.clone(), || Ok(committee))
+                .unwrap();
+        committee_var
+            .enforce_valid(&UInt64::constant(TOTAL_VOTING_POWER))
+            .unwrap();
+        if distinct {
+            committee_var.enforce_distinct_keys().unwrap();
+        }
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_committee_enforce_valid() {
+        let bc: Blockchain<MAX_COMMITTEE_SIZE> =
+            gen_blockchain_with_params(2, MAX_COMMITTEE_SIZE, &mut thread_rng());
+        let committee = bc.into_blocks().last().unwrap().committee.clone();
+        assert!(committee_is_valid(&committee, false));
+        assert!(committee_is_valid(&Committee::default(), false));
+
+        let mut overweight = committee.clone();
+        let quarter = TOTAL_VOTING_POWER / 4;
+        for (i, (_, weight)) in overweight.signers.iter_mut().enumerate() {
+            *weight = if i == 0 { quarter + 1 } else { quarter };
+        }
+        assert_eq!(
+            overweight.signers.iter().map(|(_, w)| w).sum::<u64>(),
+            TOTAL_VOTING_POWER + 1
+        );
+        assert!(!committee_is_valid(&overweight, false));
+    }
+
+    #[test]
+    fn test_committee_enforce_distinct_keys() {
+        let bc: Blockchain<MAX_COMMITTEE_SIZE> =
+            gen_blockchain_with_params(2, MAX_COMMITTEE_SIZE, &mut thread_rng());
+        let mut committee = bc.into_blocks().last().unwrap().committee.clone();
+        for (_, weight) in committee.signers.iter_mut() {
+            *weight = TOTAL_VOTING_POWER / 4;
+        }
+        assert!(committee_is_valid(&committee, true));
+        // the padding of a default committee repeats the same key with weight 0
+        assert!(committee_is_valid(&Committee::default(), true));
+
+        let mut duplicated = committee.clone();
+        duplicated.signers[1].0 = duplicated.signers[0].0.clone();
+        assert!(!committee_is_valid(&duplicated, true));
+
+        // a repeated key without weight cannot count towards a quorum
+        duplicated.signers[1].1 = 0;
+        assert!(committee_is_valid(&duplicated, true));
+    }
+
+    #[test]
+    fn test_block_digest() {
+        let bc: Blockchain<MAX_COMMITTEE_SIZE> =
+            gen_blockchain_with_params(3, MAX_COMMITTEE_SIZE, &mut thread_rng());
+        let blocks: Vec<_> = bc.into_blocks().collect();
+
+        for (block, next) in blocks.iter().zip(&blocks[1..]) {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let block_var =
+                BlockVar::<_, MAX_COMMITTEE_SIZE>::new_witness(cs.clone(), || Ok(block)).unwrap();
+            let digest = block_var.digest().unwrap().value().unwrap();
+
+            assert!(cs.is_satisfied().unwrap());
+            assert_eq!(digest, compute_digest(block));
+            assert_eq!(digest, next.prev_digest);
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! Versioned checkpoints of a folding instance on disk.
+//!
+//! A checkpoint is a small header followed by the IVC proof written by
+//! `resume::save`, i.e. `i`, `z_0`, `z_i` and the running and incoming
+//! instances and witnesses. The header identifies the file as a checkpoint
+//! and carries `CHECKPOINT_VERSION`, so that a checkpoint written with an
+//! incompatible layout is rejected up front instead of failing somewhere in
+//! the middle of deserialization.
+//!
+//! As for `resume`, the prover and verifier parameters are not part of the
+//! checkpoint and have to be passed back to `load_nova_state`.
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use folding_schemes::{frontend::FCircuit, Curve, FoldingScheme};
+use thiserror::Error;
+
+use super::resume::{restore, save};
+
+/// Bytes every checkpoint starts with.
+pub const CHECKPOINT_MAGIC: [u8; 8] = *b"MIMFOLD\0";
+
+/// Version of the checkpoint layout, bumped whenever a checkpoint written by
+/// an older version can no longer be loaded.
+pub const CHECKPOINT_VERSION: u32 = 1;
+
+#[derive(Error, Debug)]
+pub enum CheckpointError {
+    #[error("I/O error while accessing the checkpoint: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("File is not a folding checkpoint")]
+    InvalidMagic,
+
+    #[error("Checkpoint version {found} is not supported, expected version {expected}")]
+    UnsupportedVersion { found: u32, expected: u32 },
+
+    #[error("Folding scheme error occurred: {0}")]
+    FoldingError(#[from] folding_schemes::Error),
+}
+
+/// Write the running state of `fs` to a checkpoint at `path`, replacing any
+/// existing file.
+pub fn save_nova_state<C1, C2, FC, FS>(
+    path: impl AsRef<Path>,
+    fs: &FS,
+) -> Result<(), CheckpointError>
+where
+    C1: Curve,
+    C2: Curve,
+    FC: FCircuit<C1::ScalarField>,
+    FS: FoldingScheme<C1, C2, FC>,
+{
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(&CHECKPOINT_MAGIC)?;
+    writer.write_all(&CHECKPOINT_VERSION.to_le_bytes())?;
+    save(fs, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Restore a folding instance from a checkpoint written by `save_nova_state`,
+/// which then continues folding with `prove_step` as if it was never
+/// interrupted.
+#[allow(clippy::type_complexity)]
+pub fn load_nova_state<C1, C2, FC, FS>(
+    path: impl AsRef<Path>,
+    params: (FS::ProverParam, FS::VerifierParam),
+    fcircuit_params: FC::Params,
+) -> Result<FS, CheckpointError>
+where
+    C1: Curve,
+    C2: Curve,
+    FC: FCircuit<C1::ScalarField>,
+    FS: FoldingScheme<C1, C2, FC>,
+{
+    let mut reader = BufReader::new(File::open(path)?);
+
+    let mut magic = [0; CHECKPOINT_MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    if magic != CHECKPOINT_MAGIC {
+        return Err(CheckpointError::InvalidMagic);
+    }
+
+    let mut version = [0; 4];
+    reader.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version != CHECKPOINT_VERSION {
+        return Err(CheckpointError::UnsupportedVersion {
+            found: version,
+            expected: CHECKPOINT_VERSION,
+        });
+    }
+
+    Ok(restore(params, fcircuit_params, reader)?)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::PathBuf};
+
+    use ark_mnt4_298::{Fr, G1Projective as G1};
+    use ark_mnt6_298::G1Projective as G2;
+    use folding_schemes::{
+        commitment::pedersen::Pedersen,
+        folding::nova::{Nova, PreprocessorParam},
+        frontend::FCircuit,
+        transcript::poseidon::poseidon_canonical_config,
+        FoldingScheme,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::folding::test_circuit::MockCircuit;
+
+    use super::{
+        load_nova_state, save_nova_state, CheckpointError, CHECKPOINT_MAGIC, CHECKPOINT_VERSION,
+    };
+
+    type FC = MockCircuit<Fr>;
+    type N = Nova<G1, G2, FC, Pedersen<G1>, Pedersen<G2>, false>;
+
+    fn checkpoint_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("{name}-{}.ckpt", std::process::id()))
+    }
+
+    #[test]
+    fn test_checkpoint_resume() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let f_circuit = FC::new(()).unwrap();
+        let nova_params = N::preprocess(
+            &mut rng,
+            &PreprocessorParam::new(poseidon_canonical_config::<Fr>(), f_circuit),
+        )
+        .unwrap();
+        let inputs = [Fr::from(1u64), Fr::from(2u64), Fr::from(3u64)];
+
+        // fold every step without interruption
+        let mut uninterrupted = N::init(&nova_params, f_circuit, vec![Fr::from(7u64)]).unwrap();
+        for x in inputs {
+            uninterrupted.prove_step(&mut rng, x, None).unwrap();
+        }
+
+        // fold 2 steps, checkpoint, and fold the last step after reloading
+        let mut nova = N::init(&nova_params, f_circuit, vec![Fr::from(7u64)]).unwrap();
+        for x in &inputs[..2] {
+            nova.prove_step(&mut rng, *x, None).unwrap();
+        }
+
+        let path = checkpoint_path("test_checkpoint_resume");
+        save_nova_state(&path, &nova).unwrap();
+        drop(nova);
+
+        let mut nova: N = load_nova_state(&path, nova_params.clone(), ()).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(nova.i, Fr::from(2u64));
+
+        nova.prove_step(&mut rng, inputs[2], None).unwrap();
+        assert_eq!(nova.i, Fr::from(3u64));
+        assert_eq!(nova.z_i, uninterrupted.z_i);
+        assert!(nova.ivc_proof() == uninterrupted.ivc_proof());
+        N::verify(nova_params.1, nova.ivc_proof()).unwrap();
+    }
+
+    #[test]
+    fn test_checkpoint_header() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let f_circuit = FC::new(()).unwrap();
+        let nova_params = N::preprocess(
+            &mut rng,
+            &PreprocessorParam::new(poseidon_canonical_config::<Fr>(), f_circuit),
+        )
+        .unwrap();
+        let nova = N::init(&nova_params, f_circuit, vec![Fr::from(7u64)]).unwrap();
+
+        let path = checkpoint_path("test_checkpoint_header");
+        save_nova_state(&path, &nova).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(bytes[..CHECKPOINT_MAGIC.len()], CHECKPOINT_MAGIC);
+
+        // a checkpoint from another version of the layout
+        let mut newer = bytes.clone();
+        newer[CHECKPOINT_MAGIC.len()..CHECKPOINT_MAGIC.len() + 4]
+            .copy_from_slice(&(CHECKPOINT_VERSION + 1).to_le_bytes());
+        fs::write(&path, newer).unwrap();
+        assert!(matches!(
+            load_nova_state::<_, _, _, N>(&path, nova_params.clone(), ()),
+            Err(CheckpointError::UnsupportedVersion { found, expected })
+                if found == CHECKPOINT_VERSION + 1 && expected == CHECKPOINT_VERSION
+        ));
+
+        // a file that is not a checkpoint
+        let mut corrupted = bytes;
+        corrupted[0] ^= 1;
+        fs::write(&path, corrupted).unwrap();
+        assert!(matches!(
+            load_nova_state::<_, _, _, N>(&path, nova_params, ()),
+            Err(CheckpointError::InvalidMagic)
+        ));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
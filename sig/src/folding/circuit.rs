@@ -1,6 +1,12 @@
 use std::{cmp::Ordering, marker::PhantomData};
 
-use ark_crypto_primitives::{crh::poseidon::constraints::CRHParametersVar, sponge::Absorb};
+use ark_crypto_primitives::{
+    crh::{
+        poseidon::constraints::{CRHGadget, CRHParametersVar},
+        CRHSchemeGadget,
+    },
+    sponge::Absorb,
+};
 use ark_ff::PrimeField;
 use ark_r1cs_std::{
     alloc::AllocVar,
@@ -17,29 +23,126 @@ use folding_schemes::{frontend::FCircuit, transcript::poseidon::poseidon_canonic
 
 use crate::{
     bc::{
-        block::{Block, QuorumSignature},
-        params::STRONG_THRESHOLD,
+        block::{Block, Committee, QuorumSignature},
+        params::{QuorumThreshold, TOTAL_VOTING_POWER},
     },
     bls::{BLSAggregateSignatureVerifyGadget, Parameters, ParametersVar},
-    folding::bc::{CommitteeVar, QuorumSignatureVar},
+    folding::bc::{BlockBatch, BlockWithCommittee, CommitteeVar, DigestVar, QuorumSignatureVar},
     merkle::{constraints::LeveledMerkleForestVar, forest::optimal_forest_params, Config},
-    params::BlsSigConfig,
+    params::{BlsSigConfig, BlsSigField},
 };
 
 use super::{
-    bc::BlockVar, from_constraint_field::FromConstraintFieldGadget, serialize::SerializeGadget,
+    bc::{BlockBatchVar, BlockVar, BlockWithCommitteeVar},
+    decider::committee_commitment_for,
+    from_constraint_field::FromConstraintFieldGadget,
+    serialize::SerializeGadget,
 };
 
+/// The state is `committee || epoch || digest || acc`, where `digest` is the
+/// digest of the last folded block, which the next block must store as
+/// `prev_digest`, and `acc = Poseidon(acc_prev || digest)` accumulates the
+/// digests of all folded blocks.
+///
+/// Without `acc`, two chains that reach the same committee and last block
+/// would give the same final state. `fold_digest_chain` computes the expected
+/// `acc` of a sequence of blocks, so a verifier can check which blocks were
+/// folded, and in which order.
+#[derive(Clone, Debug)]
+pub struct BCCircuitNoMerkle<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize> {
+    sig_params: Parameters<BlsSigConfig>,
+    threshold: QuorumThreshold,
+    /// The optimization goal of the constraint system, which sets the number
+    /// of limbs of the committee in the state.
+    optim: OptimizationGoal,
+    /// The genesis committee, if genesis steps are allowed, see
+    /// `with_genesis`. Its commitment is derived when the circuit is
+    /// synthesized, as it depends on the optimization goal.
+    genesis: Option<Committee<MAX_COMMITTEE_SIZE>>,
+    _cf: PhantomData<CF>,
+}
+
+/// Same as `BCCircuitNoMerkle`, but every step processes `K` consecutive
+/// blocks, which amortizes the fixed cost of a folding step over `K` blocks.
+///
+/// The committee and epoch are threaded between the blocks of a step, and a
+/// step with fewer than `K` new blocks disables the remaining slots, see
+/// `BlockBatch`. With `K = 1`, the state after each step is the same as in
+/// `BCCircuitNoMerkle`, without the accumulator of the digests.
+///
+/// Genesis steps are not supported, see `BCCircuitNoMerkle::with_genesis`:
+/// every block has to be signed by the committee of the state, and one with
+/// `is_genesis` set is rejected.
 #[derive(Clone, Copy, Debug)]
-pub struct BCCircuitNoMerkle<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize> {
+pub struct BCCircuitNoMerkleBatched<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize, const K: usize>
+{
+    sig_params: Parameters<BlsSigConfig>,
+    threshold: QuorumThreshold,
+    /// The optimization goal of the constraint system, which sets the number
+    /// of limbs of the committee in the state.
+    optim: OptimizationGoal,
+    _cf: PhantomData<CF>,
+}
+
+/// Same as `BCCircuitNoMerkle`, but the state is
+/// `Poseidon(committee) || epoch || digest` instead of the committee itself.
+///
+/// This shrinks the folding state from hundreds of field elements to two, at
+/// the cost of hashing the old and the new committee in every step. Because
+/// the state no longer contains the committee, the prover supplies it as part
+/// of the external inputs, and the circuit enforces that it hashes to the
+/// committed value. Both circuits accept the same chains.
+///
+/// As in `BCCircuitNoMerkleBatched`, genesis steps are not supported.
+#[derive(Clone, Derivative)]
+#[derivative(Debug)]
+pub struct BCCircuitHashedCommittee<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize> {
     sig_params: Parameters<BlsSigConfig>,
+    threshold: QuorumThreshold,
+
+    #[derivative(Debug = "ignore")]
+    hash_params: CRHParametersVar<CF>,
+
     _cf: PhantomData<CF>,
 }
 
+/// The state is `committee || epoch || digest || forest`, where the forest
+/// stores the hash of the committee of every epoch.
+///
+/// As in `BCCircuitNoMerkleBatched`, genesis steps are not supported.
 #[derive(Clone, Derivative)]
 #[derivative(Debug)]
 pub struct BCCircuitMerkleForest<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize> {
     sig_params: Parameters<BlsSigConfig>,
+    threshold: QuorumThreshold,
+    /// See `BCCircuitNoMerkle::optim`.
+    optim: OptimizationGoal,
+
+    // Merkle Forest params
+    capacity_per_tree: u32,
+    num_tree: u32,
+
+    #[derivative(Debug = "ignore")]
+    hash_params: CRHParametersVar<CF>,
+
+    _cf: PhantomData<CF>,
+}
+
+/// Same as `BCCircuitMerkleForest`, but the state stores the Poseidon hash of
+/// the committee instead of the committee itself, as in
+/// `BCCircuitHashedCommittee`, i.e. it is
+/// `Poseidon(committee) || epoch || digest || forest`.
+///
+/// The previous committee is thus part of the external inputs, and the forest
+/// stores the same leaves as in `BCCircuitMerkleForest`, so both circuits
+/// compute the same forest for a chain.
+///
+/// As in `BCCircuitNoMerkleBatched`, genesis steps are not supported.
+#[derive(Clone, Derivative)]
+#[derivative(Debug)]
+pub struct BCCircuitMerkleOnly<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize> {
+    sig_params: Parameters<BlsSigConfig>,
+    threshold: QuorumThreshold,
 
     // Merkle Forest params
     capacity_per_tree: u32,
@@ -51,24 +154,77 @@ pub struct BCCircuitMerkleForest<CF: PrimeField + Absorb, const MAX_COMMITTEE_SI
     _cf: PhantomData<CF>,
 }
 
-impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize> FCircuit<CF>
+impl<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize>
+    BCCircuitNoMerkle<CF, MAX_COMMITTEE_SIZE>
+{
+    /// Require the signers of every block to have a total weight that meets
+    /// `threshold` instead of the one given in `new`, as
+    /// `Blockchain::with_threshold`.
+    #[must_use]
+    pub fn with_threshold(self, threshold: impl Into<QuorumThreshold>) -> Self {
+        Self {
+            threshold: threshold.into(),
+            ..self
+        }
+    }
+
+    /// Encode the committee in the state for a constraint system with the
+    /// optimization goal `optim` instead of `OptimizationGoal::Constraints`.
+    ///
+    /// The emulated coordinates of the public keys have a different number of
+    /// limbs for each goal, so the circuit has to be synthesized in a
+    /// constraint system with the goal given here, and the initial state has
+    /// to come from `initial_state_for` with the same goal.
+    #[must_use]
+    pub fn with_optimization_goal(self, optim: OptimizationGoal) -> Self {
+        Self { optim, ..self }
+    }
+
+    /// Fold the genesis block in the first step, which starts the chain with
+    /// `committee` at epoch 0.
+    ///
+    /// A genesis block is not signed, so instead its committee has to hash to
+    /// the commitment of `committee`, see `committee_commitment_for`. The step
+    /// is a genesis step iff the accumulator of `z_i` is the one of an empty
+    /// chain, so `z_0` is `initial_state_for` of any block, and no later step
+    /// can restart the chain. Without a genesis committee, every genesis block
+    /// is rejected.
+    #[must_use]
+    pub fn with_genesis(self, committee: &Committee<MAX_COMMITTEE_SIZE>) -> Self {
+        Self {
+            genesis: Some(committee.clone()),
+            ..self
+        }
+    }
+
+    /// The length of the state in a constraint system with the optimization
+    /// goal `optim`.
+    #[must_use]
+    pub fn state_len_for(optim: OptimizationGoal) -> usize {
+        block_state_len_for::<CF, MAX_COMMITTEE_SIZE>(optim) + 1
+    }
+}
+
+impl<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize> FCircuit<CF>
     for BCCircuitNoMerkle<CF, MAX_COMMITTEE_SIZE>
 {
-    type Params = Parameters<BlsSigConfig>;
+    /// The BLS parameters and the quorum threshold of every block.
+    type Params = (Parameters<BlsSigConfig>, QuorumThreshold);
     type ExternalInputs = Block<MAX_COMMITTEE_SIZE>;
     type ExternalInputsVar = BlockVar<CF, MAX_COMMITTEE_SIZE>;
 
     fn new(params: Self::Params) -> Result<Self, Error> {
         Ok(Self {
-            sig_params: params,
+            sig_params: params.0,
+            threshold: params.1,
+            optim: OptimizationGoal::Constraints,
+            genesis: None,
             _cf: PhantomData,
         })
     }
 
     fn state_len(&self) -> usize {
-        CommitteeVar::<CF, MAX_COMMITTEE_SIZE>::num_constraint_var_needed(
-            OptimizationGoal::Constraints,
-        ) + UInt64::<CF>::num_constraint_var_needed(OptimizationGoal::Constraints)
+        Self::state_len_for(self.optim)
     }
 
     /// generates the constraints for the step of F for the given z_i
@@ -80,32 +236,67 @@ impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize> FCircuit<CF>
         z_i: Vec<FpVar<CF>>,
         external_inputs: Self::ExternalInputsVar,
     ) -> Result<Vec<FpVar<CF>>, SynthesisError> {
-        tracing::info!("start reconstructing committee and epoch");
+        tracing::info!("start reconstructing committee, epoch, digest and accumulator");
 
-        let optim = cs.optimization_goal();
+        let optim = optimization_goal(&cs, self.optim)?;
 
-        // 1. Reconstruct epoch and committee from z_i
+        // 1. Reconstruct epoch, committee and digest of the previous block, and
+        // the accumulator of the digests from z_i
         let mut iter = z_i.into_iter();
         let committee = CommitteeVar::from_constraint_field(iter.by_ref(), optim)?;
         let epoch = UInt64::from_constraint_field(iter.by_ref(), optim)?;
+        let digest = DigestVar::from_constraint_field(iter.by_ref(), optim)?;
+        let acc = FpVar::from_constraint_field(iter.by_ref(), optim)?;
+        ensure_consumed(iter)?;
 
         tracing::info!(num_constraints = cs.num_constraints());
 
-        // 2. Enforce constraints
-        bc_generate_constraints(
+        // 2. Enforce constraints, where only the first step, which starts
+        // from the accumulator of an empty chain, folds the genesis block, and
+        // the genesis block has to carry the genesis committee
+        let is_genesis_committee = match &self.genesis {
+            Some(genesis) => {
+                // `fold_digest_chain` of no blocks is zero
+                let is_first_step = acc.is_zero()?;
+                external_inputs.is_genesis.enforce_equal(&is_first_step)?;
+
+                let commitment = committee_commitment_for(genesis, optim)?;
+                hash_committee(
+                    &external_inputs.committee,
+                    &CRHParametersVar {
+                        parameters: poseidon_canonical_config::<CF>(),
+                    },
+                )?
+                .is_eq(&FpVar::constant(commitment))?
+            }
+            None => Boolean::FALSE,
+        };
+        let digest = bc_generate_constraints(
             cs.clone(),
             &external_inputs,
             epoch,
             committee,
+            &digest,
             self.sig_params,
+            self.threshold,
+            &is_genesis_committee,
         )?;
 
-        // 3. Return the new state
+        // 3. Fold the digest of the block into the accumulator
+        tracing::info!("start accumulating the digest");
+
+        let acc = accumulate_digest(&acc, &digest)?;
+
+        tracing::info!(num_constraints = cs.num_constraints());
+
+        // 4. Return the new state
         tracing::info!("start returning the new state");
 
         let mut committee = external_inputs.committee.to_constraint_field()?;
         let epoch = external_inputs.epoch.to_fp()?;
         committee.push(epoch);
+        committee.extend(digest.to_constraint_field()?);
+        committee.push(acc);
 
         tracing::info!(num_constraints = cs.num_constraints());
 
@@ -113,10 +304,245 @@ impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize> FCircuit<CF>
     }
 }
 
+impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize, const K: usize>
+    BCCircuitNoMerkleBatched<CF, MAX_COMMITTEE_SIZE, K>
+{
+    /// See `BCCircuitNoMerkle::with_threshold`.
+    #[must_use]
+    pub fn with_threshold(self, threshold: impl Into<QuorumThreshold>) -> Self {
+        Self {
+            threshold: threshold.into(),
+            ..self
+        }
+    }
+
+    /// See `BCCircuitNoMerkle::with_optimization_goal`.
+    #[must_use]
+    pub fn with_optimization_goal(self, optim: OptimizationGoal) -> Self {
+        Self { optim, ..self }
+    }
+
+    /// See `BCCircuitNoMerkle::state_len_for`.
+    #[must_use]
+    pub fn state_len_for(optim: OptimizationGoal) -> usize {
+        block_state_len_for::<CF, MAX_COMMITTEE_SIZE>(optim)
+    }
+}
+
+impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize, const K: usize> FCircuit<CF>
+    for BCCircuitNoMerkleBatched<CF, MAX_COMMITTEE_SIZE, K>
+{
+    /// The BLS parameters and the quorum threshold of every block.
+    type Params = (Parameters<BlsSigConfig>, QuorumThreshold);
+    type ExternalInputs = BlockBatch<MAX_COMMITTEE_SIZE, K>;
+    type ExternalInputsVar = BlockBatchVar<CF, MAX_COMMITTEE_SIZE, K>;
+
+    fn new(params: Self::Params) -> Result<Self, Error> {
+        Ok(Self {
+            sig_params: params.0,
+            threshold: params.1,
+            optim: OptimizationGoal::Constraints,
+            _cf: PhantomData,
+        })
+    }
+
+    fn state_len(&self) -> usize {
+        Self::state_len_for(self.optim)
+    }
+
+    /// generates the constraints for the step of F for the given z_i
+    #[tracing::instrument(skip_all)]
+    fn generate_step_constraints(
+        &self,
+        cs: ConstraintSystemRef<CF>,
+        _: usize,
+        z_i: Vec<FpVar<CF>>,
+        external_inputs: Self::ExternalInputsVar,
+    ) -> Result<Vec<FpVar<CF>>, SynthesisError> {
+        tracing::info!("start reconstructing committee, epoch and digest");
+
+        let optim = optimization_goal(&cs, self.optim)?;
+
+        // 1. Reconstruct epoch, committee and digest of the previous block from z_i
+        let mut iter = z_i.into_iter();
+        let mut committee = CommitteeVar::from_constraint_field(iter.by_ref(), optim)?;
+        let mut epoch = UInt64::from_constraint_field(iter.by_ref(), optim)?;
+        let mut digest = DigestVar::from_constraint_field(iter.by_ref(), optim)?;
+        ensure_consumed(iter)?;
+
+        tracing::info!(num_constraints = cs.num_constraints());
+
+        // 2. Enforce constraints for each block, where the committee of a
+        // block signs the next one
+        for (block, enabled) in external_inputs
+            .blocks
+            .into_iter()
+            .zip(&external_inputs.enabled)
+        {
+            // 2.1 a disabled slot repeats the current committee and epoch, and
+            // leaves the digest unchanged
+            let disabled = !enabled;
+            block.epoch.conditional_enforce_equal(&epoch, &disabled)?;
+            block
+                .committee
+                .to_constraint_field()?
+                .conditional_enforce_equal(&committee.to_constraint_field()?, &disabled)?;
+
+            // 2.2 an enabled slot is a new block
+            digest = bc_generate_constraints_conditional(
+                cs.clone(),
+                &block,
+                epoch,
+                committee,
+                &digest,
+                self.sig_params,
+                self.threshold,
+                &Boolean::FALSE,
+                enabled,
+            )?;
+
+            committee = block.committee;
+            epoch = block.epoch;
+        }
+
+        // 3. Return the new state
+        tracing::info!("start returning the new state");
+
+        let mut state = committee.to_constraint_field()?;
+        state.push(epoch.to_fp()?);
+        state.extend(digest.to_constraint_field()?);
+
+        tracing::info!(num_constraints = cs.num_constraints());
+
+        Ok(state)
+    }
+}
+
+impl<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize>
+    BCCircuitHashedCommittee<CF, MAX_COMMITTEE_SIZE>
+{
+    /// See `BCCircuitNoMerkle::with_threshold`.
+    #[must_use]
+    pub fn with_threshold(self, threshold: impl Into<QuorumThreshold>) -> Self {
+        Self {
+            threshold: threshold.into(),
+            ..self
+        }
+    }
+}
+
+impl<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize> FCircuit<CF>
+    for BCCircuitHashedCommittee<CF, MAX_COMMITTEE_SIZE>
+{
+    /// The BLS parameters and the quorum threshold of every block.
+    type Params = (Parameters<BlsSigConfig>, QuorumThreshold);
+    type ExternalInputs = BlockWithCommittee<MAX_COMMITTEE_SIZE>;
+    type ExternalInputsVar = BlockWithCommitteeVar<CF, MAX_COMMITTEE_SIZE>;
+
+    fn new(params: Self::Params) -> Result<Self, Error> {
+        Ok(Self {
+            sig_params: params.0,
+            threshold: params.1,
+            hash_params: CRHParametersVar {
+                parameters: poseidon_canonical_config::<CF>(),
+            },
+            _cf: PhantomData,
+        })
+    }
+
+    fn state_len(&self) -> usize {
+        1 + UInt64::<CF>::num_constraint_var_needed(OptimizationGoal::Constraints)
+            + DigestVar::<CF>::num_constraint_var_needed(OptimizationGoal::Constraints)
+    }
+
+    /// generates the constraints for the step of F for the given z_i
+    #[tracing::instrument(skip_all)]
+    fn generate_step_constraints(
+        &self,
+        cs: ConstraintSystemRef<CF>,
+        _: usize,
+        z_i: Vec<FpVar<CF>>,
+        external_inputs: Self::ExternalInputsVar,
+    ) -> Result<Vec<FpVar<CF>>, SynthesisError> {
+        tracing::info!("start reconstructing committee hash, epoch and digest");
+
+        let optim = cs.optimization_goal();
+
+        // 1. Reconstruct epoch, committee hash and digest from z_i, and check
+        // the committee supplied by the prover is the committed one
+        let mut iter = z_i.into_iter();
+        let committee_hash = FpVar::from_constraint_field(iter.by_ref(), optim)?;
+        let epoch = UInt64::from_constraint_field(iter.by_ref(), optim)?;
+        let digest = DigestVar::from_constraint_field(iter.by_ref(), optim)?;
+        ensure_consumed(iter)?;
+
+        let BlockWithCommitteeVar { committee, block } = external_inputs;
+        hash_committee(&committee, &self.hash_params)?.enforce_equal(&committee_hash)?;
+
+        tracing::info!(num_constraints = cs.num_constraints());
+
+        // 2. Enforce constraints
+        let digest = bc_generate_constraints(
+            cs.clone(),
+            &block,
+            epoch,
+            committee,
+            &digest,
+            self.sig_params,
+            self.threshold,
+            &Boolean::FALSE,
+        )?;
+
+        // 3. Return the new state
+        tracing::info!("start returning the new state");
+
+        let mut state = vec![
+            hash_committee(&block.committee, &self.hash_params)?,
+            block.epoch.to_fp()?,
+        ];
+        state.extend(digest.to_constraint_field()?);
+
+        tracing::info!(num_constraints = cs.num_constraints());
+
+        Ok(state)
+    }
+}
+
+impl<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize>
+    BCCircuitMerkleForest<CF, MAX_COMMITTEE_SIZE>
+{
+    /// See `BCCircuitNoMerkle::with_threshold`.
+    #[must_use]
+    pub fn with_threshold(self, threshold: impl Into<QuorumThreshold>) -> Self {
+        Self {
+            threshold: threshold.into(),
+            ..self
+        }
+    }
+
+    /// See `BCCircuitNoMerkle::with_optimization_goal`.
+    #[must_use]
+    pub fn with_optimization_goal(self, optim: OptimizationGoal) -> Self {
+        Self { optim, ..self }
+    }
+
+    /// See `BCCircuitNoMerkle::state_len_for`.
+    #[must_use]
+    pub fn state_len_for(&self, optim: OptimizationGoal) -> usize {
+        block_state_len_for::<CF, MAX_COMMITTEE_SIZE>(optim)
+            + LeveledMerkleForestVar::<Config<CF>>::num_constraint_var_needed(
+                self.capacity_per_tree,
+                self.num_tree,
+            )
+    }
+}
+
 impl<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize> FCircuit<CF>
     for BCCircuitMerkleForest<CF, MAX_COMMITTEE_SIZE>
 {
-    type Params = (Parameters<BlsSigConfig>, usize);
+    /// The BLS parameters, the number of blocks the forest stores and the
+    /// quorum threshold of every block.
+    type Params = (Parameters<BlsSigConfig>, usize, QuorumThreshold);
     type ExternalInputs = Block<MAX_COMMITTEE_SIZE>;
     type ExternalInputsVar = BlockVar<CF, MAX_COMMITTEE_SIZE>;
 
@@ -125,6 +551,8 @@ impl<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize> FCircuit<CF>
 
         Ok(Self {
             sig_params: params.0,
+            threshold: params.2,
+            optim: OptimizationGoal::Constraints,
             capacity_per_tree,
             num_tree,
             hash_params: CRHParametersVar {
@@ -135,13 +563,7 @@ impl<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize> FCircuit<CF>
     }
 
     fn state_len(&self) -> usize {
-        CommitteeVar::<CF, MAX_COMMITTEE_SIZE>::num_constraint_var_needed(
-            OptimizationGoal::Constraints,
-        ) + UInt64::<CF>::num_constraint_var_needed(OptimizationGoal::Constraints)
-            + LeveledMerkleForestVar::<Config<CF>>::num_constraint_var_needed(
-                self.capacity_per_tree,
-                self.num_tree,
-            )
+        self.state_len_for(self.optim)
     }
 
     /// generates the constraints for the step of F for the given z_i
@@ -153,30 +575,35 @@ impl<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize> FCircuit<CF>
         z_i: Vec<FpVar<CF>>,
         external_inputs: Self::ExternalInputsVar,
     ) -> Result<Vec<FpVar<CF>>, SynthesisError> {
-        tracing::info!("start reconstructing committee and epoch");
+        tracing::info!("start reconstructing committee, epoch, digest and forest");
 
-        let optim = cs.optimization_goal();
+        let optim = optimization_goal(&cs, self.optim)?;
 
-        // 1. Reconstruct epoch and committee from z_i
+        // 1. Reconstruct epoch, committee, digest and forest from z_i
         let mut iter = z_i.into_iter();
         let committee = CommitteeVar::from_constraint_field(iter.by_ref(), optim)?;
         let epoch = UInt64::from_constraint_field(iter.by_ref(), optim)?;
+        let digest = DigestVar::from_constraint_field(iter.by_ref(), optim)?;
         let mut forest = LeveledMerkleForestVar::<Config<CF>>::from_constraint_field(
             iter.by_ref(),
             self.capacity_per_tree,
             self.num_tree,
             &self.hash_params,
         )?;
+        ensure_consumed(iter)?;
 
         tracing::info!(num_constraints = cs.num_constraints());
 
         // 2. Enforce constraints
-        bc_generate_constraints(
+        let digest = bc_generate_constraints(
             cs.clone(),
             &external_inputs,
             epoch,
             committee,
+            &digest,
             self.sig_params,
+            self.threshold,
+            &Boolean::FALSE,
         )?;
 
         // 2.1 Prove forest Update
@@ -200,6 +627,7 @@ impl<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize> FCircuit<CF>
 
         let mut committee = external_inputs.committee.to_constraint_field()?;
         committee.push(epoch);
+        committee.extend(digest.to_constraint_field()?);
         committee.extend(forest.to_constraint_field()?);
 
         tracing::info!(num_constraints = cs.num_constraints());
@@ -208,102 +636,466 @@ impl<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize> FCircuit<CF>
     }
 }
 
-#[tracing::instrument(skip_all)]
-fn bc_generate_constraints<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize>(
-    cs: ConstraintSystemRef<CF>,
-    external_inputs: &BlockVar<CF, MAX_COMMITTEE_SIZE>,
-    epoch: UInt64<CF>,
-    committee: CommitteeVar<CF, MAX_COMMITTEE_SIZE>,
-    sig_params: Parameters<BlsSigConfig>,
-) -> Result<(), SynthesisError> {
-    // 1. enforce epoch of new committee = epoch of old committee + 1
-    tracing::info!("start enforcing epoch of new committee = epoch of old committee + 1");
-
-    external_inputs
-        .epoch
-        .is_eq(&(epoch.wrapping_add(&UInt64::constant(1))))?
-        .enforce_equal(&Boolean::TRUE)?;
-
-    tracing::info!(num_constraints = cs.num_constraints());
+impl<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize>
+    BCCircuitMerkleOnly<CF, MAX_COMMITTEE_SIZE>
+{
+    /// See `BCCircuitNoMerkle::with_threshold`.
+    #[must_use]
+    pub fn with_threshold(self, threshold: impl Into<QuorumThreshold>) -> Self {
+        Self {
+            threshold: threshold.into(),
+            ..self
+        }
+    }
+}
 
-    // 2. enforce the signature matches
-    tracing::info!("start enforcing signature matches");
-    let sig = &external_inputs.sig.sig;
-    let signers = &external_inputs.sig.signers;
+impl<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize> FCircuit<CF>
+    for BCCircuitMerkleOnly<CF, MAX_COMMITTEE_SIZE>
+{
+    /// The BLS parameters, the number of blocks the forest stores and the
+    /// quorum threshold of every block.
+    type Params = (Parameters<BlsSigConfig>, usize, QuorumThreshold);
+    type ExternalInputs = BlockWithCommittee<MAX_COMMITTEE_SIZE>;
+    type ExternalInputsVar = BlockWithCommitteeVar<CF, MAX_COMMITTEE_SIZE>;
 
-    // 2.1 aggregate public keys
-    tracing::info!("start aggregating public keys");
+    fn new(params: Self::Params) -> Result<Self, Error> {
+        let (capacity_per_tree, num_tree) = optimal_forest_params(params.1);
 
-    let mut weight = UInt64::constant(0);
-    let mut aggregate_pk = G1Var::<BlsSigConfig, EmulatedFpVar<_, CF>, CF>::zero();
-    for (signed, signer) in signers.iter().zip(committee.committee) {
-        let pk = signed.select(
-            &(signer.pk.into()),
-            &G1Var::<BlsSigConfig, EmulatedFpVar<_, CF>, CF>::zero(),
-        )?;
-        let w = signed.select(&(signer.weight), &UInt64::constant(0))?;
-        aggregate_pk += pk;
-        weight.wrapping_add_in_place(&w);
+        Ok(Self {
+            sig_params: params.0,
+            threshold: params.2,
+            capacity_per_tree,
+            num_tree,
+            hash_params: CRHParametersVar {
+                parameters: poseidon_canonical_config::<CF>(),
+            },
+            _cf: PhantomData,
+        })
     }
-    let aggregate_pk = aggregate_pk.into();
 
-    tracing::info!(num_constraints = cs.num_constraints());
-
-    // 2.2 check signature
-    tracing::info!("start checking signatures");
+    fn state_len(&self) -> usize {
+        1 + UInt64::<CF>::num_constraint_var_needed(OptimizationGoal::Constraints)
+            + DigestVar::<CF>::num_constraint_var_needed(OptimizationGoal::Constraints)
+            + LeveledMerkleForestVar::<Config<CF>>::num_constraint_var_needed(
+                self.capacity_per_tree,
+                self.num_tree,
+            )
+    }
 
-    let params = ParametersVar::new_constant(cs.clone(), sig_params)?;
-    let mut external_inputs_without_sig = external_inputs.clone();
-    external_inputs_without_sig.sig = QuorumSignatureVar::new_constant(
-        cs.clone(),
-        QuorumSignature::<MAX_COMMITTEE_SIZE>::default(),
-    )?;
-    BLSAggregateSignatureVerifyGadget::verify(
-        &params,
-        &aggregate_pk,
-        &external_inputs_without_sig.serialize()?,
-        sig,
-    )?;
+    /// generates the constraints for the step of F for the given z_i
+    #[tracing::instrument(skip_all)]
+    fn generate_step_constraints(
+        &self,
+        cs: ConstraintSystemRef<CF>,
+        _: usize,
+        z_i: Vec<FpVar<CF>>,
+        external_inputs: Self::ExternalInputsVar,
+    ) -> Result<Vec<FpVar<CF>>, SynthesisError> {
+        tracing::info!("start reconstructing committee hash, epoch, digest and forest");
 
-    tracing::info!(num_constraints = cs.num_constraints());
+        let optim = cs.optimization_goal();
 
-    // 2.3 check weight > threshold
-    tracing::info!("start checking weight > threshold");
+        // 1. Reconstruct epoch, committee hash, digest and forest from z_i, and
+        // check the committee supplied by the prover is the committed one
+        let mut iter = z_i.into_iter();
+        let committee_hash = FpVar::from_constraint_field(iter.by_ref(), optim)?;
+        let epoch = UInt64::from_constraint_field(iter.by_ref(), optim)?;
+        let digest = DigestVar::from_constraint_field(iter.by_ref(), optim)?;
+        let mut forest = LeveledMerkleForestVar::<Config<CF>>::from_constraint_field(
+            iter.by_ref(),
+            self.capacity_per_tree,
+            self.num_tree,
+            &self.hash_params,
+        )?;
+        ensure_consumed(iter)?;
 
-    weight.to_fp()?.enforce_cmp(
-        &FpVar::constant(STRONG_THRESHOLD.into()),
-        Ordering::Greater,
-        true,
-    )?;
+        let BlockWithCommitteeVar { committee, block } = external_inputs;
+        hash_committee(&committee, &self.hash_params)?.enforce_equal(&committee_hash)?;
 
-    tracing::info!(num_constraints = cs.num_constraints());
+        tracing::info!(num_constraints = cs.num_constraints());
 
-    Ok(())
+        // 2. Enforce constraints
+        let digest = bc_generate_constraints(
+            cs.clone(),
+            &block,
+            epoch,
+            committee,
+            &digest,
+            self.sig_params,
+            self.threshold,
+            &Boolean::FALSE,
+        )?;
+
+        // 2.1 Prove forest Update
+        // - the forest stores the hash of the committee
+        tracing::info!("start proving forest update");
+        let new_committee = block.committee.to_constraint_field()?;
+        let epoch = block.epoch.to_fp()?;
+        let _ = forest.update(epoch.clone(), &new_committee)?;
+
+        // 2.2 Ensure the new epoch is < max # of leaves the tree can store
+        epoch.enforce_cmp(
+            &FpVar::Constant((forest.max_leaves() as u64).into()),
+            Ordering::Less,
+            false,
+        )?;
+
+        // 3. Return the new state
+        tracing::info!("start returning the new state");
+
+        let mut state = vec![
+            CRHGadget::evaluate(&self.hash_params, &new_committee)?,
+            epoch,
+        ];
+        state.extend(digest.to_constraint_field()?);
+        state.extend(forest.to_constraint_field()?);
+
+        tracing::info!(num_constraints = cs.num_constraints());
+
+        Ok(state)
+    }
+}
+
+fn hash_committee<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize>(
+    committee: &CommitteeVar<CF, MAX_COMMITTEE_SIZE>,
+    hash_params: &CRHParametersVar<CF>,
+) -> Result<FpVar<CF>, SynthesisError> {
+    CRHGadget::evaluate(hash_params, &committee.to_constraint_field()?)
+}
+
+/// The length of `committee || epoch || digest`, the state of the last block
+/// that every circuit storing the committee in the clear starts with.
+pub(crate) fn block_state_len_for<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize>(
+    optim: OptimizationGoal,
+) -> usize {
+    CommitteeVar::<CF, MAX_COMMITTEE_SIZE>::num_constraint_var_needed(optim)
+        + UInt64::<CF>::num_constraint_var_needed(optim)
+        + DigestVar::<CF>::num_constraint_var_needed(optim)
+}
+
+/// In-circuit counterpart of a step of `fold_digest_chain`: the Poseidon hash
+/// of `acc` and the packed `digest`.
+fn accumulate_digest<CF: PrimeField + Absorb>(
+    acc: &FpVar<CF>,
+    digest: &DigestVar<CF>,
+) -> Result<FpVar<CF>, SynthesisError> {
+    let hash_params = CRHParametersVar {
+        parameters: poseidon_canonical_config::<CF>(),
+    };
+    let mut input = vec![acc.clone()];
+    input.extend(digest.to_constraint_field()?);
+    CRHGadget::evaluate(&hash_params, &input)
+}
+
+/// Enforce that `external_inputs` is a valid successor of the block with
+/// the given `epoch`, `committee` and `digest`, or a genesis block whose
+/// committee is the genesis one, as told by `is_genesis_committee`, and
+/// return its digest.
+#[allow(clippy::too_many_arguments)]
+fn bc_generate_constraints<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize>(
+    cs: ConstraintSystemRef<CF>,
+    external_inputs: &BlockVar<CF, MAX_COMMITTEE_SIZE>,
+    epoch: UInt64<CF>,
+    committee: CommitteeVar<CF, MAX_COMMITTEE_SIZE>,
+    digest: &DigestVar<CF>,
+    sig_params: Parameters<BlsSigConfig>,
+    threshold: QuorumThreshold,
+    is_genesis_committee: &Boolean<CF>,
+) -> Result<DigestVar<CF>, SynthesisError> {
+    bc_generate_constraints_conditional(
+        cs,
+        external_inputs,
+        epoch,
+        committee,
+        digest,
+        sig_params,
+        threshold,
+        is_genesis_committee,
+        &Boolean::TRUE,
+    )
+}
+
+/// Same as `bc_generate_constraints`, but the block is only checked if
+/// `enable` is true. Otherwise, `digest` is returned unchanged.
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(skip_all)]
+fn bc_generate_constraints_conditional<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize>(
+    cs: ConstraintSystemRef<CF>,
+    external_inputs: &BlockVar<CF, MAX_COMMITTEE_SIZE>,
+    epoch: UInt64<CF>,
+    committee: CommitteeVar<CF, MAX_COMMITTEE_SIZE>,
+    digest: &DigestVar<CF>,
+    sig_params: Parameters<BlsSigConfig>,
+    threshold: QuorumThreshold,
+    is_genesis_committee: &Boolean<CF>,
+    enable: &Boolean<CF>,
+) -> Result<DigestVar<CF>, SynthesisError> {
+    // 0. a genesis block starts the chain at epoch 0 with the genesis
+    // committee, so it neither extends the previous block nor is signed
+    tracing::info!("start enforcing a genesis block has epoch 0 and the genesis committee");
+
+    let is_genesis = &external_inputs.is_genesis;
+    let is_first_epoch = external_inputs.epoch.to_fp()?.is_zero()?;
+    (is_first_epoch & is_genesis_committee)
+        .conditional_enforce_equal(&Boolean::TRUE, &(enable & is_genesis))?;
+    let extends_prev = enable & !is_genesis;
+
+    tracing::info!(num_constraints = cs.num_constraints());
+
+    // 1. enforce epoch of new committee = epoch of old committee + 1
+    tracing::info!("start enforcing epoch of new committee = epoch of old committee + 1");
+
+    enforce_next_epoch(&external_inputs.epoch, &epoch, &extends_prev)?;
+
+    tracing::info!(num_constraints = cs.num_constraints());
+
+    // 2. enforce the signature matches
+    tracing::info!("start enforcing signature matches");
+    let sig = &external_inputs.sig.sig;
+    let signers = &external_inputs.sig.signers.0;
+
+    // 2.1 aggregate public keys
+    tracing::info!("start aggregating public keys");
+
+    let mut aggregate_pk = G1Var::<BlsSigConfig, EmulatedFpVar<_, CF>, CF>::zero();
+    for (signed, signer) in signers.iter().zip(&committee.committee) {
+        let pk = signed.select(
+            &(signer.pk.clone().into()),
+            &G1Var::<BlsSigConfig, EmulatedFpVar<_, CF>, CF>::zero(),
+        )?;
+        aggregate_pk += pk;
+    }
+    let aggregate_pk = aggregate_pk.into();
+
+    tracing::info!(num_constraints = cs.num_constraints());
+
+    // 2.2 check signature
+    tracing::info!("start checking signatures");
+
+    let params = ParametersVar::new_constant(cs.clone(), sig_params)?;
+    let mut external_inputs_without_sig = external_inputs.clone();
+    external_inputs_without_sig.sig = QuorumSignatureVar::new_constant(
+        cs.clone(),
+        QuorumSignature::<MAX_COMMITTEE_SIZE>::default(),
+    )?;
+    BLSAggregateSignatureVerifyGadget::conditional_verify(
+        &params,
+        &aggregate_pk,
+        &external_inputs_without_sig.serialize()?,
+        sig,
+        &extends_prev,
+    )?;
+
+    tracing::info!(num_constraints = cs.num_constraints());
+
+    // 2.3 check weight meets the threshold
+    tracing::info!("start checking weight meets the threshold");
+
+    enforce_quorum_weight(signers, &committee, threshold, &extends_prev)?;
+
+    tracing::info!(num_constraints = cs.num_constraints());
+
+    // 2.4 check the new committee is well-formed. A disabled slot carries the
+    // current committee, which was checked when it was introduced, so this
+    // does not need `enable`.
+    tracing::info!("start checking the new committee is valid");
+
+    external_inputs
+        .committee
+        .enforce_valid(&UInt64::constant(TOTAL_VOTING_POWER))?;
+
+    tracing::info!(num_constraints = cs.num_constraints());
+
+    // 3. enforce the block extends the previous one
+    tracing::info!("start enforcing prev_digest = digest of the previous block");
+
+    external_inputs
+        .prev_digest
+        .conditional_enforce_equal(digest, &extends_prev)?;
+
+    // 3.1 compute the digest of the new block, as in `compute_digest`
+    let mut new_digest = external_inputs.digest()?;
+    for (new, old) in new_digest.iter_mut().zip(digest) {
+        *new = enable.select(new, old)?;
+    }
+
+    tracing::info!(num_constraints = cs.num_constraints());
+
+    Ok(new_digest)
+}
+
+/// The optimization goal of `cs`, which has to encode the committee with as
+/// many limbs as `expected`, the goal the state length was computed for.
+fn optimization_goal<CF: PrimeField>(
+    cs: &ConstraintSystemRef<CF>,
+    expected: OptimizationGoal,
+) -> Result<OptimizationGoal, SynthesisError> {
+    let optim = cs.optimization_goal();
+    let num_limbs = EmulatedFpVar::<BlsSigField<BlsSigConfig>, CF>::num_constraint_var_needed;
+    if num_limbs(optim) != num_limbs(expected) {
+        tracing::error!(
+            ?optim,
+            ?expected,
+            "the constraint system has another optimization goal than the state"
+        );
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    Ok(optim)
+}
+
+/// Check that the reconstruction of the state consumed all of it, i.e. that
+/// `z_i` is not longer than `state_len`. A shorter `z_i` is already rejected
+/// by `from_constraint_field` with `SynthesisError::AssignmentMissing`.
+fn ensure_consumed<CF: PrimeField>(
+    rest: impl Iterator<Item = FpVar<CF>>,
+) -> Result<(), SynthesisError> {
+    let num_extra = rest.count();
+    if num_extra != 0 {
+        tracing::error!(num_extra, "the state has more elements than state_len");
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    Ok(())
+}
+
+/// Enforce that `new_epoch = epoch + 1` if `enable` is true.
+///
+/// The sum is computed in `CF`, where it cannot wrap around. `new_epoch` is
+/// at most `u64::MAX` as a `UInt64`, so `epoch` has to be below `u64::MAX`
+/// without another range check, and a block cannot follow the last epoch
+/// with epoch 0, as `Block::verify` rejects it.
+fn enforce_next_epoch<CF: PrimeField>(
+    new_epoch: &UInt64<CF>,
+    epoch: &UInt64<CF>,
+    enable: &Boolean<CF>,
+) -> Result<(), SynthesisError> {
+    new_epoch
+        .to_fp()?
+        .conditional_enforce_equal(&(epoch.to_fp()? + FpVar::one()), enable)
+}
+
+/// Enforce that the total weight of the signers meets `threshold` if `enable`
+/// is true.
+///
+/// The weights are summed in `CF`, where `MAX_COMMITTEE_SIZE` 64-bit weights
+/// cannot wrap around, and the sum is enforced to fit in 64 bits, so a
+/// committee whose weights overflow a `u64` is rejected as in `Block::verify`.
+/// Both checks are 64-bit decompositions, where `weight >= min` holds iff
+/// `weight - min` does not wrap around the field. A strict threshold is
+/// checked as `weight >= threshold.weight + 1`, which cannot overflow in `CF`.
+fn enforce_quorum_weight<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize>(
+    signers: &[Boolean<CF>],
+    committee: &CommitteeVar<CF, MAX_COMMITTEE_SIZE>,
+    threshold: QuorumThreshold,
+    enable: &Boolean<CF>,
+) -> Result<(), SynthesisError> {
+    let mut weight = FpVar::zero();
+    for (signed, signer) in signers.iter().zip(&committee.committee) {
+        weight += signed.select(&signer.weight.to_fp()?, &FpVar::zero())?;
+    }
+
+    let min = CF::from(threshold.weight) + CF::from(threshold.strict);
+    let excess = enable.select(&(&weight - FpVar::constant(min)), &FpVar::zero())?;
+    let weight = enable.select(&weight, &FpVar::zero())?;
+    weight.to_bits_le_with_top_bits_zero(64)?;
+    excess.to_bits_le_with_top_bits_zero(64)?;
+    Ok(())
 }
 
 #[cfg(test)]
 mod test {
     use ark_crypto_primitives::crh::poseidon::constraints::CRHParametersVar;
-    use ark_r1cs_std::{alloc::AllocVar, convert::ToConstraintFieldGadget, uint64::UInt64};
-    use ark_relations::r1cs::ConstraintSystem;
+    use ark_r1cs_std::{
+        alloc::AllocVar, convert::ToConstraintFieldGadget, fields::fp::FpVar, prelude::Boolean,
+        uint64::UInt64, R1CSVar,
+    };
+    use ark_relations::r1cs::{ConstraintSystem, OptimizationGoal, SynthesisError};
     use folding_schemes::{frontend::FCircuit, transcript::poseidon::poseidon_canonical_config};
     use rand::{rngs::StdRng, SeedableRng};
 
     use crate::{
-        bc::block::{gen_blockchain_with_params, Blockchain},
+        bc::{
+            block::{
+                compute_digest, gen_blockchain_with_params, Block, Blockchain, Committee,
+                QuorumSignature,
+            },
+            params::{
+                AuthorityAggregatedSignature, AuthorityPublicKey, AuthoritySecretKey,
+                QuorumThreshold, Weight, HASH_OUTPUT_SIZE, STRONG_THRESHOLD,
+            },
+        },
         bls::Parameters,
         folding::{
-            bc::{BlockVar, CommitteeVar},
-            circuit::BCCircuitMerkleForest,
+            bc::{
+                BlockBatch, BlockBatchVar, BlockVar, BlockWithCommittee, BlockWithCommitteeVar,
+                CommitteeVar,
+            },
+            circuit::{BCCircuitMerkleForest, BCCircuitMerkleOnly, BCCircuitNoMerkleBatched},
+            decider::{
+                fold_digest_chain, initial_block_state, initial_block_state_for,
+                initial_hashed_state, initial_state, initial_state_for,
+            },
         },
         merkle::{constraints::LeveledMerkleForestVar, Config},
     };
 
-    use super::BCCircuitNoMerkle;
+    use super::{
+        enforce_next_epoch, enforce_quorum_weight, BCCircuitHashedCommittee, BCCircuitNoMerkle,
+    };
     use ark_bls12_381::Fr;
 
     const COMMITTEE_SIZE: usize = 25;
 
+    fn check_quorum_weight(
+        weights: [Weight; 3],
+        signed: [bool; 3],
+        threshold: QuorumThreshold,
+    ) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let committee = Committee::<3> {
+            signers: weights.map(|weight| (AuthorityPublicKey::default(), weight)),
+        };
+        let committee = CommitteeVar::new_witness(cs.clone(), || Ok(committee)).unwrap();
+        let signers = Vec::<Boolean<Fr>>::new_witness(cs.clone(), || Ok(signed)).unwrap();
+        enforce_quorum_weight(&signers, &committee, threshold, &Boolean::TRUE).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_quorum_weight() {
+        let default = QuorumThreshold::default();
+        assert!(check_quorum_weight(
+            [STRONG_THRESHOLD - 1, 1, 10_000],
+            [true, true, false],
+            default
+        ));
+        assert!(!check_quorum_weight(
+            [STRONG_THRESHOLD - 2, 1, 10_000],
+            [true, true, false],
+            default
+        ));
+
+        let strict = QuorumThreshold::more_than(STRONG_THRESHOLD);
+        assert!(!check_quorum_weight(
+            [STRONG_THRESHOLD - 1, 1, 10_000],
+            [true, true, false],
+            strict
+        ));
+        assert!(check_quorum_weight(
+            [STRONG_THRESHOLD, 1, 10_000],
+            [true, true, false],
+            strict
+        ));
+
+        // the weights wrap around to `STRONG_THRESHOLD` in a u64
+        let weights = [u64::MAX, STRONG_THRESHOLD, 1];
+        assert_eq!(
+            weights[0].wrapping_add(weights[1]).wrapping_add(weights[2]),
+            STRONG_THRESHOLD
+        );
+        assert!(!check_quorum_weight(weights, [true, true, true], default));
+        assert!(check_quorum_weight(weights, [true, false, false], default));
+    }
+
     #[test]
     #[ignore = "folding circuit generates ~2^26 constraints"]
     fn test_bc_no_merkle() {
@@ -313,19 +1105,11 @@ mod test {
         let cs = ConstraintSystem::new_ref();
 
         let f_circuit: BCCircuitNoMerkle<Fr, COMMITTEE_SIZE> =
-            BCCircuitNoMerkle::new(Parameters::setup()).unwrap();
-        let z_0: Vec<_> = {
-            let cs = ConstraintSystem::<Fr>::new_ref();
-            CommitteeVar::new_constant(cs.clone(), bc.get(0).unwrap().committee.clone())
-                .unwrap()
-                .to_constraint_field()
-                .unwrap()
-                .into_iter()
-                .chain(std::iter::once(
-                    UInt64::constant(bc.get(0).unwrap().epoch).to_fp().unwrap(),
-                ))
-                .collect()
-        };
+            BCCircuitNoMerkle::new((Parameters::setup(), QuorumThreshold::default())).unwrap();
+        let z_0 = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || {
+            initial_state::<Fr, COMMITTEE_SIZE>(bc.get(0).unwrap())
+        })
+        .unwrap();
         assert_eq!(
             z_0.len(),
             f_circuit.state_len(),
@@ -344,6 +1128,118 @@ mod test {
         assert!(cs.is_satisfied().unwrap());
     }
 
+    #[test]
+    fn test_bc_no_merkle_state_len() {
+        const SIZE: usize = 4;
+
+        let mut rng = StdRng::from_seed([42; 32]);
+        let bc: Blockchain<SIZE> = gen_blockchain_with_params(2, SIZE, &mut rng);
+        let f_circuit: BCCircuitNoMerkle<Fr, SIZE> =
+            BCCircuitNoMerkle::new((Parameters::setup(), QuorumThreshold::default())).unwrap();
+        let z_0 = initial_state::<Fr, SIZE>(bc.get(0).unwrap()).unwrap();
+
+        // the state is rejected before the block is checked
+        let step = |z: &[Fr]| {
+            let cs = ConstraintSystem::new_ref();
+            f_circuit.generate_step_constraints(
+                cs.clone(),
+                0,
+                Vec::<FpVar<Fr>>::new_witness(cs.clone(), || Ok(z)).unwrap(),
+                BlockVar::new_witness(cs.clone(), || Ok(bc.get(1).unwrap())).unwrap(),
+            )
+        };
+
+        assert!(matches!(
+            step(&z_0[..z_0.len() - 1]),
+            Err(SynthesisError::AssignmentMissing)
+        ));
+        assert!(matches!(
+            step(&[&z_0[..], &[Fr::from(0u64)]].concat()),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+
+    #[test]
+    #[ignore = "folding circuit generates ~2^26 constraints"]
+    fn test_bc_no_merkle_weight() {
+        let optim = OptimizationGoal::Weight;
+
+        let mut rng = StdRng::from_seed([42; 32]);
+        let bc: Blockchain<COMMITTEE_SIZE> =
+            gen_blockchain_with_params(2, COMMITTEE_SIZE, &mut rng);
+        let cs = ConstraintSystem::new_ref();
+        cs.set_optimization_goal(optim);
+
+        let f_circuit: BCCircuitNoMerkle<Fr, COMMITTEE_SIZE> =
+            BCCircuitNoMerkle::new((Parameters::setup(), QuorumThreshold::default()))
+                .unwrap()
+                .with_optimization_goal(optim);
+        let z_0 = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || {
+            initial_state_for::<Fr, COMMITTEE_SIZE>(bc.get(0).unwrap(), optim)
+        })
+        .unwrap();
+        assert_eq!(
+            z_0.len(),
+            f_circuit.state_len(),
+            "state length should match"
+        );
+
+        let z_1 = f_circuit
+            .generate_step_constraints(
+                cs.clone(),
+                0,
+                z_0,
+                BlockVar::new_witness(cs.clone(), || Ok(bc.get(1).unwrap())).unwrap(),
+            )
+            .unwrap();
+
+        // the new state is the initial state of the next step, with the
+        // digest of the folded block in the accumulator
+        let mut expected =
+            initial_block_state_for::<Fr, COMMITTEE_SIZE>(bc.get(1).unwrap(), optim).unwrap();
+        expected.push(fold_digest_chain::<Fr, COMMITTEE_SIZE>([bc.get(1).unwrap()]).unwrap());
+        assert_eq!(z_1.value().unwrap(), expected);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_bc_no_merkle_optimization_goal() {
+        const SIZE: usize = 4;
+
+        let mut rng = StdRng::from_seed([42; 32]);
+        let bc: Blockchain<SIZE> = gen_blockchain_with_params(2, SIZE, &mut rng);
+        let f_circuit: BCCircuitNoMerkle<Fr, SIZE> =
+            BCCircuitNoMerkle::new((Parameters::setup(), QuorumThreshold::default())).unwrap();
+
+        for optim in [OptimizationGoal::Constraints, OptimizationGoal::Weight] {
+            let z_0 = initial_state_for::<Fr, SIZE>(bc.get(0).unwrap(), optim).unwrap();
+            assert_eq!(
+                z_0.len(),
+                BCCircuitNoMerkle::<Fr, SIZE>::state_len_for(optim)
+            );
+            assert_eq!(
+                f_circuit.clone().with_optimization_goal(optim).state_len(),
+                z_0.len()
+            );
+        }
+
+        // a circuit set up for `Constraints` rejects a constraint system
+        // optimized for `Weight`
+        let cs = ConstraintSystem::new_ref();
+        cs.set_optimization_goal(OptimizationGoal::Weight);
+        let z_0 =
+            initial_state_for::<Fr, SIZE>(bc.get(0).unwrap(), OptimizationGoal::Weight).unwrap();
+        assert!(matches!(
+            f_circuit.generate_step_constraints(
+                cs.clone(),
+                0,
+                Vec::<FpVar<Fr>>::new_witness(cs.clone(), || Ok(z_0)).unwrap(),
+                BlockVar::new_witness(cs.clone(), || Ok(bc.get(1).unwrap())).unwrap(),
+            ),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+
     #[test]
     #[ignore = "folding circuit generates ~2^26 constraints"]
     fn test_bc_merkle() {
@@ -354,34 +1250,28 @@ mod test {
             gen_blockchain_with_params(2, COMMITTEE_SIZE, &mut rng);
         let cs = ConstraintSystem::new_ref();
 
-        let f_circuit: BCCircuitMerkleForest<Fr, COMMITTEE_SIZE> =
-            BCCircuitMerkleForest::new((Parameters::setup(), STATE_SIZE)).unwrap();
-        let z_0: Vec<_> = {
-            let cs = ConstraintSystem::<Fr>::new_ref();
-            let poseidon_config = poseidon_canonical_config();
-
-            CommitteeVar::new_constant(cs.clone(), bc.get(0).unwrap().committee.clone())
-                .unwrap()
-                .to_constraint_field()
-                .unwrap()
-                .into_iter()
-                .chain(std::iter::once(
-                    UInt64::constant(bc.get(0).unwrap().epoch).to_fp().unwrap(),
-                ))
-                .chain(
-                    LeveledMerkleForestVar::<Config<Fr>>::new_optimal(
-                        STATE_SIZE,
-                        &CRHParametersVar {
-                            parameters: poseidon_config,
-                        },
-                    )
-                    .expect("LMS should be constructed successfully")
-                    .to_constraint_field()
-                    .unwrap()
-                    .into_iter(),
+        let f_circuit: BCCircuitMerkleForest<Fr, COMMITTEE_SIZE> = BCCircuitMerkleForest::new((
+            Parameters::setup(),
+            STATE_SIZE,
+            QuorumThreshold::default(),
+        ))
+        .unwrap();
+        let z_0 = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || {
+            let mut z_0 = initial_block_state::<Fr, COMMITTEE_SIZE>(bc.get(0).unwrap())?;
+            z_0.extend(
+                LeveledMerkleForestVar::<Config<Fr>>::new_optimal(
+                    STATE_SIZE,
+                    &CRHParametersVar {
+                        parameters: poseidon_canonical_config(),
+                    },
                 )
-                .collect()
-        };
+                .expect("LMS should be constructed successfully")
+                .to_constraint_field()?
+                .value()?,
+            );
+            Ok(z_0)
+        })
+        .unwrap();
         assert_eq!(
             z_0.len(),
             f_circuit.state_len(),
@@ -399,4 +1289,520 @@ mod test {
 
         assert!(cs.is_satisfied().unwrap());
     }
+
+    #[test]
+    #[ignore = "folding circuit generates ~2^26 constraints"]
+    fn test_bc_merkle_only() {
+        const STATE_SIZE: usize = 1024;
+
+        let mut rng = StdRng::from_seed([42; 32]);
+        let bc: Blockchain<COMMITTEE_SIZE> =
+            gen_blockchain_with_params(2, COMMITTEE_SIZE, &mut rng);
+        let (prev, block) = (bc.get(0).unwrap(), bc.get(1).unwrap());
+
+        let forest_circuit: BCCircuitMerkleForest<Fr, COMMITTEE_SIZE> = BCCircuitMerkleForest::new(
+            (Parameters::setup(), STATE_SIZE, QuorumThreshold::default()),
+        )
+        .unwrap();
+        let f_circuit: BCCircuitMerkleOnly<Fr, COMMITTEE_SIZE> =
+            BCCircuitMerkleOnly::new((Parameters::setup(), STATE_SIZE, QuorumThreshold::default()))
+                .unwrap();
+        assert!(f_circuit.state_len() < forest_circuit.state_len());
+
+        let forest: Vec<Fr> = LeveledMerkleForestVar::<Config<Fr>>::new_optimal(
+            STATE_SIZE,
+            &CRHParametersVar {
+                parameters: poseidon_canonical_config(),
+            },
+        )
+        .expect("LMS should be constructed successfully")
+        .to_constraint_field()
+        .unwrap()
+        .value()
+        .unwrap();
+        let step = |z_0: Vec<Fr>, merkle_only: bool| {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let z_0 = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || Ok(z_0)).unwrap();
+            let z_1 = if merkle_only {
+                let input = BlockWithCommittee {
+                    committee: prev.committee.clone(),
+                    block: block.clone(),
+                };
+                let input = BlockWithCommitteeVar::new_witness(cs.clone(), || Ok(input)).unwrap();
+                f_circuit.generate_step_constraints(cs.clone(), 0, z_0, input)
+            } else {
+                let input = BlockVar::new_witness(cs.clone(), || Ok(block)).unwrap();
+                forest_circuit.generate_step_constraints(cs.clone(), 0, z_0, input)
+            };
+            assert!(cs.is_satisfied().unwrap());
+            z_1.unwrap().value().unwrap()
+        };
+
+        let mut z_0 = initial_hashed_state::<Fr, COMMITTEE_SIZE>(prev).unwrap();
+        z_0.extend(&forest);
+        assert_eq!(
+            z_0.len(),
+            f_circuit.state_len(),
+            "state length should match"
+        );
+        let z_1 = step(z_0, true);
+
+        let mut z_0 = initial_block_state::<Fr, COMMITTEE_SIZE>(prev).unwrap();
+        z_0.extend(&forest);
+        let expected = step(z_0, false);
+
+        // both circuits compute the same forest, and the state commits to the
+        // new committee and block
+        let hashed = initial_hashed_state::<Fr, COMMITTEE_SIZE>(block).unwrap();
+        assert_eq!(z_1[..hashed.len()], hashed);
+        assert_eq!(
+            z_1[hashed.len()..],
+            expected[expected.len() - forest.len()..]
+        );
+    }
+
+    const SMALL_COMMITTEE_SIZE: usize = 4;
+
+    fn no_merkle_accepts(
+        prev: &Block<SMALL_COMMITTEE_SIZE>,
+        block: &Block<SMALL_COMMITTEE_SIZE>,
+    ) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let f_circuit: BCCircuitNoMerkle<Fr, SMALL_COMMITTEE_SIZE> =
+            BCCircuitNoMerkle::new((Parameters::setup(), QuorumThreshold::default())).unwrap();
+
+        let z_i = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || {
+            initial_state::<Fr, SMALL_COMMITTEE_SIZE>(prev)
+        })
+        .unwrap();
+        let block = BlockVar::new_witness(cs.clone(), || Ok(block)).unwrap();
+
+        f_circuit
+            .generate_step_constraints(cs.clone(), 0, z_i, block)
+            .is_ok()
+            && cs.is_satisfied().unwrap()
+    }
+
+    fn hashed_committee_accepts(
+        prev: &Block<SMALL_COMMITTEE_SIZE>,
+        committee: &Committee<SMALL_COMMITTEE_SIZE>,
+        block: &Block<SMALL_COMMITTEE_SIZE>,
+    ) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let f_circuit: BCCircuitHashedCommittee<Fr, SMALL_COMMITTEE_SIZE> =
+            BCCircuitHashedCommittee::new((Parameters::setup(), QuorumThreshold::default()))
+                .unwrap();
+
+        let z_i = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || {
+            initial_hashed_state::<Fr, SMALL_COMMITTEE_SIZE>(prev)
+        })
+        .unwrap();
+        assert_eq!(z_i.len(), f_circuit.state_len());
+
+        let input = BlockWithCommittee {
+            committee: committee.clone(),
+            block: block.clone(),
+        };
+        let input = BlockWithCommitteeVar::new_witness(cs.clone(), || Ok(input)).unwrap();
+
+        f_circuit
+            .generate_step_constraints(cs.clone(), 0, z_i, input)
+            .is_ok()
+            && cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    #[ignore = "each step generates millions of constraints"]
+    fn test_bc_hashed_committee_matches_no_merkle() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let bc: Blockchain<SMALL_COMMITTEE_SIZE> =
+            gen_blockchain_with_params(3, SMALL_COMMITTEE_SIZE, &mut rng);
+        let (prev, block) = (bc.get(0).unwrap(), bc.get(1).unwrap());
+
+        let no_merkle: BCCircuitNoMerkle<Fr, SMALL_COMMITTEE_SIZE> =
+            BCCircuitNoMerkle::new((Parameters::setup(), QuorumThreshold::default())).unwrap();
+        let hashed: BCCircuitHashedCommittee<Fr, SMALL_COMMITTEE_SIZE> =
+            BCCircuitHashedCommittee::new((Parameters::setup(), QuorumThreshold::default()))
+                .unwrap();
+        assert!(hashed.state_len() < no_merkle.state_len());
+
+        let mut wrong_epoch = block.clone();
+        wrong_epoch.epoch += 1;
+
+        let mut wrong_signers = block.clone();
+        wrong_signers.sig.signers[0] = !wrong_signers.sig.signers[0];
+
+        let mut wrong_committee = block.clone();
+        wrong_committee.committee = bc.get(2).unwrap().committee.clone();
+
+        for (candidate, expected) in [
+            (block, true),
+            (&wrong_epoch, false),
+            (&wrong_signers, false),
+            (&wrong_committee, false),
+        ] {
+            assert_eq!(no_merkle_accepts(prev, candidate), expected);
+            assert_eq!(
+                hashed_committee_accepts(prev, &prev.committee, candidate),
+                expected
+            );
+        }
+
+        // the prover cannot swap in a committee other than the committed one
+        assert!(!hashed_committee_accepts(
+            prev,
+            &bc.get(1).unwrap().committee,
+            block
+        ));
+    }
+
+    #[test]
+    #[ignore = "each step generates millions of constraints"]
+    fn test_bc_prev_digest() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = Parameters::setup();
+
+        let signers: Vec<_> = (0..SMALL_COMMITTEE_SIZE)
+            .map(|_| AuthoritySecretKey::new(&mut rng))
+            .collect();
+        let committee = Committee::<SMALL_COMMITTEE_SIZE> {
+            signers: core::array::from_fn(|i| {
+                (AuthorityPublicKey::new(&signers[i], &params), 2_500)
+            }),
+        };
+        let genesis = Block::genesis(committee.clone());
+        let sign = |prev: &Block<SMALL_COMMITTEE_SIZE>| {
+            Block::new(
+                prev,
+                committee.clone(),
+                &signers,
+                &[true; SMALL_COMMITTEE_SIZE],
+                &params,
+            )
+            .unwrap()
+        };
+
+        // the committee of `genesis` signs a block that extends another block
+        // of the same epoch
+        let mut fork = genesis.clone();
+        fork.prev_digest = [1; HASH_OUTPUT_SIZE];
+        let block = sign(&genesis);
+        let tampered = sign(&fork);
+        assert_ne!(tampered.prev_digest, block.prev_digest);
+        assert!(tampered.verify(&genesis.committee, genesis.epoch, &params));
+
+        assert!(no_merkle_accepts(&genesis, &block));
+        assert!(!no_merkle_accepts(&genesis, &tampered));
+        assert!(hashed_committee_accepts(&genesis, &committee, &block));
+        assert!(!hashed_committee_accepts(&genesis, &committee, &tampered));
+    }
+
+    #[test]
+    #[ignore = "each step generates millions of constraints"]
+    fn test_bc_no_merkle_threshold() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = Parameters::setup();
+
+        // half of the voting power signs the block
+        let signers: Vec<_> = (0..SMALL_COMMITTEE_SIZE)
+            .map(|_| AuthoritySecretKey::new(&mut rng))
+            .collect();
+        let committee = Committee::<SMALL_COMMITTEE_SIZE> {
+            signers: core::array::from_fn(|i| {
+                (AuthorityPublicKey::new(&signers[i], &params), 2_500)
+            }),
+        };
+        let genesis = Block::genesis(committee.clone());
+        let block = Block::new(
+            &genesis,
+            committee,
+            &signers,
+            &[true, true, false, false],
+            &params,
+        )
+        .unwrap();
+
+        let accepts = |threshold: QuorumThreshold| {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let f_circuit: BCCircuitNoMerkle<Fr, SMALL_COMMITTEE_SIZE> =
+                BCCircuitNoMerkle::new((params, threshold)).unwrap();
+
+            let z_i = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || {
+                initial_state::<Fr, SMALL_COMMITTEE_SIZE>(&genesis)
+            })
+            .unwrap();
+            let input = BlockVar::new_witness(cs.clone(), || Ok(&block)).unwrap();
+
+            f_circuit
+                .generate_step_constraints(cs.clone(), 0, z_i, input)
+                .unwrap();
+            assert_eq!(
+                block.verify_with_threshold(&genesis.committee, 0, &params, threshold),
+                cs.is_satisfied().unwrap(),
+                "the circuit should agree with the native check"
+            );
+            cs.is_satisfied().unwrap()
+        };
+
+        assert!(accepts(QuorumThreshold::at_least(5_000)));
+        assert!(!accepts(QuorumThreshold::more_than(5_000)));
+        assert!(!accepts(QuorumThreshold::default()));
+    }
+
+    #[test]
+    fn test_next_epoch() {
+        let accepts = |new_epoch: u64, epoch: u64| {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let new_epoch = UInt64::new_witness(cs.clone(), || Ok(new_epoch)).unwrap();
+            let epoch = UInt64::new_witness(cs.clone(), || Ok(epoch)).unwrap();
+            enforce_next_epoch(&new_epoch, &epoch, &Boolean::TRUE).unwrap();
+            cs.is_satisfied().unwrap()
+        };
+
+        assert!(accepts(1, 0));
+        assert!(accepts(u64::MAX, u64::MAX - 1));
+        assert!(!accepts(2, 0));
+        assert!(!accepts(0, 0));
+
+        // the last epoch has no successor, where a wrapping addition gives 0
+        assert_eq!(u64::MAX.wrapping_add(1), 0);
+        assert!(!accepts(0, u64::MAX));
+    }
+
+    #[test]
+    #[ignore = "each step generates millions of constraints"]
+    fn test_bc_no_merkle_genesis() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = Parameters::setup();
+        let bc: Blockchain<SMALL_COMMITTEE_SIZE> =
+            gen_blockchain_with_params(2, SMALL_COMMITTEE_SIZE, &mut rng);
+        let genesis = bc.get(0).unwrap();
+        let other = bc.get(1).unwrap();
+
+        let plain: BCCircuitNoMerkle<Fr, SMALL_COMMITTEE_SIZE> =
+            BCCircuitNoMerkle::new((params, QuorumThreshold::default())).unwrap();
+        let with_genesis = plain.clone().with_genesis(&genesis.committee);
+
+        // the first step starts from the accumulator of an empty chain, and
+        // its committee, epoch and digest are overwritten by a genesis step
+        let first = initial_state::<Fr, SMALL_COMMITTEE_SIZE>(other).unwrap();
+        // a later step, after the genesis block was folded
+        let mut second = initial_state::<Fr, SMALL_COMMITTEE_SIZE>(genesis).unwrap();
+        *second.last_mut().unwrap() =
+            fold_digest_chain::<Fr, SMALL_COMMITTEE_SIZE>([genesis]).unwrap();
+
+        // `is_genesis` defaults to `epoch == 0`
+        let step = |f_circuit: &BCCircuitNoMerkle<Fr, SMALL_COMMITTEE_SIZE>,
+                    z_i: &[Fr],
+                    block: &Block<SMALL_COMMITTEE_SIZE>,
+                    is_genesis: Option<bool>| {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let z_i = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || Ok(z_i)).unwrap();
+            let mut input = BlockVar::new_witness(cs.clone(), || Ok(block)).unwrap();
+            if let Some(is_genesis) = is_genesis {
+                input.is_genesis = Boolean::new_witness(cs.clone(), || Ok(is_genesis)).unwrap();
+            }
+            let z_1 = f_circuit
+                .generate_step_constraints(cs.clone(), 0, z_i, input)
+                .unwrap();
+            (cs.is_satisfied().unwrap(), z_1.value().unwrap())
+        };
+
+        // the genesis block starts the chain from the genesis committee
+        let (accepted, z_1) = step(&with_genesis, &first, genesis, None);
+        assert!(accepted);
+        let expected = initial_block_state::<Fr, SMALL_COMMITTEE_SIZE>(genesis).unwrap();
+        assert_eq!(z_1[..expected.len()], expected);
+        assert_eq!(z_1, second);
+
+        // but not without a genesis committee in the circuit
+        assert!(!step(&plain, &first, genesis, None).0);
+
+        // nor with another committee
+        let mut wrong_committee = genesis.clone();
+        wrong_committee.committee = other.committee.clone();
+        assert!(!step(&with_genesis, &first, &wrong_committee, None).0);
+
+        // nor at another epoch
+        let mut wrong_epoch = genesis.clone();
+        wrong_epoch.epoch = 1;
+        assert!(!step(&with_genesis, &first, &wrong_epoch, Some(true)).0);
+
+        // the first step has to fold the genesis block
+        assert!(!step(&with_genesis, &first, genesis, Some(false)).0);
+
+        // and a later step cannot restart the chain, but folds signed blocks
+        assert!(!step(&with_genesis, &second, genesis, Some(true)).0);
+        assert!(step(&with_genesis, &second, other, None).0);
+
+        // the genesis commitment follows the optimization goal set afterwards
+        let optim = OptimizationGoal::Weight;
+        let weight = plain
+            .with_genesis(&genesis.committee)
+            .with_optimization_goal(optim);
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        cs.set_optimization_goal(optim);
+        let z_i = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || {
+            initial_state_for::<Fr, SMALL_COMMITTEE_SIZE>(other, optim)
+        })
+        .unwrap();
+        let input = BlockVar::new_witness(cs.clone(), || Ok(genesis)).unwrap();
+        weight
+            .generate_step_constraints(cs.clone(), 0, z_i, input)
+            .unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    #[ignore = "each step generates millions of constraints"]
+    fn test_bc_no_merkle_epoch_wrap_around() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = Parameters::setup();
+
+        let signers: Vec<_> = (0..SMALL_COMMITTEE_SIZE)
+            .map(|_| AuthoritySecretKey::new(&mut rng))
+            .collect();
+        let committee = Committee::<SMALL_COMMITTEE_SIZE> {
+            signers: core::array::from_fn(|i| {
+                (AuthorityPublicKey::new(&signers[i], &params), 2_500)
+            }),
+        };
+
+        // the committee of the last epoch signs a block at epoch 0
+        let mut prev = Block::genesis(committee.clone());
+        prev.epoch = u64::MAX;
+        let mut block = Block {
+            epoch: 0,
+            prev_digest: compute_digest(&prev),
+            sig: QuorumSignature::default(),
+            committee,
+        };
+        block.sig = QuorumSignature {
+            sig: AuthorityAggregatedSignature::aggregate_sign(
+                &bincode::serialize(&block).unwrap(),
+                &signers,
+                &params,
+            )
+            .unwrap(),
+            signers: [true; SMALL_COMMITTEE_SIZE],
+        };
+
+        let f_circuit: BCCircuitNoMerkle<Fr, SMALL_COMMITTEE_SIZE> =
+            BCCircuitNoMerkle::new((params, QuorumThreshold::default())).unwrap();
+        for is_genesis in [true, false] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let z_i = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || {
+                initial_state::<Fr, SMALL_COMMITTEE_SIZE>(&prev)
+            })
+            .unwrap();
+            let mut input = BlockVar::new_witness(cs.clone(), || Ok(&block)).unwrap();
+            input.is_genesis = Boolean::new_witness(cs.clone(), || Ok(is_genesis)).unwrap();
+            f_circuit
+                .generate_step_constraints(cs.clone(), 0, z_i, input)
+                .unwrap();
+            assert!(!cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    #[ignore = "folding the circuit takes a long time"]
+    fn test_bc_no_merkle_batched_folding() {
+        use ark_mnt4_298::{Fr, G1Projective as G1, MNT4_298 as MNT4};
+        use ark_mnt6_298::{G1Projective as G2, MNT6_298 as MNT6};
+        use folding_schemes::{
+            commitment::kzg::KZG,
+            folding::nova::{Nova, PreprocessorParam},
+            FoldingScheme,
+        };
+
+        type Single = BCCircuitNoMerkle<Fr, SMALL_COMMITTEE_SIZE>;
+        type Batched = BCCircuitNoMerkleBatched<Fr, SMALL_COMMITTEE_SIZE, 2>;
+        type N<FC> = Nova<G1, G2, FC, KZG<'static, MNT4>, KZG<'static, MNT6>, false>;
+
+        let mut rng = StdRng::from_seed([42; 32]);
+        let bc: Blockchain<SMALL_COMMITTEE_SIZE> =
+            gen_blockchain_with_params(5, SMALL_COMMITTEE_SIZE, &mut rng);
+        let blocks: Vec<_> = bc.into_blocks().collect();
+        let params = (Parameters::setup(), QuorumThreshold::default());
+
+        // one block per step
+        let f_circuit = Single::new(params).unwrap();
+        let nova_params = N::<Single>::preprocess(
+            &mut rng,
+            &PreprocessorParam::new(poseidon_canonical_config::<Fr>(), f_circuit.clone()),
+        )
+        .unwrap();
+        let z_0 = initial_state::<Fr, SMALL_COMMITTEE_SIZE>(&blocks[0]).unwrap();
+        let mut single = N::<Single>::init(&nova_params, f_circuit, z_0).unwrap();
+        for block in &blocks[1..] {
+            single.prove_step(&mut rng, block.clone(), None).unwrap();
+        }
+
+        // two blocks per step
+        let f_circuit = Batched::new(params).unwrap();
+        let nova_params = N::<Batched>::preprocess(
+            &mut rng,
+            &PreprocessorParam::new(poseidon_canonical_config::<Fr>(), f_circuit),
+        )
+        .unwrap();
+        let z_0 = initial_block_state::<Fr, SMALL_COMMITTEE_SIZE>(&blocks[0]).unwrap();
+        let mut batched = N::<Batched>::init(&nova_params, f_circuit, z_0).unwrap();
+        for (i, batch) in blocks[1..].chunks(2).enumerate() {
+            let batch = BlockBatch::new(&blocks[2 * i], batch);
+            batched.prove_step(&mut rng, batch, None).unwrap();
+        }
+        N::<Batched>::verify(nova_params.1, batched.ivc_proof()).unwrap();
+
+        // the batched state has no accumulator
+        assert_eq!(batched.i, Fr::from(2u64));
+        assert_eq!(batched.z_i, single.z_i[..single.z_i.len() - 1]);
+    }
+
+    #[test]
+    #[ignore = "each step generates millions of constraints"]
+    fn test_bc_no_merkle_batched_padding() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let bc: Blockchain<SMALL_COMMITTEE_SIZE> =
+            gen_blockchain_with_params(3, SMALL_COMMITTEE_SIZE, &mut rng);
+        let (prev, block) = (bc.get(0).unwrap(), bc.get(1).unwrap());
+
+        let f_circuit: BCCircuitNoMerkleBatched<Fr, SMALL_COMMITTEE_SIZE, 2> =
+            BCCircuitNoMerkleBatched::new((Parameters::setup(), QuorumThreshold::default()))
+                .unwrap();
+        let step = |batch: &BlockBatch<SMALL_COMMITTEE_SIZE, 2>| {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let z_i = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || {
+                initial_block_state::<Fr, SMALL_COMMITTEE_SIZE>(prev)
+            })
+            .unwrap();
+            let input = BlockBatchVar::new_witness(cs.clone(), || Ok(batch)).unwrap();
+            let z_1 = f_circuit
+                .generate_step_constraints(cs.clone(), 0, z_i, input)
+                .unwrap();
+            (cs.is_satisfied().unwrap(), z_1.value().unwrap())
+        };
+
+        // a single block, padded with a disabled copy of it
+        let batch = BlockBatch::new(prev, std::slice::from_ref(block));
+        assert_eq!(batch.enabled, [true, false]);
+        let (satisfied, z_1) = step(&batch);
+        assert!(satisfied);
+        assert_eq!(
+            z_1,
+            initial_block_state::<Fr, SMALL_COMMITTEE_SIZE>(block).unwrap()
+        );
+
+        // an empty batch leaves the state unchanged
+        let (satisfied, z_1) = step(&BlockBatch::new(prev, &[]));
+        assert!(satisfied);
+        assert_eq!(
+            z_1,
+            initial_block_state::<Fr, SMALL_COMMITTEE_SIZE>(prev).unwrap()
+        );
+
+        // a disabled slot cannot skip to another committee
+        let mut skipped = batch;
+        skipped.blocks[1] = bc.get(2).unwrap().clone();
+        assert!(!step(&skipped).0);
+    }
 }
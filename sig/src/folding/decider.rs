@@ -0,0 +1,369 @@
+//! Helpers that turn a folded blockchain into a succinct proof that light
+//! clients can verify with a reusable verifier key.
+//!
+//! The flow mirrors `benches/archives/nova_folding_no_merkle.rs`:
+//! 1. `Nova::preprocess` and `Nova::init` with `initial_state`,
+//! 2. `setup_decider` once per circuit to get the decider prover/verifier keys,
+//! 3. `prove_chain` to fold the blocks and compress the result,
+//! 4. `verify_chain` on the light client side.
+
+use ark_crypto_primitives::{
+    crh::{poseidon::CRH, CRHScheme},
+    sponge::Absorb,
+};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::AllocVar, convert::ToConstraintFieldGadget, uint64::UInt64, uint8::UInt8, R1CSVar,
+};
+use ark_relations::r1cs::{ConstraintSystem, OptimizationGoal, SynthesisError};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::{CryptoRng, RngCore};
+use folding_schemes::{
+    commitment::CommitmentScheme, folding::nova::Nova, frontend::FCircuit,
+    transcript::poseidon::poseidon_canonical_config, Curve, Decider, Error, FoldingScheme,
+};
+
+use crate::{
+    bc::block::{compute_digest, Block, Committee},
+    folding::bc::CommitteeVar,
+};
+
+/// A decider proof together with the public values needed to verify it.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ChainProof<C1: Curve, P> {
+    /// number of folded steps
+    pub i: C1::ScalarField,
+    pub z_0: Vec<C1::ScalarField>,
+    pub z_i: Vec<C1::ScalarField>,
+    pub running_commitments: Vec<C1>,
+    pub incoming_commitments: Vec<C1>,
+    pub proof: P,
+}
+
+/// The initial state `z_0` of `BCCircuitNoMerkle`: the committee of `block`
+/// followed by its epoch, its digest and the accumulator of an empty chain.
+pub fn initial_state<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize>(
+    block: &Block<MAX_COMMITTEE_SIZE>,
+) -> Result<Vec<CF>, SynthesisError> {
+    initial_state_for(block, OptimizationGoal::Constraints)
+}
+
+/// Same as `initial_state`, for a circuit synthesized in a constraint system
+/// with the optimization goal `optim`, see
+/// `BCCircuitNoMerkle::with_optimization_goal`.
+pub fn initial_state_for<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize>(
+    block: &Block<MAX_COMMITTEE_SIZE>,
+    optim: OptimizationGoal,
+) -> Result<Vec<CF>, SynthesisError> {
+    let mut state = initial_block_state_for(block, optim)?;
+    state.push(fold_digest_chain::<CF, MAX_COMMITTEE_SIZE>([])?);
+    Ok(state)
+}
+
+/// The committee of `block` followed by its epoch and its digest, i.e. the
+/// initial state `z_0` of `BCCircuitNoMerkleBatched`, and the part of the
+/// initial state of `BCCircuitMerkleForest` that precedes the forest.
+pub fn initial_block_state<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize>(
+    block: &Block<MAX_COMMITTEE_SIZE>,
+) -> Result<Vec<CF>, SynthesisError> {
+    initial_block_state_for(block, OptimizationGoal::Constraints)
+}
+
+/// Same as `initial_block_state`, for a constraint system with the
+/// optimization goal `optim`.
+pub fn initial_block_state_for<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize>(
+    block: &Block<MAX_COMMITTEE_SIZE>,
+    optim: OptimizationGoal,
+) -> Result<Vec<CF>, SynthesisError> {
+    let cs = ConstraintSystem::<CF>::new_ref();
+    cs.set_optimization_goal(optim);
+
+    // constants are always encoded for `OptimizationGoal::Constraints`, so the
+    // committee is a witness to be encoded for `optim`
+    let mut state = CommitteeVar::new_witness(cs, || Ok(&block.committee))?
+        .to_constraint_field()?
+        .value()?;
+    state.extend(epoch_and_digest(block)?);
+    Ok(state)
+}
+
+/// The initial state `z_0` of `BCCircuitHashedCommittee`: the Poseidon hash of
+/// the committee of `block` followed by its epoch and its digest.
+pub fn initial_hashed_state<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize>(
+    block: &Block<MAX_COMMITTEE_SIZE>,
+) -> Result<Vec<CF>, SynthesisError> {
+    let mut state = vec![committee_commitment_for(
+        &block.committee,
+        OptimizationGoal::Constraints,
+    )?];
+    state.extend(epoch_and_digest(block)?);
+    Ok(state)
+}
+
+/// The Poseidon hash of `committee` as encoded in the state of a circuit
+/// synthesized with the optimization goal `optim`.
+///
+/// This is the committed committee of `BCCircuitHashedCommittee`, and the
+/// genesis commitment of `BCCircuitNoMerkle::with_genesis`.
+pub fn committee_commitment_for<CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize>(
+    committee: &Committee<MAX_COMMITTEE_SIZE>,
+    optim: OptimizationGoal,
+) -> Result<CF, SynthesisError> {
+    let cs = ConstraintSystem::<CF>::new_ref();
+    cs.set_optimization_goal(optim);
+
+    let committee = CommitteeVar::new_witness(cs, || Ok(committee))?
+        .to_constraint_field()?
+        .value()?;
+    CRH::evaluate(&poseidon_canonical_config::<CF>(), committee)
+        .map_err(|_| SynthesisError::Unsatisfiable)
+}
+
+/// The accumulator of `BCCircuitNoMerkle` after folding `blocks` in order,
+/// i.e. the last element of its state.
+///
+/// Starting from zero, every block updates the accumulator to
+/// `Poseidon(acc || digest)`, where `digest` is `compute_digest` of the block
+/// packed into field elements. The block of the initial state is not part of
+/// the chain, so `blocks` are the blocks passed to `prove_step`.
+pub fn fold_digest_chain<'a, CF: PrimeField + Absorb, const MAX_COMMITTEE_SIZE: usize>(
+    blocks: impl IntoIterator<Item = &'a Block<MAX_COMMITTEE_SIZE>>,
+) -> Result<CF, SynthesisError> {
+    let params = poseidon_canonical_config::<CF>();
+    blocks.into_iter().try_fold(CF::zero(), |acc, block| {
+        let mut input = vec![acc];
+        input.extend(packed_digest(block)?);
+        CRH::evaluate(&params, input).map_err(|_| SynthesisError::Unsatisfiable)
+    })
+}
+
+fn epoch_and_digest<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize>(
+    block: &Block<MAX_COMMITTEE_SIZE>,
+) -> Result<Vec<CF>, SynthesisError> {
+    let mut state = vec![UInt64::<CF>::constant(block.epoch).to_fp()?.value()?];
+    state.extend(packed_digest(block)?);
+    Ok(state)
+}
+
+fn packed_digest<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize>(
+    block: &Block<MAX_COMMITTEE_SIZE>,
+) -> Result<Vec<CF>, SynthesisError> {
+    UInt8::<CF>::constant_vec(&compute_digest(block))
+        .to_constraint_field()?
+        .value()
+}
+
+/// Generate the decider prover and verifier keys for a Nova instance whose
+/// step circuit has a state of `state_len` field elements.
+///
+/// The verifier key does not depend on the blocks, so it can be generated once
+/// and shipped to light clients.
+#[allow(clippy::type_complexity)]
+pub fn setup_decider<C1, C2, FC, CS1, CS2, D>(
+    rng: impl RngCore + CryptoRng,
+    nova_params: (
+        <Nova<C1, C2, FC, CS1, CS2, false> as FoldingScheme<C1, C2, FC>>::ProverParam,
+        <Nova<C1, C2, FC, CS1, CS2, false> as FoldingScheme<C1, C2, FC>>::VerifierParam,
+    ),
+    state_len: usize,
+) -> Result<(D::ProverParam, D::VerifierParam), Error>
+where
+    C1: Curve<BaseField = C2::ScalarField, ScalarField = C2::BaseField>,
+    C2: Curve,
+    FC: FCircuit<C1::ScalarField>,
+    CS1: CommitmentScheme<C1, false>,
+    CS2: CommitmentScheme<C2, false>,
+    D: Decider<
+        C1,
+        C2,
+        FC,
+        Nova<C1, C2, FC, CS1, CS2, false>,
+        PreprocessorParam = (
+            (
+                <Nova<C1, C2, FC, CS1, CS2, false> as FoldingScheme<C1, C2, FC>>::ProverParam,
+                <Nova<C1, C2, FC, CS1, CS2, false> as FoldingScheme<C1, C2, FC>>::VerifierParam,
+            ),
+            usize,
+        ),
+    >,
+{
+    D::preprocess(rng, (nova_params, state_len))
+}
+
+/// Fold every block into `nova` and compress the result with the decider.
+pub fn prove_chain<C1, C2, FC, CS1, CS2, D>(
+    mut rng: impl RngCore + CryptoRng,
+    nova: &mut Nova<C1, C2, FC, CS1, CS2, false>,
+    blocks: impl IntoIterator<Item = FC::ExternalInputs>,
+    decider_pp: D::ProverParam,
+) -> Result<ChainProof<C1, D::Proof>, Error>
+where
+    C1: Curve<BaseField = C2::ScalarField, ScalarField = C2::BaseField>,
+    C2: Curve,
+    FC: FCircuit<C1::ScalarField>,
+    CS1: CommitmentScheme<C1, false>,
+    CS2: CommitmentScheme<C2, false>,
+    D: Decider<C1, C2, FC, Nova<C1, C2, FC, CS1, CS2, false>, CommittedInstance = Vec<C1>>,
+{
+    use folding_schemes::folding::traits::CommittedInstanceOps;
+
+    for block in blocks {
+        nova.prove_step(&mut rng, block, None)?;
+    }
+
+    let proof = D::prove(&mut rng, decider_pp, nova.clone())?;
+
+    Ok(ChainProof {
+        i: nova.i,
+        z_0: nova.z_0.clone(),
+        z_i: nova.z_i.clone(),
+        running_commitments: nova.U_i.get_commitments(),
+        incoming_commitments: nova.u_i.get_commitments(),
+        proof,
+    })
+}
+
+/// Verify a proof produced by `prove_chain`.
+pub fn verify_chain<C1, C2, FC, FS, D>(
+    decider_vp: D::VerifierParam,
+    proof: &ChainProof<C1, D::Proof>,
+) -> Result<bool, Error>
+where
+    C1: Curve,
+    C2: Curve,
+    FC: FCircuit<C1::ScalarField>,
+    FS: FoldingScheme<C1, C2, FC>,
+    D: Decider<C1, C2, FC, FS, CommittedInstance = Vec<C1>>,
+{
+    D::verify(
+        decider_vp,
+        proof.i,
+        proof.z_0.clone(),
+        proof.z_i.clone(),
+        &proof.running_commitments,
+        &proof.incoming_commitments,
+        &proof.proof,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use ark_groth16::Groth16;
+    use ark_mnt4_298::{Fr, G1Projective as G1, MNT4_298 as MNT4};
+    use ark_mnt6_298::{G1Projective as G2, MNT6_298 as MNT6};
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use folding_schemes::{
+        commitment::kzg::KZG,
+        folding::nova::{decider::Decider as NovaDecider, Nova},
+        frontend::FCircuit,
+        transcript::poseidon::poseidon_canonical_config,
+        Decider,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::{
+        fold_digest_chain, initial_state, prove_chain, setup_decider, verify_chain, ChainProof,
+    };
+    use crate::{
+        bc::{
+            block::{gen_blockchain_with_params, Blockchain},
+            params::QuorumThreshold,
+        },
+        bls::Parameters,
+        folding::{circuit::BCCircuitNoMerkle, schemes::init_folding},
+    };
+
+    const COMMITTEE_SIZE: usize = 4;
+
+    type FC = BCCircuitNoMerkle<Fr, COMMITTEE_SIZE>;
+    type N = Nova<G1, G2, FC, KZG<'static, MNT4>, KZG<'static, MNT6>, false>;
+    type D = NovaDecider<
+        G1,
+        G2,
+        FC,
+        KZG<'static, MNT4>,
+        KZG<'static, MNT6>,
+        Groth16<MNT4>,
+        Groth16<MNT6>,
+        N,
+    >;
+
+    #[test]
+    fn test_fold_digest_chain() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let bc: Blockchain<COMMITTEE_SIZE> =
+            gen_blockchain_with_params(3, COMMITTEE_SIZE, &mut rng);
+        let (genesis, b1, b2) = (bc.get(0).unwrap(), bc.get(1).unwrap(), bc.get(2).unwrap());
+
+        // the initial state starts from the accumulator of an empty chain
+        let empty = fold_digest_chain::<Fr, COMMITTEE_SIZE>([]).unwrap();
+        assert_eq!(
+            initial_state::<Fr, COMMITTEE_SIZE>(genesis).unwrap().last(),
+            Some(&empty)
+        );
+
+        let chain = fold_digest_chain::<Fr, COMMITTEE_SIZE>([b1, b2]).unwrap();
+        assert_ne!(chain, empty);
+        assert_ne!(
+            chain,
+            fold_digest_chain::<Fr, COMMITTEE_SIZE>([b1]).unwrap()
+        );
+        assert_ne!(
+            chain,
+            fold_digest_chain::<Fr, COMMITTEE_SIZE>([b2, b1]).unwrap()
+        );
+    }
+
+    #[test]
+    #[ignore = "folding and deciding the circuit takes a long time"]
+    fn test_prove_chain_roundtrip() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let bc: Blockchain<COMMITTEE_SIZE> =
+            gen_blockchain_with_params(3, COMMITTEE_SIZE, &mut rng);
+
+        let f_circuit = FC::new((Parameters::setup(), QuorumThreshold::default())).unwrap();
+        let z_0 = initial_state(bc.get(0).unwrap()).unwrap();
+        let (mut nova, nova_params) = init_folding::<N, _, _, _>(
+            poseidon_canonical_config::<Fr>(),
+            f_circuit.clone(),
+            z_0,
+            &mut rng,
+        )
+        .unwrap();
+
+        let (decider_pp, decider_vp) =
+            setup_decider::<_, _, _, _, _, D>(&mut rng, nova_params, f_circuit.state_len())
+                .unwrap();
+
+        let blocks: Vec<_> = bc.into_blocks().skip(1).collect();
+        let proof =
+            prove_chain::<_, _, _, _, _, D>(&mut rng, &mut nova, blocks.clone(), decider_pp)
+                .unwrap();
+
+        // the state commits to the folded blocks
+        assert_eq!(
+            proof.z_i.last(),
+            Some(&fold_digest_chain::<Fr, COMMITTEE_SIZE>(&blocks).unwrap())
+        );
+
+        // light clients only receive the serialized verifier key and proof
+        let mut vp_bytes = vec![];
+        decider_vp.serialize_compressed(&mut vp_bytes).unwrap();
+        let mut proof_bytes = vec![];
+        proof.serialize_compressed(&mut proof_bytes).unwrap();
+
+        let decider_vp = <<D as Decider<G1, G2, FC, N>>::VerifierParam as CanonicalDeserialize>::deserialize_compressed(&*vp_bytes)
+            .unwrap();
+        let proof = ChainProof::<G1, <D as Decider<G1, G2, FC, N>>::Proof>::deserialize_compressed(
+            &*proof_bytes,
+        )
+        .unwrap();
+
+        assert!(verify_chain::<_, _, _, N, D>(decider_vp.clone(), &proof).unwrap());
+
+        // a proof for a different final state should be rejected
+        let mut tampered = proof;
+        tampered.z_i[0] += Fr::from(1u64);
+        assert!(!verify_chain::<_, _, _, N, D>(decider_vp, &tampered).unwrap_or(false));
+    }
+}
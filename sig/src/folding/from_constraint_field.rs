@@ -9,16 +9,18 @@ use ark_r1cs_std::{
     },
     groups::bls12::G1Var,
     uint64::UInt64,
+    uint8::UInt8,
     R1CSVar,
 };
 use ark_relations::r1cs::{OptimizationGoal, SynthesisError};
 
 use crate::{
+    bc::params::HASH_OUTPUT_SIZE,
     bls::PublicKeyVar,
     params::{BlsSigConfig, BlsSigField},
 };
 
-use super::bc::{CommitteeVar, SignerVar};
+use super::bc::{CommitteeVar, DigestVar, SignerVar};
 
 /// Specifies how to convert from `Vec<FpVar<ConstraintF>>` to `Self`
 ///
@@ -26,20 +28,79 @@ use super::bc::{CommitteeVar, SignerVar};
 pub trait FromConstraintFieldGadget<CF: PrimeField>: Sized {
     fn num_constraint_var_needed(optim: OptimizationGoal) -> usize;
 
-    /// Converts from `Vec<FpVar<ConstraintF>>` to `Self`.
+    /// Converts from `Vec<FpVar<ConstraintF>>` to `Self`, consuming exactly
+    /// `num_constraint_var_needed(optim)` elements of `iter`.
+    ///
+    /// Returns `SynthesisError::AssignmentMissing` if `iter` runs out before.
     fn from_constraint_field(
         iter: impl Iterator<Item = FpVar<CF>>,
         optim: OptimizationGoal,
     ) -> Result<Self, SynthesisError>;
 }
 
+/// Take the `T::num_constraint_var_needed(optim)` elements encoding a `T`
+/// from `iter`, and decode them with `decode`.
+///
+/// Missing elements are reported with the name of `T`, so that a state that
+/// is shorter than `state_len` is easy to track down. In debug builds,
+/// `decode` is also checked to consume every element it is given, i.e. that
+/// `num_constraint_var_needed` agrees with `from_constraint_field`.
+fn decode_exact<CF: PrimeField, T: FromConstraintFieldGadget<CF>>(
+    iter: impl Iterator<Item = FpVar<CF>>,
+    optim: OptimizationGoal,
+    decode: impl FnOnce(&mut std::vec::IntoIter<FpVar<CF>>) -> Result<T, SynthesisError>,
+) -> Result<T, SynthesisError> {
+    let needed = T::num_constraint_var_needed(optim);
+    let elems: Vec<_> = iter.take(needed).collect();
+    if elems.len() != needed {
+        tracing::error!(
+            ty = std::any::type_name::<T>(),
+            expected = needed,
+            got = elems.len(),
+            "not enough field elements to rebuild the variable"
+        );
+        return Err(SynthesisError::AssignmentMissing);
+    }
+
+    let mut elems = elems.into_iter();
+    let value = decode(&mut elems)?;
+    debug_assert_eq!(
+        elems.len(),
+        0,
+        "{} consumes fewer elements than num_constraint_var_needed",
+        std::any::type_name::<T>()
+    );
+    Ok(value)
+}
+
+fn next<CF: PrimeField>(
+    elems: &mut impl Iterator<Item = FpVar<CF>>,
+) -> Result<FpVar<CF>, SynthesisError> {
+    elems.next().ok_or(SynthesisError::AssignmentMissing)
+}
+
+impl<CF: PrimeField> FromConstraintFieldGadget<CF> for FpVar<CF> {
+    fn from_constraint_field(
+        iter: impl Iterator<Item = FpVar<CF>>,
+        optim: OptimizationGoal,
+    ) -> Result<Self, SynthesisError> {
+        decode_exact(iter, optim, next)
+    }
+
+    fn num_constraint_var_needed(_: OptimizationGoal) -> usize {
+        1
+    }
+}
+
 impl<CF: PrimeField> FromConstraintFieldGadget<CF> for UInt64<CF> {
     fn from_constraint_field(
-        mut iter: impl Iterator<Item = FpVar<CF>>,
-        _: OptimizationGoal,
+        iter: impl Iterator<Item = FpVar<CF>>,
+        optim: OptimizationGoal,
     ) -> Result<Self, SynthesisError> {
-        let (num, _) = Self::from_fp(&iter.next().ok_or(SynthesisError::Unsatisfiable)?)?;
-        Ok(num)
+        decode_exact(iter, optim, |elems| {
+            let (num, _) = Self::from_fp(&next(elems)?)?;
+            Ok(num)
+        })
     }
 
     fn num_constraint_var_needed(_: OptimizationGoal) -> usize {
@@ -47,6 +108,35 @@ impl<CF: PrimeField> FromConstraintFieldGadget<CF> for UInt64<CF> {
     }
 }
 
+/// Inverse of `ToConstraintFieldGadget` for `[UInt8<CF>]`, which packs
+/// `(CF::MODULUS_BIT_SIZE - 1) / 8` bytes into each field element.
+///
+/// Each field element is enforced to fit in the bytes it packs, so a digest
+/// has a unique representation in the state.
+impl<CF: PrimeField> FromConstraintFieldGadget<CF> for DigestVar<CF> {
+    fn from_constraint_field(
+        iter: impl Iterator<Item = FpVar<CF>>,
+        optim: OptimizationGoal,
+    ) -> Result<Self, SynthesisError> {
+        let bytes_per_fp = ((CF::MODULUS_BIT_SIZE - 1) / 8) as usize;
+
+        decode_exact(iter, optim, |elems| {
+            let mut bytes = Vec::with_capacity(HASH_OUTPUT_SIZE);
+            while bytes.len() < HASH_OUTPUT_SIZE {
+                let num_bytes = bytes_per_fp.min(HASH_OUTPUT_SIZE - bytes.len());
+                let (bits, _) = next(elems)?.to_bits_le_with_top_bits_zero(8 * num_bytes)?;
+                bytes.extend(bits.chunks(8).map(UInt8::from_bits_le));
+            }
+
+            bytes.try_into().map_err(|_| SynthesisError::Unsatisfiable)
+        })
+    }
+
+    fn num_constraint_var_needed(_: OptimizationGoal) -> usize {
+        HASH_OUTPUT_SIZE.div_ceil(((CF::MODULUS_BIT_SIZE - 1) / 8) as usize)
+    }
+}
+
 impl<CF: PrimeField> FromConstraintFieldGadget<CF>
     for EmulatedFpVar<BlsSigField<BlsSigConfig>, CF>
 {
@@ -54,31 +144,18 @@ impl<CF: PrimeField> FromConstraintFieldGadget<CF>
         iter: impl Iterator<Item = FpVar<CF>>,
         optim: OptimizationGoal,
     ) -> Result<Self, SynthesisError> {
-        let optim = match optim {
-            OptimizationGoal::None => OptimizationType::Constraints,
-            OptimizationGoal::Constraints => OptimizationType::Constraints,
-            OptimizationGoal::Weight => OptimizationType::Weight,
-        };
-
-        let params = get_params(
-            <BlsSigField<BlsSigConfig> as PrimeField>::MODULUS_BIT_SIZE as usize,
-            CF::MODULUS_BIT_SIZE as usize,
-            optim,
-        );
+        decode_exact(iter, optim, |elems| {
+            let limbs = Vec::from_iter(elems);
 
-        let limbs = Vec::from_iter(iter.take(params.num_limbs));
-        if limbs.len() != params.num_limbs {
-            return Err(SynthesisError::Unsatisfiable);
-        }
-
-        // `to_constraint_field` promises to give a normal repr of EmulatedFpVar
-        Ok(Self::Var(AllocatedEmulatedFpVar {
-            cs: limbs.cs(),
-            limbs,
-            num_of_additions_over_normal_form: CF::zero(),
-            is_in_the_normal_form: true,
-            target_phantom: std::marker::PhantomData,
-        }))
+            // `to_constraint_field` promises to give a normal repr of EmulatedFpVar
+            Ok(Self::Var(AllocatedEmulatedFpVar {
+                cs: limbs.cs(),
+                limbs,
+                num_of_additions_over_normal_form: CF::zero(),
+                is_in_the_normal_form: true,
+                target_phantom: std::marker::PhantomData,
+            }))
+        })
     }
 
     fn num_constraint_var_needed(optim: OptimizationGoal) -> usize {
@@ -101,21 +178,23 @@ impl<CF: PrimeField> FromConstraintFieldGadget<CF>
     for PublicKeyVar<BlsSigConfig, EmulatedFpVar<BlsSigField<BlsSigConfig>, CF>, CF>
 {
     fn from_constraint_field(
-        mut iter: impl Iterator<Item = FpVar<CF>>,
+        iter: impl Iterator<Item = FpVar<CF>>,
         optim: OptimizationGoal,
     ) -> Result<Self, SynthesisError> {
         // There are no checks to ensure this point is on the curve and the prime order subgroup.
         // This is safe because all the reconstructed `PublicKeyVar` are either public input or
         // signed by some committee. Since the committee is the trusted base of the safety of the circuit,
         // we don't need to perform additional check here.
-        Ok(
-            G1Var::<BlsSigConfig, EmulatedFpVar<BlsSigField<BlsSigConfig>, CF>, CF>::new(
-                EmulatedFpVar::from_constraint_field(iter.by_ref(), optim)?,
-                EmulatedFpVar::from_constraint_field(iter.by_ref(), optim)?,
-                EmulatedFpVar::from_constraint_field(iter.by_ref(), optim)?,
+        decode_exact(iter, optim, |elems| {
+            Ok(
+                G1Var::<BlsSigConfig, EmulatedFpVar<BlsSigField<BlsSigConfig>, CF>, CF>::new(
+                    EmulatedFpVar::from_constraint_field(elems.by_ref(), optim)?,
+                    EmulatedFpVar::from_constraint_field(elems.by_ref(), optim)?,
+                    EmulatedFpVar::from_constraint_field(elems.by_ref(), optim)?,
+                )
+                .into(),
             )
-            .into(),
-        )
+        })
     }
 
     fn num_constraint_var_needed(optim: OptimizationGoal) -> usize {
@@ -145,12 +224,14 @@ impl<CF: PrimeField> FromConstraintFieldGadget<CF>
 
 impl<CF: PrimeField> FromConstraintFieldGadget<CF> for SignerVar<CF> {
     fn from_constraint_field(
-        mut iter: impl Iterator<Item = FpVar<CF>>,
+        iter: impl Iterator<Item = FpVar<CF>>,
         optim: OptimizationGoal,
     ) -> Result<Self, SynthesisError> {
-        Ok(Self {
-            pk: PublicKeyVar::from_constraint_field(iter.by_ref(), optim)?,
-            weight: UInt64::from_constraint_field(iter.by_ref(), optim)?,
+        decode_exact(iter, optim, |elems| {
+            Ok(Self {
+                pk: PublicKeyVar::from_constraint_field(elems.by_ref(), optim)?,
+                weight: UInt64::from_constraint_field(elems.by_ref(), optim)?,
+            })
         })
     }
 
@@ -163,21 +244,112 @@ impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize> FromConstraintFieldGadget<
     for CommitteeVar<CF, MAX_COMMITTEE_SIZE>
 {
     fn from_constraint_field(
-        mut iter: impl Iterator<Item = FpVar<CF>>,
+        iter: impl Iterator<Item = FpVar<CF>>,
         optim: OptimizationGoal,
     ) -> Result<Self, SynthesisError> {
-        let mut committee = Vec::new();
-        committee.reserve_exact(MAX_COMMITTEE_SIZE);
+        decode_exact(iter, optim, |elems| {
+            let mut committee = Vec::new();
+            committee.reserve_exact(MAX_COMMITTEE_SIZE);
 
-        for _ in 0..MAX_COMMITTEE_SIZE {
-            let signer = SignerVar::from_constraint_field(iter.by_ref(), optim)?;
-            committee.push(signer);
-        }
+            for _ in 0..MAX_COMMITTEE_SIZE {
+                let signer = SignerVar::from_constraint_field(elems.by_ref(), optim)?;
+                committee.push(signer);
+            }
 
-        Ok(Self { committee })
+            Ok(Self { committee })
+        })
     }
 
     fn num_constraint_var_needed(optim: OptimizationGoal) -> usize {
         SignerVar::<CF>::num_constraint_var_needed(optim) * MAX_COMMITTEE_SIZE
     }
 }
+
+#[cfg(test)]
+mod test {
+    use ark_ff::PrimeField;
+    use ark_r1cs_std::{
+        alloc::AllocVar, convert::ToConstraintFieldGadget, fields::fp::FpVar, uint8::UInt8, R1CSVar,
+    };
+    use ark_relations::r1cs::{ConstraintSystem, OptimizationGoal, SynthesisError};
+    use rand::{thread_rng, Rng};
+
+    use crate::{
+        bc::{
+            block::{gen_blockchain_with_params, Blockchain},
+            params::HASH_OUTPUT_SIZE,
+        },
+        folding::bc::{CommitteeVar, DigestVar},
+    };
+
+    use super::FromConstraintFieldGadget;
+
+    fn check_digest_round_trip<CF: PrimeField>() {
+        let digest: [u8; HASH_OUTPUT_SIZE] = thread_rng().gen();
+        let optim = OptimizationGoal::Constraints;
+
+        let cs = ConstraintSystem::<CF>::new_ref();
+        let packed = UInt8::new_witness_vec(cs.clone(), &digest)
+            .unwrap()
+            .to_constraint_field()
+            .unwrap();
+        assert_eq!(
+            packed.len(),
+            DigestVar::<CF>::num_constraint_var_needed(optim)
+        );
+
+        let packed = Vec::<FpVar<CF>>::new_witness(cs.clone(), || packed.value()).unwrap();
+        let unpacked = DigestVar::from_constraint_field(packed.into_iter(), optim).unwrap();
+        assert_eq!(unpacked.value().unwrap(), digest);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_digest_round_trip() {
+        // 31 bytes per field element
+        check_digest_round_trip::<ark_bls12_381::Fr>();
+        // 37 bytes per field element
+        check_digest_round_trip::<ark_mnt4_298::Fr>();
+    }
+
+    #[test]
+    fn test_committee_exact_length() {
+        type F = ark_bls12_381::Fr;
+        const SIZE: usize = 4;
+
+        let bc: Blockchain<SIZE> = gen_blockchain_with_params(1, SIZE, &mut thread_rng());
+        let committee = &bc.get(0).unwrap().committee;
+
+        for optim in [OptimizationGoal::Constraints, OptimizationGoal::Weight] {
+            let cs = ConstraintSystem::<F>::new_ref();
+            cs.set_optimization_goal(optim);
+            let state = CommitteeVar::<F, SIZE>::new_witness(cs.clone(), || Ok(committee))
+                .unwrap()
+                .to_constraint_field()
+                .unwrap();
+            let needed = CommitteeVar::<F, SIZE>::num_constraint_var_needed(optim);
+            assert_eq!(state.len(), needed);
+
+            // exactly `needed` elements are consumed from a longer state
+            let mut iter = state
+                .iter()
+                .cloned()
+                .chain([FpVar::Constant(F::from(0u64))]);
+            let restored =
+                CommitteeVar::<F, SIZE>::from_constraint_field(iter.by_ref(), optim).unwrap();
+            assert_eq!(iter.count(), 1);
+            assert_eq!(
+                restored.to_constraint_field().unwrap().value().unwrap(),
+                state.value().unwrap()
+            );
+
+            assert!(matches!(
+                CommitteeVar::<F, SIZE>::from_constraint_field(
+                    state[..needed - 1].iter().cloned(),
+                    optim
+                ),
+                Err(SynthesisError::AssignmentMissing)
+            ));
+        }
+    }
+}
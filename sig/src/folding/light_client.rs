@@ -0,0 +1,307 @@
+//! Verification of a `ChainProof` by a light client that only trusts the
+//! genesis committee.
+//!
+//! `verify_chain` checks a proof against the `z_0` and `z_i` carried by the
+//! proof itself, so a light client would still have to check that `z_0` starts
+//! from the committee it trusts. `verify_chain_proof` rebuilds `z_0` from the
+//! genesis committee instead, and decodes the final state into the committee
+//! and the epoch the chain has reached.
+
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar, uint64::UInt64, R1CSVar};
+use ark_relations::r1cs::{
+    ConstraintSystem, ConstraintSystemRef, OptimizationGoal, SynthesisError,
+};
+use folding_schemes::{frontend::FCircuit, Curve, Decider, FoldingScheme};
+use thiserror::Error;
+
+use super::{
+    bc::CommitteeVar,
+    decider::{initial_state, ChainProof},
+    from_constraint_field::FromConstraintFieldGadget,
+};
+use crate::bc::block::{Block, Committee};
+
+#[derive(Error, Debug)]
+pub enum LightClientError {
+    #[error("Folding scheme error occurred: {0}")]
+    FoldingError(#[from] folding_schemes::Error),
+
+    #[error("The proof does not prove the final state from the genesis committee")]
+    InvalidProof,
+
+    #[error("The state cannot be decoded: {0}")]
+    InvalidState(#[from] SynthesisError),
+}
+
+/// Verify that `proof` folds `num_steps` blocks from the genesis block of
+/// `genesis_committee` to `final_state`, and return the committee and the
+/// epoch of `final_state`.
+///
+/// Only the commitments and the decider proof of `proof` are used: its `i`,
+/// `z_0` and `z_i` come from the prover, so they are replaced by `num_steps`,
+/// the initial state of `Block::genesis(genesis_committee)` and `final_state`.
+#[allow(clippy::type_complexity)]
+pub fn verify_chain_proof<C1, C2, FC, FS, D, const MAX_COMMITTEE_SIZE: usize>(
+    decider_vp: D::VerifierParam,
+    genesis_committee: &Committee<MAX_COMMITTEE_SIZE>,
+    final_state: &[C1::ScalarField],
+    proof: &ChainProof<C1, D::Proof>,
+    num_steps: u64,
+) -> Result<(Committee<MAX_COMMITTEE_SIZE>, u64), LightClientError>
+where
+    C1: Curve,
+    C1::ScalarField: Absorb,
+    C2: Curve,
+    FC: FCircuit<C1::ScalarField>,
+    FS: FoldingScheme<C1, C2, FC>,
+    D: Decider<C1, C2, FC, FS, CommittedInstance = Vec<C1>>,
+{
+    let z_0 = initial_state(&Block::genesis(genesis_committee.clone()))?;
+
+    let verified = D::verify(
+        decider_vp,
+        C1::ScalarField::from(num_steps),
+        z_0,
+        final_state.to_vec(),
+        &proof.running_commitments,
+        &proof.incoming_commitments,
+        &proof.proof,
+    )?;
+    if !verified {
+        return Err(LightClientError::InvalidProof);
+    }
+
+    let committee = committee_from_field_elements(final_state, OptimizationGoal::Constraints)?;
+    let epoch = epoch_from_field_elements::<_, MAX_COMMITTEE_SIZE>(
+        final_state,
+        OptimizationGoal::Constraints,
+    )?;
+    Ok((committee, epoch))
+}
+
+/// Decode the committee encoded by `CommitteeVar::to_constraint_field` at the
+/// start of `elems`, e.g. the state of one of the blockchain circuits.
+///
+/// This runs `CommitteeVar::from_constraint_field` on `elems` and reads the
+/// values back, so that it decodes exactly what the circuits decode. `optim`
+/// is the optimization goal the elements were encoded for.
+pub fn committee_from_field_elements<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize>(
+    elems: &[CF],
+    optim: OptimizationGoal,
+) -> Result<Committee<MAX_COMMITTEE_SIZE>, SynthesisError> {
+    let (committee, cs) = decode::<CF, CommitteeVar<CF, MAX_COMMITTEE_SIZE>>(elems, optim)?;
+
+    let mut signers = Committee::<MAX_COMMITTEE_SIZE>::default().signers;
+    for (signer, signer_var) in signers.iter_mut().zip(&committee.committee) {
+        *signer = (
+            signer_var.pk.as_ref().value()?.into(),
+            signer_var.weight.value()?,
+        );
+    }
+
+    // the limbs of the keys and the weights are range checked while decoding
+    if !cs.is_satisfied()? {
+        tracing::error!("the field elements do not encode a committee");
+        return Err(SynthesisError::Unsatisfiable);
+    }
+
+    Ok(Committee { signers })
+}
+
+/// Decode the epoch that follows the committee in the state of
+/// `BCCircuitNoMerkle`.
+fn epoch_from_field_elements<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize>(
+    elems: &[CF],
+    optim: OptimizationGoal,
+) -> Result<u64, SynthesisError> {
+    let offset = CommitteeVar::<CF, MAX_COMMITTEE_SIZE>::num_constraint_var_needed(optim);
+    let (epoch, cs) = decode::<CF, UInt64<CF>>(elems.get(offset..).unwrap_or_default(), optim)?;
+    let epoch = epoch.value()?;
+
+    if !cs.is_satisfied()? {
+        tracing::error!("the field element does not encode an epoch");
+        return Err(SynthesisError::Unsatisfiable);
+    }
+
+    Ok(epoch)
+}
+
+/// Allocate `elems` in a new constraint system with the optimization goal
+/// `optim`, and decode a `T` from them.
+fn decode<CF: PrimeField, T: FromConstraintFieldGadget<CF>>(
+    elems: &[CF],
+    optim: OptimizationGoal,
+) -> Result<(T, ConstraintSystemRef<CF>), SynthesisError> {
+    let cs = ConstraintSystem::<CF>::new_ref();
+    cs.set_optimization_goal(optim);
+
+    let vars = Vec::<FpVar<CF>>::new_witness(cs.clone(), || Ok(elems))?;
+    Ok((T::from_constraint_field(vars.into_iter(), optim)?, cs))
+}
+
+#[cfg(test)]
+mod test {
+    use ark_ec::CurveGroup;
+    use ark_mnt4_298::Fr;
+    use ark_relations::r1cs::{OptimizationGoal, SynthesisError};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::{committee_from_field_elements, epoch_from_field_elements};
+    use crate::{
+        bc::block::{gen_blockchain_with_params, Blockchain, Committee},
+        bls::params::G1,
+        folding::decider::initial_state_for,
+        params::BlsSigConfig,
+    };
+
+    const COMMITTEE_SIZE: usize = 4;
+
+    fn assert_same_committee(a: &Committee<COMMITTEE_SIZE>, b: &Committee<COMMITTEE_SIZE>) {
+        for ((pk_a, weight_a), (pk_b, weight_b)) in a.signers.iter().zip(&b.signers) {
+            assert_eq!(
+                G1::<BlsSigConfig>::from(*pk_a).into_affine(),
+                G1::<BlsSigConfig>::from(*pk_b).into_affine()
+            );
+            assert_eq!(weight_a, weight_b);
+        }
+    }
+
+    #[test]
+    fn test_committee_from_field_elements() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let bc: Blockchain<COMMITTEE_SIZE> =
+            gen_blockchain_with_params(3, COMMITTEE_SIZE, &mut rng);
+
+        for optim in [OptimizationGoal::Constraints, OptimizationGoal::Weight] {
+            for block in [bc.get(0).unwrap(), bc.get(2).unwrap()] {
+                let state = initial_state_for::<Fr, COMMITTEE_SIZE>(block, optim).unwrap();
+
+                let committee =
+                    committee_from_field_elements::<_, COMMITTEE_SIZE>(&state, optim).unwrap();
+                assert_same_committee(&committee, &block.committee);
+                assert_eq!(
+                    epoch_from_field_elements::<_, COMMITTEE_SIZE>(&state, optim).unwrap(),
+                    block.epoch
+                );
+            }
+        }
+
+        // a state that is too short to hold a committee
+        let state = initial_state_for::<Fr, COMMITTEE_SIZE>(
+            bc.get(0).unwrap(),
+            OptimizationGoal::Constraints,
+        )
+        .unwrap();
+        assert!(matches!(
+            committee_from_field_elements::<_, COMMITTEE_SIZE>(
+                &state[..state.len() / 2],
+                OptimizationGoal::Constraints
+            ),
+            Err(SynthesisError::AssignmentMissing)
+        ));
+    }
+
+    #[cfg(feature = "bench-utils")]
+    mod decider {
+        use ark_groth16::Groth16;
+        use ark_mnt4_298::{Fr, G1Projective as G1, MNT4_298 as MNT4};
+        use ark_mnt6_298::{G1Projective as G2, MNT6_298 as MNT6};
+        use folding_schemes::{
+            commitment::kzg::KZG,
+            folding::nova::{decider::Decider as NovaDecider, Nova},
+            frontend::FCircuit,
+            transcript::poseidon::poseidon_canonical_config,
+        };
+        use rand::{rngs::StdRng, SeedableRng};
+
+        use super::{assert_same_committee, COMMITTEE_SIZE};
+        use crate::{
+            bc::block::{gen_blockchain_with_params, Block, Blockchain},
+            bench_support::{DummyBlockVar, MockCircuit},
+            folding::{
+                decider::{initial_state, prove_chain, setup_decider},
+                light_client::{verify_chain_proof, LightClientError},
+                schemes::init_folding,
+            },
+        };
+
+        type FC = MockCircuit<Fr, Block<COMMITTEE_SIZE>, DummyBlockVar>;
+        type N = Nova<G1, G2, FC, KZG<'static, MNT4>, KZG<'static, MNT6>, false>;
+        type D = NovaDecider<
+            G1,
+            G2,
+            FC,
+            KZG<'static, MNT4>,
+            KZG<'static, MNT6>,
+            Groth16<MNT4>,
+            Groth16<MNT6>,
+            N,
+        >;
+
+        #[test]
+        #[ignore = "deciding the folded chain takes a long time"]
+        fn test_verify_chain_proof() {
+            let mut rng = StdRng::from_seed([42; 32]);
+            let bc: Blockchain<COMMITTEE_SIZE> =
+                gen_blockchain_with_params(3, COMMITTEE_SIZE, &mut rng);
+            let genesis = bc.get(0).unwrap().clone();
+
+            let f_circuit = FC::no_merkle(0);
+            let (mut nova, nova_params) = init_folding::<N, _, _, _>(
+                poseidon_canonical_config::<Fr>(),
+                f_circuit,
+                initial_state(&genesis).unwrap(),
+                &mut rng,
+            )
+            .unwrap();
+            let (decider_pp, decider_vp) =
+                setup_decider::<_, _, _, _, _, D>(&mut rng, nova_params, f_circuit.state_len())
+                    .unwrap();
+
+            let blocks: Vec<_> = bc.into_blocks().skip(1).collect();
+            let num_steps = u64::try_from(blocks.len()).unwrap();
+            let proof =
+                prove_chain::<_, _, _, _, _, D>(&mut rng, &mut nova, blocks, decider_pp).unwrap();
+
+            // the mock keeps the state, so the chain stays at the genesis block
+            let (committee, epoch) = verify_chain_proof::<_, _, _, N, D, COMMITTEE_SIZE>(
+                decider_vp.clone(),
+                &genesis.committee,
+                &proof.z_i,
+                &proof,
+                num_steps,
+            )
+            .unwrap();
+            assert_same_committee(&committee, &genesis.committee);
+            assert_eq!(epoch, genesis.epoch);
+
+            // a tampered final state should be rejected
+            let mut tampered = proof.z_i.clone();
+            tampered[0] += Fr::from(1u64);
+            assert!(matches!(
+                verify_chain_proof::<_, _, _, N, D, COMMITTEE_SIZE>(
+                    decider_vp.clone(),
+                    &genesis.committee,
+                    &tampered,
+                    &proof,
+                    num_steps,
+                ),
+                Err(LightClientError::InvalidProof | LightClientError::FoldingError(_))
+            ));
+
+            // as should a proof for a different number of steps
+            assert!(matches!(
+                verify_chain_proof::<_, _, _, N, D, COMMITTEE_SIZE>(
+                    decider_vp,
+                    &genesis.committee,
+                    &proof.z_i,
+                    &proof,
+                    num_steps + 1,
+                ),
+                Err(LightClientError::InvalidProof | LightClientError::FoldingError(_))
+            ));
+        }
+    }
+}
@@ -1,6 +1,51 @@
-mod serialize;
+pub(crate) mod serialize;
 
 pub mod bc;
+pub mod checkpoint;
 pub mod circuit;
+pub mod decider;
 pub mod from_constraint_field;
+pub mod light_client;
+pub mod resume;
+pub mod schemes;
 pub mod to_constraint_field;
+
+/// A cheap step circuit shared by the tests of the folding schemes.
+#[cfg(test)]
+mod test_circuit {
+    use std::marker::PhantomData;
+
+    use ark_ff::PrimeField;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+    use folding_schemes::{frontend::FCircuit, Error};
+
+    /// Computes `z_{i+1} = z_i^3 + z_i + x_i`, so that folding is cheap.
+    #[derive(Clone, Copy, Debug)]
+    pub(crate) struct MockCircuit<F>(PhantomData<F>);
+
+    impl<F: PrimeField> FCircuit<F> for MockCircuit<F> {
+        type Params = ();
+        type ExternalInputs = F;
+        type ExternalInputsVar = FpVar<F>;
+
+        fn new(_: Self::Params) -> Result<Self, Error> {
+            Ok(Self(PhantomData))
+        }
+
+        fn state_len(&self) -> usize {
+            1
+        }
+
+        fn generate_step_constraints(
+            &self,
+            _: ConstraintSystemRef<F>,
+            _: usize,
+            z_i: Vec<FpVar<F>>,
+            external_inputs: Self::ExternalInputsVar,
+        ) -> Result<Vec<FpVar<F>>, SynthesisError> {
+            let z = &z_i[0];
+            Ok(vec![z * z * z + z + external_inputs])
+        }
+    }
+}
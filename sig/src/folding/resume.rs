@@ -0,0 +1,132 @@
+//! Persist a folding instance to resume folding a long chain later.
+//!
+//! Only the IVC proof of the folding scheme needs to be stored. For Nova this
+//! is the step counter `i`, the initial and current states `z_0` and `z_i`,
+//! the running instance and witness `U_i`/`W_i`, and the incoming instance and
+//! witness `u_i`/`w_i`. The prover and verifier parameters are much larger but
+//! only depend on the step circuit, so they are stored once (or regenerated)
+//! and passed back to `resume` together with the circuit parameters.
+
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, Write};
+use ark_std::rand::{CryptoRng, RngCore};
+use folding_schemes::{frontend::FCircuit, Curve, Error, FoldingScheme};
+
+/// Serialize the running state of `fs` into `writer`.
+pub fn save<C1, C2, FC, FS>(fs: &FS, writer: impl Write) -> Result<(), Error>
+where
+    C1: Curve,
+    C2: Curve,
+    FC: FCircuit<C1::ScalarField>,
+    FS: FoldingScheme<C1, C2, FC>,
+{
+    Ok(fs.ivc_proof().serialize_compressed(writer)?)
+}
+
+/// Restore a folding instance saved by `save`.
+#[allow(clippy::type_complexity)]
+pub fn restore<C1, C2, FC, FS>(
+    params: (FS::ProverParam, FS::VerifierParam),
+    fcircuit_params: FC::Params,
+    state: impl Read,
+) -> Result<FS, Error>
+where
+    C1: Curve,
+    C2: Curve,
+    FC: FCircuit<C1::ScalarField>,
+    FS: FoldingScheme<C1, C2, FC>,
+{
+    let ivc_proof = FS::IVCProof::deserialize_compressed(state)?;
+    FS::from_ivc_proof(ivc_proof, fcircuit_params, params)
+}
+
+/// Restore a folding instance saved by `save` and fold `next_blocks` into it.
+#[allow(clippy::type_complexity)]
+pub fn resume<C1, C2, FC, FS>(
+    mut rng: impl RngCore + CryptoRng,
+    params: (FS::ProverParam, FS::VerifierParam),
+    fcircuit_params: FC::Params,
+    state: impl Read,
+    next_blocks: impl IntoIterator<Item = FC::ExternalInputs>,
+) -> Result<FS, Error>
+where
+    C1: Curve,
+    C2: Curve,
+    FC: FCircuit<C1::ScalarField>,
+    FS: FoldingScheme<C1, C2, FC>,
+{
+    let mut fs = restore(params, fcircuit_params, state)?;
+
+    for block in next_blocks {
+        fs.prove_step(&mut rng, block, None)?;
+    }
+
+    Ok(fs)
+}
+
+#[cfg(test)]
+mod test {
+    use ark_mnt4_298::{Fr, G1Projective as G1, MNT4_298 as MNT4};
+    use ark_mnt6_298::{G1Projective as G2, MNT6_298 as MNT6};
+    use folding_schemes::{
+        commitment::kzg::KZG,
+        folding::nova::{Nova, PreprocessorParam},
+        frontend::FCircuit,
+        transcript::poseidon::poseidon_canonical_config,
+        FoldingScheme,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::{resume, save};
+    use crate::{
+        bc::{
+            block::{gen_blockchain_with_params, Blockchain},
+            params::QuorumThreshold,
+        },
+        bls::Parameters,
+        folding::{circuit::BCCircuitNoMerkle, decider::initial_state},
+    };
+
+    const COMMITTEE_SIZE: usize = 4;
+
+    type FC = BCCircuitNoMerkle<Fr, COMMITTEE_SIZE>;
+    type N = Nova<G1, G2, FC, KZG<'static, MNT4>, KZG<'static, MNT6>, false>;
+
+    #[test]
+    #[ignore = "folding the circuit takes a long time"]
+    fn test_save_and_resume() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let bc: Blockchain<COMMITTEE_SIZE> =
+            gen_blockchain_with_params(4, COMMITTEE_SIZE, &mut rng);
+
+        let f_circuit = FC::new((Parameters::setup(), QuorumThreshold::default())).unwrap();
+        let nova_params = N::preprocess(
+            &mut rng,
+            &PreprocessorParam::new(poseidon_canonical_config::<Fr>(), f_circuit.clone()),
+        )
+        .unwrap();
+
+        let z_0 = initial_state(bc.get(0).unwrap()).unwrap();
+        let mut nova = N::init(&nova_params, f_circuit, z_0).unwrap();
+
+        let mut blocks = bc.into_blocks().skip(1);
+        for block in blocks.by_ref().take(2) {
+            nova.prove_step(&mut rng, block, None).unwrap();
+        }
+
+        let mut state = vec![];
+        save(&nova, &mut state).unwrap();
+        drop(nova);
+
+        let nova: N = resume(
+            &mut rng,
+            nova_params.clone(),
+            (Parameters::setup(), QuorumThreshold::default()),
+            &*state,
+            blocks,
+        )
+        .unwrap();
+
+        assert_eq!(nova.i, Fr::from(3u64));
+        N::verify(nova_params.1, nova.ivc_proof()).unwrap();
+    }
+}
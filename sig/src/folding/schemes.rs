@@ -0,0 +1,184 @@
+//! Folding schemes that fold the blockchain circuits.
+//!
+//! Every scheme of sonobe takes the same `FCircuit`, so the circuits of
+//! `circuit` can be folded by any of them. The aliases below fix the
+//! parameters that do not matter for the blockchain circuits, and
+//! `init_folding` hides how each scheme is preprocessed, so that switching
+//! scheme only takes changing the alias:
+//!
+//! ```ignore
+//! type FS = BCHyperNova<G1, G2, FC, KZG<'static, E1>, Pedersen<G2>>;
+//! let (mut folding, params) = init_folding::<FS, _, _, _>(poseidon_config, f_circuit, z_0, &mut rng)?;
+//! ```
+
+use ark_crypto_primitives::sponge::poseidon::PoseidonConfig;
+use ark_std::rand::RngCore;
+use folding_schemes::{
+    commitment::CommitmentScheme,
+    folding::{hypernova, hypernova::HyperNova, nova, nova::Nova},
+    frontend::FCircuit,
+    Curve, Error, FoldingScheme,
+};
+
+/// Nova without hiding the witnesses.
+pub type BCNova<C1, C2, FC, CS1, CS2> = Nova<C1, C2, FC, CS1, CS2, false>;
+
+/// HyperNova that folds one running and one incoming instance per step, as
+/// Nova does, without hiding the witnesses.
+pub type BCHyperNova<C1, C2, FC, CS1, CS2> = HyperNova<C1, C2, FC, CS1, CS2, 1, 1, false>;
+
+/// A folding scheme whose preprocessing only needs a Poseidon configuration
+/// and the step circuit.
+pub trait BCFoldingScheme<C1: Curve, C2: Curve, FC: FCircuit<C1::ScalarField>>:
+    FoldingScheme<C1, C2, FC>
+{
+    fn preprocessor_param(
+        poseidon_config: PoseidonConfig<C1::ScalarField>,
+        f_circuit: FC,
+    ) -> Self::PreprocessorParam;
+}
+
+impl<C1, C2, FC, CS1, CS2, const H: bool> BCFoldingScheme<C1, C2, FC>
+    for Nova<C1, C2, FC, CS1, CS2, H>
+where
+    C1: Curve,
+    C2: Curve,
+    FC: FCircuit<C1::ScalarField>,
+    CS1: CommitmentScheme<C1, H>,
+    CS2: CommitmentScheme<C2, H>,
+    Self: FoldingScheme<
+        C1,
+        C2,
+        FC,
+        PreprocessorParam = nova::PreprocessorParam<C1, C2, FC, CS1, CS2, H>,
+    >,
+{
+    fn preprocessor_param(
+        poseidon_config: PoseidonConfig<C1::ScalarField>,
+        f_circuit: FC,
+    ) -> Self::PreprocessorParam {
+        nova::PreprocessorParam::new(poseidon_config, f_circuit)
+    }
+}
+
+impl<C1, C2, FC, CS1, CS2, const MU: usize, const NU: usize, const H: bool>
+    BCFoldingScheme<C1, C2, FC> for HyperNova<C1, C2, FC, CS1, CS2, MU, NU, H>
+where
+    C1: Curve,
+    C2: Curve,
+    FC: FCircuit<C1::ScalarField>,
+    CS1: CommitmentScheme<C1, H>,
+    CS2: CommitmentScheme<C2, H>,
+    Self: FoldingScheme<
+        C1,
+        C2,
+        FC,
+        PreprocessorParam = hypernova::PreprocessorParam<C1, C2, FC, CS1, CS2, H>,
+    >,
+{
+    fn preprocessor_param(
+        poseidon_config: PoseidonConfig<C1::ScalarField>,
+        f_circuit: FC,
+    ) -> Self::PreprocessorParam {
+        hypernova::PreprocessorParam::new(poseidon_config, f_circuit)
+    }
+}
+
+/// Preprocess `FS` for `f_circuit` and start folding from `z_0`.
+///
+/// The parameters are returned as well, as they are needed to set up a decider
+/// or to `resume` folding later.
+#[allow(clippy::type_complexity)]
+pub fn init_folding<FS, C1, C2, FC>(
+    poseidon_config: PoseidonConfig<C1::ScalarField>,
+    f_circuit: FC,
+    z_0: Vec<C1::ScalarField>,
+    rng: impl RngCore,
+) -> Result<(FS, (FS::ProverParam, FS::VerifierParam)), Error>
+where
+    C1: Curve,
+    C2: Curve,
+    FC: FCircuit<C1::ScalarField>,
+    FS: BCFoldingScheme<C1, C2, FC>,
+{
+    let params = FS::preprocess(
+        rng,
+        &FS::preprocessor_param(poseidon_config, f_circuit.clone()),
+    )?;
+    let folding = FS::init(&params, f_circuit, z_0)?;
+    Ok((folding, params))
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bn254::{Bn254, Fr, G1Projective as G1};
+    use ark_groth16::Groth16;
+    use ark_grumpkin::Projective as G2;
+    use folding_schemes::{
+        commitment::{kzg::KZG, pedersen::Pedersen},
+        folding::hypernova::decider_eth::Decider as HyperNovaDecider,
+        frontend::FCircuit,
+        transcript::poseidon::poseidon_canonical_config,
+        Decider, FoldingScheme,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::folding::test_circuit::MockCircuit;
+
+    use super::{init_folding, BCHyperNova};
+
+    type FC = MockCircuit<Fr>;
+    type HN = BCHyperNova<G1, G2, FC, KZG<'static, Bn254>, Pedersen<G2>>;
+    type D =
+        HyperNovaDecider<G1, G2, FC, KZG<'static, Bn254>, Pedersen<G2>, Groth16<Bn254>, HN, 1, 1>;
+
+    #[test]
+    #[ignore = "deciding a HyperNova instance takes a long time"]
+    fn test_hypernova_fold_and_decide() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let f_circuit = FC::new(()).unwrap();
+
+        let (mut hypernova, params) = init_folding::<HN, _, _, _>(
+            poseidon_canonical_config::<Fr>(),
+            f_circuit,
+            vec![Fr::from(7u64)],
+            &mut rng,
+        )
+        .unwrap();
+
+        for x in [Fr::from(1u64), Fr::from(2u64)] {
+            hypernova.prove_step(&mut rng, x, None).unwrap();
+        }
+        assert_eq!(hypernova.i, Fr::from(2u64));
+        HN::verify(params.1.clone(), hypernova.ivc_proof()).unwrap();
+
+        let (decider_pp, decider_vp) =
+            D::preprocess(&mut rng, (params, f_circuit.state_len())).unwrap();
+        let proof = D::prove(&mut rng, decider_pp, hypernova.clone()).unwrap();
+
+        assert!(D::verify(
+            decider_vp.clone(),
+            hypernova.i,
+            hypernova.z_0.clone(),
+            hypernova.z_i.clone(),
+            &(),
+            &(),
+            &proof,
+        )
+        .unwrap());
+
+        // a proof for a different final state should be rejected
+        let mut z_i = hypernova.z_i.clone();
+        z_i[0] += Fr::from(1u64);
+        assert!(!D::verify(
+            decider_vp,
+            hypernova.i,
+            hypernova.z_0,
+            z_i,
+            &(),
+            &(),
+            &proof
+        )
+        .unwrap_or(false));
+    }
+}
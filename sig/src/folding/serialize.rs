@@ -1,31 +1,40 @@
-use ark_ff::PrimeField;
+use ark_ec::bls12::Bls12Config;
+use ark_ff::{BigInteger, PrimeField};
 use ark_r1cs_std::{
-    fields::emulated_fp::EmulatedFpVar,
-    prelude::{Boolean, ToBytesGadget},
+    fields::{emulated_fp::EmulatedFpVar, FieldOpsBounds, FieldVar},
+    groups::bls12::{G1Var, G2Var},
+    prelude::{Boolean, ToBitsGadget, ToBytesGadget},
     uint64::UInt64,
     uint8::UInt8,
 };
 use ark_relations::r1cs::SynthesisError;
 
 use crate::{
+    bc::block::Block,
     bls::{PublicKeyVar, SignatureVar},
     params::{BlsSigConfig, BlsSigField},
 };
 
-use super::bc::{BlockVar, CommitteeVar, QuorumSignatureVar, SignerVar};
+use super::bc::{BlockVar, CommitteeVar, QuorumSignatureVar, SignerVar, SignersVar};
 
 /// Serialize a R1CS variable to a canonical byte representation
 /// Implementation should match the result of `bincode::serialize`.
+///
+/// Every implementation documents its byte layout. A field added to `Block`
+/// needs the same layout on both sides, which `Block::encoded_len` and the
+/// tests below check.
 pub trait SerializeGadget<F: PrimeField> {
     fn serialize(&self) -> Result<Vec<UInt8<F>>, SynthesisError>;
 }
 
+/// The byte itself.
 impl<CF: PrimeField> SerializeGadget<CF> for UInt8<CF> {
     fn serialize(&self) -> Result<Vec<Self>, SynthesisError> {
         self.to_bytes_le()
     }
 }
 
+/// 8 bytes in little-endian order, as `bincode` encodes `u64`.
 impl<CF: PrimeField> SerializeGadget<CF> for UInt64<CF> {
     fn serialize(&self) -> Result<Vec<UInt8<CF>>, SynthesisError> {
         self.to_bytes_le()
@@ -40,32 +49,157 @@ impl<CF: PrimeField> SerializeGadget<CF> for [Boolean<CF>] {
     }
 }
 
+/// The bytes in order, without a length prefix, as `bincode` encodes a
+/// fixed-size array.
 impl<CF: PrimeField> SerializeGadget<CF> for [UInt8<CF>] {
     fn serialize(&self) -> Result<Vec<UInt8<CF>>, SynthesisError> {
         self.to_bytes_le()
     }
 }
 
-impl<CF: PrimeField> SerializeGadget<CF>
-    for SignatureVar<BlsSigConfig, EmulatedFpVar<BlsSigField<BlsSigConfig>, CF>, CF>
-{
+/// The `N` bytes in order, without a length prefix, as `bincode` encodes
+/// `[u8; N]`, e.g. a digest.
+impl<CF: PrimeField, const N: usize> SerializeGadget<CF> for [UInt8<CF>; N] {
     fn serialize(&self) -> Result<Vec<UInt8<CF>>, SynthesisError> {
-        self.as_ref().to_bytes_le()
+        self.as_slice().serialize()
     }
 }
 
-impl<CF: PrimeField> SerializeGadget<CF>
-    for PublicKeyVar<BlsSigConfig, EmulatedFpVar<BlsSigField<BlsSigConfig>, CF>, CF>
-{
+/// A bitmap: 8 booleans per byte, from the least significant bit, with the
+/// unused bits of the last byte set to zero, and without a length prefix.
+impl<CF: PrimeField> SerializeGadget<CF> for Vec<Boolean<CF>> {
+    fn serialize(&self) -> Result<Vec<UInt8<CF>>, SynthesisError> {
+        Ok(self
+            .chunks(8)
+            .map(|chunk| {
+                let mut bits = chunk.to_vec();
+                bits.resize(8, Boolean::FALSE);
+                UInt8::from_bits_le(&bits)
+            })
+            .collect())
+    }
+}
+
+/// The encoding of the point under `POINT_ENCODING`, i.e. `encoded_point_len`
+/// bytes, which is what `to_bytes_le` returns.
+impl<CF: PrimeField> SerializeGadget<CF> for SignatureVar<BlsSigConfig, SigFieldVar<CF>, CF> {
     fn serialize(&self) -> Result<Vec<UInt8<CF>>, SynthesisError> {
-        self.as_ref().to_bytes_le()
+        self.to_bytes_le()
+    }
+}
+
+/// The encoding of the point under `POINT_ENCODING`, i.e. `encoded_point_len`
+/// bytes, which is what `to_bytes_le` returns.
+impl<CF: PrimeField> SerializeGadget<CF> for PublicKeyVar<BlsSigConfig, SigFieldVar<CF>, CF> {
+    fn serialize(&self) -> Result<Vec<UInt8<CF>>, SynthesisError> {
+        self.to_bytes_le()
+    }
+}
+
+type SigFieldVar<CF> = EmulatedFpVar<BlsSigField<BlsSigConfig>, CF>;
+
+/// The compressed encoding of a point on G1, i.e. `serialize_compressed` of a
+/// BLS12-381 public key, which message augmentation prepends to the message.
+pub(crate) fn compress_g1<SigCurveConfig, FV, CF>(
+    point: &G1Var<SigCurveConfig, FV, CF>,
+) -> Result<Vec<UInt8<CF>>, SynthesisError>
+where
+    SigCurveConfig: Bls12Config,
+    FV: FieldVar<BlsSigField<SigCurveConfig>, CF>,
+    CF: PrimeField,
+    for<'a> &'a FV: FieldOpsBounds<'a, BlsSigField<SigCurveConfig>, FV>,
+{
+    let affine = point.to_affine()?;
+    compressed_point_bytes::<BlsSigField<SigCurveConfig>, _, _>(
+        &[&affine.x],
+        &[&affine.y],
+        &affine.infinity,
+    )
+}
+
+/// Same as `compress_g1` for a point on G2, i.e. `serialize_compressed` of a
+/// BLS12-381 signature.
+pub(crate) fn compress_g2<SigCurveConfig, FV, CF>(
+    point: &G2Var<SigCurveConfig, FV, CF>,
+) -> Result<Vec<UInt8<CF>>, SynthesisError>
+where
+    SigCurveConfig: Bls12Config,
+    FV: FieldVar<BlsSigField<SigCurveConfig>, CF>,
+    CF: PrimeField,
+    for<'a> &'a FV: FieldOpsBounds<'a, BlsSigField<SigCurveConfig>, FV>,
+{
+    let affine = point.to_affine()?;
+    compressed_point_bytes::<BlsSigField<SigCurveConfig>, _, _>(
+        &[&affine.x.c1, &affine.x.c0],
+        &[&affine.y.c1, &affine.y.c0],
+        &affine.infinity,
+    )
+}
+
+/// The compressed `CanonicalSerialize` encoding of a BLS12-381 point, whose
+/// coordinates are given by their coefficients from the most significant one.
+///
+/// This is the big-endian `x`, where the top 3 bits of the first byte flag
+/// that the point is compressed, is at infinity, and has the
+/// lexicographically largest `y`. The point at infinity has `x = 0`, as
+/// returned by `to_affine`.
+fn compressed_point_bytes<F: PrimeField, FV: FieldVar<F, CF>, CF: PrimeField>(
+    x: &[&FV],
+    y: &[&FV],
+    infinity: &Boolean<CF>,
+) -> Result<Vec<UInt8<CF>>, SynthesisError> {
+    let half = F::MODULUS_MINUS_ONE_DIV_TWO;
+
+    // `y > -y` compares the coefficients from the most significant one, and
+    // a coefficient `c` is larger than `-c` iff `c > (p - 1) / 2`
+    let mut y_is_largest = Boolean::FALSE;
+    for c in y.iter().rev() {
+        let bits = c.to_bits_le()?;
+        let is_zero = !Boolean::kary_or(&bits)?;
+        y_is_largest = is_greater_than_le(&bits, half)? | (is_zero & y_is_largest);
+    }
+
+    let mut bytes = vec![];
+    for c in x {
+        let mut c_bytes = c.to_bytes_le()?;
+        c_bytes.reverse();
+        bytes.extend(c_bytes);
+    }
+
+    // the modulus has 381 bits, so the top 3 bits of `x` are zero
+    let mut flags = bytes[0].to_bits_le()?;
+    flags[7] = Boolean::TRUE;
+    flags[6] = infinity.clone();
+    flags[5] = !infinity & y_is_largest;
+    bytes[0] = UInt8::from_bits_le(&flags);
+
+    Ok(bytes)
+}
+
+/// Whether the little-endian `bits` are greater than `element`.
+fn is_greater_than_le<CF: PrimeField>(
+    bits: &[Boolean<CF>],
+    element: impl BigInteger,
+) -> Result<Boolean<CF>, SynthesisError> {
+    let mut is_greater = Boolean::FALSE;
+    let mut is_equal = Boolean::TRUE;
+    for (i, bit) in bits.iter().enumerate().rev() {
+        if element.get_bit(i) {
+            is_equal = &is_equal & bit;
+        } else {
+            is_greater = &is_greater | &(&is_equal & bit);
+            is_equal = &is_equal & !bit;
+        }
     }
+    Ok(is_greater)
 }
 
 /*
 `.to_bytes_le()` should not exist after this line
 */
 
+/// The key followed by the weight, as `bincode` encodes the tuple
+/// `(AuthorityPublicKey, Weight)`.
 impl<CF: PrimeField> SerializeGadget<CF> for SignerVar<CF> {
     fn serialize(&self) -> Result<Vec<UInt8<CF>>, SynthesisError> {
         let mut pk = self.pk.serialize()?;
@@ -75,6 +209,7 @@ impl<CF: PrimeField> SerializeGadget<CF> for SignerVar<CF> {
     }
 }
 
+/// Every signer in order, without a length prefix.
 impl<CF: PrimeField> SerializeGadget<CF> for [SignerVar<CF>] {
     fn serialize(&self) -> Result<Vec<UInt8<CF>>, SynthesisError> {
         self.iter()
@@ -84,6 +219,15 @@ impl<CF: PrimeField> SerializeGadget<CF> for [SignerVar<CF>] {
     }
 }
 
+/// The bitmap of the signers, i.e. `MAX_COMMITTEE_SIZE.div_ceil(8)` bytes, as
+/// `packed_signers` in `bc::block`.
+impl<CF: PrimeField> SerializeGadget<CF> for SignersVar<CF> {
+    fn serialize(&self) -> Result<Vec<UInt8<CF>>, SynthesisError> {
+        self.0.serialize()
+    }
+}
+
+/// The signature followed by the bitmap of the signers.
 impl<CF: PrimeField> SerializeGadget<CF> for QuorumSignatureVar<CF> {
     fn serialize(&self) -> Result<Vec<UInt8<CF>>, SynthesisError> {
         let mut sig = self.sig.serialize()?;
@@ -94,6 +238,9 @@ impl<CF: PrimeField> SerializeGadget<CF> for QuorumSignatureVar<CF> {
     }
 }
 
+/// The `MAX_COMMITTEE_SIZE` signers in order, i.e.
+/// `MAX_COMMITTEE_SIZE` times the length of a signer, without a length prefix,
+/// as `serde_as` encodes the array of `Committee`.
 impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize> SerializeGadget<CF>
     for CommitteeVar<CF, MAX_COMMITTEE_SIZE>
 {
@@ -102,6 +249,9 @@ impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize> SerializeGadget<CF>
     }
 }
 
+/// The fields in the order of `Block`: the epoch, the digest of the previous
+/// block, the quorum signature and the committee, i.e.
+/// `Block::encoded_len()` bytes.
 impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize> SerializeGadget<CF>
     for BlockVar<CF, MAX_COMMITTEE_SIZE>
 {
@@ -115,18 +265,21 @@ impl<CF: PrimeField, const MAX_COMMITTEE_SIZE: usize> SerializeGadget<CF>
         epoch.extend(sig);
         epoch.extend(committee);
 
+        debug_assert_eq!(epoch.len(), Block::<MAX_COMMITTEE_SIZE>::encoded_len());
         Ok(epoch)
     }
 }
 
 #[cfg(test)]
 mod test {
-    use ark_r1cs_std::{alloc::AllocVar, uint64::UInt64, uint8::UInt8, R1CSVar};
+    use ark_r1cs_std::{alloc::AllocVar, prelude::Boolean, uint64::UInt64, uint8::UInt8, R1CSVar};
     use ark_relations::r1cs::ConstraintSystem;
 
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
     use crate::{
-        bc::block::{Block, Committee, QuorumSignature},
-        bls::{Parameters, PublicKey, SecretKey, Signature, SignatureVar},
+        bc::block::{gen_blockchain_with_params, Block, Blockchain, Committee, QuorumSignature},
+        bls::{Parameters, PublicKey, PublicKeyVar, SecretKey, Signature, SignatureVar},
         folding::bc::{BlockVar, CommitteeVar, QuorumSignatureVar, SignerVar},
         params::{BlsSigConfig, BlsSigField},
     };
@@ -170,6 +323,52 @@ mod test {
             .collect();
 
         assert_eq!(xs, xvs);
+
+        // a fixed-size array has no length prefix either
+        let xv: [UInt8<CF>; 20] = xv.try_into().unwrap();
+        assert_eq!(xs, xv.serialize().unwrap().value().unwrap());
+    }
+
+    #[test]
+    fn bitmap_ser() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let cs = ConstraintSystem::<CF>::new_ref();
+
+        for len in [0, 1, 8, 13] {
+            let x: Vec<bool> = (0..len).map(|_| rng.gen_bool(0.5)).collect();
+            let xv = Vec::<Boolean<CF>>::new_witness(cs.clone(), || Ok(x.as_slice())).unwrap();
+
+            let expected: Vec<u8> = x
+                .chunks(8)
+                .map(|chunk| {
+                    chunk
+                        .iter()
+                        .enumerate()
+                        .fold(0, |byte, (i, bit)| byte | (u8::from(*bit) << i))
+                })
+                .collect();
+            assert_eq!(xv.serialize().unwrap().value().unwrap(), expected);
+        }
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    /// The lengths for `POINT_ENCODING` and a committee of 25, so that a change
+    /// of the layout on either side fails here.
+    #[test]
+    fn golden_block_len() {
+        #[cfg(not(feature = "compressed-points"))]
+        const GOLDEN_BLOCK_LEN: usize = 2862;
+        #[cfg(feature = "compressed-points")]
+        const GOLDEN_BLOCK_LEN: usize = 1540;
+
+        assert_eq!(Block::<MAX_COMMITTEE_SIZE>::encoded_len(), GOLDEN_BLOCK_LEN);
+
+        let x = Block::<MAX_COMMITTEE_SIZE>::default();
+        assert_eq!(bincode::serialize(&x).unwrap().len(), GOLDEN_BLOCK_LEN);
+
+        let cs = ConstraintSystem::<CF>::new_ref();
+        let xv = BlockVar::new_constant(cs, x).unwrap();
+        assert_eq!(xv.serialize().unwrap().len(), GOLDEN_BLOCK_LEN);
     }
 
     #[test]
@@ -208,6 +407,37 @@ mod test {
         assert_eq!(xs, xvs);
     }
 
+    fn check_packed_signers_ser<const N: usize>(rng: &mut StdRng) {
+        let cs = ConstraintSystem::<CF>::new_ref();
+
+        let x = QuorumSignature::<N> {
+            sig: Default::default(),
+            signers: core::array::from_fn(|_| rng.gen_bool(0.5)),
+        };
+        let xv = QuorumSignatureVar::new_witness(cs.clone(), || Ok(x.clone())).unwrap();
+
+        let xs = bincode::serialize(&x).unwrap();
+        let xvs: Vec<u8> = xv
+            .serialize()
+            .unwrap()
+            .iter()
+            .map(|v| v.value().unwrap())
+            .collect();
+
+        assert_eq!(xs, xvs);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn packed_signers_ser() {
+        let mut rng = StdRng::seed_from_u64(42);
+
+        // committee sizes that do and don't fill the last byte
+        check_packed_signers_ser::<MAX_COMMITTEE_SIZE>(&mut rng);
+        check_packed_signers_ser::<16>(&mut rng);
+        check_packed_signers_ser::<1>(&mut rng);
+    }
+
     #[test]
     fn signer_ser() {
         let cs = ConstraintSystem::<CF>::new_ref();
@@ -264,4 +494,107 @@ mod test {
 
         assert_eq!(xs, xvs);
     }
+
+    #[test]
+    fn compressed_point_ser() {
+        use ark_ec::{AffineRepr, CurveGroup};
+        use ark_std::UniformRand;
+
+        use crate::bc::{block::encode_curve_point, params::PointEncoding};
+
+        use super::{compress_g1, compress_g2, SigFieldVar};
+
+        let mut rng = StdRng::from_seed([42; 32]);
+        let mut pks = vec![ark_bls12_381::G1Projective::default()];
+        let mut sigs = vec![ark_bls12_381::G2Projective::default()];
+        for _ in 0..4 {
+            // a point and its negation differ only in the sign flag
+            let pk = ark_bls12_381::G1Projective::rand(&mut rng);
+            let sig = ark_bls12_381::G2Projective::rand(&mut rng);
+            pks.extend([pk, -pk]);
+            sigs.extend([sig, -sig]);
+        }
+
+        let cs = ConstraintSystem::<CF>::new_ref();
+        for pk in pks {
+            let expected = encode_curve_point(pk.into_affine(), PointEncoding::Compressed).unwrap();
+            let pk = PublicKey::<BlsSigConfig>::from(pk);
+            let witness =
+                PublicKeyVar::<_, SigFieldVar<CF>, _>::new_witness(cs.clone(), || Ok(pk)).unwrap();
+            let constant =
+                PublicKeyVar::<_, SigFieldVar<CF>, _>::new_constant(cs.clone(), pk).unwrap();
+            assert_eq!(
+                compress_g1(witness.as_ref()).unwrap().value().unwrap(),
+                expected
+            );
+            assert_eq!(
+                compress_g1(constant.as_ref()).unwrap().value().unwrap(),
+                expected
+            );
+        }
+        for sig in sigs {
+            let expected =
+                encode_curve_point(sig.into_affine(), PointEncoding::Compressed).unwrap();
+            // the top bits of the first byte are the flags
+            assert_eq!(expected[0] >> 7, 1);
+            assert_eq!(
+                (expected[0] >> 6) & 1,
+                u8::from(sig.into_affine().is_zero())
+            );
+
+            let sig = Signature::<BlsSigConfig>::from(sig);
+            let witness =
+                SignatureVar::<_, SigFieldVar<CF>, _>::new_witness(cs.clone(), || Ok(sig)).unwrap();
+            let constant =
+                SignatureVar::<_, SigFieldVar<CF>, _>::new_constant(cs.clone(), sig).unwrap();
+            assert_eq!(
+                compress_g2(witness.as_ref()).unwrap().value().unwrap(),
+                expected
+            );
+            assert_eq!(
+                compress_g2(constant.as_ref()).unwrap().value().unwrap(),
+                expected
+            );
+        }
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn random_block_ser() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let bc: Blockchain<MAX_COMMITTEE_SIZE> =
+            gen_blockchain_with_params(4, MAX_COMMITTEE_SIZE, &mut rng);
+
+        for mut block in bc.into_blocks() {
+            // also exercise epochs and digests that do not come from a valid chain
+            if rng.gen_bool(0.5) {
+                block.epoch = rng.gen();
+                block.prev_digest = rng.gen();
+                block.sig.signers = core::array::from_fn(|_| rng.gen_bool(0.5));
+            }
+
+            let cs = ConstraintSystem::<CF>::new_ref();
+            let block_var = BlockVar::new_witness(cs.clone(), || Ok(block.clone())).unwrap();
+
+            // the bytes hashed by `compute_digest`
+            let xs = bincode::serialize(&block).unwrap();
+            let xvs = block_var.serialize().unwrap().value().unwrap();
+            assert_eq!(xs, xvs);
+            assert_eq!(xs.len(), Block::<MAX_COMMITTEE_SIZE>::encoded_len());
+
+            // the bytes signed by the committee, built the same way as
+            // `bc_generate_constraints` does in the folding circuit
+            let mut block_without_sig = block.clone();
+            block_without_sig.sig = QuorumSignature::default();
+            let mut block_var_without_sig = block_var;
+            block_var_without_sig.sig =
+                QuorumSignatureVar::new_constant(cs.clone(), QuorumSignature::default()).unwrap();
+
+            let xs = bincode::serialize(&block_without_sig).unwrap();
+            let xvs = block_var_without_sig.serialize().unwrap().value().unwrap();
+            assert_eq!(xs, xvs);
+
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
 }
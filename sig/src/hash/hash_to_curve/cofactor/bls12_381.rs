@@ -195,20 +195,24 @@ where
 mod test {
     use std::ops::Neg;
 
-    use ark_bls12_381::{g2::Config, Fq, Fq2, Fq2Config};
+    use ark_bls12_381::{g2::Config, Fq, Fq2, Fq2Config, G2Projective};
     use ark_ec::{
         short_weierstrass::{Affine, Projective},
-        AffineRepr,
+        AffineRepr, CurveGroup,
     };
     use ark_ff::{AdditiveGroup, Field, Fp2ConfigWrapper, MontFp, UniformRand};
     use ark_r1cs_std::{
+        alloc::AllocationMode,
         fields::{fp::FpVar, quadratic_extension::QuadExtVar},
         groups::{curves::short_weierstrass::ProjectiveVar, CurveVar},
+        R1CSVar,
     };
+    use ark_relations::r1cs::ConstraintSystem;
     use rand::{thread_rng, Rng};
 
-    use crate::hash::hash_to_curve::cofactor::bls12_381::{
-        double_p_power_endomorphism_var, p_power_endomorphism_var,
+    use crate::hash::hash_to_curve::cofactor::{
+        bls12_381::{double_p_power_endomorphism_var, p_power_endomorphism_var},
+        CofactorGadget,
     };
 
     // PSI_X = 1/(u+1)^((p-1)/3)
@@ -319,4 +323,34 @@ mod test {
 
         assert_eq!(psi_p, psi_p_var);
     }
+
+    #[test]
+    fn test_clear_cofactor() {
+        for _ in 0..4 {
+            let p = sample_unchecked();
+
+            let cs = ConstraintSystem::<Fq>::new_ref();
+            let p_var: ProjectiveVar<
+                Config,
+                QuadExtVar<FpVar<Fq>, Fp2ConfigWrapper<Fq2Config>, _>,
+                _,
+            > = ProjectiveVar::new_variable_omit_prime_order_check(
+                cs.clone(),
+                || Ok(p.into_group()),
+                AllocationMode::Witness,
+            )
+            .unwrap();
+
+            let cleared = <G2Projective as CofactorGadget<_, _>>::clear_cofactor_var(&p_var)
+                .unwrap()
+                .value()
+                .unwrap()
+                .into_affine();
+
+            assert_eq!(cleared, p.clear_cofactor());
+            assert!(cleared.is_on_curve());
+            assert!(cleared.is_in_correct_subgroup_assuming_on_curve());
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
 }
@@ -35,3 +35,153 @@ where
         point.scalar_mul_le_unchecked(cofactor_bits.iter())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use ark_ec::{
+        short_weierstrass::{Affine, SWCurveConfig},
+        AffineRepr, CurveGroup,
+    };
+    use ark_ff::{BigInteger, BigInteger64, Fp2ConfigWrapper, PrimeField, UniformRand};
+    use ark_r1cs_std::{
+        alloc::AllocationMode,
+        fields::{fp::FpVar, quadratic_extension::QuadExtVar, FieldOpsBounds, FieldVar},
+        groups::{curves::short_weierstrass::ProjectiveVar, CurveVar},
+        prelude::Boolean,
+        R1CSVar,
+    };
+    use ark_relations::r1cs::{ConstraintSystem, SynthesisError};
+    use rand::{thread_rng, Rng};
+
+    use super::CofactorGadget;
+
+    // No faster method is implemented for G1 of BLS12-377, so it falls back to the default
+    impl CofactorGadget<FpVar<ark_bls12_377::Fq>, ark_bls12_377::Fq> for ark_bls12_377::G1Projective {}
+
+    fn sample_unchecked<P: SWCurveConfig>() -> Affine<P> {
+        let mut rng = thread_rng();
+
+        loop {
+            let x = P::BaseField::rand(&mut rng);
+            let greatest = rng.gen();
+
+            if let Some(p) = Affine::get_point_from_x_unchecked(x, greatest) {
+                return p;
+            }
+        }
+    }
+
+    /// Multiply `point` by the cofactor of its curve, as the default `clear_cofactor_var` does.
+    fn mul_by_cofactor_var<P, FP, CF>(
+        point: &ProjectiveVar<P, FP, CF>,
+    ) -> Result<ProjectiveVar<P, FP, CF>, SynthesisError>
+    where
+        P: SWCurveConfig,
+        FP: FieldVar<P::BaseField, CF>,
+        CF: PrimeField,
+        for<'a> &'a FP: FieldOpsBounds<'a, P::BaseField, FP>,
+    {
+        let cofactor_bits: Vec<_> = P::COFACTOR
+            .iter()
+            .flat_map(|value| {
+                BigInteger64::from(*value)
+                    .to_bits_le()
+                    .into_iter()
+                    .map(Boolean::constant)
+            })
+            .collect();
+
+        point.scalar_mul_le_unchecked(cofactor_bits.iter())
+    }
+
+    /// Clear the cofactor of `p` in a circuit with `clear`, and return the result along with the
+    /// number of constraints spent on clearing.
+    fn clear_in_circuit<P, FP, CF>(
+        p: Affine<P>,
+        clear: impl FnOnce(
+            &ProjectiveVar<P, FP, CF>,
+        ) -> Result<ProjectiveVar<P, FP, CF>, SynthesisError>,
+    ) -> (Affine<P>, usize)
+    where
+        P: SWCurveConfig,
+        FP: FieldVar<P::BaseField, CF>,
+        CF: PrimeField,
+        for<'a> &'a FP: FieldOpsBounds<'a, P::BaseField, FP>,
+    {
+        let cs = ConstraintSystem::<CF>::new_ref();
+        let p_var = ProjectiveVar::<P, FP, CF>::new_variable_omit_prime_order_check(
+            cs.clone(),
+            || Ok(p.into_group()),
+            AllocationMode::Witness,
+        )
+        .unwrap();
+
+        let before = cs.num_constraints();
+        let cleared = clear(&p_var).unwrap().value().unwrap().into_affine();
+        assert!(cs.is_satisfied().unwrap());
+
+        (cleared, cs.num_constraints() - before)
+    }
+
+    #[test]
+    fn test_bls12_381_g2_uses_fast_path() {
+        use ark_bls12_381::{g2::Config, Fq, Fq2Config, G2Projective};
+        type Fq2Var = QuadExtVar<FpVar<Fq>, Fp2ConfigWrapper<Fq2Config>, Fq>;
+
+        for _ in 0..2 {
+            let p = sample_unchecked::<Config>();
+
+            let (fast, fast_constraints) = clear_in_circuit::<_, Fq2Var, _>(
+                p,
+                <G2Projective as CofactorGadget<_, _>>::clear_cofactor_var,
+            );
+            let (default, default_constraints) =
+                clear_in_circuit::<_, Fq2Var, _>(p, mul_by_cofactor_var);
+
+            assert_eq!(fast, p.clear_cofactor());
+            assert_eq!(default, p.mul_by_cofactor_to_group().into_affine());
+            assert!(fast_constraints < default_constraints);
+        }
+    }
+
+    #[test]
+    fn test_bls12_377_g2_uses_fast_path() {
+        use ark_bls12_377::{g2::Config, Fq, Fq2Config, G2Projective};
+        type Fq2Var = QuadExtVar<FpVar<Fq>, Fp2ConfigWrapper<Fq2Config>, Fq>;
+
+        for _ in 0..2 {
+            let p = sample_unchecked::<Config>();
+
+            let (fast, fast_constraints) = clear_in_circuit::<_, Fq2Var, _>(
+                p,
+                <G2Projective as CofactorGadget<_, _>>::clear_cofactor_var,
+            );
+            let (default, default_constraints) =
+                clear_in_circuit::<_, Fq2Var, _>(p, mul_by_cofactor_var);
+
+            assert_eq!(fast, p.clear_cofactor());
+            assert_eq!(default, p.mul_by_cofactor_to_group().into_affine());
+            assert!(fast_constraints < default_constraints);
+        }
+    }
+
+    #[test]
+    fn test_other_curves_use_default() {
+        use ark_bls12_377::{g1::Config, Fq, G1Projective};
+
+        for _ in 0..2 {
+            let p = sample_unchecked::<Config>();
+
+            let (cleared, constraints) = clear_in_circuit::<_, FpVar<Fq>, _>(
+                p,
+                <G1Projective as CofactorGadget<_, _>>::clear_cofactor_var,
+            );
+            let (default, default_constraints) =
+                clear_in_circuit::<_, FpVar<Fq>, _>(p, mul_by_cofactor_var);
+
+            assert_eq!(cleared, default);
+            assert_eq!(cleared, p.mul_by_cofactor_to_group().into_affine());
+            assert_eq!(constraints, default_constraints);
+        }
+    }
+}
@@ -1,18 +1,36 @@
 pub mod cofactor;
+#[cfg(test)]
+mod test_vectors;
 
 use std::marker::PhantomData;
 
-use ark_ec::{short_weierstrass::SWCurveConfig, CurveGroup};
+use ark_ec::{
+    bls12::Bls12Config, hashing::curve_maps::wb::WBConfig, short_weierstrass::SWCurveConfig,
+    CurveGroup,
+};
 use ark_ff::PrimeField;
 use ark_r1cs_std::{
     fields::{FieldOpsBounds, FieldVar},
-    groups::curves::short_weierstrass::ProjectiveVar,
+    groups::{bls12::G2Var, curves::short_weierstrass::ProjectiveVar},
     uint8::UInt8,
     R1CSVar,
 };
 use ark_relations::r1cs::SynthesisError;
 
-use super::{hash_to_field::HashToFieldGadget, map_to_curve::MapToCurveGadget};
+use super::{
+    hash_to_field::{
+        default_hasher::DefaultFieldHasherGadget, from_base_field::FromBaseFieldVarGadget,
+        HashToFieldGadget,
+    },
+    map_to_curve::{
+        sqrt::SqrtGadget, to_base_field::ToBaseFieldVarGadget, wb::WBMapGadget, MapToCurveGadget,
+    },
+    prf::blake2s::constraints::StatefulBlake2sGadget,
+};
+use crate::{
+    bls::params::{HashCurveConfig, HashCurveGroup, HashCurveVar},
+    params::BlsSigField,
+};
 use cofactor::CofactorGadget;
 
 /// Helper struct that can be used to construct elements on the elliptic curve
@@ -48,19 +66,35 @@ where
         }
     }
 
+    /// Hash `msg` to `N` field elements with the field hasher, i.e. the first
+    /// step of [`Self::hash`], for callers that map or encode the field
+    /// elements themselves.
+    pub fn hash_to_field<const N: usize>(
+        &self,
+        msg: &[UInt8<CF>],
+    ) -> Result<[FP; N], SynthesisError> {
+        self.field_hasher.hash_to_field::<N>(msg)
+    }
+
     /// Produce a hash of the message, using the hash to field and map to curve
     /// traits. This uses the IETF hash to curve's specification for Random
     /// oracle encoding (hash_to_curve) defined by combining these components.
     /// See <https://tools.ietf.org/html/draft-irtf-cfrg-hash-to-curve-09#section-3>
-    #[tracing::instrument(skip_all)]
     pub fn hash(
         &self,
         msg: &[UInt8<CF>],
-    ) -> Result<ProjectiveVar<T::Config, FP, CF>, SynthesisError>
-    where
-        <T as CurveGroup>::Config: SWCurveConfig,
-        for<'a> &'a FP: FieldOpsBounds<'a, <T as CurveGroup>::BaseField, FP>,
-    {
+    ) -> Result<ProjectiveVar<T::Config, FP, CF>, SynthesisError> {
+        self.hash_with_trace(msg).map(|trace| trace.p)
+    }
+
+    /// Same as [`Self::hash`], but also returns the intermediate values of the
+    /// computation (`u`, `Q0`, `Q1`) so that they can be checked against the
+    /// test vectors of RFC 9380.
+    #[tracing::instrument(skip_all)]
+    pub fn hash_with_trace(
+        &self,
+        msg: &[UInt8<CF>],
+    ) -> Result<HashToCurveTrace<T, CF, FP>, SynthesisError> {
         // IETF spec of hash_to_curve, from hash_to_field and map_to_curve
         // sub-components
         // 1. u = hash_to_field(msg, 2)
@@ -73,7 +107,7 @@ where
         let cs = msg.cs();
         tracing::info!(num_constraints = cs.num_constraints());
 
-        let rand_field_elems = self.field_hasher.hash_to_field::<2>(msg)?;
+        let rand_field_elems = self.hash_to_field::<2>(msg)?;
 
         let rand_curve_elem_0 = M2C::map_to_curve(rand_field_elems[0].clone())?;
         let rand_curve_elem_1 = M2C::map_to_curve(rand_field_elems[1].clone())?;
@@ -103,21 +137,103 @@ where
         // `ark-bls12-381-0.5.0/src/curves/g2.rs`.
         //
         // rand_subgroup_elem.clear_cofactor()
-        let curve_elem = T::clear_cofactor_var(&rand_curve_elem);
+        let curve_elem = T::clear_cofactor_var(&rand_curve_elem)?;
 
         tracing::info!(num_constraints = cs.num_constraints());
 
-        curve_elem
+        Ok(HashToCurveTrace {
+            u: rand_field_elems,
+            q0: rand_curve_elem_0,
+            q1: rand_curve_elem_1,
+            p: curve_elem,
+        })
     }
 }
 
+/// Intermediate values of [`MapToCurveBasedHasherGadget::hash_with_trace`].
+/// Names follow the test vectors in Appendix J of RFC 9380.
+pub struct HashToCurveTrace<T, CF, FP>
+where
+    T: CurveGroup,
+    <T as CurveGroup>::Config: SWCurveConfig,
+    CF: PrimeField,
+    FP: FieldVar<T::BaseField, CF>,
+    for<'a> &'a FP: FieldOpsBounds<'a, <T as CurveGroup>::BaseField, FP>,
+{
+    /// `u = hash_to_field(msg, 2)`
+    pub u: [FP; 2],
+    /// `Q0 = map_to_curve(u[0])`
+    pub q0: ProjectiveVar<T::Config, FP, CF>,
+    /// `Q1 = map_to_curve(u[1])`
+    pub q1: ProjectiveVar<T::Config, FP, CF>,
+    /// `P = clear_cofactor(Q0 + Q1)`
+    pub p: ProjectiveVar<T::Config, FP, CF>,
+}
+
+/// Hash `msg` to G2 of the BLS12 curve `SigCurveConfig`, using `dst` as the
+/// domain separation tag.
+///
+/// This follows the IETF hash to curve specification, with Blake2s as the hash
+/// of `expand_message_xmd`, and is the hash used by `bls::Signature` (with an
+/// empty `dst`). The result matches the native `MapToCurveBasedHasher` with
+/// `DefaultFieldHasher<Blake2s256, 128>` and `WBMap`.
+pub fn hash_to_g2<SigCurveConfig, FV, CF>(
+    dst: &[UInt8<CF>],
+    msg: &[UInt8<CF>],
+) -> Result<G2Var<SigCurveConfig, FV, CF>, SynthesisError>
+where
+    SigCurveConfig: Bls12Config,
+    FV: FieldVar<BlsSigField<SigCurveConfig>, CF>
+        + FromBaseFieldVarGadget<CF>
+        + ToBaseFieldVarGadget<BlsSigField<SigCurveConfig>, CF>
+        + SqrtGadget<BlsSigField<SigCurveConfig>, CF>,
+    CF: PrimeField,
+    for<'a> &'a FV: FieldOpsBounds<'a, BlsSigField<SigCurveConfig>, FV>,
+    <SigCurveConfig as Bls12Config>::G2Config: WBConfig,
+
+    HashCurveConfig<SigCurveConfig>: SWCurveConfig,
+    for<'a> &'a HashCurveVar<SigCurveConfig, FV, CF>: FieldOpsBounds<
+        'a,
+        <HashCurveGroup<SigCurveConfig> as CurveGroup>::BaseField,
+        HashCurveVar<SigCurveConfig, FV, CF>,
+    >,
+    HashCurveVar<SigCurveConfig, FV, CF>:
+        FieldVar<<HashCurveGroup<SigCurveConfig> as CurveGroup>::BaseField, CF>,
+    HashCurveGroup<SigCurveConfig>: CofactorGadget<HashCurveVar<SigCurveConfig, FV, CF>, CF>,
+{
+    type FieldHasherGadget<SigCurveConfig, FV, CF> = DefaultFieldHasherGadget<
+        StatefulBlake2sGadget<CF>,
+        <HashCurveGroup<SigCurveConfig> as CurveGroup>::BaseField,
+        CF,
+        HashCurveVar<SigCurveConfig, FV, CF>,
+        128,
+    >;
+
+    // this is slightly different from its counterpart in `bls.rs` because of how WBMapGadget is defined
+    type CurveMapGadget<SigCurveConfig> = WBMapGadget<<SigCurveConfig as Bls12Config>::G2Config>;
+
+    type HasherGadget<SigCurveConfig, FV, CF> = MapToCurveBasedHasherGadget<
+        HashCurveGroup<SigCurveConfig>,
+        FieldHasherGadget<SigCurveConfig, FV, CF>,
+        CurveMapGadget<SigCurveConfig>,
+        CF,
+        HashCurveVar<SigCurveConfig, FV, CF>,
+    >;
+
+    let hash = HasherGadget::<SigCurveConfig, FV, CF>::new(dst).hash(msg)?;
+    Ok(G2Var::<SigCurveConfig, FV, CF>::new(hash.x, hash.y, hash.z))
+}
+
 #[cfg(test)]
 mod test {
     use ark_ec::{
         hashing::{curve_maps::wb::WBMap, map_to_curve_hasher::MapToCurveBasedHasher, HashToCurve},
         CurveConfig, CurveGroup,
     };
-    use ark_ff::{field_hashers::DefaultFieldHasher, Field};
+    use ark_ff::{
+        field_hashers::{DefaultFieldHasher, HashToField},
+        Field,
+    };
     use ark_r1cs_std::{alloc::AllocVar, fields::fp2::Fp2Var, uint8::UInt8, R1CSVar};
     use ark_relations::r1cs::ConstraintSystem;
     use blake2::Blake2s256;
@@ -265,8 +381,30 @@ mod test {
                     }
                 }
 
+                fn test_hash_to_field() {
+                    let mut rng = thread_rng();
+                    let domain = b"domain";
+                    let field_hasher = <FieldHasher as HashToField<BaseField>>::new(domain);
+                    let hasher_gadget = HasherGadget::new(&domain.map(UInt8::constant));
+
+                    for len in [0, 1, 64] {
+                        let cs = ConstraintSystem::new_ref();
+                        let mut msg = vec![0; len];
+                        rng.fill_bytes(&mut msg);
+                        let msg_var = UInt8::new_witness_vec(cs.clone(), &msg).unwrap();
+
+                        let expected: [BaseField; 2] = field_hasher.hash_to_field::<2>(&msg);
+                        let elems = hasher_gadget.hash_to_field::<2>(&msg_var).unwrap();
+                        for (elem, expected) in elems.iter().zip(expected) {
+                            assert_eq!(elem.value().unwrap(), expected);
+                        }
+                        assert!(cs.is_satisfied().unwrap());
+                    }
+                }
+
                 test_constant();
                 test_input();
+                test_hash_to_field();
             }
         };
     }
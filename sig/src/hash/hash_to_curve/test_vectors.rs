@@ -0,0 +1,206 @@
+//! Harness for checking [`MapToCurveBasedHasherGadget`] against the test
+//! vectors of RFC 9380, in the JSON format published alongside it
+//! (see <https://github.com/cfrg/draft-irtf-cfrg-hash-to-curve/tree/main/poc/vectors>).
+//!
+//! The BLS12-381 G2 suite (`BLS12381G2_XMD:SHA-256_SSWU_RO_`, Appendix J.10.1)
+//! is embedded as is, and checked both natively and in-circuit, with SHA-256
+//! as the hash of `expand_message_xmd`. Every intermediate value (`u`, `Q0`,
+//! `Q1`, `P`) is compared, so a deviation in any step of the pipeline is caught.
+
+use ark_bls12_381::{g2::Config, Fq, Fq2, Fq2Config, G2Affine, G2Projective};
+use ark_ec::{
+    hashing::{
+        curve_maps::wb::WBMap,
+        map_to_curve_hasher::{MapToCurve, MapToCurveBasedHasher},
+        HashToCurve,
+    },
+    AffineRepr, CurveGroup,
+};
+use ark_ff::{
+    field_hashers::{DefaultFieldHasher, HashToField},
+    BigInteger, PrimeField,
+};
+use ark_r1cs_std::{
+    fields::{fp::FpVar, fp2::Fp2Var},
+    uint8::UInt8,
+    R1CSVar,
+};
+use ark_relations::r1cs::ConstraintSystem;
+use blake2::Blake2s256;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::hash::{
+    hash_to_curve::MapToCurveBasedHasherGadget,
+    hash_to_field::default_hasher::DefaultFieldHasherGadget, map_to_curve::wb::WBMapGadget,
+    prf::sha256::constraints::StatefulSha256Gadget,
+};
+
+/// `poc/vectors/BLS12381G2_XMD:SHA-256_SSWU_RO_.json`
+const BLS12381G2_XMD_SHA256_SSWU_RO: &str =
+    include_str!("vectors/BLS12381G2_XMD_SHA-256_SSWU_RO_.json");
+
+/// A hash-to-curve suite, as found in `poc/vectors/*.json`. Only the fields
+/// used by the harness are parsed.
+#[derive(Serialize, Deserialize)]
+struct Suite {
+    dst: String,
+    vectors: Vec<Vector>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[allow(non_snake_case)]
+struct Vector {
+    msg: String,
+    u: [String; 2],
+    Q0: Point,
+    Q1: Point,
+    P: Point,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Point {
+    x: String,
+    y: String,
+}
+
+fn decode_fq(s: &str) -> Fq {
+    let s = s.trim().trim_start_matches("0x");
+    assert!(s.len() % 2 == 0, "hex string should have an even length");
+    let bytes: Vec<u8> = (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).expect("invalid hex string"))
+        .collect();
+    let v = Fq::from_be_bytes_mod_order(&bytes);
+    assert_eq!(
+        v.into_bigint().to_bytes_be(),
+        bytes,
+        "element is not reduced"
+    );
+    v
+}
+
+/// `Fp2` elements are encoded as `"c0,c1"`.
+fn decode_fq2(s: &str) -> Fq2 {
+    let (c0, c1) = s.split_once(',').expect("fp2 element should be `c0,c1`");
+    Fq2::new(decode_fq(c0), decode_fq(c1))
+}
+
+fn decode_point(p: &Point) -> G2Affine {
+    let point = G2Affine::new_unchecked(decode_fq2(&p.x), decode_fq2(&p.y));
+    assert!(point.is_on_curve(), "point is not on the curve");
+    point
+}
+
+fn sha256_suite() -> Suite {
+    serde_json::from_str(BLS12381G2_XMD_SHA256_SSWU_RO).expect("the suite should be valid JSON")
+}
+
+/// Check the native arkworks pipeline against every vector of the suite.
+fn check_suite_native(suite: &Suite) {
+    let hasher = <DefaultFieldHasher<Sha256, 128> as HashToField<Fq2>>::new(suite.dst.as_bytes());
+
+    for vector in &suite.vectors {
+        let u: [Fq2; 2] = hasher.hash_to_field::<2>(vector.msg.as_bytes());
+        let q0 = WBMap::<Config>::map_to_curve(u[0]).unwrap();
+        let q1 = WBMap::<Config>::map_to_curve(u[1]).unwrap();
+        let p = (q0 + q1).into_affine().clear_cofactor();
+
+        assert_eq!(u[0], decode_fq2(&vector.u[0]));
+        assert_eq!(u[1], decode_fq2(&vector.u[1]));
+        assert_eq!(q0, decode_point(&vector.Q0));
+        assert_eq!(q1, decode_point(&vector.Q1));
+        assert_eq!(p, decode_point(&vector.P));
+    }
+}
+
+/// Check the gadget against every vector of the suite, allocating each message
+/// as a public input.
+fn check_suite_sha256(suite: &Suite) {
+    type FieldHasherGadget =
+        DefaultFieldHasherGadget<StatefulSha256Gadget<Fq>, Fq2, Fq, Fp2Var<Fq2Config>, 128>;
+    type HasherGadget = MapToCurveBasedHasherGadget<
+        G2Projective,
+        FieldHasherGadget,
+        WBMapGadget<Config>,
+        Fq,
+        Fp2Var<Fq2Config>,
+    >;
+
+    for vector in &suite.vectors {
+        let cs = ConstraintSystem::new_ref();
+        let dst: Vec<_> = suite.dst.bytes().map(UInt8::constant).collect();
+        let msg = UInt8::new_input_vec(cs.clone(), vector.msg.as_bytes()).unwrap();
+
+        let trace = HasherGadget::new(&dst).hash_with_trace(&msg).unwrap();
+
+        assert_eq!(trace.u[0].value().unwrap(), decode_fq2(&vector.u[0]));
+        assert_eq!(trace.u[1].value().unwrap(), decode_fq2(&vector.u[1]));
+        assert_eq!(
+            trace.q0.value_unchecked().unwrap().into_affine(),
+            decode_point(&vector.Q0)
+        );
+        assert_eq!(
+            trace.q1.value_unchecked().unwrap().into_affine(),
+            decode_point(&vector.Q1)
+        );
+        assert_eq!(trace.p.value().unwrap(), decode_point(&vector.P));
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
+
+#[test]
+fn test_vector_encoding_roundtrip() {
+    let suite = sha256_suite();
+    assert_eq!(
+        suite.dst,
+        "QUUX-V01-CS02-with-BLS12381G2_XMD:SHA-256_SSWU_RO_"
+    );
+    assert_eq!(suite.vectors.len(), 5);
+
+    let json = serde_json::to_string(&suite).unwrap();
+    let parsed: Suite = serde_json::from_str(&json).unwrap();
+    for (a, b) in parsed.vectors.iter().zip(&suite.vectors) {
+        assert_eq!(a.msg, b.msg);
+        assert_eq!(decode_point(&a.P), decode_point(&b.P));
+        assert_eq!(decode_fq2(&a.u[0]), decode_fq2(&b.u[0]));
+    }
+}
+
+#[test]
+fn test_hash_to_curve_vectors_native() {
+    check_suite_native(&sha256_suite());
+}
+
+#[test]
+fn test_hash_to_curve_vectors_sha256() {
+    check_suite_sha256(&sha256_suite());
+}
+
+#[test]
+fn test_hash_to_g2() {
+    // `hash_to_g2` hashes with Blake2s, for which there are no official
+    // vectors, so it is checked against the native hasher
+    let dst = "QUUX-V01-CS02-with-BLS12381G2_XMD:BLAKE2S_SSWU_RO_";
+    let hasher = MapToCurveBasedHasher::<
+        G2Projective,
+        DefaultFieldHasher<Blake2s256, 128>,
+        WBMap<Config>,
+    >::new(dst.as_bytes())
+    .unwrap();
+
+    for msg in ["", "abc", "abcdef0123456789"] {
+        let cs = ConstraintSystem::new_ref();
+        let dst: Vec<_> = dst.bytes().map(UInt8::constant).collect();
+        let msg_var = UInt8::new_input_vec(cs.clone(), msg.as_bytes()).unwrap();
+
+        let p = crate::hash::hash_to_g2::<ark_bls12_381::Config, FpVar<Fq>, Fq>(&dst, &msg_var)
+            .unwrap();
+
+        assert_eq!(
+            p.value().unwrap().into_affine(),
+            hasher.hash(msg.as_bytes()).unwrap()
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
@@ -102,6 +102,22 @@ pub trait FromBaseFieldVarGadget<CF: PrimeField>: Sized {
     ) -> Result<Self, SynthesisError>;
 }
 
+/// Rebuild a variable from its coordinates over the base prime field, in the
+/// order returned by [`crate::hash::to_base_prime_field_vars`].
+///
+/// Returns `SynthesisError::Unsatisfiable` if `vars` has too few or too many
+/// elements.
+pub fn from_base_prime_field_vars<CF: PrimeField, V: FromBaseFieldVarGadget<CF>>(
+    vars: impl IntoIterator<Item = V::BasePrimeFieldVar>,
+) -> Result<V, SynthesisError> {
+    let mut iter = vars.into_iter();
+    let v = V::from_base_field_var(iter.by_ref())?;
+    if iter.next().is_some() {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    Ok(v)
+}
+
 impl<CF: PrimeField> FromBaseFieldVarGadget<CF> for FpVar<CF> {
     type BasePrimeFieldVar = Self;
 
@@ -162,3 +178,79 @@ where
         Ok(Self::new(c0, c1, c2))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use ark_ff::{Field, PrimeField};
+    use ark_r1cs_std::{
+        alloc::AllocVar,
+        eq::EqGadget,
+        fields::{emulated_fp::EmulatedFpVar, fp2::Fp2Var, fp3::Fp3Var, FieldVar},
+        R1CSVar,
+    };
+    use ark_relations::r1cs::{ConstraintSystem, ConstraintSystemRef};
+    use rand::thread_rng;
+
+    use super::{from_base_prime_field_vars, FromBaseFieldVarGadget};
+    use crate::hash::map_to_curve::to_base_field::{
+        to_base_prime_field_vars, ToBaseFieldVarGadget,
+    };
+
+    fn check_roundtrip<F, CF, B, V>(cs: ConstraintSystemRef<CF>)
+    where
+        F: Field,
+        CF: PrimeField,
+        B: FieldVar<F::BasePrimeField, CF>,
+        V: AllocVar<F, CF>
+            + EqGadget<CF>
+            + R1CSVar<CF, Value = F>
+            + FromBaseFieldVarGadget<CF, BasePrimeFieldVar = B>
+            + ToBaseFieldVarGadget<F::BasePrimeField, CF, BasePrimeFieldVar = B>,
+    {
+        let x = F::rand(&mut thread_rng());
+        let x_var = V::new_witness(cs.clone(), || Ok(x)).unwrap();
+
+        let coords = to_base_prime_field_vars(&x_var).unwrap();
+        let expected: Vec<_> = x.to_base_prime_field_elements().collect();
+        assert_eq!(coords.len(), expected.len());
+        for (coord, expected) in coords.iter().zip(&expected) {
+            assert_eq!(coord.value().unwrap(), *expected);
+        }
+
+        let y_var: V = from_base_prime_field_vars(coords.iter().cloned()).unwrap();
+        y_var.enforce_equal(&x_var).unwrap();
+        assert_eq!(y_var.value().unwrap(), x);
+        assert!(cs.is_satisfied().unwrap());
+
+        // the number of coordinates must match exactly
+        assert!(from_base_prime_field_vars::<CF, V>(coords[1..].iter().cloned()).is_err());
+        assert!(
+            from_base_prime_field_vars::<CF, V>(coords.iter().chain(&coords[..1]).cloned())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_fp2_roundtrip() {
+        use ark_bls12_381::{Fq, Fq2, Fq2Config};
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        check_roundtrip::<Fq2, Fq, _, Fp2Var<Fq2Config>>(cs);
+    }
+
+    #[test]
+    fn test_fp2_emulated_roundtrip() {
+        use ark_bls12_381::{Fq, Fq2, Fq2Config, Fr};
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        check_roundtrip::<Fq2, Fr, _, Fp2Var<Fq2Config, EmulatedFpVar<Fq, Fr>, Fr>>(cs);
+    }
+
+    #[test]
+    fn test_fp3_roundtrip() {
+        use ark_mnt6_298::{Fq, Fq3, Fq3Config};
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        check_roundtrip::<Fq3, Fq, _, Fp3Var<Fq3Config>>(cs);
+    }
+}
@@ -3,10 +3,11 @@ use ark_r1cs_std::{fields::FieldVar, uint8::UInt8};
 use ark_relations::r1cs::SynthesisError;
 
 mod expander;
-mod poseidon;
 
 pub mod default_hasher;
 pub mod from_base_field;
+#[cfg(feature = "poseidon-hash-to-field")]
+pub mod poseidon;
 
 pub trait HashToFieldGadget<TF: Field, CF: PrimeField, FP: FieldVar<TF, CF>>: Sized {
     /// Initialises a new hash-to-field helper struct.
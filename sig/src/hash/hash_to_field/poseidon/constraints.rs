@@ -0,0 +1,109 @@
+use core::marker::PhantomData;
+
+use ark_crypto_primitives::sponge::{
+    constraints::CryptographicSpongeVar,
+    poseidon::{constraints::PoseidonSpongeVar, PoseidonConfig},
+};
+use ark_ff::{field_hashers::get_len_per_elem, Field, PrimeField};
+use ark_r1cs_std::{fields::FieldVar, uint8::UInt8, R1CSVar};
+use ark_relations::r1cs::SynthesisError;
+use folding_schemes::transcript::poseidon::poseidon_canonical_config;
+
+use crate::hash::hash_to_field::{
+    from_base_field::{FromBaseFieldVarGadget, FromBitsGadget},
+    HashToFieldGadget,
+};
+
+/// R1CS equivalent of `PoseidonFieldHasher`. See its documentation for the
+/// security caveats.
+pub struct PoseidonFieldHasherGadget<
+    TF: Field,
+    CF: PrimeField,
+    FP: FieldVar<TF, CF>,
+    const SEC_PARAM: usize = 128,
+> {
+    config: PoseidonConfig<CF>,
+    domain: Vec<UInt8<CF>>,
+    _params: PhantomData<(TF, FP)>,
+}
+
+impl<
+        TF: Field,
+        CF: PrimeField,
+        FP: FieldVar<TF, CF> + FromBaseFieldVarGadget<CF>,
+        const SEC_PARAM: usize,
+    > HashToFieldGadget<TF, CF, FP> for PoseidonFieldHasherGadget<TF, CF, FP, SEC_PARAM>
+{
+    fn new(domain: &[UInt8<CF>]) -> Self {
+        Self {
+            config: poseidon_canonical_config::<CF>(),
+            domain: domain.to_vec(),
+            _params: PhantomData,
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn hash_to_field<const N: usize>(&self, msg: &[UInt8<CF>]) -> Result<[FP; N], SynthesisError> {
+        let cs = msg.cs().or(self.domain.cs());
+        tracing::info!(num_constraints = cs.num_constraints());
+
+        let mut sponge = PoseidonSpongeVar::new(cs.clone(), &self.config);
+        sponge.absorb(&self.domain)?;
+        sponge.absorb(&msg.to_vec())?;
+
+        let m = usize::try_from(TF::extension_degree())
+            .expect("extension degree should be able to store in usize");
+        let len_per_base_elem = get_len_per_elem::<TF, SEC_PARAM>();
+
+        let bits = sponge.squeeze_bits(N * m * len_per_base_elem * 8)?;
+        let mut base_field_var_iter = bits
+            .chunks(len_per_base_elem * 8)
+            .map(FP::BasePrimeFieldVar::from_le_bits);
+
+        let f = |_| FP::from_base_field_var(&mut base_field_var_iter);
+        let result = array_util::try_from_fn::<Result<FP, SynthesisError>, N, _>(f);
+
+        tracing::info!(num_constraints = cs.num_constraints());
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_377::{Fq, Fq2, Fq2Config};
+    use ark_ff::field_hashers::HashToField;
+    use ark_r1cs_std::{fields::fp2::Fp2Var, uint8::UInt8, R1CSVar};
+    use ark_relations::r1cs::ConstraintSystem;
+    use rand::{thread_rng, RngCore};
+
+    use crate::hash::hash_to_field::{poseidon::PoseidonFieldHasher, HashToFieldGadget};
+
+    use super::PoseidonFieldHasherGadget;
+
+    #[test]
+    fn test_poseidon_hash_to_field() {
+        let mut rng = thread_rng();
+        let domain = b"domain";
+
+        for len in [0, 1, 31, 32, 100] {
+            let mut msg = vec![0u8; len];
+            rng.fill_bytes(&mut msg);
+
+            let hasher = <PoseidonFieldHasher<Fq> as HashToField<Fq2>>::new(domain);
+            let expected: [Fq2; 2] = hasher.hash_to_field::<2>(&msg);
+
+            let cs = ConstraintSystem::new_ref();
+            let domain_var: Vec<_> = domain.iter().copied().map(UInt8::constant).collect();
+            let msg_var = UInt8::new_witness_vec(cs.clone(), &msg).unwrap();
+            let hasher_gadget =
+                PoseidonFieldHasherGadget::<Fq2, Fq, Fp2Var<Fq2Config>>::new(&domain_var);
+            let result = hasher_gadget.hash_to_field::<2>(&msg_var).unwrap();
+
+            for (r, e) in result.iter().zip(expected) {
+                assert_eq!(r.value().unwrap(), e);
+            }
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+}
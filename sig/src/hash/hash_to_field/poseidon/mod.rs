@@ -1,55 +1,74 @@
 /// A hash to field implementation based on Poseidon hash function.
 ///
-/// Because of the following reasons, this mod is not used in any other files in this project.
-/// - Poseidon hash is not mentioned in IRTF's hash to curve specification
+/// This mod is only compiled with the `poseidon-hash-to-field` feature, and should be used with care:
+/// - Poseidon hash is not mentioned in IRTF's hash to curve specification, so signatures produced with
+///   it are not interoperable with other BLS implementations.
 /// - It requires curve-dependent setup parameter selection.
-///   - Specifically, this implementation only works for `MontBackend::<_, 4>` right now because `PoseidonDefacultConfig`
-///     is only implemented for that.
+///   - Specifically, this implementation uses `poseidon_canonical_config`, whose round numbers are
+///     only analysed for ~255-bit fields. Using it over other fields has no security guarantee.
+/// - The sponge is defined over `F`, so a circuit can only verify the output cheaply if it is
+///   defined over the same field.
+pub mod constraints;
+
 use core::{array, marker::PhantomData};
 
 use ark_crypto_primitives::sponge::{
-    poseidon::{PoseidonConfig, PoseidonDefaultConfigField, PoseidonSponge},
+    poseidon::{PoseidonConfig, PoseidonSponge},
     CryptographicSponge,
 };
-use ark_ff::{field_hashers::HashToField, Field};
+use ark_ff::{
+    field_hashers::{get_len_per_elem, HashToField},
+    Field, PrimeField,
+};
+use folding_schemes::transcript::poseidon::poseidon_canonical_config;
 
 /// Implement `PoseidonFieldHasher` to enable interopability with arkworks.
-pub struct PoseidonFieldHasher<F: PoseidonDefaultConfigField, const SEC_LEVEL: usize = 128> {
+///
+/// The output is derived by squeezing `len_per_elem` bytes worth of bits for each base prime field
+/// element (as `DefaultFieldHasher` does with `expand_message_xmd`) and reducing them modulo the
+/// field characteristic. This keeps it consistent with `PoseidonFieldHasherGadget` whether or not
+/// the target field is emulated in the circuit.
+pub struct PoseidonFieldHasher<F: PrimeField, const SEC_PARAM: usize = 128> {
     config: PoseidonConfig<F>,
     domain: Vec<u8>,
     _params: PhantomData<F>,
 }
 
-impl<TF: Field, F: PoseidonDefaultConfigField, const SEC_LEVEL: usize> HashToField<TF>
-    for PoseidonFieldHasher<F, SEC_LEVEL>
+impl<TF: Field, F: PrimeField, const SEC_PARAM: usize> HashToField<TF>
+    for PoseidonFieldHasher<F, SEC_PARAM>
 {
     fn new(domain: &[u8]) -> Self {
-        // set capacity based on the suggestion at https://www.poseidon-hash.info
-        // ensure that the hash provides at least 128 bit security level
-        //
-        // capacity = ceil(SEC_LEVEL * 2 / MODULUS_BIT_SIZE)
-        let mut config = F::get_default_poseidon_parameters(2, false).unwrap();
-        config.capacity = ((SEC_LEVEL << 1) + F::BasePrimeField::MODULUS_BIT_SIZE as usize - 1)
-            / (F::BasePrimeField::MODULUS_BIT_SIZE) as usize;
-
         Self {
-            config,
+            config: poseidon_canonical_config::<F>(),
             domain: domain.into(),
             _params: PhantomData,
         }
     }
 
     fn hash_to_field<const N: usize>(&self, msg: &[u8]) -> [TF; N] {
-        // let msg: Vec<F::BasePrimeField> = msg.to_field_elements().unwrap();
         let mut sponge = PoseidonSponge::new(&self.config);
         sponge.absorb(&self.domain);
         sponge.absorb(&msg);
 
         let ext_degree = usize::try_from(TF::extension_degree())
             .expect("extension degree should be able to store in usize");
-        // if TF::BasePrimeFielf is the same as F, this will be equivalent to squeeze_native_field_elements
-        // (with a runtime type check)
-        let res: Vec<TF::BasePrimeField> = sponge.squeeze_field_elements(N * ext_degree);
+        let len_per_base_elem = get_len_per_elem::<TF, SEC_PARAM>();
+
+        let bits = sponge.squeeze_bits(N * ext_degree * len_per_base_elem * 8);
+        let res: Vec<TF::BasePrimeField> = bits
+            .chunks(len_per_base_elem * 8)
+            .map(|chunk| {
+                let bytes: Vec<u8> = chunk
+                    .chunks(8)
+                    .map(|byte| {
+                        byte.iter()
+                            .enumerate()
+                            .fold(0u8, |acc, (i, bit)| acc | (u8::from(*bit) << i))
+                    })
+                    .collect();
+                TF::BasePrimeField::from_le_bytes_mod_order(&bytes)
+            })
+            .collect();
 
         let cb = |i| {
             TF::from_base_prime_field_elems((0..ext_degree).map(|j| res[i * ext_degree + j]))
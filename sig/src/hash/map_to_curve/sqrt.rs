@@ -226,6 +226,18 @@ mod test {
                         assert_eq!(sqrt_one_var.value().unwrap(), sqrt_one);
                         assert!(sqrt_one_var.is_constant());
                     }
+
+                    {
+                        // test quadratic residue
+                        let r = <$field>::rand(&mut rng);
+                        let sq = r.square();
+                        let sq_var = <$field_var>::constant(sq);
+                        let (legendre_var, sqrt_var) = sq_var.sqrt().unwrap();
+                        assert!(legendre_var.value().unwrap());
+                        assert_eq!(sqrt_var.value().unwrap(), sq.sqrt().unwrap());
+                        assert_eq!(sqrt_var.value().unwrap().square(), sq);
+                        assert!(sqrt_var.is_constant());
+                    }
                 }
 
                 fn test_input() {
@@ -269,6 +281,19 @@ mod test {
                         assert_eq!(sqrt_one_var.value().unwrap(), sqrt_one);
                         assert!(cs.is_satisfied().unwrap());
                     }
+
+                    {
+                        // test quadratic residue
+                        let cs = ConstraintSystem::new_ref();
+                        let r = <$field>::rand(&mut rng);
+                        let sq = r.square();
+                        let sq_var = <$field_var>::new_input(cs.clone(), || Ok(sq)).unwrap();
+                        let (legendre_var, sqrt_var) = sq_var.sqrt().unwrap();
+                        assert!(legendre_var.value().unwrap());
+                        assert_eq!(sqrt_var.value().unwrap(), sq.sqrt().unwrap());
+                        assert_eq!(sqrt_var.value().unwrap().square(), sq);
+                        assert!(cs.is_satisfied().unwrap());
+                    }
                 }
 
                 test_constant();
@@ -17,6 +17,16 @@ pub trait ToBaseFieldVarGadget<F: PrimeField, CF: PrimeField>: Sized {
     fn to_base_field_vars(&self) -> Result<Vec<Self::BasePrimeFieldVar>, SynthesisError>;
 }
 
+/// Decompose `v` into its coordinates over the base prime field `F`, in the
+/// same order as `Field::to_base_prime_field_elements`.
+///
+/// See [`crate::hash::from_base_prime_field_vars`] for the inverse.
+pub fn to_base_prime_field_vars<F: PrimeField, CF: PrimeField, V: ToBaseFieldVarGadget<F, CF>>(
+    v: &V,
+) -> Result<Vec<V::BasePrimeFieldVar>, SynthesisError> {
+    v.to_base_field_vars()
+}
+
 impl<CF: PrimeField> ToBaseFieldVarGadget<CF, CF> for FpVar<CF> {
     type BasePrimeFieldVar = Self;
 
@@ -2,3 +2,7 @@ pub mod hash_to_curve;
 pub mod hash_to_field;
 pub mod map_to_curve;
 pub mod prf;
+
+pub use hash_to_curve::hash_to_g2;
+pub use hash_to_field::from_base_field::from_base_prime_field_vars;
+pub use map_to_curve::to_base_field::to_base_prime_field_vars;
@@ -0,0 +1,346 @@
+use crate::hash::prf::{
+    blake2s::constraints::{OutputVar, SIGMA},
+    constraints::PRFGadget,
+};
+use ark_ff::PrimeField;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::SynthesisError;
+
+// See `blake2s::constraints` for the full excerpt of RFC 7693. The only
+// differences for BLAKE2b are the word size (w = 64), the number of rounds
+// (r = 12), the block size (bb = 128) and the rotation constants below.
+//
+// --------------+------------------+------------------+
+// G Rotation    | (R1, R2, R3, R4) | (R1, R2, R3, R4) |
+// constants =   | (32, 24, 16, 63) | (16, 12,  8,  7) |
+// --------------+------------------+------------------+
+//
+
+const R1: usize = 32;
+const R2: usize = 24;
+const R3: usize = 16;
+const R4: usize = 63;
+
+// IV[i] = floor(2**w * frac(sqrt(prime(i+1)))), where prime(i) is the i:th
+// prime number ( 2, 3, 5, 7, 11, 13, 17, 19 ) and sqrt(x) is the square root
+// of x.
+const IV: [u64; 8] = [
+    0x6A09E667F3BCC908,
+    0xBB67AE8584CAA73B,
+    0x3C6EF372FE94F82B,
+    0xA54FF53A5F1D36F1,
+    0x510E527FADE682D1,
+    0x9B05688C2B3E6C1F,
+    0x1F83D9ABFB41BD6B,
+    0x5BE0CD19137E2179,
+];
+
+// block size in bits
+const BLOCK_BITS: usize = 1024;
+
+fn mixing_g<ConstraintF: PrimeField>(
+    v: &mut [UInt64<ConstraintF>],
+    a: usize,
+    b: usize,
+    c: usize,
+    d: usize,
+    x: &UInt64<ConstraintF>,
+    y: &UInt64<ConstraintF>,
+) -> Result<(), SynthesisError> {
+    v[a] = UInt64::wrapping_add_many(&[v[a].clone(), v[b].clone(), x.clone()])?;
+    v[d] = (&v[d] ^ &v[a]).rotate_right(R1);
+    v[c] = v[c].wrapping_add(&v[d]);
+    v[b] = (&v[b] ^ &v[c]).rotate_right(R2);
+    v[a] = UInt64::wrapping_add_many(&[v[a].clone(), v[b].clone(), y.clone()])?;
+    v[d] = (&v[d] ^ &v[a]).rotate_right(R3);
+    v[c] = v[c].wrapping_add(&v[d]);
+    v[b] = (&v[b] ^ &v[c]).rotate_right(R4);
+
+    Ok(())
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn blake2b_compression<ConstraintF: PrimeField>(
+    h: &mut [UInt64<ConstraintF>],
+    m: &[UInt64<ConstraintF>],
+    t: u128,
+    f: bool,
+) -> Result<(), SynthesisError> {
+    assert_eq!(h.len(), 8);
+    assert_eq!(m.len(), 16);
+
+    let mut v = Vec::with_capacity(16);
+    v.extend_from_slice(h);
+    v.extend(IV.iter().map(|iv| UInt64::constant(*iv)));
+
+    assert_eq!(v.len(), 16);
+
+    v[12] ^= t as u64;
+    v[13] ^= (t >> 64) as u64;
+
+    if f {
+        v[14] ^= u64::MAX;
+    }
+
+    for i in 0..12 {
+        let s = SIGMA[i % 10];
+
+        mixing_g(&mut v, 0, 4, 8, 12, &m[s[0]], &m[s[1]])?;
+        mixing_g(&mut v, 1, 5, 9, 13, &m[s[2]], &m[s[3]])?;
+        mixing_g(&mut v, 2, 6, 10, 14, &m[s[4]], &m[s[5]])?;
+        mixing_g(&mut v, 3, 7, 11, 15, &m[s[6]], &m[s[7]])?;
+        mixing_g(&mut v, 0, 5, 10, 15, &m[s[8]], &m[s[9]])?;
+        mixing_g(&mut v, 1, 6, 11, 12, &m[s[10]], &m[s[11]])?;
+        mixing_g(&mut v, 2, 7, 8, 13, &m[s[12]], &m[s[13]])?;
+        mixing_g(&mut v, 3, 4, 9, 14, &m[s[14]], &m[s[15]])?;
+    }
+
+    for i in 0..8 {
+        h[i] ^= &v[i];
+        h[i] ^= &v[i + 8];
+    }
+
+    Ok(())
+}
+
+pub struct Blake2bState<ConstraintF: PrimeField> {
+    h: [UInt64<ConstraintF>; 8],
+    buffer: Vec<Boolean<ConstraintF>>,
+    t: u128,
+}
+
+impl<ConstraintF: PrimeField> Blake2bState<ConstraintF> {
+    pub fn new() -> Result<Self, SynthesisError> {
+        let mut h = IV.map(UInt64::constant);
+        h[0] = UInt64::constant(IV[0] ^ (0x01010000 ^ 64));
+
+        Ok(Self {
+            h,
+            buffer: Vec::new(),
+            t: 0,
+        })
+    }
+
+    pub fn update(&mut self, input: &[Boolean<ConstraintF>]) -> Result<(), SynthesisError> {
+        self.buffer.extend_from_slice(input);
+
+        // if there are only multiple of 1024 bits, reserve it for next round
+        // because we might want to compress it as the last block
+        let mut buffer_end = (self.buffer.len() / BLOCK_BITS) * BLOCK_BITS;
+        if self.buffer.len() % BLOCK_BITS == 0 {
+            buffer_end = buffer_end.saturating_sub(BLOCK_BITS);
+        }
+
+        for block in self.buffer[..buffer_end].chunks(BLOCK_BITS) {
+            let this_block: Vec<_> = block.chunks(64).map(UInt64::from_bits_le).collect();
+
+            self.t += 128;
+            blake2b_compression(&mut self.h, &this_block, self.t, false)?;
+        }
+
+        self.buffer.drain(..buffer_end);
+
+        Ok(())
+    }
+
+    pub fn finalize(mut self) -> Result<[UInt64<ConstraintF>; 8], SynthesisError> {
+        // hash the remaining bits in the buffer
+        if !self.buffer.is_empty() {
+            let mut final_block = Vec::with_capacity(16);
+
+            for word in self.buffer.chunks(64) {
+                let mut tmp = word.to_vec();
+                tmp.resize(64, Boolean::constant(false));
+                final_block.push(UInt64::from_bits_le(&tmp));
+            }
+
+            final_block.resize(16, UInt64::constant(0));
+
+            self.t += (self.buffer.len() / 8) as u128;
+            blake2b_compression(&mut self.h, &final_block, self.t, true)?;
+        }
+
+        // if no input is consumed, hash a block of 0
+        if self.t == 0 {
+            let final_block = vec![UInt64::constant(0); 16];
+            blake2b_compression(&mut self.h, &final_block, self.t, true)?;
+        }
+
+        Ok(self.h)
+    }
+}
+
+pub struct StatefulBlake2bGadget<F: PrimeField> {
+    state: Blake2bState<F>,
+}
+
+impl<F: PrimeField> PRFGadget<F> for StatefulBlake2bGadget<F> {
+    type OutputVar = OutputVar<F>;
+    const OUTPUT_SIZE: usize = 64;
+
+    fn update(&mut self, input: &[UInt8<F>]) -> Result<(), SynthesisError> {
+        let input_bits: Vec<_> = input.iter().flat_map(|b| b.to_bits_le().unwrap()).collect();
+        self.state.update(&input_bits)
+    }
+
+    fn finalize(self) -> Result<<Self as PRFGadget<F>>::OutputVar, SynthesisError> {
+        let result: Vec<_> = self
+            .state
+            .finalize()?
+            .iter()
+            .flat_map(|int| int.to_bytes_le().unwrap())
+            .collect();
+        Ok(OutputVar(result))
+    }
+}
+
+impl<F: PrimeField> Default for StatefulBlake2bGadget<F> {
+    fn default() -> Self {
+        Self {
+            state: Blake2bState::new().unwrap(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fq as Fr;
+    use ark_std::rand::Rng;
+    use blake2::digest;
+
+    use crate::hash::prf::blake2b::constraints::Blake2bState;
+    use crate::hash::prf::blake2s::constraints::OutputVar;
+    use ark_ff::PrimeField;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_relations::r1cs::SynthesisError;
+    use blake2::Blake2b512;
+    use digest::{Digest, FixedOutput};
+
+    use super::StatefulBlake2bGadget;
+    use crate::testing::{assert_constraints_within, count_constraints};
+    use ark_r1cs_std::prelude::*;
+
+    fn evaluate_blake2b<ConstraintF: PrimeField>(
+        input: &[Boolean<ConstraintF>],
+    ) -> Result<[UInt64<ConstraintF>; 8], SynthesisError> {
+        assert!(input.len() % 8 == 0);
+        let mut state = Blake2bState::new()?;
+        state.update(input)?;
+        state.finalize()
+    }
+
+    fn witness_block_constraints() -> usize {
+        count_constraints::<Fr, _>(|cs| {
+            let input_bits = (0..1024)
+                .map(|_| Boolean::new_witness(ark_relations::ns!(cs, "input bit"), || Ok(true)))
+                .collect::<Result<Vec<_>, _>>()?;
+            evaluate_blake2b(&input_bits)
+        })
+    }
+
+    #[test]
+    fn test_blake2b_constraints() {
+        assert_constraints_within(witness_block_constraints(), 51136, 0);
+    }
+
+    #[test]
+    fn test_blake2b_precomp_constraints() {
+        // Test that 1024 fixed leading bits (constants)
+        // doesn't result in more constraints.
+
+        let mut rng = ark_std::test_rng();
+        let num_constraints = count_constraints::<Fr, _>(|cs| {
+            let input_bits = (0..1024)
+                .map(|_| Ok(Boolean::constant(rng.gen())))
+                .chain((0..1024).map(|_| {
+                    Boolean::new_witness(ark_relations::ns!(cs, "input bit"), || Ok(true))
+                }))
+                .collect::<Result<Vec<_>, _>>()?;
+            evaluate_blake2b(&input_bits)
+        });
+        assert_constraints_within(num_constraints, witness_block_constraints(), 0);
+    }
+
+    #[test]
+    fn test_blake2b_constant_constraints() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let mut rng = ark_std::test_rng();
+        let input_bits: Vec<_> = (0..1024)
+            .map(|_| Boolean::<Fr>::constant(rng.gen()))
+            .collect();
+        evaluate_blake2b(&input_bits).unwrap();
+        assert_eq!(cs.num_constraints(), 0);
+    }
+
+    #[test]
+    fn test_blake2b_prf() {
+        use crate::hash::prf::constraints::PRFGadget;
+
+        let mut rng = ark_std::test_rng();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+
+        let mut input = [0u8; 64];
+        rng.fill(&mut input);
+
+        let input_var =
+            UInt8::new_witness_vec(ark_relations::ns!(cs, "declare_input"), &input).unwrap();
+        let out: [u8; 64] = {
+            let mut h = Blake2b512::new();
+            h.update(input);
+            h.finalize().into()
+        };
+        let actual_out_var = OutputVar(
+            UInt8::new_witness_vec(ark_relations::ns!(cs, "declare_output"), &out).unwrap(),
+        );
+
+        let mut hasher = StatefulBlake2bGadget::default();
+        hasher.update(&input_var).unwrap();
+        let output_var = hasher.finalize().unwrap();
+        output_var.enforce_equal(&actual_out_var).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    fn check_blake2b(data: &[u8], mode: AllocationMode) {
+        let mut h = Blake2b512::new();
+        h.update(data);
+        let hash_result = h.finalize_fixed();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let input_bits: Vec<_> = data
+            .iter()
+            .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1u8 == 1u8))
+            .map(|bit| Boolean::new_variable(cs.clone(), || Ok(bit), mode).unwrap())
+            .collect();
+
+        let r = evaluate_blake2b(&input_bits).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+
+        let mut s = hash_result
+            .iter()
+            .flat_map(|&byte| (0..8).map(move |i| (byte >> i) & 1u8 == 1u8));
+
+        for chunk in r {
+            for b in chunk.to_bits_le().unwrap() {
+                assert_eq!(s.next().unwrap(), b.value().unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_blake2b() {
+        let mut rng = ark_std::test_rng();
+
+        for input_len in 0..=256 {
+            let data: Vec<u8> = (0..input_len).map(|_| rng.gen()).collect();
+            check_blake2b(&data, AllocationMode::Constant);
+        }
+
+        // witnesses around the block boundaries
+        for input_len in [0, 1, 64, 127, 128, 129, 255, 256] {
+            let data: Vec<u8> = (0..input_len).map(|_| rng.gen()).collect();
+            check_blake2b(&data, AllocationMode::Witness);
+        }
+    }
+}
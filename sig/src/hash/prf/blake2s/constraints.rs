@@ -1,6 +1,6 @@
 use crate::hash::prf::constraints::PRFGadget;
 use ark_ff::PrimeField;
-use ark_r1cs_std::prelude::*;
+use ark_r1cs_std::{convert::ToConstraintFieldGadget, fields::fp::FpVar, prelude::*};
 use ark_relations::r1cs::SynthesisError;
 
 // 2.1.  Parameters
@@ -39,7 +39,7 @@ const R4: usize = 7;
 // ----------+-------------------------------------------------+
 //
 
-const SIGMA: [[usize; 16]; 10] = [
+pub(crate) const SIGMA: [[usize; 16]; 10] = [
     [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
     [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
     [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
@@ -231,33 +231,107 @@ pub struct Blake2sState<ConstraintF: PrimeField> {
     // maybe we can adapt that?
     buffer: Vec<Boolean<ConstraintF>>,
     t: u64,
+    nn: usize,
 }
 
 impl<ConstraintF: PrimeField> Blake2sState<ConstraintF> {
     pub fn new() -> Result<Self, SynthesisError> {
+        Self::new_keyed(&[])
+    }
+
+    /// Create a state whose digest is `nn` bytes long (`1 <= nn <= 32`).
+    pub fn new_with_output_size(nn: usize) -> Result<Self, SynthesisError> {
+        Self::new_keyed_with_output_size(&[], nn)
+    }
+
+    /// Create a state for keyed hashing (MAC) with `key` of at most 32 bytes.
+    ///
+    /// The zero-padded key block is buffered as the first block of the input,
+    /// so the final block is compressed with `t = ll + bb` as required by the
+    /// spec (and with `t = bb` if the message is empty).
+    pub fn new_keyed(key: &[UInt8<ConstraintF>]) -> Result<Self, SynthesisError> {
+        Self::new_keyed_with_output_size(key, 32)
+    }
+
+    /// Keyed hashing with a digest of `nn` bytes.
+    pub fn new_keyed_with_output_size(
+        key: &[UInt8<ConstraintF>],
+        nn: usize,
+    ) -> Result<Self, SynthesisError> {
+        Self::new_with_params(key, nn, &[0; 8], &[0; 8])
+    }
+
+    /// Create an unkeyed state with the given salt and personalization.
+    pub fn with_params(salt: &[u8; 8], personal: &[u8; 8]) -> Result<Self, SynthesisError> {
+        Self::new_with_params(&[], 32, salt, personal)
+    }
+
+    /// Create a state from every field of the parameter block that RFC 7693
+    /// exposes: key, digest length `nn`, salt and personalization.
+    ///
+    /// As in the reference implementation, the salt is XORed into h[4..6] and
+    /// the personalization into h[6..8], both read as little-endian words.
+    pub fn new_with_params(
+        key: &[UInt8<ConstraintF>],
+        nn: usize,
+        salt: &[u8; 8],
+        personal: &[u8; 8],
+    ) -> Result<Self, SynthesisError> {
+        assert!(key.len() <= 32, "Blake2s key should be at most 32 bytes");
+        assert!(
+            (1..=32).contains(&nn),
+            "Blake2s output size should be between 1 and 32 bytes"
+        );
+
+        // safe: key.len() <= 32 and nn <= 32
+        #[allow(clippy::cast_possible_truncation)]
+        let (kk, nn_u32) = (key.len() as u32, nn as u32);
+
+        let word = |bytes: &[u8]| u32::from_le_bytes(bytes.try_into().unwrap());
+
         let h = [
-            UInt32::constant(0x6A09E667 ^ (0x01010000 ^ 32)),
+            UInt32::constant(0x6A09E667 ^ (0x01010000 ^ (kk << 8) ^ nn_u32)),
             UInt32::constant(0xBB67AE85),
             UInt32::constant(0x3C6EF372),
             UInt32::constant(0xA54FF53A),
-            UInt32::constant(0x510E527F),
-            UInt32::constant(0x9B05688C),
-            UInt32::constant(0x1F83D9AB),
-            UInt32::constant(0x5BE0CD19),
+            UInt32::constant(0x510E527F ^ word(&salt[..4])),
+            UInt32::constant(0x9B05688C ^ word(&salt[4..])),
+            UInt32::constant(0x1F83D9AB ^ word(&personal[..4])),
+            UInt32::constant(0x5BE0CD19 ^ word(&personal[4..])),
         ];
 
+        let mut buffer = Vec::new();
+        if !key.is_empty() {
+            buffer.reserve(512);
+            for byte in key {
+                buffer.extend(byte.to_bits_le()?);
+            }
+            buffer.resize(512, Boolean::constant(false));
+        }
+
         Ok(Self {
             h,
-            buffer: Vec::new(),
+            buffer,
             t: 0,
+            nn,
         })
     }
 
+    /// The digest length in bytes. Callers should only use the first `nn`
+    /// bytes of the little-endian words returned by `finalize`.
+    pub fn output_size(&self) -> usize {
+        self.nn
+    }
+
     pub fn update(&mut self, input: &[Boolean<ConstraintF>]) -> Result<(), SynthesisError> {
         self.buffer.extend_from_slice(input);
 
         // if there are only multiple of 512 bytes, reserve it for next round
         // because we might want to compress it as the last block
+        //
+        // Invariant: `t` only counts the compressed blocks, and the buffer is
+        // never empty after an update if any input was seen, so `finalize`
+        // always compresses exactly one final block with `t` = total bytes.
         let mut buffer_end = (self.buffer.len() / 512) * 512;
         if self.buffer.len() % 512 == 0 {
             buffer_end = buffer_end.saturating_sub(512);
@@ -308,7 +382,8 @@ impl<ConstraintF: PrimeField> Blake2sState<ConstraintF> {
     }
 }
 
-pub struct StatefulBlake2sGadget<F: PrimeField> {
+/// Blake2s as a `PRFGadget`, producing `NN` bytes of output (`1 <= NN <= 32`).
+pub struct StatefulBlake2sGadget<F: PrimeField, const NN: usize = 32> {
     state: Blake2sState<F>,
 }
 #[derive(Clone, Debug)]
@@ -352,9 +427,40 @@ impl<ConstraintF: PrimeField> ToBytesGadget<ConstraintF> for OutputVar<Constrain
     }
 }
 
-impl<F: PrimeField> PRFGadget<F> for StatefulBlake2sGadget<F> {
+/// Packs the output into field elements, e.g. to hash it with Poseidon.
+///
+/// Every element takes as many bytes, in little-endian order, as fit below the
+/// modulus, so this matches `ToConstraintField::to_field_elements` of the
+/// native output.
+impl<ConstraintF: PrimeField> ToConstraintFieldGadget<ConstraintF> for OutputVar<ConstraintF> {
+    #[inline]
+    fn to_constraint_field(&self) -> Result<Vec<FpVar<ConstraintF>>, SynthesisError> {
+        self.0.to_constraint_field()
+    }
+}
+
+impl<F: PrimeField, const NN: usize> StatefulBlake2sGadget<F, NN> {
+    /// Keyed Blake2s, i.e. `Blake2sMac` with a key of at most 32 bytes.
+    pub fn new_keyed(key: &[UInt8<F>]) -> Result<Self, SynthesisError> {
+        Ok(Self {
+            state: Blake2sState::new_keyed_with_output_size(key, NN)?,
+        })
+    }
+
+    /// Unkeyed Blake2s with a salt and personalization for domain separation.
+    pub fn new_with_personalization(
+        salt: &[u8; 8],
+        personal: &[u8; 8],
+    ) -> Result<Self, SynthesisError> {
+        Ok(Self {
+            state: Blake2sState::new_with_params(&[], NN, salt, personal)?,
+        })
+    }
+}
+
+impl<F: PrimeField, const NN: usize> PRFGadget<F> for StatefulBlake2sGadget<F, NN> {
     type OutputVar = OutputVar<F>;
-    const OUTPUT_SIZE: usize = 32;
+    const OUTPUT_SIZE: usize = NN;
 
     fn update(&mut self, input: &[UInt8<F>]) -> Result<(), SynthesisError> {
         let input_bits: Vec<_> = input.iter().flat_map(|b| b.to_bits_le().unwrap()).collect();
@@ -367,15 +473,16 @@ impl<F: PrimeField> PRFGadget<F> for StatefulBlake2sGadget<F> {
             .finalize()?
             .iter()
             .flat_map(|int| int.to_bytes_le().unwrap())
+            .take(NN)
             .collect();
         Ok(OutputVar(result))
     }
 }
 
-impl<F: PrimeField> Default for StatefulBlake2sGadget<F> {
+impl<F: PrimeField, const NN: usize> Default for StatefulBlake2sGadget<F, NN> {
     fn default() -> Self {
         Self {
-            state: Blake2sState::new().unwrap(),
+            state: Blake2sState::new_with_output_size(NN).unwrap(),
         }
     }
 }
@@ -395,6 +502,7 @@ mod test {
     use digest::{Digest, FixedOutput};
 
     use super::StatefulBlake2sGadget;
+    use crate::testing::{assert_constraints_within, count_constraints};
     use ark_r1cs_std::prelude::*;
 
     fn evaluate_blake2s<ConstraintF: PrimeField>(
@@ -408,15 +516,13 @@ mod test {
 
     #[test]
     fn test_blake2s_constraints() {
-        let cs = ConstraintSystem::<Fr>::new_ref();
-        let input_bits: Vec<_> = (0..512)
-            .map(|_| {
-                Boolean::new_witness(ark_relations::ns!(cs, "input bit"), || Ok(true)).unwrap()
-            })
-            .collect();
-        evaluate_blake2s(&input_bits).unwrap();
-        assert!(cs.is_satisfied().unwrap());
-        assert_eq!(cs.num_constraints(), 21792);
+        let num_constraints = count_constraints::<Fr, _>(|cs| {
+            let input_bits = (0..512)
+                .map(|_| Boolean::new_witness(ark_relations::ns!(cs, "input bit"), || Ok(true)))
+                .collect::<Result<Vec<_>, _>>()?;
+            evaluate_blake2s(&input_bits)
+        });
+        assert_constraints_within(num_constraints, 21792, 0);
     }
 
     #[test]
@@ -440,7 +546,7 @@ mod test {
             UInt8::new_witness_vec(ark_relations::ns!(cs, "declare_output"), &out).unwrap(),
         );
 
-        let mut hasher = StatefulBlake2sGadget::default();
+        let mut hasher = StatefulBlake2sGadget::<Fr>::default();
         hasher.update(&input_var).unwrap();
         let output_var = hasher.finalize().unwrap();
         output_var.enforce_equal(&actual_out_var).unwrap();
@@ -454,22 +560,53 @@ mod test {
         assert!(cs.is_satisfied().unwrap());
     }
 
+    #[test]
+    fn test_blake2s_output_to_constraint_field() {
+        use crate::hash::prf::constraints::PRFGadget;
+        use ark_ff::ToConstraintField;
+        use ark_r1cs_std::convert::ToConstraintFieldGadget;
+
+        fn check<F: PrimeField>(input: &[u8], num_elems: usize) {
+            let cs = ConstraintSystem::<F>::new_ref();
+            let input_var = UInt8::new_witness_vec(cs.clone(), input).unwrap();
+
+            let mut hasher = StatefulBlake2sGadget::<F>::default();
+            hasher.update(&input_var).unwrap();
+            let elems = hasher.finalize().unwrap().to_constraint_field().unwrap();
+
+            let out = Blake2s256::digest(input);
+            let expected: Vec<F> = out.as_slice().to_field_elements().unwrap();
+            assert_eq!(elems.len(), num_elems);
+            assert_eq!(elems.value().unwrap(), expected);
+            assert!(cs.is_satisfied().unwrap());
+        }
+
+        let mut rng = ark_std::test_rng();
+        let mut input = [0u8; 40];
+        rng.fill(&mut input);
+
+        // 32 bytes fit in one element of a 381-bit field, but need two
+        // elements of a 255-bit field, which holds 31 bytes each
+        check::<Fr>(&input, 1);
+        check::<ark_bls12_381::Fr>(&input, 2);
+    }
+
     #[test]
     fn test_blake2s_precomp_constraints() {
         // Test that 512 fixed leading bits (constants)
         // doesn't result in more constraints.
 
-        let cs = ConstraintSystem::<Fr>::new_ref();
         let mut rng = ark_std::test_rng();
-        let input_bits: Vec<_> = (0..512)
-            .map(|_| Boolean::constant(rng.gen()))
-            .chain((0..512).map(|_| {
-                Boolean::new_witness(ark_relations::ns!(cs, "input bit"), || Ok(true)).unwrap()
-            }))
-            .collect();
-        evaluate_blake2s(&input_bits).unwrap();
-        assert!(cs.is_satisfied().unwrap());
-        assert_eq!(cs.num_constraints(), 21792);
+        let num_constraints = count_constraints::<Fr, _>(|cs| {
+            let input_bits = (0..512)
+                .map(|_| Ok(Boolean::constant(rng.gen())))
+                .chain((0..512).map(|_| {
+                    Boolean::new_witness(ark_relations::ns!(cs, "input bit"), || Ok(true))
+                }))
+                .collect::<Result<Vec<_>, _>>()?;
+            evaluate_blake2s(&input_bits)
+        });
+        assert_constraints_within(num_constraints, 21792, 0);
     }
 
     #[test]
@@ -526,4 +663,147 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_blake2s_keyed() {
+        use crate::hash::prf::constraints::PRFGadget;
+        use blake2::{digest::Mac, Blake2sMac256};
+
+        let mut rng = ark_std::test_rng();
+
+        for key_len in [1, 16, 31, 32] {
+            for input_len in [0, 1, 63, 64, 65, 128] {
+                let key: Vec<u8> = (0..key_len).map(|_| rng.gen()).collect();
+                let input: Vec<u8> = (0..input_len).map(|_| rng.gen()).collect();
+
+                let out = {
+                    let mut mac = <Blake2sMac256 as Mac>::new_from_slice(&key).unwrap();
+                    mac.update(&input);
+                    mac.finalize().into_bytes()
+                };
+
+                let cs = ConstraintSystem::<Fr>::new_ref();
+                let key_var = StatefulBlake2sGadget::<Fr>::new_seed(cs.clone(), &key).unwrap();
+                let input_var = UInt8::new_witness_vec(cs.clone(), &input).unwrap();
+
+                let mut hasher = StatefulBlake2sGadget::<Fr>::new_keyed(&key_var).unwrap();
+                hasher.update(&input_var).unwrap();
+                let output_var = hasher.finalize().unwrap();
+
+                assert_eq!(output_var.0.value().unwrap(), out.to_vec());
+                assert!(cs.is_satisfied().unwrap());
+            }
+        }
+    }
+
+    fn check_output_size<const NN: usize, D: Digest>() {
+        use crate::hash::prf::constraints::PRFGadget;
+
+        let mut rng = ark_std::test_rng();
+
+        for input_len in [0, 1, 32, 64, 65, 128] {
+            let input: Vec<u8> = (0..input_len).map(|_| rng.gen()).collect();
+            let out = D::digest(&input);
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let input_var = UInt8::new_witness_vec(cs.clone(), &input).unwrap();
+
+            let mut hasher = StatefulBlake2sGadget::<Fr, NN>::default();
+            hasher.update(&input_var).unwrap();
+            let output_var = hasher.finalize().unwrap();
+
+            assert_eq!(output_var.0.len(), NN);
+            assert_eq!(output_var.0.value().unwrap(), out.to_vec());
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_blake2s_output_size() {
+        use blake2::{
+            digest::consts::{U16, U20, U28, U32},
+            Blake2s,
+        };
+
+        check_output_size::<16, Blake2s<U16>>();
+        check_output_size::<20, Blake2s<U20>>();
+        check_output_size::<28, Blake2s<U28>>();
+        check_output_size::<32, Blake2s<U32>>();
+    }
+
+    #[test]
+    fn test_blake2s_personalization() {
+        use crate::hash::prf::constraints::PRFGadget;
+        use blake2::{
+            digest::core_api::{Buffer, UpdateCore, VariableOutputCore},
+            Blake2sVarCore,
+        };
+
+        let mut rng = ark_std::test_rng();
+
+        for personal in [b"Zcashivk", b"MIM_sig_"] {
+            for salt in [[0u8; 8], rng.gen()] {
+                for input_len in [0, 1, 64, 100] {
+                    let input: Vec<u8> = (0..input_len).map(|_| rng.gen()).collect();
+
+                    // drive the core directly, as `Blake2s256` has no salt or personalization
+                    let out = {
+                        let mut core = Blake2sVarCore::new_with_params(&salt, personal, 0, 32);
+                        let mut buffer = Buffer::<Blake2sVarCore>::default();
+                        buffer.digest_blocks(&input, |blocks| core.update_blocks(blocks));
+                        let mut out = Default::default();
+                        core.finalize_variable_core(&mut buffer, &mut out);
+                        out
+                    };
+
+                    let cs = ConstraintSystem::<Fr>::new_ref();
+                    let input_var = UInt8::new_witness_vec(cs.clone(), &input).unwrap();
+
+                    let mut hasher =
+                        StatefulBlake2sGadget::<Fr>::new_with_personalization(&salt, personal)
+                            .unwrap();
+                    hasher.update(&input_var).unwrap();
+                    let output_var = hasher.finalize().unwrap();
+
+                    assert_eq!(output_var.0.value().unwrap(), out.to_vec());
+                    assert!(cs.is_satisfied().unwrap());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_blake2s_two_chunk_splits() {
+        use crate::hash::prf::{blake2s::StatefulBlake2s, constraints::PRFGadget};
+
+        let mut rng = ark_std::test_rng();
+        let input: Vec<u8> = (0..128).map(|_| rng.gen()).collect();
+        let expected = Blake2s256::digest(&input).to_vec();
+
+        for split in 0..=input.len() {
+            let (first, second) = input.split_at(split);
+
+            let mut native = StatefulBlake2s::<32>::default();
+            native.update(first);
+            native.update(second);
+            assert_eq!(native.finalize(), expected, "native, split at {split}");
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let mut hasher = StatefulBlake2sGadget::<Fr>::default();
+            hasher
+                .update(&UInt8::new_witness_vec(cs.clone(), first).unwrap())
+                .unwrap();
+            hasher
+                .update(&UInt8::new_witness_vec(cs.clone(), second).unwrap())
+                .unwrap();
+            let output_var = hasher.finalize().unwrap();
+
+            assert_eq!(
+                output_var.0.value().unwrap(),
+                expected,
+                "gadget, split at {split}"
+            );
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
 }
@@ -1 +1,374 @@
 pub mod constraints;
+
+use ark_crypto_primitives::Error;
+
+use self::constraints::SIGMA;
+use super::PRF;
+
+// A native Blake2s that follows the buffering and finalization logic of
+// `constraints::Blake2sState` step by step, so that the two can be tested
+// against each other (and against the `blake2` crate) on the same inputs.
+
+const IV: [u32; 8] = [
+    0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB, 0x5BE0CD19,
+];
+
+const BLOCK_BYTES: usize = 64;
+
+fn mixing_g(v: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize, x: u32, y: u32) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(12);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(8);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(7);
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn blake2s_compression(h: &mut [u32; 8], block: &[u8], t: u64, f: bool) {
+    assert_eq!(block.len(), BLOCK_BYTES);
+
+    let m: Vec<u32> = block
+        .chunks(4)
+        .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+        .collect();
+
+    let mut v = [0u32; 16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&IV);
+
+    v[12] ^= t as u32;
+    v[13] ^= (t >> 32) as u32;
+
+    if f {
+        v[14] ^= u32::MAX;
+    }
+
+    for s in SIGMA {
+        mixing_g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+        mixing_g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+        mixing_g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+        mixing_g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+        mixing_g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+        mixing_g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+        mixing_g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+        mixing_g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+    }
+
+    for i in 0..8 {
+        h[i] ^= v[i] ^ v[i + 8];
+    }
+}
+
+/// Native counterpart of `constraints::Blake2sState`, operating on bytes.
+pub struct Blake2sState {
+    h: [u32; 8],
+    buffer: Vec<u8>,
+    t: u64,
+    nn: usize,
+}
+
+impl Blake2sState {
+    pub fn new() -> Self {
+        Self::new_with_params(&[], 32, &[0; 8], &[0; 8])
+    }
+
+    /// Create a state for keyed hashing (MAC) with `key` of at most 32 bytes.
+    pub fn new_keyed(key: &[u8]) -> Self {
+        Self::new_with_params(key, 32, &[0; 8], &[0; 8])
+    }
+
+    /// See `constraints::Blake2sState::new_with_params`.
+    pub fn new_with_params(key: &[u8], nn: usize, salt: &[u8; 8], personal: &[u8; 8]) -> Self {
+        assert!(key.len() <= 32, "Blake2s key should be at most 32 bytes");
+        assert!(
+            (1..=32).contains(&nn),
+            "Blake2s output size should be between 1 and 32 bytes"
+        );
+
+        // safe: key.len() <= 32 and nn <= 32
+        #[allow(clippy::cast_possible_truncation)]
+        let (kk, nn_u32) = (key.len() as u32, nn as u32);
+
+        let word = |bytes: &[u8]| u32::from_le_bytes(bytes.try_into().unwrap());
+
+        let mut h = IV;
+        h[0] ^= 0x01010000 ^ (kk << 8) ^ nn_u32;
+        h[4] ^= word(&salt[..4]);
+        h[5] ^= word(&salt[4..]);
+        h[6] ^= word(&personal[..4]);
+        h[7] ^= word(&personal[4..]);
+
+        let mut buffer = Vec::new();
+        if !key.is_empty() {
+            buffer.extend_from_slice(key);
+            buffer.resize(BLOCK_BYTES, 0);
+        }
+
+        Self {
+            h,
+            buffer,
+            t: 0,
+            nn,
+        }
+    }
+
+    /// The digest length in bytes.
+    pub fn output_size(&self) -> usize {
+        self.nn
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.buffer.extend_from_slice(input);
+
+        // if there are only multiple of 64 bytes, reserve it for next round
+        // because we might want to compress it as the last block
+        let mut buffer_end = (self.buffer.len() / BLOCK_BYTES) * BLOCK_BYTES;
+        if self.buffer.len() % BLOCK_BYTES == 0 {
+            buffer_end = buffer_end.saturating_sub(BLOCK_BYTES);
+        }
+
+        for block in self.buffer[..buffer_end].chunks(BLOCK_BYTES) {
+            self.t += BLOCK_BYTES as u64;
+            blake2s_compression(&mut self.h, block, self.t, false);
+        }
+
+        self.buffer.drain(..buffer_end);
+    }
+
+    pub fn finalize(mut self) -> [u32; 8] {
+        // hash the remaining bytes in the buffer
+        if !self.buffer.is_empty() {
+            self.t += self.buffer.len() as u64;
+            self.buffer.resize(BLOCK_BYTES, 0);
+            blake2s_compression(&mut self.h, &self.buffer, self.t, true);
+        }
+
+        // if no input is consumed, hash a block of 0
+        if self.t == 0 {
+            blake2s_compression(&mut self.h, &[0; BLOCK_BYTES], self.t, true);
+        }
+
+        self.h
+    }
+}
+
+impl Default for Blake2sState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Native counterpart of `constraints::StatefulBlake2sGadget`, producing `NN`
+/// bytes of output (`1 <= NN <= 32`).
+pub struct StatefulBlake2s<const NN: usize = 32> {
+    state: Blake2sState,
+}
+
+impl<const NN: usize> StatefulBlake2s<NN> {
+    /// Keyed Blake2s, i.e. `Blake2sMac` with a key of at most 32 bytes.
+    pub fn new_keyed(key: &[u8]) -> Self {
+        Self {
+            state: Blake2sState::new_with_params(key, NN, &[0; 8], &[0; 8]),
+        }
+    }
+
+    /// Unkeyed Blake2s with a salt and personalization for domain separation.
+    pub fn new_with_personalization(salt: &[u8; 8], personal: &[u8; 8]) -> Self {
+        Self {
+            state: Blake2sState::new_with_params(&[], NN, salt, personal),
+        }
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.state.update(input);
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        self.state
+            .finalize()
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .take(NN)
+            .collect()
+    }
+}
+
+impl<const NN: usize> Default for StatefulBlake2s<NN> {
+    fn default() -> Self {
+        Self {
+            state: Blake2sState::new_with_params(&[], NN, &[0; 8], &[0; 8]),
+        }
+    }
+}
+
+/// Keyed Blake2s; the empty seed gives plain (unkeyed) Blake2s.
+impl<const NN: usize> PRF for StatefulBlake2s<NN> {
+    type Seed = Vec<u8>;
+    type Input = Vec<u8>;
+    type Output = Vec<u8>;
+
+    fn evaluate(seed: &Self::Seed, input: &Self::Input) -> Result<Self::Output, Error> {
+        if seed.len() > 32 {
+            return Err("Blake2s key should be at most 32 bytes".into());
+        }
+
+        let mut hasher = Self::new_keyed(seed);
+        hasher.update(input);
+        Ok(hasher.finalize())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fq as Fr;
+    use ark_r1cs_std::{uint8::UInt8, R1CSVar};
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::rand::Rng;
+    use blake2::{Blake2s256, Digest};
+
+    use super::StatefulBlake2s;
+    use crate::hash::prf::{
+        blake2s::constraints::StatefulBlake2sGadget, constraints::PRFGadget, UnkeyedPRF, PRF,
+    };
+
+    /// Split `len` bytes into random chunks, favouring sizes around the block
+    /// boundary and empty chunks.
+    fn random_chunks<R: Rng>(rng: &mut R, len: usize) -> Vec<Vec<u8>> {
+        let mut chunks = vec![];
+        let mut remaining = len;
+        while remaining > 0 {
+            let size = match rng.gen_range(0..4) {
+                0 => 0,
+                1 => 64,
+                2 => rng.gen_range(1..=8),
+                _ => rng.gen_range(1..=130),
+            }
+            .min(remaining);
+            chunks.push((0..size).map(|_| rng.gen()).collect());
+            remaining -= size;
+        }
+        if rng.gen_bool(0.5) {
+            chunks.push(vec![]);
+        }
+        chunks
+    }
+
+    #[test]
+    fn test_native_blake2s_agrees_with_gadget() {
+        let mut rng = ark_std::test_rng();
+
+        for len in [0, 1, 63, 64, 65, 128, 129, 200] {
+            for _ in 0..4 {
+                let chunks = random_chunks(&mut rng, len);
+
+                let mut native = StatefulBlake2s::<32>::default();
+                let mut reference = Blake2s256::new();
+                let cs = ConstraintSystem::<Fr>::new_ref();
+                let mut gadget = StatefulBlake2sGadget::<Fr>::default();
+
+                for chunk in &chunks {
+                    native.update(chunk);
+                    reference.update(chunk);
+                    gadget
+                        .update(&UInt8::new_witness_vec(cs.clone(), chunk).unwrap())
+                        .unwrap();
+                }
+
+                let native = native.finalize();
+                let reference = reference.finalize().to_vec();
+                let gadget = gadget.finalize().unwrap().0.value().unwrap();
+
+                assert_eq!(native, reference);
+                assert_eq!(gadget, reference);
+                assert!(cs.is_satisfied().unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_native_blake2s_prf() {
+        let mut rng = ark_std::test_rng();
+
+        for len in [0, 32, 64, 100] {
+            let input: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            let expected = Blake2s256::digest(&input).to_vec();
+            assert_eq!(
+                StatefulBlake2s::<32>::evaluate_unkeyed(&input).unwrap(),
+                expected
+            );
+        }
+    }
+
+    /// `selftest_seq` from RFC 7693 Appendix E.
+    fn selftest_seq(len: usize, seed: u32) -> Vec<u8> {
+        let mut a = 0xDEAD4BADu32.wrapping_mul(seed);
+        let mut b = 1u32;
+        (0..len)
+            .map(|_| {
+                let t = a.wrapping_add(b);
+                a = b;
+                b = t;
+                t.to_be_bytes()[0]
+            })
+            .collect()
+    }
+
+    /// The unkeyed and keyed digests of RFC 7693 Appendix E for one output size.
+    fn selftest_digests<const NN: usize>() -> Vec<u8> {
+        let mut digests = vec![];
+        for in_len in [0, 3, 64, 65, 255, 1024] {
+            let input = selftest_seq(in_len, u32::try_from(in_len).unwrap());
+            digests.extend(StatefulBlake2s::<NN>::evaluate_unkeyed(&input).unwrap());
+
+            let key = selftest_seq(NN, u32::try_from(NN).unwrap());
+            digests.extend(StatefulBlake2s::<NN>::evaluate(&key, &input).unwrap());
+        }
+        digests
+    }
+
+    #[test]
+    fn test_native_blake2s_rfc7693_selftest() {
+        const EXPECTED: [u8; 32] = [
+            0x6A, 0x41, 0x1F, 0x08, 0xCE, 0x25, 0xAD, 0xCD, 0xFB, 0x02, 0xAB, 0xA6, 0x41, 0x45,
+            0x1C, 0xEC, 0x53, 0xC5, 0x98, 0xB2, 0x4F, 0x4F, 0xC7, 0x87, 0xFB, 0xDC, 0x88, 0x79,
+            0x7F, 0x4C, 0x1D, 0xFE,
+        ];
+
+        let mut hasher = StatefulBlake2s::<32>::default();
+        hasher.update(&selftest_digests::<16>());
+        hasher.update(&selftest_digests::<20>());
+        hasher.update(&selftest_digests::<28>());
+        hasher.update(&selftest_digests::<32>());
+
+        assert_eq!(hasher.finalize(), EXPECTED);
+    }
+
+    #[test]
+    fn test_native_blake2s_keyed() {
+        use blake2::{digest::Mac, Blake2sMac256};
+
+        let mut rng = ark_std::test_rng();
+
+        for key_len in [1, 32] {
+            for len in [0, 64, 65] {
+                let key: Vec<u8> = (0..key_len).map(|_| rng.gen()).collect();
+                let input: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+
+                let mut mac = <Blake2sMac256 as Mac>::new_from_slice(&key).unwrap();
+                mac.update(&input);
+                let expected = mac.finalize().into_bytes().to_vec();
+
+                let mut native = StatefulBlake2s::<32>::new_keyed(&key);
+                native.update(&input);
+                assert_eq!(native.finalize(), expected);
+                assert_eq!(
+                    StatefulBlake2s::<32>::evaluate(&key, &input).unwrap(),
+                    expected
+                );
+            }
+        }
+    }
+}
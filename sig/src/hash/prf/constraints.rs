@@ -1,6 +1,6 @@
 use ark_ff::Field;
 use ark_r1cs_std::prelude::*;
-use ark_relations::r1cs::SynthesisError;
+use ark_relations::r1cs::{Namespace, SynthesisError};
 use ark_std::fmt::Debug;
 
 pub trait PRFGadget<F: Field> {
@@ -12,4 +12,9 @@ pub trait PRFGadget<F: Field> {
     fn update(&mut self, input: &[UInt8<F>]) -> Result<(), SynthesisError>;
 
     fn finalize(self) -> Result<Self::OutputVar, SynthesisError>;
+
+    /// Allocate the seed (key) of the PRF as witnesses.
+    fn new_seed(cs: impl Into<Namespace<F>>, seed: &[u8]) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        UInt8::new_witness_vec(cs, seed)
+    }
 }
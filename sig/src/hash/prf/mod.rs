@@ -3,12 +3,25 @@ use ark_crypto_primitives::Error;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::{fmt::Debug, hash::Hash};
 
+pub mod blake2b;
 pub mod blake2s;
 pub mod constraints;
+pub mod sha256;
 
+/// A pseudorandom function keyed by `Seed`, following the arkworks `PRF` trait.
 pub trait PRF {
+    type Seed: CanonicalDeserialize + Default;
     type Input: CanonicalDeserialize + Default;
     type Output: CanonicalSerialize + Eq + Clone + Debug + Default + Hash;
 
-    fn evaluate(input: &Self::Input) -> Result<Self::Output, Error>;
+    fn evaluate(seed: &Self::Seed, input: &Self::Input) -> Result<Self::Output, Error>;
 }
+
+/// A `PRF` evaluated under its default seed, e.g. unkeyed Blake2s.
+pub trait UnkeyedPRF: PRF {
+    fn evaluate_unkeyed(input: &Self::Input) -> Result<Self::Output, Error> {
+        Self::evaluate(&Self::Seed::default(), input)
+    }
+}
+
+impl<P: PRF> UnkeyedPRF for P {}
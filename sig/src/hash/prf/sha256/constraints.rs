@@ -0,0 +1,131 @@
+use crate::hash::prf::{blake2s::constraints::OutputVar, constraints::PRFGadget};
+use ark_crypto_primitives::crh::sha256::constraints::Sha256Gadget;
+use ark_ff::PrimeField;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::SynthesisError;
+
+/// SHA-256 as a `PRFGadget`, e.g. to interoperate with systems that hash with
+/// SHA-256 instead of Blake2s.
+///
+/// This wraps the SHA-256 gadget of arkworks, which buffers the input into
+/// 64-byte blocks and applies the length padding of FIPS 180-4 on `finalize`.
+#[derive(Default)]
+pub struct StatefulSha256Gadget<F: PrimeField> {
+    state: Sha256Gadget<F>,
+}
+
+impl<F: PrimeField> PRFGadget<F> for StatefulSha256Gadget<F> {
+    type OutputVar = OutputVar<F>;
+    const OUTPUT_SIZE: usize = 32;
+
+    fn update(&mut self, input: &[UInt8<F>]) -> Result<(), SynthesisError> {
+        self.state.update(input)
+    }
+
+    fn finalize(self) -> Result<<Self as PRFGadget<F>>::OutputVar, SynthesisError> {
+        Ok(OutputVar(self.state.finalize()?.to_bytes_le()?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::Fq as Fr;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::r1cs::{ConstraintSystem, SynthesisError};
+    use ark_std::rand::Rng;
+    use sha2::{Digest, Sha256};
+
+    use super::StatefulSha256Gadget;
+    use crate::{
+        hash::prf::constraints::PRFGadget,
+        testing::{assert_constraints_within, count_constraints},
+    };
+
+    fn check_sha256(data: &[u8], mode: AllocationMode) {
+        let expected = Sha256::digest(data);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let data_var = data
+            .iter()
+            .map(|byte| UInt8::new_variable(cs.clone(), || Ok(*byte), mode))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        // the input is split across several updates, so that the buffered
+        // bytes have to be carried over to the next block
+        let mut hasher = StatefulSha256Gadget::default();
+        for chunk in data_var.chunks(23) {
+            hasher.update(chunk).unwrap();
+        }
+        let output = hasher.finalize().unwrap();
+
+        assert_eq!(output.0.value().unwrap(), expected.as_slice());
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_sha256() {
+        let mut rng = ark_std::test_rng();
+
+        // around the block boundaries, including the padding that only fits
+        // in an extra block (56 to 63 bytes)
+        for input_len in [0, 1, 55, 56, 63, 64, 65, 119, 120, 128] {
+            let data: Vec<u8> = (0..input_len).map(|_| rng.gen()).collect();
+            check_sha256(&data, AllocationMode::Constant);
+            check_sha256(&data, AllocationMode::Witness);
+        }
+    }
+
+    #[test]
+    fn test_sha256_prf() {
+        let mut rng = ark_std::test_rng();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+
+        let mut input = [0u8; 64];
+        rng.fill(&mut input);
+
+        let input_var =
+            UInt8::new_witness_vec(ark_relations::ns!(cs, "declare_input"), &input).unwrap();
+        let out: [u8; 32] = Sha256::digest(input).into();
+        let actual_out_var = super::OutputVar(
+            UInt8::new_witness_vec(ark_relations::ns!(cs, "declare_output"), &out).unwrap(),
+        );
+
+        let mut hasher = StatefulSha256Gadget::default();
+        hasher.update(&input_var).unwrap();
+        let output_var = hasher.finalize().unwrap();
+        output_var.enforce_equal(&actual_out_var).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    fn witness_constraints(input_len: usize) -> usize {
+        count_constraints::<Fr, _>(|cs| {
+            let input = UInt8::new_witness_vec(cs, &vec![0u8; input_len])?;
+            let mut hasher = StatefulSha256Gadget::default();
+            hasher.update(&input)?;
+            hasher.finalize()
+        })
+    }
+
+    #[test]
+    fn test_sha256_constraints() {
+        // 8 constraints per input byte, and the padding of a multiple of 64
+        // bytes is a block of constants, compressed into a witness state
+        assert_constraints_within(witness_constraints(64), 74270, 0);
+        assert_constraints_within(witness_constraints(128), 115662, 0);
+        assert_constraints_within(witness_constraints(192), 157054, 0);
+    }
+
+    #[test]
+    fn test_sha256_constant_constraints() -> Result<(), SynthesisError> {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let input: Vec<_> = (0..128u8).map(UInt8::constant).collect();
+
+        let mut hasher = StatefulSha256Gadget::<Fr>::default();
+        hasher.update(&input)?;
+        hasher.finalize()?;
+        assert_eq!(cs.num_constraints(), 0);
+        Ok(())
+    }
+}
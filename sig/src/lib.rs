@@ -5,9 +5,12 @@
 #![deny(clippy::cast_lossless)]
 
 pub mod bc;
+#[cfg(feature = "bench-utils")]
+pub mod bench_support;
 pub mod bls;
 pub mod folding;
 pub mod hash;
 pub mod merkle;
 pub mod params;
+pub mod testing;
 mod tests;
@@ -1,36 +1,122 @@
-use std::marker::PhantomData;
-
-use ark_crypto_primitives::crh::{
-    poseidon::constraints::{
-        CRHGadget as Poseidon, CRHParametersVar as PoseidonParams,
-        TwoToOneCRHGadget as PoseidonTwoToOne,
+use std::{borrow::Borrow, marker::PhantomData};
+
+use ark_crypto_primitives::{
+    crh::{
+        poseidon::constraints::{
+            CRHGadget as Poseidon, CRHParametersVar as PoseidonParams,
+            TwoToOneCRHGadget as PoseidonTwoToOne,
+        },
+        CRHSchemeGadget, TwoToOneCRHScheme, TwoToOneCRHSchemeGadget,
     },
-    CRHSchemeGadget, TwoToOneCRHSchemeGadget,
+    sponge::Absorb,
 };
 use ark_ff::{BigInteger, PrimeField};
 use ark_r1cs_std::{
-    alloc::AllocVar, convert::ToConstraintFieldGadget, eq::EqGadget, fields::fp::FpVar, R1CSVar,
+    alloc::{AllocVar, AllocationMode},
+    boolean::Boolean,
+    convert::{ToBitsGadget, ToConstraintFieldGadget},
+    eq::EqGadget,
+    fields::fp::FpVar,
+    select::CondSelectGadget,
+    R1CSVar,
 };
-use ark_relations::r1cs::SynthesisError;
+use ark_relations::r1cs::{Namespace, SynthesisError};
 
 use super::{
-    forest::{optimal_forest_params, MerkleForestError},
-    left, right,
-    tree::MerkleTreeError,
-    MerkleConfig,
+    arity, first_child,
+    forest::{optimal_forest_params_for_arity, tree_height, MerkleForestError},
+    is_valid_capacity, num_leaves, parent,
+    tree::{MerklePathUpdate, MerkleTreeError, MerkleTreeProof},
+    Config, DomainSeparatedConfig, MerkleConfig, WideConfig, LEAF_DOMAIN, NODE_DOMAIN,
 };
 
-pub struct MerkleTreeVar<'a, P: MerkleConfig> {
+/// In-circuit counterparts of the hashes of a `MerkleConfig`, used by all the
+/// gadgets of this module.
+///
+/// The gadgets keep the nodes as `FpVar`s, so `LeafHash` and `TwoToOneHash`
+/// have to output elements of `BasePrimeField`. `Config`,
+/// `DomainSeparatedConfig` and `WideConfig` hash with Poseidon, see
+/// `poseidon_hash_leaf` and `poseidon_hash_node`.
+pub trait MerkleConfigGadget: MerkleConfig {
+    /// Parameters of the hashes, allocated in the circuit.
+    type HashParamsVar;
+
+    /// Hash the values of a leaf, as `LeafHash` does natively.
+    fn hash_leaf(
+        params: &Self::HashParamsVar,
+        leaf: &[FpVar<Self::BasePrimeField>],
+    ) -> Result<FpVar<Self::BasePrimeField>, SynthesisError>;
+
+    /// Hash the `ARITY` children of a node into the node, as
+    /// `MerkleConfig::compress` does natively.
+    fn hash_node(
+        params: &Self::HashParamsVar,
+        children: &[FpVar<Self::BasePrimeField>],
+    ) -> Result<FpVar<Self::BasePrimeField>, SynthesisError>;
+}
+
+impl<CF: PrimeField + Absorb> MerkleConfigGadget for Config<CF> {
+    type HashParamsVar = PoseidonParams<CF>;
+
+    fn hash_leaf(
+        params: &PoseidonParams<CF>,
+        leaf: &[FpVar<CF>],
+    ) -> Result<FpVar<CF>, SynthesisError> {
+        poseidon_hash_leaf::<Self>(params, leaf)
+    }
+
+    fn hash_node(
+        params: &PoseidonParams<CF>,
+        children: &[FpVar<CF>],
+    ) -> Result<FpVar<CF>, SynthesisError> {
+        poseidon_hash_node::<Self>(params, children)
+    }
+}
+
+impl<CF: PrimeField + Absorb> MerkleConfigGadget for DomainSeparatedConfig<CF> {
+    type HashParamsVar = PoseidonParams<CF>;
+
+    fn hash_leaf(
+        params: &PoseidonParams<CF>,
+        leaf: &[FpVar<CF>],
+    ) -> Result<FpVar<CF>, SynthesisError> {
+        poseidon_hash_leaf::<Self>(params, leaf)
+    }
+
+    fn hash_node(
+        params: &PoseidonParams<CF>,
+        children: &[FpVar<CF>],
+    ) -> Result<FpVar<CF>, SynthesisError> {
+        poseidon_hash_node::<Self>(params, children)
+    }
+}
+
+impl<CF: PrimeField + Absorb, const N: usize> MerkleConfigGadget for WideConfig<CF, N> {
+    type HashParamsVar = PoseidonParams<CF>;
+
+    fn hash_leaf(
+        params: &PoseidonParams<CF>,
+        leaf: &[FpVar<CF>],
+    ) -> Result<FpVar<CF>, SynthesisError> {
+        poseidon_hash_leaf::<Self>(params, leaf)
+    }
+
+    fn hash_node(
+        params: &PoseidonParams<CF>,
+        children: &[FpVar<CF>],
+    ) -> Result<FpVar<CF>, SynthesisError> {
+        poseidon_hash_node::<Self>(params, children)
+    }
+}
+
+pub struct MerkleTreeVar<'a, P: MerkleConfigGadget> {
     nodes: Vec<FpVar<P::BasePrimeField>>,
-    hash_params: &'a PoseidonParams<P::BasePrimeField>,
+    hash_params: &'a P::HashParamsVar,
 }
 
-impl<'a, P: MerkleConfig> MerkleTreeVar<'a, P> {
-    pub fn new(
-        capacity: usize,
-        params: &'a PoseidonParams<P::BasePrimeField>,
-    ) -> Result<Self, MerkleTreeError> {
-        if !(capacity + 1).is_power_of_two() {
+impl<'a, P: MerkleConfigGadget> MerkleTreeVar<'a, P> {
+    pub fn new(capacity: usize, params: &'a P::HashParamsVar) -> Result<Self, MerkleTreeError> {
+        if !is_valid_capacity(capacity, arity::<P>()) {
             return Err(MerkleTreeError::InvalidCapacity);
         }
 
@@ -65,7 +151,7 @@ impl<'a, P: MerkleConfig> MerkleTreeVar<'a, P> {
 
         tracing::info!(num_constraints = cs.num_constraints());
 
-        let hash = Poseidon::evaluate(self.hash_params, new_leaf)?;
+        let hash = P::hash_leaf(self.hash_params, new_leaf)?;
 
         tracing::info!(num_constraints = cs.num_constraints());
 
@@ -80,30 +166,69 @@ impl<'a, P: MerkleConfig> MerkleTreeVar<'a, P> {
         &mut self,
         index: FpVar<P::BasePrimeField>,
         new_leaf: FpVar<P::BasePrimeField>,
+    ) -> Result<FpVar<P::BasePrimeField>, SynthesisError> {
+        self.update_with_hash_conditional(&Boolean::TRUE, index, new_leaf)
+    }
+
+    /// Same as `update`, but the tree is only updated if `enable` is true,
+    /// and otherwise keeps all its nodes. Returns the root after the update.
+    pub fn update_conditional(
+        &mut self,
+        enable: &Boolean<P::BasePrimeField>,
+        index: FpVar<P::BasePrimeField>,
+        new_leaf: &[FpVar<P::BasePrimeField>],
+    ) -> Result<FpVar<P::BasePrimeField>, SynthesisError> {
+        let hash = P::hash_leaf(self.hash_params, new_leaf)?;
+        self.update_with_hash_conditional(enable, index, hash)
+    }
+
+    /// Same as `update_with_hash`, but the tree is only updated if `enable`
+    /// is true.
+    ///
+    /// `enable` is folded into the selection of the leaf, so a disabled
+    /// update keeps every leaf and rehashes them into the same nodes. This
+    /// costs one `and` per leaf on top of the unconditional update, and no
+    /// `select` on the internal nodes.
+    pub fn update_with_hash_conditional(
+        &mut self,
+        enable: &Boolean<P::BasePrimeField>,
+        index: FpVar<P::BasePrimeField>,
+        new_leaf: FpVar<P::BasePrimeField>,
     ) -> Result<FpVar<P::BasePrimeField>, SynthesisError> {
         let num_leaves = self.num_leaves();
-        let leaves_start = num_leaves - 1;
+        let leaves_start = self.nodes.len() - num_leaves;
+
+        if let FpVar::Constant(index) = &index {
+            // The position is known at circuit construction time, so the leaf
+            // can be placed directly without any `is_eq`/`select` constraints.
+            // An out-of-range index leaves all leaves untouched, matching the
+            // behaviour of the general path below.
+            if let Some(i) = constant_to_usize(index).filter(|i| *i < num_leaves) {
+                let leaf = &mut self.nodes[leaves_start + i];
+                *leaf = enable.select(&new_leaf, leaf)?;
+            }
+        } else {
+            // Create an updated leaves vector by iterating over each leaf.
+            // For each leaf position i (a constant), compare i with the provided index.
+            // If they are equal then select new_leaf; otherwise keep the original leaf.
+            let mut updated_leaves = Vec::with_capacity(num_leaves);
+            for i in 0..num_leaves {
+                // Create a constant FpVar for the index value i.
+                let i_const = FpVar::Constant(P::BasePrimeField::from(i as u64));
+                // Enforce equality check: index == i_const, if the update is enabled.
+                let eq = index.is_eq(&i_const)? & enable;
+                // Use the equality gadget to conditionally select new_leaf if eq holds.
+                let leaf_val = eq.select(&new_leaf, &self.nodes[leaves_start + i])?;
+                updated_leaves.push(leaf_val);
+            }
 
-        // Create an updated leaves vector by iterating over each leaf.
-        // For each leaf position i (a constant), compare i with the provided index.
-        // If they are equal then select new_leaf; otherwise keep the original leaf.
-        let mut updated_leaves = Vec::with_capacity(num_leaves);
-        for i in 0..num_leaves {
-            // Create a constant FpVar for the index value i.
-            let i_const = FpVar::Constant(P::BasePrimeField::from(i as u64));
-            // Enforce equality check: index == i_const.
-            let eq = index.is_eq(&i_const)?;
-            // Use the equality gadget to conditionally select new_leaf if eq holds.
-            let leaf_val = eq.select(&new_leaf, &self.nodes[leaves_start + i])?;
-            updated_leaves.push(leaf_val);
+            // Replace the old leaves
+            self.nodes.splice(leaves_start.., updated_leaves);
         }
 
-        // Replace the old leaves
-        self.nodes.splice(leaves_start.., updated_leaves);
-
         // Recompute the internal nodes in a bottom-up fashion.
         // For every internal node (from leaves_start-1 down to 0),
-        // compute the hash of its two children.
+        // compute the hash of its children.
         for i in (0..leaves_start).rev() {
             self.update_state(i)?;
         }
@@ -112,18 +237,157 @@ impl<'a, P: MerkleConfig> MerkleTreeVar<'a, P> {
         Ok(self.root())
     }
 
+    /// The hash of the leaf at `index`.
+    ///
+    /// An `index` that is not smaller than the number of leaves makes the
+    /// circuit unsatisfiable.
+    pub fn open(
+        &self,
+        index: FpVar<P::BasePrimeField>,
+    ) -> Result<FpVar<P::BasePrimeField>, SynthesisError> {
+        let index_bits = split_index(&index, self.num_leaves(), 1)?;
+        self.open_bits(&index_bits[0])
+    }
+
+    /// The hash of the leaf whose index is given by the little-endian
+    /// `index_bits`, selected from all the leaves.
+    fn open_bits(
+        &self,
+        index_bits: &[Boolean<P::BasePrimeField>],
+    ) -> Result<FpVar<P::BasePrimeField>, SynthesisError> {
+        let leaves_start = self.nodes.len() - self.num_leaves();
+        let position: Vec<_> = index_bits.iter().rev().cloned().collect();
+        FpVar::conditionally_select_power_of_two_vector(&position, &self.nodes[leaves_start..])
+    }
+
     pub fn root(&self) -> FpVar<P::BasePrimeField> {
         self.nodes[0].clone()
     }
 
+    /// Check that `leaf` is the leaf at `index` of the tree committed to by
+    /// `root`, given the `siblings` on its path as in `MerkleTreeProof`, by
+    /// recomputing the root as `MerkleTree::hash_path` does.
+    ///
+    /// Unlike `open`, this only hashes the path instead of the whole tree. An
+    /// `index` that is not addressable by a tree of the path's height makes
+    /// the circuit unsatisfiable, and a path whose length is not a multiple
+    /// of `ARITY - 1` is rejected with `Unsatisfiable`.
+    pub fn verify_path(
+        params: &P::HashParamsVar,
+        leaf: &[FpVar<P::BasePrimeField>],
+        index: &FpVar<P::BasePrimeField>,
+        siblings: &[FpVar<P::BasePrimeField>],
+        root: &FpVar<P::BasePrimeField>,
+    ) -> Result<Boolean<P::BasePrimeField>, SynthesisError> {
+        let arity = arity::<P>();
+        if siblings.is_empty() || siblings.len() % (arity - 1) != 0 {
+            tracing::error!(
+                num_siblings = siblings.len(),
+                arity,
+                "path length should be a non-zero multiple of ARITY - 1"
+            );
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        // each level takes `log2(ARITY)` bits of the leaf index
+        let num_bits = siblings.len() / (arity - 1) * arity.ilog2() as usize;
+        let (index_bits, _) = index.to_bits_le_with_top_bits_zero(num_bits)?;
+
+        let hash = P::hash_leaf(params, leaf)?;
+        hash_path::<P>(params, hash, siblings, &index_bits)?.is_eq(root)
+    }
+
+    /// The siblings on the path of the leaf whose index is given by the
+    /// little-endian `index_bits`, ordered as in `MerkleTree::prove`.
+    ///
+    /// `index_bits` should have `log2(ARITY)` bits per level of the tree.
+    pub(crate) fn siblings(
+        &self,
+        index_bits: &[Boolean<P::BasePrimeField>],
+    ) -> Result<Vec<FpVar<P::BasePrimeField>>, SynthesisError> {
+        let arity = arity::<P>();
+        let bits_per_level = arity.ilog2() as usize;
+        let mut level_len = self.num_leaves();
+        assert_eq!(
+            index_bits.len(),
+            level_len.ilog2() as usize,
+            "index_bits should address every leaf of the tree"
+        );
+
+        let mut siblings = Vec::with_capacity(index_bits.len() / bits_per_level * (arity - 1));
+        let mut level_start = self.nodes.len() - level_len;
+        for (d, position) in index_bits.chunks(bits_per_level).enumerate() {
+            // the bits above `position` select the children that the node on
+            // the path belongs to
+            let group: Vec<_> = index_bits[(d + 1) * bits_per_level..]
+                .iter()
+                .rev()
+                .cloned()
+                .collect();
+            let children = (0..arity)
+                .map(|k| {
+                    let candidates: Vec<_> = (level_start + k..level_start + level_len)
+                        .step_by(arity)
+                        .map(|i| self.nodes[i].clone())
+                        .collect();
+                    FpVar::conditionally_select_power_of_two_vector(&group, &candidates)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            siblings.extend(remove_at(&children, position)?);
+
+            level_start = parent(level_start, arity);
+            level_len /= arity;
+        }
+
+        Ok(siblings)
+    }
+
+    /// Build a tree of `capacity` whose first leaves are the hashes of
+    /// `values`, padding the remaining leaves with the default value, as
+    /// `MerkleTree::from_values` does.
+    pub fn from_values(
+        values: &[&[FpVar<P::BasePrimeField>]],
+        capacity: usize,
+        params: &'a P::HashParamsVar,
+    ) -> Result<Self, SynthesisError> {
+        let mut s = Self::new(capacity, params).map_err(|_| SynthesisError::Unsatisfiable)?;
+        let num_leaves = s.num_leaves();
+        if values.len() > num_leaves {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        let leaves_start = s.nodes.len() - num_leaves;
+        for (i, value) in values.iter().enumerate() {
+            s.nodes[leaves_start + i] = P::hash_leaf(params, value)?;
+        }
+        for i in (0..leaves_start).rev() {
+            s.update_state(i)?;
+        }
+
+        Ok(s)
+    }
+
+    /// Rebuild a tree from the nodes returned by `to_constraint_field`.
+    ///
+    /// The nodes are taken as they are, so a tree whose internal nodes do not
+    /// match its leaves is only detected by `validate_structure`.
     pub fn from_constraint_field(
         iter: impl Iterator<Item = FpVar<P::BasePrimeField>>,
         capacity: usize,
-        params: &'a PoseidonParams<P::BasePrimeField>,
+        params: &'a P::HashParamsVar,
     ) -> Result<Self, SynthesisError> {
+        if !is_valid_capacity(capacity, arity::<P>()) {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
         let nodes: Vec<_> = iter.take(capacity).collect();
         if nodes.len() != capacity {
-            return Err(SynthesisError::Unsatisfiable);
+            tracing::error!(
+                expected = capacity,
+                got = nodes.len(),
+                "not enough field elements to rebuild the Merkle tree"
+            );
+            return Err(SynthesisError::AssignmentMissing);
         }
         Ok(Self {
             nodes,
@@ -131,43 +395,342 @@ impl<'a, P: MerkleConfig> MerkleTreeVar<'a, P> {
         })
     }
 
+    /// Enforce that every internal node is the hash of its children if
+    /// `enable` is true, i.e. that the tree is the one committed to by its
+    /// leaves.
+    ///
+    /// This costs one hash per internal node, as for building the tree.
+    pub fn validate_structure(
+        &self,
+        enable: &Boolean<P::BasePrimeField>,
+    ) -> Result<(), SynthesisError> {
+        let arity = arity::<P>();
+        for i in 0..self.nodes.len() - self.num_leaves() {
+            let first = first_child(i, arity);
+            P::hash_node(self.hash_params, &self.nodes[first..first + arity])?
+                .conditional_enforce_equal(&self.nodes[i], enable)?;
+        }
+        Ok(())
+    }
+
     pub const fn num_constraint_var_needed(capacity: usize) -> usize {
         capacity
     }
 
     fn update_state(&mut self, index: usize) -> Result<(), SynthesisError> {
-        let left_child = &self.nodes[left(index)];
-        let right_child = &self.nodes[right(index)];
+        let arity = arity::<P>();
+        let first = first_child(index, arity);
         // Note: I originally thought we can select between hash and the
         // old tree hash. But, in either case, we need to compute a hash.
         // So, to avoid waste constraints to select, we can just use the new hash to
         // as the new tree node.
-        self.nodes[index] = PoseidonTwoToOne::evaluate(self.hash_params, left_child, right_child)?;
+        self.nodes[index] = P::hash_node(self.hash_params, &self.nodes[first..first + arity])?;
         Ok(())
     }
 
     #[inline]
     pub(crate) fn num_leaves(&self) -> usize {
-        (self.nodes.len() + 1) / 2
+        num_leaves(self.nodes.len(), arity::<P>())
     }
 }
 
-impl<'a, P: MerkleConfig> ToConstraintFieldGadget<P::BasePrimeField> for MerkleTreeVar<'a, P> {
+/// The nodes of the tree in index order, i.e. the root first and the leaves
+/// last, where the children of node `i` start at `first_child(i, ARITY)`.
+impl<'a, P: MerkleConfigGadget> ToConstraintFieldGadget<P::BasePrimeField>
+    for MerkleTreeVar<'a, P>
+{
     fn to_constraint_field(&self) -> Result<Vec<FpVar<P::BasePrimeField>>, SynthesisError> {
         Ok(self.nodes.clone())
     }
 }
 
-pub struct LeveledMerkleForestVar<'a, P: MerkleConfig> {
+/// R1CS equivalent of a `MerkleTreeProof` produced by `MerkleTree::prove`.
+///
+/// Unlike `MerkleTreeVar`, only the siblings on the path from the leaf to the
+/// root are allocated, so verifying membership costs `height` hashes.
+pub struct MerklePathVar<P: MerkleConfigGadget> {
+    siblings: Vec<FpVar<P::BasePrimeField>>,
+    // little-endian bits of the leaf index within its level, where every
+    // `log2(ARITY)` bits are the position of the node on the path among its
+    // siblings
+    index_bits: Vec<Boolean<P::BasePrimeField>>,
+}
+
+impl<P: MerkleConfigGadget> MerklePathVar<P> {
+    /// Check that `leaf` is the leaf committed to by `root` at the position
+    /// of this path.
+    pub fn verify(
+        &self,
+        params: &P::HashParamsVar,
+        root: &FpVar<P::BasePrimeField>,
+        leaf: &[FpVar<P::BasePrimeField>],
+    ) -> Result<Boolean<P::BasePrimeField>, SynthesisError> {
+        let hash = P::hash_leaf(params, leaf)?;
+        self.verify_with_hash(params, root, hash)
+    }
+
+    /// Same as `verify`, but for a leaf that has already been hashed.
+    pub fn verify_with_hash(
+        &self,
+        params: &P::HashParamsVar,
+        root: &FpVar<P::BasePrimeField>,
+        hash: FpVar<P::BasePrimeField>,
+    ) -> Result<Boolean<P::BasePrimeField>, SynthesisError> {
+        self.hash_path(params, hash)?.is_eq(root)
+    }
+
+    /// Recompute the root from the leaf `hash`, replicating `MerkleTree::hash_path`.
+    pub fn hash_path(
+        &self,
+        params: &P::HashParamsVar,
+        hash: FpVar<P::BasePrimeField>,
+    ) -> Result<FpVar<P::BasePrimeField>, SynthesisError> {
+        hash_path::<P>(params, hash, &self.siblings, &self.index_bits)
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.siblings.len() / (arity::<P>() - 1)
+    }
+}
+
+/// The length of the path is taken from the proof, so the proof must be
+/// available even when generating parameters.
+impl<P> AllocVar<MerkleTreeProof<P>, P::BasePrimeField> for MerklePathVar<P>
+where
+    P: MerkleConfigGadget,
+    P::TwoToOneHash: TwoToOneCRHScheme<Output = P::BasePrimeField>,
+{
+    fn new_variable<T: Borrow<MerkleTreeProof<P>>>(
+        cs: impl Into<Namespace<P::BasePrimeField>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let cs = cs.into().cs();
+
+        let proof = f()?;
+        let MerkleTreeProof {
+            siblings,
+            leaf_index,
+        } = proof.borrow();
+
+        let arity = arity::<P>();
+        if siblings.len() % (arity - 1) != 0 {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        // each level takes `log2(ARITY)` bits of the leaf index
+        let num_bits = siblings.len() / (arity - 1) * arity.ilog2() as usize;
+        if num_bits >= usize::BITS as usize || *leaf_index >= 1 << num_bits {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let bits: Vec<_> = (0..num_bits).map(|i| (leaf_index >> i) & 1 == 1).collect();
+
+        Ok(Self {
+            siblings: Vec::new_variable(cs.clone(), || Ok(&siblings[..]), mode)?,
+            index_bits: Vec::new_variable(cs, || Ok(bits), mode)?,
+        })
+    }
+}
+
+/// R1CS gadget that replaces a leaf of a tree which is only known by its
+/// root, given the old leaf and its siblings as witnesses.
+///
+/// An update costs `2 * height` hashes, as the path is hashed once to check
+/// the old leaf against the old root and once to compute the new root, where
+/// `MerkleTreeVar::update` rehashes the whole tree.
+pub struct MerklePathUpdateVar<P: MerkleConfigGadget> {
+    old_leaf: FpVar<P::BasePrimeField>,
+    siblings: Vec<FpVar<P::BasePrimeField>>,
+}
+
+impl<P: MerkleConfigGadget> MerklePathUpdateVar<P> {
+    /// Replace the leaf at the position given by the little-endian
+    /// `index_bits` with the hash `new_leaf`, and return the new root.
+    ///
+    /// The old leaf and the siblings are enforced to hash up to `old_root`,
+    /// so the circuit is unsatisfiable if the witness does not come from the
+    /// tree with `old_root`.
+    pub fn update_with_hash(
+        &self,
+        params: &P::HashParamsVar,
+        index_bits: &[Boolean<P::BasePrimeField>],
+        old_root: &FpVar<P::BasePrimeField>,
+        new_leaf: FpVar<P::BasePrimeField>,
+    ) -> Result<FpVar<P::BasePrimeField>, SynthesisError> {
+        hash_path::<P>(params, self.old_leaf.clone(), &self.siblings, index_bits)?
+            .enforce_equal(old_root)?;
+        hash_path::<P>(params, new_leaf, &self.siblings, index_bits)
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.siblings.len() / (arity::<P>() - 1)
+    }
+}
+
+/// As for `MerklePathVar`, the length of the path is taken from the witness.
+impl<P> AllocVar<MerklePathUpdate<P>, P::BasePrimeField> for MerklePathUpdateVar<P>
+where
+    P: MerkleConfigGadget,
+    P::TwoToOneHash: TwoToOneCRHScheme<Output = P::BasePrimeField>,
+{
+    fn new_variable<T: Borrow<MerklePathUpdate<P>>>(
+        cs: impl Into<Namespace<P::BasePrimeField>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let cs = cs.into().cs();
+
+        let update = f()?;
+        let MerklePathUpdate { old_leaf, siblings } = update.borrow();
+        if siblings.len() % (arity::<P>() - 1) != 0 {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        Ok(Self {
+            old_leaf: FpVar::new_variable(cs.clone(), || Ok(old_leaf), mode)?,
+            siblings: Vec::new_variable(cs, || Ok(&siblings[..]), mode)?,
+        })
+    }
+}
+
+/// R1CS equivalent of a `MerkleForestProof` produced by `LeveledMerkleForest::prove`.
+pub struct MerkleForestProofVar<P: MerkleConfigGadget> {
+    siblings: Vec<FpVar<P::BasePrimeField>>,
+    leaf_index: FpVar<P::BasePrimeField>,
+    num_leaves_per_tree: usize,
+}
+
+impl<P: MerkleConfigGadget> MerkleForestProofVar<P> {
+    /// Build a proof from its parts. `num_leaves_per_tree` is a circuit
+    /// constant, and the number of siblings should be a non-zero multiple of
+    /// the number of siblings per tree, as checked by `LeveledMerkleForest::verify`.
+    pub fn new(
+        siblings: Vec<FpVar<P::BasePrimeField>>,
+        leaf_index: FpVar<P::BasePrimeField>,
+        num_leaves_per_tree: usize,
+    ) -> Result<Self, MerkleForestError> {
+        let tree_height = tree_height::<P>(num_leaves_per_tree)
+            .ok_or(MerkleForestError::InvalidNumLeavesPerTree)?;
+
+        let siblings_per_tree = tree_height * (arity::<P>() - 1);
+        if siblings.is_empty() || siblings.len() % siblings_per_tree != 0 {
+            return Err(MerkleForestError::InvalidProofLength);
+        }
+
+        Ok(Self {
+            siblings,
+            leaf_index,
+            num_leaves_per_tree,
+        })
+    }
+
+    /// Check that `leaf` is stored at `leaf_index` of the forest with `root`.
+    ///
+    /// A leaf index that is not addressable by the trees in the proof makes
+    /// the result `false`, where the native `verify` returns an error.
+    pub fn verify(
+        &self,
+        params: &P::HashParamsVar,
+        root: &FpVar<P::BasePrimeField>,
+        leaf: &[FpVar<P::BasePrimeField>],
+    ) -> Result<Boolean<P::BasePrimeField>, SynthesisError> {
+        let hash = P::hash_leaf(params, leaf)?;
+        self.verify_with_hash(params, root, hash)
+    }
+
+    /// Same as `verify`, but for a leaf that has already been hashed.
+    pub fn verify_with_hash(
+        &self,
+        params: &P::HashParamsVar,
+        root: &FpVar<P::BasePrimeField>,
+        mut hash: FpVar<P::BasePrimeField>,
+    ) -> Result<Boolean<P::BasePrimeField>, SynthesisError> {
+        // As `num_leaves_per_tree` is a power of 2, the index within each tree
+        // is the next `log2(num_leaves_per_tree)` bits of the leaf index. The
+        // native code offsets it by `leaf_start` to reuse `MerkleTree::hash_path`,
+        // which only changes how the position is encoded, not the bits themselves.
+        let bits_per_tree = self.num_leaves_per_tree.ilog2() as usize;
+        let siblings_per_tree = bits_per_tree / arity::<P>().ilog2() as usize * (arity::<P>() - 1);
+        let num_bits = self.siblings.len() / siblings_per_tree * bits_per_tree;
+
+        let bits = self.leaf_index.to_bits_le()?;
+        let index_bits: Vec<_> = bits
+            .iter()
+            .cloned()
+            .chain(std::iter::repeat(Boolean::FALSE))
+            .take(num_bits)
+            .collect();
+
+        for (siblings, index_bits) in self
+            .siblings
+            .chunks(siblings_per_tree)
+            .zip(index_bits.chunks(bits_per_tree))
+        {
+            hash = hash_path::<P>(params, hash, siblings, index_bits)?;
+        }
+
+        let is_member = hash.is_eq(root)?;
+        match bits.get(num_bits..) {
+            Some(high_bits) if !high_bits.is_empty() => {
+                Ok(is_member & !Boolean::kary_or(high_bits)?)
+            }
+            _ => Ok(is_member),
+        }
+    }
+
+    #[inline]
+    pub fn leaf_index(&self) -> &FpVar<P::BasePrimeField> {
+        &self.leaf_index
+    }
+
+    #[inline]
+    pub fn num_leaves_per_tree(&self) -> usize {
+        self.num_leaves_per_tree
+    }
+}
+
+/// As for `MerklePathVar`, the length of the proof is taken from the witness,
+/// and `num_leaves_per_tree` is a circuit constant.
+impl<P> AllocVar<MerkleForestProof<P>, P::BasePrimeField> for MerkleForestProofVar<P>
+where
+    P: MerkleConfigGadget,
+    P::TwoToOneHash: TwoToOneCRHScheme<Output = P::BasePrimeField>,
+{
+    fn new_variable<T: Borrow<MerkleForestProof<P>>>(
+        cs: impl Into<Namespace<P::BasePrimeField>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let cs = cs.into().cs();
+
+        let proof = f()?;
+        let MerkleForestProof {
+            siblings,
+            leaf_index,
+            num_leaves_per_tree,
+        } = proof.borrow();
+
+        Self::new(
+            Vec::new_variable(cs.clone(), || Ok(&siblings[..]), mode)?,
+            FpVar::new_variable(cs, || Ok(P::BasePrimeField::from(*leaf_index as u64)), mode)?,
+            *num_leaves_per_tree,
+        )
+        .map_err(|_| SynthesisError::Unsatisfiable)
+    }
+}
+
+pub struct LeveledMerkleForestVar<'a, P: MerkleConfigGadget> {
     trees: Vec<MerkleTreeVar<'a, P>>,
     _hash_params: PhantomData<&'a P>,
 }
 
-impl<'a, P: MerkleConfig> LeveledMerkleForestVar<'a, P> {
+impl<'a, P: MerkleConfigGadget> LeveledMerkleForestVar<'a, P> {
     pub fn new(
         capacity_per_tree: u32,
         num_tree: u32,
-        params: &'a PoseidonParams<P::BasePrimeField>,
+        params: &'a P::HashParamsVar,
     ) -> Result<Self, MerkleForestError> {
         if num_tree == 0 {
             return Err(MerkleForestError::InvalidNumTree);
@@ -184,55 +747,139 @@ impl<'a, P: MerkleConfig> LeveledMerkleForestVar<'a, P> {
         })
     }
 
-    pub fn new_optimal(
-        n: usize,
-        params: &'a PoseidonParams<P::BasePrimeField>,
-    ) -> Result<Self, MerkleForestError> {
-        let (capacity_per_tree, num_tree) = optimal_forest_params(n);
+    pub fn new_optimal(n: usize, params: &'a P::HashParamsVar) -> Result<Self, MerkleForestError> {
+        let (capacity_per_tree, num_tree) = optimal_forest_params_for_arity(n, arity::<P>());
         LeveledMerkleForestVar::new(capacity_per_tree, num_tree, params)
     }
 
     /// Update the Merkle forest with the `new_leaf` at `index`.
     ///
-    /// Note: caller of this method should ensure `index` is within the acceptable
-    /// range of the Merkle tree.
+    /// An `index` that is not smaller than `max_leaves` makes the circuit
+    /// unsatisfiable.
     #[tracing::instrument(skip_all)]
     pub fn update(
         &mut self,
         index: FpVar<P::BasePrimeField>,
         new_leaf: &[FpVar<P::BasePrimeField>],
     ) -> Result<FpVar<P::BasePrimeField>, SynthesisError> {
+        self.update_inner(index, new_leaf, false)
+            .map(|(new_root, _)| new_root)
+    }
+
+    /// Same as `update`, but also returns a proof that `new_leaf` is stored at
+    /// `index` of the updated forest, which `MerkleForestProofVar::verify`
+    /// accepts against the returned root.
+    ///
+    /// The siblings are selected from the trees by the bits of `index`, which
+    /// costs a multiplexer over every level of each tree on top of `update`.
+    #[tracing::instrument(skip_all)]
+    pub fn update_with_proof(
+        &mut self,
+        index: FpVar<P::BasePrimeField>,
+        new_leaf: &[FpVar<P::BasePrimeField>],
+    ) -> Result<(FpVar<P::BasePrimeField>, MerkleForestProofVar<P>), SynthesisError> {
+        let (new_root, siblings) = self.update_inner(index.clone(), new_leaf, true)?;
+        let proof = MerkleForestProofVar {
+            siblings,
+            leaf_index: index,
+            num_leaves_per_tree: self.num_leaves_per_tree(),
+        };
+        Ok((new_root, proof))
+    }
+
+    fn update_inner(
+        &mut self,
+        index: FpVar<P::BasePrimeField>,
+        new_leaf: &[FpVar<P::BasePrimeField>],
+        with_proof: bool,
+    ) -> Result<(FpVar<P::BasePrimeField>, Vec<FpVar<P::BasePrimeField>>), SynthesisError> {
         tracing::info!("start updating the LMF");
 
         let cs = self.cs();
 
         tracing::info!(num_constraints = cs.num_constraints());
 
-        let num_leaves_per_tree = self.num_leaves_per_tree();
-        let (mut index, index_within_tree) = div_rem_power_of_2(index, num_leaves_per_tree)?;
+        let index_bits = split_index(&index, self.num_leaves_per_tree(), self.trees.len())?;
+        let mut siblings = vec![];
+        let mut new_root = P::hash_leaf(self.trees[0].hash_params, new_leaf)?;
 
-        let mut new_root = self.trees[0].update(index_within_tree, new_leaf)?;
-
-        for tree in self.trees.iter_mut().skip(1) {
-            let (new_index, index_within_tree) = div_rem_power_of_2(index, num_leaves_per_tree)?;
+        for (tree, bits) in self.trees.iter_mut().zip(&index_bits) {
+            let index_within_tree = Boolean::le_bits_to_fp(bits)?;
             new_root = tree.update_with_hash(index_within_tree, new_root)?;
-            index = new_index;
+            if with_proof {
+                siblings.extend(tree.siblings(bits)?);
+            }
         }
 
         tracing::info!(num_constraints = cs.num_constraints());
 
+        Ok((new_root, siblings))
+    }
+
+    /// Same as `update`, but the forest is only updated if `enable` is true,
+    /// and otherwise keeps all its nodes. Returns the root after the update.
+    ///
+    /// See `MerkleTreeVar::update_with_hash_conditional` for the cost.
+    #[tracing::instrument(skip_all)]
+    pub fn update_conditional(
+        &mut self,
+        enable: &Boolean<P::BasePrimeField>,
+        index: FpVar<P::BasePrimeField>,
+        new_leaf: &[FpVar<P::BasePrimeField>],
+    ) -> Result<FpVar<P::BasePrimeField>, SynthesisError> {
+        let index_bits = split_index(&index, self.num_leaves_per_tree(), self.trees.len())?;
+        let mut new_root = P::hash_leaf(self.trees[0].hash_params, new_leaf)?;
+
+        for (tree, bits) in self.trees.iter_mut().zip(&index_bits) {
+            let index_within_tree = Boolean::le_bits_to_fp(bits)?;
+            new_root = tree.update_with_hash_conditional(enable, index_within_tree, new_root)?;
+        }
+
         Ok(new_root)
     }
 
+    /// The hash of the leaf at `index`, read from the bottom tree.
+    ///
+    /// Only the last tree of every level is kept in the circuit, so the
+    /// bottom tree holds the leaves of the `num_leaves_per_tree` aligned block
+    /// that was updated last, and the leaf of `index` is only the one written
+    /// at `index` if no leaf of another block was written since. Leaves of
+    /// older blocks should be opened with `MerkleForestProofVar` instead.
+    ///
+    /// An `index` that is not smaller than `max_leaves` makes the circuit
+    /// unsatisfiable.
+    pub fn open(
+        &self,
+        index: FpVar<P::BasePrimeField>,
+    ) -> Result<FpVar<P::BasePrimeField>, SynthesisError> {
+        let index_bits = split_index(&index, self.num_leaves_per_tree(), self.trees.len())?;
+        self.trees[0].open_bits(&index_bits[0])
+    }
+
+    /// Rebuild a forest from the layout of `to_constraint_field`.
+    ///
+    /// Returns `SynthesisError::AssignmentMissing` if `iter` runs out before
+    /// every tree is rebuilt. As for `MerkleTreeVar::from_constraint_field`,
+    /// the nodes are not checked against each other, see `validate_structure`.
     pub fn from_constraint_field(
         mut iter: impl Iterator<Item = FpVar<P::BasePrimeField>>,
         capacity_per_tree: u32,
         num_tree: u32,
-        params: &'a PoseidonParams<P::BasePrimeField>,
+        params: &'a P::HashParamsVar,
     ) -> Result<Self, SynthesisError> {
+        if num_tree == 0 {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
         let capacity_per_tree = capacity_per_tree as usize;
         let trees = (0..num_tree)
-            .map(|_| MerkleTreeVar::from_constraint_field(iter.by_ref(), capacity_per_tree, params))
+            .map(|i| {
+                MerkleTreeVar::from_constraint_field(iter.by_ref(), capacity_per_tree, params)
+                    .map_err(|e| {
+                        tracing::error!(tree = i, num_tree, "failed to rebuild the Merkle forest");
+                        e
+                    })
+            })
             .collect::<Result<Vec<_>, _>>()?;
         Ok(Self {
             trees,
@@ -240,6 +887,22 @@ impl<'a, P: MerkleConfig> LeveledMerkleForestVar<'a, P> {
         })
     }
 
+    /// Enforce that every tree of the forest is consistent with its leaves if
+    /// `enable` is true, see `MerkleTreeVar::validate_structure`.
+    ///
+    /// The trees are not checked against each other, i.e. that the leaf of a
+    /// tree is the root of the tree below it, as a tree only stores the root
+    /// below it when it is updated.
+    pub fn validate_structure(
+        &self,
+        enable: &Boolean<P::BasePrimeField>,
+    ) -> Result<(), SynthesisError> {
+        for tree in &self.trees {
+            tree.validate_structure(enable)?;
+        }
+        Ok(())
+    }
+
     pub const fn num_constraint_var_needed(capacity_per_tree: u32, num_tree: u32) -> usize {
         num_tree as usize
             * MerkleTreeVar::<P>::num_constraint_var_needed(capacity_per_tree as usize)
@@ -262,7 +925,9 @@ impl<'a, P: MerkleConfig> LeveledMerkleForestVar<'a, P> {
     }
 }
 
-impl<'a, P: MerkleConfig> ToConstraintFieldGadget<P::BasePrimeField>
+/// The nodes of every tree as laid out by `MerkleTreeVar`, tree by tree from
+/// the bottom tree to the top tree, whose root is the root of the forest.
+impl<'a, P: MerkleConfigGadget> ToConstraintFieldGadget<P::BasePrimeField>
     for LeveledMerkleForestVar<'a, P>
 {
     fn to_constraint_field(&self) -> Result<Vec<FpVar<P::BasePrimeField>>, SynthesisError> {
@@ -274,68 +939,448 @@ impl<'a, P: MerkleConfig> ToConstraintFieldGadget<P::BasePrimeField>
     }
 }
 
-fn div_rem_power_of_2<F: PrimeField>(
-    v: FpVar<F>,
-    p2: usize,
-) -> Result<(FpVar<F>, FpVar<F>), SynthesisError> {
-    let cs = v.cs();
-    let div = FpVar::new_witness(cs, || {
-        v.value().map(|v| {
-            let mut v = v.into_bigint();
-            for _ in 0..p2.ilog2() {
-                v.div2();
-            }
-            F::from(v)
-        })
-    })?;
-    let rem = v - &div * FpVar::Constant(F::from(p2 as u64));
-    rem.enforce_cmp(
-        &FpVar::Constant(F::from(p2 as u64)),
-        std::cmp::Ordering::Less,
-        false,
-    )?;
-    Ok((div, rem))
+/// Same as `LeveledMerkleForestVar`, but only the root of each tree is part of
+/// the state, and `update` is given the path of the index in every tree, as
+/// returned by `LeveledMerkleForest::prove_update`.
+///
+/// The number of constraints of an update thus grows with the height of the
+/// trees rather than their capacity.
+pub struct LeveledMerkleForestPathVar<'a, P: MerkleConfigGadget> {
+    roots: Vec<FpVar<P::BasePrimeField>>,
+    num_leaves_per_tree: usize,
+    hash_params: &'a P::HashParamsVar,
 }
 
-impl<'a, P: MerkleConfig> R1CSVar<P::BasePrimeField> for MerkleTreeVar<'a, P> {
-    type Value = Vec<<FpVar<P::BasePrimeField> as R1CSVar<P::BasePrimeField>>::Value>;
+impl<'a, P: MerkleConfigGadget> LeveledMerkleForestPathVar<'a, P> {
+    pub fn new(
+        capacity_per_tree: u32,
+        num_tree: u32,
+        params: &'a P::HashParamsVar,
+    ) -> Result<Self, MerkleForestError> {
+        if num_tree == 0 {
+            return Err(MerkleForestError::InvalidNumTree);
+        }
 
-    fn cs(&self) -> ark_relations::r1cs::ConstraintSystemRef<P::BasePrimeField> {
-        self.nodes.cs()
+        // the root of an empty tree is a constant
+        let empty = MerkleTreeVar::<P>::new(capacity_per_tree as usize, params)?;
+        Ok(Self {
+            roots: vec![empty.root(); num_tree as usize],
+            num_leaves_per_tree: empty.num_leaves(),
+            hash_params: params,
+        })
     }
 
-    fn value(&self) -> Result<Self::Value, SynthesisError> {
-        self.nodes.value()
+    pub fn new_optimal(n: usize, params: &'a P::HashParamsVar) -> Result<Self, MerkleForestError> {
+        let (capacity_per_tree, num_tree) = optimal_forest_params_for_arity(n, arity::<P>());
+        LeveledMerkleForestPathVar::new(capacity_per_tree, num_tree, params)
     }
-}
 
-impl<'a, P: MerkleConfig> R1CSVar<P::BasePrimeField> for LeveledMerkleForestVar<'a, P> {
-    type Value = Vec<Vec<<FpVar<P::BasePrimeField> as R1CSVar<P::BasePrimeField>>::Value>>;
+    /// Update the Merkle forest with the `new_leaf` at `index`, where `paths`
+    /// holds the path of `index` in each tree, from the leaves up to the root.
+    ///
+    /// An `index` that is not smaller than `max_leaves` makes the circuit
+    /// unsatisfiable.
+    #[tracing::instrument(skip_all)]
+    pub fn update(
+        &mut self,
+        index: FpVar<P::BasePrimeField>,
+        new_leaf: &[FpVar<P::BasePrimeField>],
+        paths: &[MerklePathUpdateVar<P>],
+    ) -> Result<FpVar<P::BasePrimeField>, SynthesisError> {
+        if paths.len() != self.roots.len() {
+            return Err(SynthesisError::Unsatisfiable);
+        }
 
-    fn cs(&self) -> ark_relations::r1cs::ConstraintSystemRef<P::BasePrimeField> {
-        self.trees.cs()
-    }
+        tracing::info!("start updating the LMF");
 
-    fn value(&self) -> Result<Self::Value, SynthesisError> {
-        self.trees.value()
-    }
-}
+        let cs = self.cs();
+
+        tracing::info!(num_constraints = cs.num_constraints());
+
+        let index_bits = split_index(&index, self.num_leaves_per_tree, self.roots.len())?;
+        let mut new_root = P::hash_leaf(self.hash_params, new_leaf)?;
+
+        for ((root, path), bits) in self.roots.iter_mut().zip(paths).zip(&index_bits) {
+            new_root = path.update_with_hash(self.hash_params, bits, root, new_root)?;
+            *root = new_root.clone();
+        }
+
+        tracing::info!(num_constraints = cs.num_constraints());
+
+        Ok(new_root)
+    }
+
+    pub fn from_constraint_field(
+        iter: impl Iterator<Item = FpVar<P::BasePrimeField>>,
+        capacity_per_tree: u32,
+        num_tree: u32,
+        params: &'a P::HashParamsVar,
+    ) -> Result<Self, SynthesisError> {
+        let capacity_per_tree = capacity_per_tree as usize;
+        if num_tree == 0 || !is_valid_capacity(capacity_per_tree, arity::<P>()) {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        let roots: Vec<_> = iter.take(num_tree as usize).collect();
+        if roots.len() != num_tree as usize {
+            tracing::error!(
+                expected = num_tree,
+                got = roots.len(),
+                "not enough field elements to rebuild the Merkle forest"
+            );
+            return Err(SynthesisError::AssignmentMissing);
+        }
+        Ok(Self {
+            roots,
+            num_leaves_per_tree: num_leaves(capacity_per_tree, arity::<P>()),
+            hash_params: params,
+        })
+    }
+
+    pub const fn num_constraint_var_needed(_capacity_per_tree: u32, num_tree: u32) -> usize {
+        num_tree as usize
+    }
+
+    pub fn root(&self) -> FpVar<P::BasePrimeField> {
+        self.roots.last().expect("there is at least 1 tree").clone()
+    }
+
+    #[inline]
+    pub fn max_leaves(&self) -> usize {
+        // safe conversion as roots.len() is limited to be <= 2^32 - 1
+        #[allow(clippy::cast_possible_truncation)]
+        self.num_leaves_per_tree.pow(self.roots.len() as u32)
+    }
+}
+
+impl<'a, P: MerkleConfigGadget> ToConstraintFieldGadget<P::BasePrimeField>
+    for LeveledMerkleForestPathVar<'a, P>
+{
+    fn to_constraint_field(&self) -> Result<Vec<FpVar<P::BasePrimeField>>, SynthesisError> {
+        Ok(self.roots.clone())
+    }
+}
+
+/// Hash `hash` up a path of `siblings`, with `ARITY - 1` siblings per level,
+/// where every `log2(ARITY)` bits of `index_bits`, from the least significant
+/// ones, are the position of the node at that level among its siblings.
+fn hash_path<P: MerkleConfigGadget>(
+    params: &P::HashParamsVar,
+    mut hash: FpVar<P::BasePrimeField>,
+    siblings: &[FpVar<P::BasePrimeField>],
+    index_bits: &[Boolean<P::BasePrimeField>],
+) -> Result<FpVar<P::BasePrimeField>, SynthesisError> {
+    let arity = arity::<P>();
+    for (siblings, position) in siblings
+        .chunks(arity - 1)
+        .zip(index_bits.chunks(arity.ilog2() as usize))
+    {
+        let children = insert_at(&hash, siblings, position)?;
+        hash = P::hash_node(params, &children)?;
+    }
+    Ok(hash)
+}
+
+/// Remove the child at the little-endian `position` from `children`, i.e.
+/// the inverse of `insert_at`.
+fn remove_at<F: PrimeField>(
+    children: &[FpVar<F>],
+    position: &[Boolean<F>],
+) -> Result<Vec<FpVar<F>>, SynthesisError> {
+    let mut siblings = Vec::with_capacity(children.len() - 1);
+    // whether the removed child is at or before the current one
+    let mut is_removed = Boolean::FALSE;
+    for j in 0..children.len() - 1 {
+        is_removed = &is_removed | &is_at(position, j)?;
+        siblings.push(is_removed.select(&children[j + 1], &children[j])?);
+    }
+    Ok(siblings)
+}
+
+/// Whether the little-endian `position` is `j`.
+fn is_at<F: PrimeField>(position: &[Boolean<F>], j: usize) -> Result<Boolean<F>, SynthesisError> {
+    let literals: Vec<_> = position
+        .iter()
+        .enumerate()
+        .map(|(i, bit)| if (j >> i) & 1 == 1 { bit.clone() } else { !bit })
+        .collect();
+    Boolean::kary_and(&literals)
+}
+
+/// Insert `node` into `siblings` at the little-endian `position`, i.e. the
+/// `j`-th child is `node` if `position == j`, and otherwise `siblings[j]` or
+/// `siblings[j - 1]` depending on whether `node` comes after or before it.
+fn insert_at<F: PrimeField>(
+    node: &FpVar<F>,
+    siblings: &[FpVar<F>],
+    position: &[Boolean<F>],
+) -> Result<Vec<FpVar<F>>, SynthesisError> {
+    let arity = siblings.len() + 1;
+
+    let mut children = Vec::with_capacity(arity);
+    // whether `node` is before the current child
+    let mut is_before = Boolean::FALSE;
+    for j in 0..arity {
+        let is_at = is_at(position, j)?;
+
+        let sibling = if j == 0 {
+            siblings[0].clone()
+        } else if j == arity - 1 {
+            siblings[j - 1].clone()
+        } else {
+            is_before.select(&siblings[j - 1], &siblings[j])?
+        };
+        children.push(is_at.select(node, &sibling)?);
+
+        if j + 1 < arity {
+            is_before = &is_before | &is_at;
+        }
+    }
+    Ok(children)
+}
+
+/// Poseidon counterpart of `LeafHash` for `Config`, `DomainSeparatedConfig`
+/// and `WideConfig`: hash the values of a leaf, prefixed with `LEAF_DOMAIN` if
+/// `P` separates leaves from internal nodes.
+pub fn poseidon_hash_leaf<P: MerkleConfig>(
+    params: &PoseidonParams<P::BasePrimeField>,
+    leaf: &[FpVar<P::BasePrimeField>],
+) -> Result<FpVar<P::BasePrimeField>, SynthesisError> {
+    if P::DOMAIN_SEPARATION {
+        let input: Vec<_> = std::iter::once(FpVar::constant(LEAF_DOMAIN.into()))
+            .chain(leaf.iter().cloned())
+            .collect();
+        Poseidon::evaluate(params, &input)
+    } else {
+        Poseidon::evaluate(params, leaf)
+    }
+}
+
+/// Poseidon counterpart of `MerkleConfig::compress` for the same configs as
+/// `poseidon_hash_leaf`: hash the children of a node into the node, prefixed
+/// with `NODE_DOMAIN` if `P` separates leaves from internal nodes. Otherwise,
+/// two children are hashed with the two-to-one hash, and more with
+/// `poseidon::CRH` as in `WideConfig`.
+pub fn poseidon_hash_node<P: MerkleConfig>(
+    params: &PoseidonParams<P::BasePrimeField>,
+    children: &[FpVar<P::BasePrimeField>],
+) -> Result<FpVar<P::BasePrimeField>, SynthesisError> {
+    if P::DOMAIN_SEPARATION {
+        let input: Vec<_> = std::iter::once(FpVar::constant(NODE_DOMAIN.into()))
+            .chain(children.iter().cloned())
+            .collect();
+        Poseidon::evaluate(params, &input)
+    } else if let [left, right] = children {
+        PoseidonTwoToOne::evaluate(params, left, right)
+    } else {
+        Poseidon::evaluate(params, children)
+    }
+}
+
+/// Convert a field element into a `usize`, returning `None` if it does not
+/// fit.
+fn constant_to_usize<F: PrimeField>(v: &F) -> Option<usize> {
+    let v = v.into_bigint();
+    let (low, high) = v.as_ref().split_first()?;
+    if high.iter().any(|limb| *limb != 0) {
+        return None;
+    }
+    usize::try_from(*low).ok()
+}
+
+/// Split the little-endian bits of `index` into the index within each of the
+/// `num_tree` trees of a forest, from the leaves up to the root.
+///
+/// The bits are the unique decomposition of `index`, so the prover cannot
+/// pick another split of the same value, and `index` is enforced to be
+/// addressable by the forest, i.e. `index < num_leaves_per_tree^num_tree`.
+/// `num_leaves_per_tree` must be a power of two, otherwise the index within a
+/// tree is not a group of bits, see `div_rem`.
+fn split_index<F: PrimeField>(
+    index: &FpVar<F>,
+    num_leaves_per_tree: usize,
+    num_tree: usize,
+) -> Result<Vec<Vec<Boolean<F>>>, SynthesisError> {
+    if !num_leaves_per_tree.is_power_of_two() {
+        tracing::error!(
+            num_leaves_per_tree,
+            "the number of leaves per tree is not a power of two"
+        );
+        return Err(SynthesisError::Unsatisfiable);
+    }
+
+    let bits_per_tree = num_leaves_per_tree.ilog2() as usize;
+    let num_bits = bits_per_tree * num_tree;
+
+    let bits = index.to_bits_le()?;
+    if let Some(high_bits) = bits.get(num_bits..).filter(|bits| !bits.is_empty()) {
+        Boolean::kary_or(high_bits)?.enforce_equal(&Boolean::FALSE)?;
+    }
+
+    let bits: Vec<_> = bits
+        .into_iter()
+        .chain(std::iter::repeat(Boolean::FALSE))
+        .take(num_bits)
+        .collect();
+    Ok(bits.chunks(bits_per_tree).map(<[_]>::to_vec).collect())
+}
+
+/// Divide `dividend` by the constant `divisor`, returning the quotient and the
+/// remainder, where `dividend` is enforced to be less than `2^num_bits`.
+///
+/// A power-of-two `divisor` splits the bits of `dividend`. Any other divisor
+/// allocates the quotient and the remainder, and enforces
+/// `dividend == quotient * divisor + remainder` with `quotient < 2^num_bits`
+/// and `remainder < divisor`. As this cannot wrap around the field, the
+/// prover cannot pick another quotient and remainder.
+///
+/// Forests only split indices by power-of-two leaf counts, which
+/// `split_index` does on the bits directly, so the general gadget is only
+/// compiled for the tests.
+#[cfg(test)]
+fn div_rem<F: PrimeField>(
+    dividend: &FpVar<F>,
+    divisor: usize,
+    num_bits: usize,
+) -> Result<(FpVar<F>, FpVar<F>), SynthesisError> {
+    let divisor_bits = (usize::BITS - divisor.leading_zeros()) as usize;
+    if divisor == 0
+        || num_bits > usize::BITS as usize
+        || num_bits + divisor_bits >= F::MODULUS_BIT_SIZE as usize
+    {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+
+    let (bits, _) = dividend.to_bits_le_with_top_bits_zero(num_bits)?;
+    if divisor.is_power_of_two() {
+        let (remainder, quotient) = bits.split_at((divisor_bits - 1).min(num_bits));
+        return Ok((
+            Boolean::le_bits_to_fp(quotient)?,
+            Boolean::le_bits_to_fp(remainder)?,
+        ));
+    }
+
+    let mode = if dividend.is_constant() {
+        AllocationMode::Constant
+    } else {
+        AllocationMode::Witness
+    };
+    let div_rem_value = || -> Result<(usize, usize), SynthesisError> {
+        let dividend =
+            constant_to_usize(&dividend.value()?).ok_or(SynthesisError::Unsatisfiable)?;
+        Ok((dividend / divisor, dividend % divisor))
+    };
+    let quotient = FpVar::new_variable(
+        dividend.cs(),
+        || div_rem_value().map(|(q, _)| F::from(q as u64)),
+        mode,
+    )?;
+    let remainder = FpVar::new_variable(
+        dividend.cs(),
+        || div_rem_value().map(|(_, r)| F::from(r as u64)),
+        mode,
+    )?;
+
+    quotient.to_bits_le_with_top_bits_zero(num_bits)?;
+    remainder.to_bits_le_with_top_bits_zero(divisor_bits)?;
+    (FpVar::constant(F::from(divisor as u64 - 1)) - &remainder)
+        .to_bits_le_with_top_bits_zero(divisor_bits)?;
+    (&quotient * F::from(divisor as u64) + &remainder).enforce_equal(dividend)?;
+
+    Ok((quotient, remainder))
+}
+
+impl<'a, P: MerkleConfigGadget> R1CSVar<P::BasePrimeField> for MerkleTreeVar<'a, P> {
+    type Value = Vec<<FpVar<P::BasePrimeField> as R1CSVar<P::BasePrimeField>>::Value>;
+
+    fn cs(&self) -> ark_relations::r1cs::ConstraintSystemRef<P::BasePrimeField> {
+        self.nodes.cs()
+    }
+
+    fn value(&self) -> Result<Self::Value, SynthesisError> {
+        self.nodes.value()
+    }
+}
+
+impl<'a, P: MerkleConfigGadget> R1CSVar<P::BasePrimeField> for LeveledMerkleForestVar<'a, P> {
+    type Value = Vec<Vec<<FpVar<P::BasePrimeField> as R1CSVar<P::BasePrimeField>>::Value>>;
+
+    fn cs(&self) -> ark_relations::r1cs::ConstraintSystemRef<P::BasePrimeField> {
+        self.trees.cs()
+    }
+
+    fn value(&self) -> Result<Self::Value, SynthesisError> {
+        self.trees.value()
+    }
+}
+
+impl<'a, P: MerkleConfigGadget> R1CSVar<P::BasePrimeField> for LeveledMerkleForestPathVar<'a, P> {
+    type Value = Vec<<FpVar<P::BasePrimeField> as R1CSVar<P::BasePrimeField>>::Value>;
+
+    fn cs(&self) -> ark_relations::r1cs::ConstraintSystemRef<P::BasePrimeField> {
+        self.roots.cs()
+    }
+
+    fn value(&self) -> Result<Self::Value, SynthesisError> {
+        self.roots.value()
+    }
+}
+
+impl<P: MerkleConfigGadget> R1CSVar<P::BasePrimeField> for MerkleForestProofVar<P> {
+    type Value = MerkleForestProof<P>;
+
+    fn cs(&self) -> ark_relations::r1cs::ConstraintSystemRef<P::BasePrimeField> {
+        self.siblings.cs().or(self.leaf_index.cs())
+    }
+
+    fn value(&self) -> Result<Self::Value, SynthesisError> {
+        let leaf_index =
+            constant_to_usize(&self.leaf_index.value()?).ok_or(SynthesisError::Unsatisfiable)?;
+        Ok(MerkleForestProof {
+            siblings: self.siblings.value()?,
+            leaf_index,
+            num_leaves_per_tree: self.num_leaves_per_tree,
+        })
+    }
+}
 
 #[cfg(test)]
 mod test {
-    use crate::merkle::{forest::LeveledMerkleForest, tree::MerkleTree};
+    use crate::merkle::{
+        forest::{LeveledMerkleForest, MerkleForestProof},
+        tree::MerkleTree,
+    };
 
     use super::*;
+    use crate::params::poseidon_canonical_config;
     use ark_bls12_381::Fr;
     use ark_ff::UniformRand;
-    use ark_r1cs_std::{alloc::AllocVar, R1CSVar};
-    use ark_relations::r1cs::ConstraintSystem;
-    use folding_schemes::transcript::poseidon::poseidon_canonical_config;
-    use rand::{rngs::StdRng, thread_rng, SeedableRng};
+    use ark_r1cs_std::{alloc::AllocVar, convert::ToBytesGadget, R1CSVar};
+    use ark_relations::r1cs::{ConstraintSystem, ConstraintSystemRef};
+    use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
 
     struct TestConfig;
     impl MerkleConfig for TestConfig {
         type BasePrimeField = Fr;
+        type LeafHash = ark_crypto_primitives::crh::poseidon::CRH<Fr>;
+        type TwoToOneHash = ark_crypto_primitives::crh::poseidon::TwoToOneCRH<Fr>;
+    }
+    impl MerkleConfigGadget for TestConfig {
+        type HashParamsVar = PoseidonParams<Fr>;
+
+        fn hash_leaf(
+            params: &PoseidonParams<Fr>,
+            leaf: &[FpVar<Fr>],
+        ) -> Result<FpVar<Fr>, SynthesisError> {
+            poseidon_hash_leaf::<Self>(params, leaf)
+        }
+
+        fn hash_node(
+            params: &PoseidonParams<Fr>,
+            children: &[FpVar<Fr>],
+        ) -> Result<FpVar<Fr>, SynthesisError> {
+            poseidon_hash_node::<Self>(params, children)
+        }
     }
 
     fn poseidon_params() -> PoseidonParams<Fr> {
@@ -378,6 +1423,970 @@ mod test {
         assert_eq!(root_var.value().unwrap(), root);
     }
 
+    #[test]
+    fn test_r1cs_merkle_tree_constant_index() {
+        let mut rng = thread_rng();
+        let params = poseidon_params();
+
+        for capacity in [3, 7, 15, 31] {
+            let leaf = Fr::rand(&mut rng);
+            let index = rng.gen_range(0..(capacity + 1) / 2);
+
+            let mut tree = MerkleTree::<TestConfig>::new(capacity, &params.parameters).unwrap();
+            tree.update_with_hash(index, leaf).unwrap();
+
+            // constant index
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let leaf_var = FpVar::new_witness(cs.clone(), || Ok(leaf)).unwrap();
+            let mut gadget_tree = MerkleTreeVar::<TestConfig>::new(capacity, &params).unwrap();
+            let root_var = gadget_tree
+                .update_with_hash(FpVar::Constant(Fr::from(index as u64)), leaf_var)
+                .unwrap();
+            assert!(cs.is_satisfied().unwrap());
+            assert_eq!(root_var.value().unwrap(), tree.root());
+            let constant_constraints = cs.num_constraints();
+
+            // witness index
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let leaf_var = FpVar::new_witness(cs.clone(), || Ok(leaf)).unwrap();
+            let index_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(index as u64))).unwrap();
+            let mut gadget_tree = MerkleTreeVar::<TestConfig>::new(capacity, &params).unwrap();
+            let root_var = gadget_tree.update_with_hash(index_var, leaf_var).unwrap();
+            assert!(cs.is_satisfied().unwrap());
+            assert_eq!(root_var.value().unwrap(), tree.root());
+            let witness_constraints = cs.num_constraints();
+
+            // only the hashes on the path from the leaf to the root depend on
+            // the witness, so at most `height` hashes are paid for
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let l = FpVar::new_witness(cs.clone(), || Ok(Fr::rand(&mut rng))).unwrap();
+            let r = FpVar::new_witness(cs.clone(), || Ok(Fr::rand(&mut rng))).unwrap();
+            PoseidonTwoToOne::evaluate(&params, &l, &r).unwrap();
+            let height = (capacity + 1).ilog2() as usize - 1;
+
+            assert!(constant_constraints <= height * cs.num_constraints());
+            assert!(constant_constraints < witness_constraints);
+        }
+    }
+
+    #[test]
+    fn test_r1cs_merkle_path() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        let values: Vec<_> = (0..8).map(|_| [Fr::rand(&mut rng)]).collect();
+        let values_ref: Vec<_> = values.iter().map(|v| &v[..]).collect();
+        let tree = MerkleTree::<TestConfig>::new_with_data(
+            either::Right(&values_ref[..]),
+            &params.parameters,
+        )
+        .unwrap();
+
+        for index in [0, 3, 7] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let root = FpVar::new_input(cs.clone(), || Ok(tree.root())).unwrap();
+            let leaf = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || Ok(values[index])).unwrap();
+            let path = MerklePathVar::<TestConfig>::new_witness(cs.clone(), || {
+                Ok(tree.prove(index).unwrap())
+            })
+            .unwrap();
+            assert_eq!(path.height(), 3);
+
+            path.verify(&params, &root, &leaf)
+                .unwrap()
+                .enforce_equal(&Boolean::TRUE)
+                .unwrap();
+            assert!(cs.is_satisfied().unwrap());
+
+            // the leaf is not at any other position
+            let other = MerklePathVar::<TestConfig>::new_witness(cs.clone(), || {
+                Ok(tree.prove((index + 1) % 8).unwrap())
+            })
+            .unwrap();
+            assert!(!other
+                .verify(&params, &root, &leaf)
+                .unwrap()
+                .value()
+                .unwrap());
+        }
+    }
+
+    #[test]
+    fn test_r1cs_merkle_verify_path() {
+        type Wide = crate::merkle::WideConfig<Fr, 4>;
+
+        fn check<P>(num_leaves: usize)
+        where
+            P: MerkleConfigGadget<
+                BasePrimeField = Fr,
+                LeafHash = ark_crypto_primitives::crh::poseidon::CRH<Fr>,
+                TwoToOneHash = ark_crypto_primitives::crh::poseidon::TwoToOneCRH<Fr>,
+                HashParamsVar = PoseidonParams<Fr>,
+            >,
+        {
+            let mut rng = StdRng::from_seed([42; 32]);
+            let params = poseidon_params();
+
+            let values: Vec<_> = (0..num_leaves).map(|_| [Fr::rand(&mut rng)]).collect();
+            let values_ref: Vec<_> = values.iter().map(|v| &v[..]).collect();
+            let tree =
+                MerkleTree::<P>::new_with_data(either::Right(&values_ref[..]), &params.parameters)
+                    .unwrap();
+
+            for (index, value) in [(0, 0), (3, 3), (num_leaves - 1, num_leaves - 1), (1, 2)] {
+                let proof = tree.prove(index).unwrap();
+                let expected = MerkleTree::<P>::verify(
+                    &params.parameters,
+                    tree.root(),
+                    either::Right(&values[value][..]),
+                    &proof,
+                )
+                .unwrap();
+
+                let cs = ConstraintSystem::<Fr>::new_ref();
+                let root = FpVar::new_input(cs.clone(), || Ok(tree.root())).unwrap();
+                let leaf = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || Ok(values[value])).unwrap();
+                let index_var =
+                    FpVar::new_witness(cs.clone(), || Ok(Fr::from(index as u64))).unwrap();
+                let siblings =
+                    Vec::<FpVar<Fr>>::new_witness(cs.clone(), || Ok(&proof.siblings[..])).unwrap();
+
+                let valid =
+                    MerkleTreeVar::<P>::verify_path(&params, &leaf, &index_var, &siblings, &root)
+                        .unwrap();
+                assert_eq!(valid.value().unwrap(), expected);
+                assert_eq!(expected, index == value);
+                assert!(cs.is_satisfied().unwrap());
+            }
+
+            // an index beyond the tree is not a valid position
+            let proof = tree.prove(0).unwrap();
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let root = FpVar::new_input(cs.clone(), || Ok(tree.root())).unwrap();
+            let leaf = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || Ok(values[0])).unwrap();
+            let index = FpVar::new_witness(cs.clone(), || Ok(Fr::from(num_leaves as u64))).unwrap();
+            let siblings =
+                Vec::<FpVar<Fr>>::new_witness(cs.clone(), || Ok(&proof.siblings[..])).unwrap();
+            MerkleTreeVar::<P>::verify_path(&params, &leaf, &index, &siblings, &root).unwrap();
+            assert!(!cs.is_satisfied().unwrap());
+        }
+
+        check::<TestConfig>(8);
+        check::<Wide>(16);
+    }
+
+    #[test]
+    fn test_r1cs_merkle_domain_separation() {
+        type Separated = crate::merkle::DomainSeparatedConfig<Fr>;
+
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        let values: Vec<_> = (0..4).map(|_| [Fr::rand(&mut rng)]).collect();
+        let values_ref: Vec<_> = values.iter().map(|v| &v[..]).collect();
+        let tree = MerkleTree::<Separated>::new_with_data(
+            either::Right(&values_ref[..]),
+            &params.parameters,
+        )
+        .unwrap();
+        let plain = MerkleTree::<TestConfig>::new_with_data(
+            either::Right(&values_ref[..]),
+            &params.parameters,
+        )
+        .unwrap();
+        assert_ne!(tree.root(), plain.root());
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let mut gadget_tree = MerkleTreeVar::<Separated>::new(7, &params).unwrap();
+        let mut root_var = gadget_tree.root();
+        for (i, v) in values.iter().enumerate() {
+            let index = FpVar::new_witness(cs.clone(), || Ok(Fr::from(i as u64))).unwrap();
+            let leaf = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || Ok(v)).unwrap();
+            root_var = gadget_tree.update(index, &leaf).unwrap();
+        }
+        assert_eq!(root_var.value().unwrap(), tree.root());
+
+        let root = FpVar::new_input(cs.clone(), || Ok(tree.root())).unwrap();
+        let leaf = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || Ok(values[2])).unwrap();
+        let path =
+            MerklePathVar::<Separated>::new_witness(cs.clone(), || Ok(tree.prove(2).unwrap()))
+                .unwrap();
+        path.verify(&params, &root, &leaf)
+            .unwrap()
+            .enforce_equal(&Boolean::TRUE)
+            .unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_r1cs_merkle_arity() {
+        type Wide = crate::merkle::WideConfig<Fr, 4>;
+
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        for num_leaves in [16, 64] {
+            let values: Vec<_> = (0..num_leaves).map(|_| [Fr::rand(&mut rng)]).collect();
+            let values_ref: Vec<_> = values.iter().map(|v| &v[..]).collect();
+            let tree = MerkleTree::<Wide>::new_with_data(
+                either::Right(&values_ref[..]),
+                &params.parameters,
+            )
+            .unwrap();
+
+            for index in [0, 6, num_leaves - 1] {
+                let cs = ConstraintSystem::<Fr>::new_ref();
+                let root = FpVar::new_input(cs.clone(), || Ok(tree.root())).unwrap();
+                let leaf = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || Ok(values[index])).unwrap();
+                let path = MerklePathVar::<Wide>::new_witness(cs.clone(), || {
+                    Ok(tree.prove(index).unwrap())
+                })
+                .unwrap();
+                assert_eq!(path.height(), num_leaves.ilog2() as usize / 2);
+
+                path.verify(&params, &root, &leaf)
+                    .unwrap()
+                    .enforce_equal(&Boolean::TRUE)
+                    .unwrap();
+                assert!(cs.is_satisfied().unwrap());
+
+                let other = MerklePathVar::<Wide>::new_witness(cs.clone(), || {
+                    Ok(tree.prove((index + 1) % num_leaves).unwrap())
+                })
+                .unwrap();
+                assert!(!other
+                    .verify(&params, &root, &leaf)
+                    .unwrap()
+                    .value()
+                    .unwrap());
+            }
+        }
+
+        let mut tree = MerkleTree::<Wide>::new(21, &params.parameters).unwrap();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let mut gadget_tree = MerkleTreeVar::<Wide>::new(21, &params).unwrap();
+        assert_eq!(gadget_tree.root().value().unwrap(), tree.root());
+        for i in [3, 9, 15] {
+            let leaf = [Fr::rand(&mut rng)];
+            tree.update(i, &leaf).unwrap();
+
+            let index = FpVar::new_witness(cs.clone(), || Ok(Fr::from(i as u64))).unwrap();
+            let leaf = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || Ok(leaf)).unwrap();
+            let root = gadget_tree.update(index, &leaf).unwrap();
+            assert_eq!(root.value().unwrap(), tree.root());
+        }
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_r1cs_merkle_path_corrupted_sibling() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        let values: Vec<_> = (0..8).map(|_| Fr::rand(&mut rng)).collect();
+        let tree =
+            MerkleTree::<TestConfig>::new_with_data(either::Left(&values[..]), &params.parameters)
+                .unwrap();
+
+        let mut proof = tree.prove(5).unwrap();
+        proof.siblings[1] += Fr::from(1);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let root = FpVar::new_input(cs.clone(), || Ok(tree.root())).unwrap();
+        let hash = FpVar::new_witness(cs.clone(), || Ok(values[5])).unwrap();
+        let path = MerklePathVar::<TestConfig>::new_witness(cs.clone(), || Ok(proof)).unwrap();
+
+        path.verify_with_hash(&params, &root, hash)
+            .unwrap()
+            .enforce_equal(&Boolean::TRUE)
+            .unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    fn forest_proof_var(
+        cs: ConstraintSystemRef<Fr>,
+        proof: &MerkleForestProof<TestConfig>,
+    ) -> MerkleForestProofVar<TestConfig> {
+        MerkleForestProofVar::new(
+            Vec::new_witness(cs.clone(), || Ok(&proof.siblings[..])).unwrap(),
+            FpVar::new_witness(cs, || Ok(Fr::from(proof.leaf_index as u64))).unwrap(),
+            proof.num_leaves_per_tree,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_r1cs_merkle_forest_proof() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        for n in [2, 5, 16, 27] {
+            let values: Vec<_> = (0..n).map(|_| [Fr::rand(&mut rng)]).collect();
+            let values_ref: Vec<_> = values.iter().map(|v| &v[..]).collect();
+            let forest = LeveledMerkleForest::<TestConfig>::new_with_data(
+                either::Right(&values_ref),
+                &params.parameters,
+            )
+            .unwrap();
+
+            for index in [0, n / 2, n - 1] {
+                let proof = forest.prove(index).unwrap();
+
+                let cs = ConstraintSystem::<Fr>::new_ref();
+                let root = FpVar::new_input(cs.clone(), || Ok(forest.root())).unwrap();
+                let leaf = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || Ok(values[index])).unwrap();
+                let proof_var = forest_proof_var(cs.clone(), &proof);
+
+                proof_var
+                    .verify(&params, &root, &leaf)
+                    .unwrap()
+                    .enforce_equal(&Boolean::TRUE)
+                    .unwrap();
+                assert!(cs.is_satisfied().unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_r1cs_merkle_forest_proof_alloc() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        let values: Vec<_> = (0..11).map(|_| [Fr::rand(&mut rng)]).collect();
+        let values_ref: Vec<_> = values.iter().map(|v| &v[..]).collect();
+        let forest = LeveledMerkleForest::<TestConfig>::new_with_data(
+            either::Right(&values_ref),
+            &params.parameters,
+        )
+        .unwrap();
+
+        for mode in [
+            AllocationMode::Constant,
+            AllocationMode::Witness,
+            AllocationMode::Input,
+        ] {
+            let index = rng.gen_range(0..values.len());
+            let proof = forest.prove(index).unwrap();
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let root = FpVar::new_input(cs.clone(), || Ok(forest.root())).unwrap();
+            let leaf = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || Ok(values[index])).unwrap();
+            let proof_var =
+                MerkleForestProofVar::new_variable(cs.clone(), || Ok(&proof), mode).unwrap();
+
+            let value = proof_var.value().unwrap();
+            assert_eq!(value.siblings, proof.siblings);
+            assert_eq!(value.leaf_index, proof.leaf_index);
+            assert_eq!(value.num_leaves_per_tree, proof.num_leaves_per_tree);
+
+            proof_var
+                .verify(&params, &root, &leaf)
+                .unwrap()
+                .enforce_equal(&Boolean::TRUE)
+                .unwrap();
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_r1cs_merkle_forest_proof_wrong_root() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        let values: Vec<_> = (0..10).map(|_| Fr::rand(&mut rng)).collect();
+        let forest = LeveledMerkleForest::<TestConfig>::new_with_data(
+            either::Left(&values),
+            &params.parameters,
+        )
+        .unwrap();
+        let proof = forest.prove(3).unwrap();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let root = FpVar::new_input(cs.clone(), || Ok(forest.root() + Fr::from(1))).unwrap();
+        let hash = FpVar::new_witness(cs.clone(), || Ok(values[3])).unwrap();
+        let proof_var = forest_proof_var(cs.clone(), &proof);
+
+        proof_var
+            .verify_with_hash(&params, &root, hash)
+            .unwrap()
+            .enforce_equal(&Boolean::TRUE)
+            .unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+
+        // an index that the trees in the proof cannot address is rejected too
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let root = FpVar::new_input(cs.clone(), || Ok(forest.root())).unwrap();
+        let hash = FpVar::new_witness(cs.clone(), || Ok(values[3])).unwrap();
+        let num_tree = proof.siblings.len() / proof.num_leaves_per_tree.ilog2() as usize;
+        let max_leaves = proof
+            .num_leaves_per_tree
+            .pow(u32::try_from(num_tree).unwrap());
+        let out_of_bound = MerkleForestProof::<TestConfig> {
+            siblings: proof.siblings.clone(),
+            leaf_index: proof.leaf_index + max_leaves,
+            num_leaves_per_tree: proof.num_leaves_per_tree,
+        };
+        let proof_var = forest_proof_var(cs.clone(), &out_of_bound);
+
+        assert!(!proof_var
+            .verify_with_hash(&params, &root, hash)
+            .unwrap()
+            .value()
+            .unwrap());
+    }
+
+    #[test]
+    fn test_r1cs_merkle_forest_update_with_proof() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        for n in [1, 2, 5, 16, 27] {
+            let values: Vec<_> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+            let values_ref: Vec<_> = values.iter().map(std::slice::from_ref).collect();
+            let forest = LeveledMerkleForest::<TestConfig>::new_with_data(
+                either::Right(&values_ref),
+                &params.parameters,
+            )
+            .unwrap();
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let mut forest_var =
+                LeveledMerkleForestVar::<TestConfig>::new_optimal(n, &params).unwrap();
+            for (i, val) in values.iter().enumerate() {
+                let index = FpVar::new_witness(cs.clone(), || Ok(Fr::from(i as u64))).unwrap();
+                let leaf = [FpVar::new_witness(cs.clone(), || Ok(val)).unwrap()];
+                let (root, proof) = forest_var.update_with_proof(index, &leaf).unwrap();
+
+                proof
+                    .verify(&params, &root, &leaf)
+                    .unwrap()
+                    .enforce_equal(&Boolean::TRUE)
+                    .unwrap();
+
+                let native_proof = MerkleForestProof::<TestConfig> {
+                    siblings: proof.siblings.value().unwrap(),
+                    leaf_index: i,
+                    num_leaves_per_tree: proof.num_leaves_per_tree,
+                };
+                assert!(LeveledMerkleForest::<TestConfig>::verify(
+                    &params.parameters,
+                    root.value().unwrap(),
+                    either::Right(&[*val]),
+                    native_proof,
+                )
+                .unwrap());
+            }
+
+            // once every leaf is inserted, the siblings match the native proof
+            assert_eq!(forest_var.root().value().unwrap(), forest.root());
+            let index = FpVar::new_witness(cs.clone(), || Ok(Fr::from(n as u64 - 1))).unwrap();
+            let leaf = [FpVar::new_witness(cs.clone(), || Ok(values[n - 1])).unwrap()];
+            let (_, proof) = forest_var.update_with_proof(index, &leaf).unwrap();
+            assert_eq!(
+                proof.siblings.value().unwrap(),
+                forest.prove(n - 1).unwrap().siblings
+            );
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_r1cs_merkle_forest_path_update() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        for n in [1, 2, 5, 16, 27] {
+            let values: Vec<_> = (0..n).map(|_| [Fr::rand(&mut rng)]).collect();
+            let mut forest =
+                LeveledMerkleForest::<TestConfig>::new_optimal(n, &params.parameters).unwrap();
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let mut dense_var =
+                LeveledMerkleForestVar::<TestConfig>::new_optimal(n, &params).unwrap();
+            let mut path_var =
+                LeveledMerkleForestPathVar::<TestConfig>::new_optimal(n, &params).unwrap();
+            assert_eq!(path_var.max_leaves(), dense_var.max_leaves());
+            assert_eq!(
+                path_var.root().value().unwrap(),
+                dense_var.root().value().unwrap()
+            );
+
+            for (i, val) in values.iter().enumerate() {
+                let paths = Vec::<MerklePathUpdateVar<TestConfig>>::new_witness(cs.clone(), || {
+                    forest
+                        .prove_update(i)
+                        .map_err(|_| SynthesisError::AssignmentMissing)
+                })
+                .unwrap();
+                forest.add_batch(&[&val[..]]).unwrap();
+
+                let index = FpVar::new_witness(cs.clone(), || Ok(Fr::from(i as u64))).unwrap();
+                let leaf = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || Ok(*val)).unwrap();
+                let dense_root = dense_var.update(index.clone(), &leaf).unwrap();
+                let path_root = path_var.update(index, &leaf, &paths).unwrap();
+
+                assert_eq!(path_root.value().unwrap(), dense_root.value().unwrap());
+                assert_eq!(path_root.value().unwrap(), forest.root());
+            }
+
+            // only the roots of the trees are carried in the state
+            let (capacity_per_tree, num_tree) = optimal_forest_params_for_arity(n, 2);
+            let state = path_var.to_constraint_field().unwrap();
+            assert_eq!(
+                state.len(),
+                LeveledMerkleForestPathVar::<TestConfig>::num_constraint_var_needed(
+                    capacity_per_tree,
+                    num_tree
+                )
+            );
+            let restored = LeveledMerkleForestPathVar::<TestConfig>::from_constraint_field(
+                state.clone().into_iter(),
+                capacity_per_tree,
+                num_tree,
+                &params,
+            )
+            .unwrap();
+            assert_eq!(restored.root().value().unwrap(), forest.root());
+            assert!(cs.is_satisfied().unwrap());
+
+            // a state that is too short
+            assert!(matches!(
+                LeveledMerkleForestPathVar::<TestConfig>::from_constraint_field(
+                    state[1..].iter().cloned(),
+                    capacity_per_tree,
+                    num_tree,
+                    &params,
+                ),
+                Err(SynthesisError::AssignmentMissing)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_r1cs_merkle_forest_path_update_wrong_witness() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        let mut forest =
+            LeveledMerkleForest::<TestConfig>::new_optimal(16, &params.parameters).unwrap();
+        let mut path_var =
+            LeveledMerkleForestPathVar::<TestConfig>::new_optimal(16, &params).unwrap();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let mut update =
+            |forest: &LeveledMerkleForest<TestConfig>, index: usize, path: usize, val: Fr| {
+                let paths = Vec::<MerklePathUpdateVar<TestConfig>>::new_witness(cs.clone(), || {
+                    Ok(forest.prove_update(path).unwrap())
+                })
+                .unwrap();
+                let index = FpVar::new_witness(cs.clone(), || Ok(Fr::from(index as u64))).unwrap();
+                let leaf = [FpVar::new_witness(cs.clone(), || Ok(val)).unwrap()];
+                path_var.update(index, &leaf, &paths).unwrap();
+            };
+
+        for i in 0..5 {
+            let val = Fr::rand(&mut rng);
+            update(&forest, i, i, val);
+            forest.add_batch(&[&[val][..]]).unwrap();
+        }
+        assert!(cs.is_satisfied().unwrap());
+
+        // a path of another index does not hash up to the current roots
+        update(&forest, 5, 0, Fr::rand(&mut rng));
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_r1cs_merkle_path_update_constraints() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let l = FpVar::new_witness(cs.clone(), || Ok(Fr::rand(&mut rng))).unwrap();
+        let r = FpVar::new_witness(cs.clone(), || Ok(Fr::rand(&mut rng))).unwrap();
+        PoseidonTwoToOne::evaluate(&params, &l, &r).unwrap();
+        let hash_constraints = cs.num_constraints();
+
+        let mut path_constraints = vec![];
+        for height in 1..=6 {
+            let num_leaves = 1 << height;
+            let values: Vec<_> = (0..num_leaves).map(|_| Fr::rand(&mut rng)).collect();
+            let tree = MerkleTree::<TestConfig>::new_with_data(
+                either::Left(&values[..]),
+                &params.parameters,
+            )
+            .unwrap();
+            let index = rng.gen_range(0..num_leaves);
+            let new_leaf = Fr::rand(&mut rng);
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let old_root = FpVar::new_witness(cs.clone(), || Ok(tree.root())).unwrap();
+            let leaf = FpVar::new_witness(cs.clone(), || Ok(new_leaf)).unwrap();
+            let index_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(index as u64))).unwrap();
+            let bits = index_var.to_bits_le().unwrap();
+            let path = MerklePathUpdateVar::<TestConfig>::new_witness(cs.clone(), || {
+                Ok(MerklePathUpdate {
+                    old_leaf: tree.leaves()[index],
+                    siblings: tree.prove(index).unwrap().siblings,
+                })
+            })
+            .unwrap();
+            assert_eq!(path.height(), height);
+
+            let before = cs.num_constraints();
+            let new_root = path
+                .update_with_hash(&params, &bits[..height], &old_root, leaf.clone())
+                .unwrap();
+            path_constraints.push(cs.num_constraints() - before);
+
+            let mut updated = tree.clone();
+            updated.update_with_hash(index, new_leaf).unwrap();
+            assert_eq!(new_root.value().unwrap(), updated.root());
+            assert!(cs.is_satisfied().unwrap());
+
+            // the dense gadget rehashes every node of the tree
+            let mut dense = MerkleTreeVar::<TestConfig>::new(2 * num_leaves - 1, &params).unwrap();
+            let before = cs.num_constraints();
+            dense.update_with_hash(index_var, leaf).unwrap();
+            let dense_constraints = cs.num_constraints() - before;
+            assert!(dense_constraints >= (num_leaves - 1) * hash_constraints);
+        }
+
+        // every level costs two hashes and a few selects, whatever the capacity
+        for (height, constraints) in (1..).zip(&path_constraints) {
+            assert!(*constraints <= height * 2 * (hash_constraints + 4));
+        }
+        for w in path_constraints.windows(2) {
+            assert!(w[1] - w[0] <= 2 * (hash_constraints + 4));
+        }
+    }
+
+    #[test]
+    fn test_r1cs_merkle_forest_malicious_index() {
+        use ark_ff::Field;
+        use ark_relations::r1cs::Variable;
+
+        // a forest of 2 trees with 4 leaves each
+        let index = 5;
+
+        // `index = div * 4 + rem` has a solution in the field for every
+        // `rem < 4`, so only bounding the remainder lets the prover write the
+        // leaf at any position of the first tree and corrupt the root
+        let rem = Fr::from(2);
+        let div = (Fr::from(index) - rem) * Fr::from(4).inverse().unwrap();
+        assert_eq!(div * Fr::from(4) + rem, Fr::from(index));
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let index_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(index))).unwrap();
+        let bits = split_index(&index_var, 4, 2).unwrap();
+        for bits in &bits {
+            assert_eq!(
+                Boolean::le_bits_to_fp(bits).unwrap().value().unwrap(),
+                Fr::from(1)
+            );
+        }
+        assert!(cs.is_satisfied().unwrap());
+
+        // the bits of the malicious remainder do not decompose the index
+        for (bit, value) in bits[0].iter().zip([false, true]) {
+            let Boolean::Var(bit) = bit else {
+                panic!("the index is a witness")
+            };
+            let Variable::Witness(i) = bit.variable() else {
+                panic!("the bits are witnesses")
+            };
+            cs.borrow_mut().unwrap().witness_assignment[i] = Fr::from(value);
+        }
+        assert!(!cs.is_satisfied().unwrap());
+
+        // an index that the forest cannot address is rejected as well
+        let params = poseidon_params();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let mut forest_var = LeveledMerkleForestVar::<TestConfig>::new(7, 2, &params).unwrap();
+        let index_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(16))).unwrap();
+        let leaf = [FpVar::new_witness(cs.clone(), || Ok(Fr::from(1))).unwrap()];
+        forest_var.update(index_var, &leaf).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_split_index_non_power_of_two() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let index_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(5))).unwrap();
+        assert!(split_index(&index_var, 4, 2).is_ok());
+        assert!(matches!(
+            split_index(&index_var, 3, 2),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+
+    #[test]
+    fn test_r1cs_div_rem() {
+        const NUM_BITS: usize = 8;
+
+        for divisor in [1usize, 2, 8, 3, 5, 7, 255] {
+            for dividend in [0u64, 1, 7, 8, 9, 100, (1 << NUM_BITS) - 1] {
+                let expected = (
+                    Fr::from(dividend / divisor as u64),
+                    Fr::from(dividend % divisor as u64),
+                );
+
+                let cs = ConstraintSystem::<Fr>::new_ref();
+                let dividend_var =
+                    FpVar::new_witness(cs.clone(), || Ok(Fr::from(dividend))).unwrap();
+                let (q, r) = div_rem(&dividend_var, divisor, NUM_BITS).unwrap();
+                assert_eq!((q.value().unwrap(), r.value().unwrap()), expected);
+                assert!(cs.is_satisfied().unwrap());
+
+                // constants stay constant
+                let (q, r) =
+                    div_rem(&FpVar::constant(Fr::from(dividend)), divisor, NUM_BITS).unwrap();
+                assert!(q.is_constant() && r.is_constant());
+                assert_eq!((q.value().unwrap(), r.value().unwrap()), expected);
+            }
+
+            // the dividend must fit in `NUM_BITS` bits
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let dividend_var =
+                FpVar::new_witness(cs.clone(), || Ok(Fr::from(1u64 << NUM_BITS))).unwrap();
+            div_rem(&dividend_var, divisor, NUM_BITS).unwrap();
+            assert!(!cs.is_satisfied().unwrap());
+        }
+
+        let dividend_var = FpVar::constant(Fr::from(1));
+        assert!(div_rem(&dividend_var, 0, NUM_BITS).is_err());
+        assert!(div_rem(&dividend_var, 3, Fr::MODULUS_BIT_SIZE as usize).is_err());
+    }
+
+    #[test]
+    fn test_r1cs_merkle_tree_conditional_update() {
+        let mut rng = thread_rng();
+        let params = poseidon_params();
+
+        for enable in [false, true] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let mut tree = MerkleTree::<TestConfig>::new(7, &params.parameters).unwrap();
+            let mut tree_var = MerkleTreeVar::<TestConfig>::new(7, &params).unwrap();
+            let leaf = Fr::rand(&mut rng);
+            tree.update_with_hash(1, leaf).unwrap();
+            let index = FpVar::new_witness(cs.clone(), || Ok(Fr::from(1))).unwrap();
+            let leaf = FpVar::new_witness(cs.clone(), || Ok(leaf)).unwrap();
+            tree_var.update_with_hash(index, leaf).unwrap();
+            let old_root = tree.root();
+
+            let leaf = Fr::rand(&mut rng);
+            let index = rng.gen_range(0..4);
+            tree.update(index, &[leaf]).unwrap();
+            let enable_var = Boolean::new_witness(cs.clone(), || Ok(enable)).unwrap();
+            let index = FpVar::new_witness(cs.clone(), || Ok(Fr::from(index as u64))).unwrap();
+            let leaf = FpVar::new_witness(cs.clone(), || Ok(leaf)).unwrap();
+            let root = tree_var
+                .update_conditional(&enable_var, index, &[leaf])
+                .unwrap();
+
+            assert!(cs.is_satisfied().unwrap());
+            let expected = if enable { tree.root() } else { old_root };
+            assert_eq!(root.value().unwrap(), expected);
+            assert_eq!(tree_var.root().value().unwrap(), expected);
+        }
+
+        // `enable` only adds an `and` per leaf to the unconditional update
+        let update = |enable: Option<bool>| {
+            crate::testing::count_constraints(|cs| {
+                let mut tree_var = MerkleTreeVar::<TestConfig>::new(7, &params).unwrap();
+                let index = FpVar::new_witness(cs.clone(), || Ok(Fr::from(2)))?;
+                let leaf = FpVar::new_witness(cs.clone(), || Ok(Fr::from(3)))?;
+                match enable {
+                    Some(enable) => {
+                        let enable = Boolean::new_witness(cs, || Ok(enable))?;
+                        tree_var.update_with_hash_conditional(&enable, index, leaf)
+                    }
+                    None => tree_var.update_with_hash(index, leaf),
+                }
+            })
+        };
+        let num_leaves = 4;
+        assert_eq!(update(Some(true)), update(None) + num_leaves);
+        assert_eq!(update(Some(false)), update(None) + num_leaves);
+    }
+
+    #[test]
+    fn test_r1cs_merkle_forest_conditional_update() {
+        let mut rng = thread_rng();
+        let params = poseidon_params();
+
+        for enable in [false, true] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let mut forest_var = LeveledMerkleForestVar::<TestConfig>::new(7, 2, &params).unwrap();
+            let mut expected_var =
+                LeveledMerkleForestVar::<TestConfig>::new(7, 2, &params).unwrap();
+            let first = Fr::rand(&mut rng);
+            for forest_var in [&mut forest_var, &mut expected_var] {
+                let index = FpVar::new_witness(cs.clone(), || Ok(Fr::from(3))).unwrap();
+                let leaf = FpVar::new_witness(cs.clone(), || Ok(first)).unwrap();
+                forest_var.update(index, &[leaf]).unwrap();
+            }
+            let old_root = forest_var.root().value().unwrap();
+
+            let leaf = Fr::rand(&mut rng);
+            let index = Fr::from(rng.gen_range(0..16u64));
+            let enable_var = Boolean::new_witness(cs.clone(), || Ok(enable)).unwrap();
+            let root = forest_var
+                .update_conditional(
+                    &enable_var,
+                    FpVar::new_witness(cs.clone(), || Ok(index)).unwrap(),
+                    &[FpVar::new_witness(cs.clone(), || Ok(leaf)).unwrap()],
+                )
+                .unwrap();
+            let expected = expected_var
+                .update(
+                    FpVar::new_witness(cs.clone(), || Ok(index)).unwrap(),
+                    &[FpVar::new_witness(cs.clone(), || Ok(leaf)).unwrap()],
+                )
+                .unwrap();
+
+            assert!(cs.is_satisfied().unwrap());
+            if enable {
+                assert_eq!(root.value().unwrap(), expected.value().unwrap());
+            } else {
+                assert_eq!(root.value().unwrap(), old_root);
+            }
+            assert_eq!(forest_var.root().value().unwrap(), root.value().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_r1cs_merkle_forest_state_round_trip() {
+        let mut rng = thread_rng();
+        let params = poseidon_params();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let mut forest_var = LeveledMerkleForestVar::<TestConfig>::new(7, 2, &params).unwrap();
+        for i in 0..5 {
+            forest_var
+                .update(
+                    FpVar::new_witness(cs.clone(), || Ok(Fr::from(i))).unwrap(),
+                    &[FpVar::new_witness(cs.clone(), || Ok(Fr::rand(&mut rng))).unwrap()],
+                )
+                .unwrap();
+        }
+
+        // the state is allocated as in a folding step
+        let state = forest_var.to_constraint_field().unwrap().value().unwrap();
+        assert_eq!(state.len(), 14);
+        let alloc_state = |state: &[Fr]| {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let state = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || Ok(state)).unwrap();
+            (cs, state)
+        };
+
+        let (cs, state_var) = alloc_state(&state);
+        let restored = LeveledMerkleForestVar::<TestConfig>::from_constraint_field(
+            state_var.into_iter(),
+            7,
+            2,
+            &params,
+        )
+        .unwrap();
+        assert_eq!(restored.value().unwrap(), forest_var.value().unwrap());
+        assert_eq!(
+            restored.to_constraint_field().unwrap().value().unwrap(),
+            state
+        );
+        restored.validate_structure(&Boolean::TRUE).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        // flip an internal node of the first tree
+        let mut tampered = state.clone();
+        tampered[1] += Fr::from(1);
+        for enable in [false, true] {
+            let (cs, state_var) = alloc_state(&tampered);
+            let restored = LeveledMerkleForestVar::<TestConfig>::from_constraint_field(
+                state_var.into_iter(),
+                7,
+                2,
+                &params,
+            )
+            .unwrap();
+            let enable_var = Boolean::new_witness(cs.clone(), || Ok(enable)).unwrap();
+            restored.validate_structure(&enable_var).unwrap();
+            assert_eq!(cs.is_satisfied().unwrap(), !enable);
+        }
+
+        // a state that is too short
+        let (_, state_var) = alloc_state(&state[..13]);
+        assert!(matches!(
+            LeveledMerkleForestVar::<TestConfig>::from_constraint_field(
+                state_var.into_iter(),
+                7,
+                2,
+                &params,
+            ),
+            Err(SynthesisError::AssignmentMissing)
+        ));
+    }
+
+    #[test]
+    fn test_r1cs_merkle_open() {
+        let mut rng = thread_rng();
+        let params = poseidon_params();
+        let default_leaf = Fr::default();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let mut tree_var = MerkleTreeVar::<TestConfig>::new(7, &params).unwrap();
+        let mut forest_var = LeveledMerkleForestVar::<TestConfig>::new(7, 2, &params).unwrap();
+        let index = |i: u64| FpVar::new_witness(cs.clone(), || Ok(Fr::from(i))).unwrap();
+
+        // a fresh tree and forest only hold default leaves
+        assert_eq!(
+            tree_var.open(index(0)).unwrap().value().unwrap(),
+            default_leaf
+        );
+        assert_eq!(
+            forest_var.open(index(0)).unwrap().value().unwrap(),
+            default_leaf
+        );
+
+        let values: Vec<_> = (0..4).map(|_| Fr::rand(&mut rng)).collect();
+        for (i, value) in values.iter().enumerate() {
+            let leaf = FpVar::new_witness(cs.clone(), || Ok(value)).unwrap();
+            tree_var.update(index(i as u64), &[leaf.clone()]).unwrap();
+            forest_var.update(index(4 + i as u64), &[leaf]).unwrap();
+        }
+
+        for (i, value) in values.iter().enumerate() {
+            let hash = TestConfig::hash_leaf(&params, &[FpVar::Constant(*value)])
+                .unwrap()
+                .value()
+                .unwrap();
+            let i = i as u64;
+            assert_eq!(tree_var.open(index(i)).unwrap().value().unwrap(), hash);
+            assert_eq!(
+                forest_var.open(index(4 + i)).unwrap().value().unwrap(),
+                hash
+            );
+            assert_eq!(
+                tree_var
+                    .open(FpVar::Constant(Fr::from(i)))
+                    .unwrap()
+                    .value()
+                    .unwrap(),
+                hash
+            );
+        }
+        assert!(cs.is_satisfied().unwrap());
+
+        // out of range
+        tree_var.open(index(4)).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let forest_var = LeveledMerkleForestVar::<TestConfig>::new(7, 2, &params).unwrap();
+        forest_var
+            .open(FpVar::new_witness(cs.clone(), || Ok(Fr::from(16))).unwrap())
+            .unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
     fn test_r1cs_merkle_forest_gadget_helper(values: Vec<Fr>) {
         let params = poseidon_params();
         let cs = ConstraintSystem::new_ref();
@@ -420,4 +2429,133 @@ mod test {
             test_r1cs_merkle_forest_gadget_helper(values);
         }
     }
+
+    /// A config hashed with Blake2s, whose digest is truncated to the bytes
+    /// that fit in a field element, to check that the gadgets only rely on
+    /// `MerkleConfigGadget`.
+    struct Blake2sConfig;
+
+    /// Number of digest bytes kept by `Blake2sToField`.
+    const BLAKE2S_BYTES: usize = (Fr::MODULUS_BIT_SIZE as usize - 1) / 8;
+
+    struct Blake2sToField;
+
+    impl ark_crypto_primitives::crh::CRHScheme for Blake2sToField {
+        type Input = [Fr];
+        type Output = Fr;
+        type Parameters = ();
+
+        fn setup<R: Rng>(_: &mut R) -> Result<(), ark_crypto_primitives::Error> {
+            Ok(())
+        }
+
+        fn evaluate<T: Borrow<[Fr]>>(_: &(), input: T) -> Result<Fr, ark_crypto_primitives::Error> {
+            use blake2::{Blake2s256, Digest};
+
+            let mut hasher = Blake2s256::new();
+            for x in input.borrow() {
+                hasher.update(x.into_bigint().to_bytes_le());
+            }
+            Ok(Fr::from_le_bytes_mod_order(
+                &hasher.finalize()[..BLAKE2S_BYTES],
+            ))
+        }
+    }
+
+    impl TwoToOneCRHScheme for Blake2sToField {
+        type Input = Fr;
+        type Output = Fr;
+        type Parameters = ();
+
+        fn setup<R: Rng>(_: &mut R) -> Result<(), ark_crypto_primitives::Error> {
+            Ok(())
+        }
+
+        fn evaluate<T: Borrow<Fr>>(
+            params: &(),
+            left: T,
+            right: T,
+        ) -> Result<Fr, ark_crypto_primitives::Error> {
+            Self::compress(params, left, right)
+        }
+
+        fn compress<T: Borrow<Fr>>(
+            params: &(),
+            left: T,
+            right: T,
+        ) -> Result<Fr, ark_crypto_primitives::Error> {
+            <Self as ark_crypto_primitives::crh::CRHScheme>::evaluate(
+                params,
+                [*left.borrow(), *right.borrow()],
+            )
+        }
+    }
+
+    fn blake2s_to_field(input: &[FpVar<Fr>]) -> Result<FpVar<Fr>, SynthesisError> {
+        use crate::hash::prf::{
+            blake2s::constraints::StatefulBlake2sGadget, constraints::PRFGadget,
+        };
+
+        let mut bytes = vec![];
+        for x in input {
+            bytes.extend(x.to_bytes_le()?);
+        }
+        let mut hasher = StatefulBlake2sGadget::<Fr>::default();
+        hasher.update(&bytes)?;
+        let digest = hasher.finalize()?.0;
+        Boolean::le_bits_to_fp(&digest[..BLAKE2S_BYTES].to_bits_le()?)
+    }
+
+    impl MerkleConfig for Blake2sConfig {
+        type BasePrimeField = Fr;
+        type LeafHash = Blake2sToField;
+        type TwoToOneHash = Blake2sToField;
+    }
+
+    impl MerkleConfigGadget for Blake2sConfig {
+        type HashParamsVar = ();
+
+        fn hash_leaf(_: &(), leaf: &[FpVar<Fr>]) -> Result<FpVar<Fr>, SynthesisError> {
+            blake2s_to_field(leaf)
+        }
+
+        fn hash_node(_: &(), children: &[FpVar<Fr>]) -> Result<FpVar<Fr>, SynthesisError> {
+            blake2s_to_field(children)
+        }
+    }
+
+    #[test]
+    fn test_r1cs_merkle_custom_hash() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let values: Vec<_> = (0..4).map(|_| [Fr::rand(&mut rng)]).collect();
+        let values_ref: Vec<_> = values.iter().map(|v| &v[..]).collect();
+        let tree = MerkleTree::<Blake2sConfig>::new_with_data(either::Right(&values_ref[..]), &())
+            .unwrap();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let values_var = values
+            .iter()
+            .map(|v| Vec::<FpVar<Fr>>::new_witness(cs.clone(), || Ok(v)))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let values_var: Vec<_> = values_var.iter().map(Vec::as_slice).collect();
+        let tree_var = MerkleTreeVar::<Blake2sConfig>::from_values(&values_var, 7, &()).unwrap();
+        assert_eq!(tree_var.root().value().unwrap(), tree.root());
+
+        let proof = tree.prove(2).unwrap();
+        let root = FpVar::new_input(cs.clone(), || Ok(tree.root())).unwrap();
+        let index = FpVar::new_witness(cs.clone(), || Ok(Fr::from(2))).unwrap();
+        let siblings =
+            Vec::<FpVar<Fr>>::new_witness(cs.clone(), || Ok(&proof.siblings[..])).unwrap();
+        let valid = MerkleTreeVar::<Blake2sConfig>::verify_path(
+            &(),
+            values_var[2],
+            &index,
+            &siblings,
+            &root,
+        )
+        .unwrap();
+        assert!(valid.value().unwrap());
+        assert!(cs.is_satisfied().unwrap());
+    }
 }
@@ -1,24 +1,33 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
-use ark_crypto_primitives::{
-    crh::{poseidon::CRH as Poseidon, CRHScheme},
-    sponge::poseidon::PoseidonConfig,
+use ark_crypto_primitives::crh::CRHScheme;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Validate, Write,
 };
+use ark_std::cfg_iter;
 use derivative::Derivative;
 use either::{for_both, Either};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use thiserror::Error;
 
 use super::{
-    tree::{MerkleTree, MerkleTreeError},
-    MerkleConfig,
+    arity, hash_leaves,
+    sparse::SparseMerkleTree,
+    tree::{MerklePathUpdate, MerkleTreeError, MerkleTreeProof},
+    HashParams, LeafInput, MerkleConfig, Node,
 };
 
 #[derive(Derivative)]
 #[derivative(Debug(bound = ""))]
 pub struct LeveledMerkleForest<'a, P: MerkleConfig> {
-    trees: Vec<MerkleTree<'a, P>>,
-    states: Vec<HashMap<usize, MerkleTree<'a, P>>>,
+    trees: Vec<SparseMerkleTree<'a, P>>,
+    states: Vec<HashMap<usize, SparseMerkleTree<'a, P>>>,
     size: usize,
+    // indices of the leaves reset by `remove`
+    removed: BTreeSet<usize>,
+    // indices of the leaves holding each hash, except the removed ones
+    leaf_indices: HashMap<Node<P>, Vec<usize>>,
 }
 
 #[derive(Error, Debug)]
@@ -32,42 +41,141 @@ pub enum MerkleForestError {
     #[error("Merkle forest is full")]
     ForestIsFull,
 
+    #[error("Leaf has been removed")]
+    LeafRemoved,
+
+    #[error("num_leaves_per_tree should be a power of ARITY and >= ARITY")]
+    InvalidNumLeavesPerTree,
+
+    #[error("proof length should be a non-zero multiple of the tree height")]
+    InvalidProofLength,
+
+    #[error("number of leaves does not match the number of indices in the proof")]
+    LeafCountMismatch,
+
     #[error("Merkle tree error occurred: {0}")]
     MerkleTreeError(#[from] MerkleTreeError),
 }
 
-#[derive(Derivative)]
+#[derive(Derivative, CanonicalSerialize, CanonicalDeserialize)]
 #[derivative(Debug(bound = ""))]
 pub struct MerkleForestProof<P: MerkleConfig> {
-    pub siblings: Vec<P::BasePrimeField>,
+    pub siblings: Vec<Node<P>>,
     pub leaf_index: usize,
     pub num_leaves_per_tree: usize,
 }
 
-#[derive(Derivative)]
+/// A proof that only goes up to the node returned by
+/// `LeveledMerkleForest::variable_root`, so that leaves added recently have
+/// shorter proofs.
+#[derive(Derivative, CanonicalSerialize, CanonicalDeserialize)]
 #[derivative(Debug(bound = ""))]
 pub struct MerkleForestVariableLengthProof<P: MerkleConfig> {
-    pub siblings: Vec<P::BasePrimeField>,
+    pub siblings: Vec<Node<P>>,
     pub leaf_index: usize,
     pub num_leaves_per_tree: usize,
+    /// The node the siblings hash the leaf up to. It is only trusted once
+    /// `verify_variable` has compared it with the verifier's own copy.
+    pub root: Node<P>,
+}
+
+/// A proof for multiple leaves of the forest, where siblings that are shared
+/// by several leaves (or can be recomputed from them) are included only once.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct MerkleForestBatchProof<P: MerkleConfig> {
+    pub siblings: Vec<Node<P>>,
+    // sorted in ascending order without duplicates
+    pub leaf_indices: Vec<usize>,
+    pub num_leaves_per_tree: usize,
+    pub num_trees: usize,
 }
 
 impl<'a, P: MerkleConfig> LeveledMerkleForest<'a, P> {
-    pub fn new_optimal(
-        n: usize,
-        params: &'a PoseidonConfig<P::BasePrimeField>,
-    ) -> Result<Self, MerkleForestError> {
-        let (capacity_per_tree, num_tree) = optimal_forest_params(n);
+    pub fn new_optimal(n: usize, params: &'a HashParams<P>) -> Result<Self, MerkleForestError> {
+        let (capacity_per_tree, num_tree) = optimal_forest_params_for_arity(n, arity::<P>());
         Self::new(capacity_per_tree, num_tree, params)
     }
 
-    // the `Construct-Fast` algorithm in the thesis
+    /// Deserialize a forest written with `CanonicalSerialize`, including the
+    /// snapshots used by `prove_variable`. The hash parameters are not
+    /// serialized, so they have to be supplied again.
+    pub fn deserialize_with_params(
+        mut reader: impl Read,
+        compress: Compress,
+        validate: Validate,
+        params: &'a HashParams<P>,
+    ) -> Result<Self, SerializationError> {
+        let num_trees = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let mut trees = Vec::new();
+        for _ in 0..num_trees {
+            trees.push(SparseMerkleTree::deserialize_with_params(
+                &mut reader,
+                compress,
+                validate,
+                params,
+            )?);
+        }
+
+        let mut states = Vec::new();
+        for _ in 0..num_trees {
+            let num_states = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+            let mut states_per_tree = HashMap::new();
+            for _ in 0..num_states {
+                let idx = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+                let tree = SparseMerkleTree::deserialize_with_params(
+                    &mut reader,
+                    compress,
+                    validate,
+                    params,
+                )?;
+                states_per_tree.insert(idx, tree);
+            }
+            states.push(states_per_tree);
+        }
+
+        let size = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let removed = BTreeSet::deserialize_with_mode(&mut reader, compress, validate)?;
+
+        let capacity = trees
+            .first()
+            .ok_or(SerializationError::InvalidData)?
+            .capacity();
+        if trees
+            .iter()
+            .chain(states.iter().flat_map(HashMap::values))
+            .any(|tree| tree.capacity() != capacity)
+        {
+            return Err(SerializationError::InvalidData);
+        }
+
+        let mut s = Self {
+            trees,
+            states,
+            size,
+            removed,
+            leaf_indices: HashMap::new(),
+        };
+        if s.size > s.max_leaves() || s.removed.last().is_some_and(|i| *i >= s.size) {
+            return Err(SerializationError::InvalidData);
+        }
+
+        // the index is not serialized, as it can be rebuilt from the leaves
+        let leaves: Vec<_> = s.leaves().collect();
+        s.index_leaves(0, leaves);
+
+        Ok(s)
+    }
+
+    /// Build a forest with the optimal parameters for `data.len()` leaves that
+    /// stores `data` in order, where `data` holds either the leaf hashes or the
+    /// values to be hashed into leaves. The result, including the snapshots
+    /// used by `prove_variable`, is the same as adding the leaves one by one.
+    ///
+    /// This is the `Construct-Fast` algorithm in the thesis.
     pub fn new_with_data(
-        data: either::Either<
-            &[P::BasePrimeField],
-            &[&<Poseidon<P::BasePrimeField> as CRHScheme>::Input],
-        >,
-        params: &'a PoseidonConfig<P::BasePrimeField>,
+        data: either::Either<&[Node<P>], &[&LeafInput<P>]>,
+        params: &'a HashParams<P>,
     ) -> Result<Self, MerkleForestError> {
         let len = for_both!(data, data => data.len());
         let mut s = Self::new_optimal(len, params)?;
@@ -75,17 +183,9 @@ impl<'a, P: MerkleConfig> LeveledMerkleForest<'a, P> {
 
         let mut data = match data {
             either::Either::Left(v) => v.to_owned(),
-            either::Either::Right(v) => {
-                let mut data = Vec::new();
-                data.reserve_exact(v.len());
-                for d in v {
-                    data.push(
-                        Poseidon::evaluate(params, *d).map_err(|_| MerkleTreeError::CRHError)?,
-                    );
-                }
-                data
-            }
+            either::Either::Right(v) => hash_leaves::<P>(params, v)?,
         };
+        s.index_leaves(0, data.iter().cloned());
 
         for i in 0..s.num_trees() as usize {
             let mut new_data = Vec::new();
@@ -98,7 +198,7 @@ impl<'a, P: MerkleConfig> LeveledMerkleForest<'a, P> {
                 let mut data_per_tree = data_per_tree.to_owned();
                 if j == 0 {
                     data_per_tree.extend(
-                        std::iter::repeat(P::BasePrimeField::default())
+                        std::iter::repeat(Node::<P>::default())
                             .take(s.num_leaves_per_tree() as usize - data_per_tree.len()),
                     );
                 } else {
@@ -109,7 +209,8 @@ impl<'a, P: MerkleConfig> LeveledMerkleForest<'a, P> {
                     );
                 }
 
-                let merkle_tree = MerkleTree::new_with_data(either::Left(&data_per_tree), params)?;
+                let merkle_tree =
+                    SparseMerkleTree::new_with_data(either::Left(&data_per_tree), params)?;
                 let root = merkle_tree.root();
                 s.states[i].insert(j, merkle_tree);
                 new_data.push(root);
@@ -124,6 +225,81 @@ impl<'a, P: MerkleConfig> LeveledMerkleForest<'a, P> {
         Ok(s)
     }
 
+    /// Reset the leaf at `leaf_index` to the default hash.
+    ///
+    /// The trees and their snapshots end up the same as if the default hash
+    /// had been added at `leaf_index` in the first place, so the root matches
+    /// a forest built from the remaining leaves with the removed ones left as
+    /// defaults. The size is unchanged, but the leaf can no longer be proven,
+    /// and the snapshot of a full first-level tree is dropped once all its
+    /// leaves are removed.
+    pub fn remove(&mut self, leaf_index: usize) -> Result<(), MerkleForestError> {
+        if leaf_index >= self.size {
+            return Err(MerkleForestError::IndexOutOfBound);
+        }
+        if !self.removed.insert(leaf_index) {
+            return Err(MerkleForestError::LeafRemoved);
+        }
+
+        let num_leaves_per_tree = self.num_leaves_per_tree() as usize;
+        let leaf = self.snapshot_leaf(
+            0,
+            leaf_index / num_leaves_per_tree,
+            leaf_index % num_leaves_per_tree,
+        );
+        if let Some(indices) = self.leaf_indices.get_mut(&leaf) {
+            indices.retain(|i| *i != leaf_index);
+            if indices.is_empty() {
+                self.leaf_indices.remove(&leaf);
+            }
+        }
+
+        // number of nodes added so far to the current level
+        let mut count = self.size;
+        // (index at the current level, hash) for every updated node
+        let mut updates = vec![(leaf_index, Node::<P>::default())];
+        for i in 0..self.trees.len() {
+            let mut touched = BTreeSet::new();
+            for (idx, hash) in &updates {
+                let state_idx = idx / num_leaves_per_tree;
+                let idx_within_tree = idx % num_leaves_per_tree;
+                self.states[i]
+                    .get_mut(&state_idx)
+                    .expect("state exists because leaf index is in bound")
+                    .update_with_hash(idx_within_tree, hash.clone())?;
+                touched.insert(state_idx);
+
+                // The next snapshot starts from this one, so it still holds
+                // the node unless it has been overwritten.
+                if (state_idx + 1) * num_leaves_per_tree + idx_within_tree >= count {
+                    if let Some(next) = self.states[i].get_mut(&(state_idx + 1)) {
+                        next.update_with_hash(idx_within_tree, hash.clone())?;
+                        touched.insert(state_idx + 1);
+                    }
+                }
+            }
+
+            let last = (count - 1) / num_leaves_per_tree;
+            self.trees[i] = self.states[i][&last].clone();
+            updates = touched
+                .into_iter()
+                .map(|state_idx| (state_idx, self.states[i][&state_idx].root()))
+                .collect();
+            count = count.div_ceil(num_leaves_per_tree);
+        }
+
+        // the last snapshot is kept as later leaves are added on top of it
+        let state_idx = leaf_index / num_leaves_per_tree;
+        let start = state_idx * num_leaves_per_tree;
+        if (start + num_leaves_per_tree < self.size)
+            && (start..start + num_leaves_per_tree).all(|i| self.removed.contains(&i))
+        {
+            self.states[0].remove(&state_idx);
+        }
+
+        Ok(())
+    }
+
     // TODO: add an `update` method that allows arbitrary position update
     // - 1. We can get rid of `s` entirely
     // - 2. During the update, we need to update each level's tree
@@ -132,6 +308,9 @@ impl<'a, P: MerkleConfig> LeveledMerkleForest<'a, P> {
         if leaf_index >= self.size {
             return Err(MerkleForestError::IndexOutOfBound);
         }
+        if self.removed.contains(&leaf_index) {
+            return Err(MerkleForestError::LeafRemoved);
+        }
 
         let mut forest_proof = vec![];
         let mut idx = leaf_index;
@@ -143,8 +322,7 @@ impl<'a, P: MerkleConfig> LeveledMerkleForest<'a, P> {
             let s = self.states[i]
                 .get(&idx)
                 .expect("state exists because leaf index is in bound");
-            let (siblings, _) = s.prove(idx_within_tree)?;
-            forest_proof.extend(siblings);
+            forest_proof.extend(s.prove(idx_within_tree)?.siblings);
         }
 
         Ok(MerkleForestProof {
@@ -154,34 +332,227 @@ impl<'a, P: MerkleConfig> LeveledMerkleForest<'a, P> {
         })
     }
 
+    /// The node that `leaf_index` addresses in every live tree, from the
+    /// leaves up to the root, along with its siblings. This is the witness
+    /// that `LeveledMerkleForestPathVar::update` needs to write `leaf_index`.
+    pub fn prove_update(
+        &self,
+        leaf_index: usize,
+    ) -> Result<Vec<MerklePathUpdate<P>>, MerkleForestError> {
+        if leaf_index >= self.max_leaves() {
+            return Err(MerkleForestError::IndexOutOfBound);
+        }
+
+        let num_leaves_per_tree = self.num_leaves_per_tree() as usize;
+        let mut idx = leaf_index;
+        self.trees
+            .iter()
+            .map(|tree| {
+                let idx_within_tree = idx % num_leaves_per_tree;
+                idx /= num_leaves_per_tree;
+                Ok(MerklePathUpdate {
+                    old_leaf: tree.leaf(idx_within_tree),
+                    siblings: tree.prove(idx_within_tree)?.siblings,
+                })
+            })
+            .collect()
+    }
+
+    /// Prove every leaf that holds `leaf_hash`, in ascending order of their
+    /// indices. Returns no proof if the hash is absent or has been removed.
+    pub fn find_and_prove(
+        &self,
+        leaf_hash: &Node<P>,
+    ) -> Result<Vec<MerkleForestProof<P>>, MerkleForestError> {
+        self.leaf_indices
+            .get(leaf_hash)
+            .map_or(&[][..], Vec::as_slice)
+            .iter()
+            .map(|i| self.prove(*i))
+            .collect()
+    }
+
     pub fn verify(
-        params: &PoseidonConfig<P::BasePrimeField>,
-        root: P::BasePrimeField,
-        leaf: Either<&P::BasePrimeField, &<Poseidon<P::BasePrimeField> as CRHScheme>::Input>,
+        params: &HashParams<P>,
+        root: Node<P>,
+        leaf: Either<&Node<P>, &LeafInput<P>>,
         proof: MerkleForestProof<P>,
     ) -> Result<bool, MerkleForestError> {
+        let tree_height = tree_height::<P>(proof.num_leaves_per_tree)
+            .ok_or(MerkleForestError::InvalidNumLeavesPerTree)?;
+
+        let siblings_per_tree = tree_height * (arity::<P>() - 1);
+        if proof.siblings.is_empty() || proof.siblings.len() % siblings_per_tree != 0 {
+            return Err(MerkleForestError::InvalidProofLength);
+        }
+
+        // the leaf index should be addressable by the number of trees in the proof
+        let num_tree = proof.siblings.len() / siblings_per_tree;
+        let max_leaves = u32::try_from(num_tree)
+            .ok()
+            .and_then(|num_tree| proof.num_leaves_per_tree.checked_pow(num_tree));
+        if max_leaves.is_some_and(|max_leaves| proof.leaf_index >= max_leaves) {
+            return Err(MerkleForestError::IndexOutOfBound);
+        }
+
         let mut index = proof.leaf_index;
-        let leaf_start = proof.num_leaves_per_tree - 1;
-        let tree_height = proof.num_leaves_per_tree.ilog2();
 
         let mut hash = match leaf {
-            Either::Left(v) => *v,
-            Either::Right(v) => {
-                Poseidon::evaluate(params, v).map_err(|_| MerkleTreeError::CRHError)?
-            }
+            Either::Left(v) => v.clone(),
+            Either::Right(v) => P::LeafHash::evaluate(params, v).map_err(MerkleTreeError::from)?,
         };
 
-        // chunk by tree_height to get siblings for each tree
-        for siblings in proof.siblings.chunks(tree_height as usize) {
-            // We need to offset the idx by `leaf_start` to be able to use MerkleTree's `hash_path` algorithm.
-            let idx_within_tree = leaf_start + index % proof.num_leaves_per_tree;
-            hash = MerkleTree::<P>::hash_path(params, hash, idx_within_tree, siblings)?;
+        // chunk by siblings_per_tree to get siblings for each tree
+        for siblings in proof.siblings.chunks(siblings_per_tree) {
+            let tree_proof = MerkleTreeProof::<P> {
+                siblings: siblings.to_vec(),
+                leaf_index: index % proof.num_leaves_per_tree,
+            };
+            hash = tree_proof.compute_root(params, hash)?;
             index /= proof.num_leaves_per_tree;
         }
 
         Ok(hash == root)
     }
 
+    /// Prove multiple leaves at once.
+    ///
+    /// The indices are sorted and deduplicated in the returned proof, and
+    /// `verify_batch` expects the leaves in the same order.
+    pub fn prove_batch(
+        &self,
+        indices: &[usize],
+    ) -> Result<MerkleForestBatchProof<P>, MerkleForestError> {
+        let mut leaf_indices = indices.to_vec();
+        leaf_indices.sort_unstable();
+        leaf_indices.dedup();
+
+        let proofs = leaf_indices
+            .iter()
+            .map(|index| self.prove(*index))
+            .collect::<Result<Vec<_>, _>>()?;
+        let arity = arity::<P>();
+        let height = proofs
+            .first()
+            .map_or(0, |proof| proof.siblings.len() / (arity - 1));
+
+        // The forest proofs form a path in an `arity`-ary tree of height
+        // `height`, so we can walk that tree level by level and only emit the
+        // siblings that cannot be computed from the nodes known so far.
+        //
+        // Each known node is stored with the index of a proof for a leaf below it.
+        let mut known: Vec<(usize, usize)> = leaf_indices
+            .iter()
+            .enumerate()
+            .map(|(i, index)| (*index, i))
+            .collect();
+        let mut siblings = vec![];
+        for level in 0..height {
+            let mut next = Vec::with_capacity(known.len());
+            for group in known.chunk_by(|a, b| a.0 / arity == b.0 / arity) {
+                // the siblings of the first known child cover all unknown ones
+                let (pos, proof_idx) = group[0];
+                let level_siblings =
+                    &proofs[proof_idx].siblings[level * (arity - 1)..(level + 1) * (arity - 1)];
+                let first = pos - pos % arity;
+                for child in first..first + arity {
+                    if group.iter().all(|(p, _)| *p != child) {
+                        let k = if child < pos {
+                            child - first
+                        } else {
+                            child - first - 1
+                        };
+                        siblings.push(level_siblings[k].clone());
+                    }
+                }
+                next.push((pos / arity, proof_idx));
+            }
+            known = next;
+        }
+
+        Ok(MerkleForestBatchProof {
+            siblings,
+            leaf_indices,
+            num_leaves_per_tree: self.num_leaves_per_tree() as usize,
+            num_trees: self.trees.len(),
+        })
+    }
+
+    /// Verify a proof produced by `prove_batch`. `leaves` should be ordered as
+    /// `proof.leaf_indices`. A proof for no leaves is never accepted.
+    pub fn verify_batch(
+        params: &HashParams<P>,
+        root: Node<P>,
+        leaves: &[Either<&Node<P>, &LeafInput<P>>],
+        proof: MerkleForestBatchProof<P>,
+    ) -> Result<bool, MerkleForestError> {
+        let tree_height = tree_height::<P>(proof.num_leaves_per_tree)
+            .ok_or(MerkleForestError::InvalidNumLeavesPerTree)?;
+        if proof.num_trees == 0 {
+            return Err(MerkleForestError::InvalidNumTree);
+        }
+        if leaves.len() != proof.leaf_indices.len() {
+            return Err(MerkleForestError::LeafCountMismatch);
+        }
+        if !proof.leaf_indices.windows(2).all(|w| w[0] < w[1]) {
+            return Ok(false);
+        }
+
+        let max_leaves = u32::try_from(proof.num_trees)
+            .ok()
+            .and_then(|num_trees| proof.num_leaves_per_tree.checked_pow(num_trees));
+        if proof
+            .leaf_indices
+            .last()
+            .is_some_and(|index| max_leaves.is_some_and(|max_leaves| *index >= max_leaves))
+        {
+            return Err(MerkleForestError::IndexOutOfBound);
+        }
+
+        let mut known = Vec::with_capacity(leaves.len());
+        for (index, leaf) in proof.leaf_indices.iter().zip(leaves) {
+            let hash = match leaf {
+                Either::Left(v) => (*v).clone(),
+                Either::Right(v) => {
+                    P::LeafHash::evaluate(params, *v).map_err(MerkleTreeError::from)?
+                }
+            };
+            known.push((*index, hash));
+        }
+
+        let arity = arity::<P>();
+        let height = proof.num_trees * tree_height;
+        let mut siblings = proof.siblings.into_iter();
+        for _ in 0..height {
+            let mut next = Vec::with_capacity(known.len());
+            for group in known.chunk_by(|a, b| a.0 / arity == b.0 / arity) {
+                let first = group[0].0 - group[0].0 % arity;
+                let mut group = group.iter().peekable();
+                let mut children = Vec::with_capacity(arity);
+                for child in first..first + arity {
+                    match group.next_if(|(p, _)| *p == child) {
+                        Some((_, hash)) => children.push(hash.clone()),
+                        None => children.push(
+                            siblings
+                                .next()
+                                .ok_or(MerkleForestError::InvalidProofLength)?,
+                        ),
+                    }
+                }
+
+                let parent = P::compress(params, &children).map_err(MerkleTreeError::from)?;
+                next.push((first / arity, parent));
+            }
+            known = next;
+        }
+
+        if siblings.next().is_some() {
+            return Err(MerkleForestError::InvalidProofLength);
+        }
+
+        Ok(known.first().is_some_and(|(_, hash)| *hash == root))
+    }
+
     pub fn prove_variable(
         &self,
         leaf_index: usize,
@@ -189,81 +560,202 @@ impl<'a, P: MerkleConfig> LeveledMerkleForest<'a, P> {
         if leaf_index >= self.size {
             return Err(MerkleForestError::IndexOutOfBound);
         }
+        if self.removed.contains(&leaf_index) {
+            return Err(MerkleForestError::LeafRemoved);
+        }
 
         let num_leaves_per_tree = self.num_leaves_per_tree() as usize;
-        let n = next_power_of_q(self.size, num_leaves_per_tree);
-        let diff = n - leaf_index - 1;
-        let diff = std::cmp::max(diff, 1); // handle the special case that leaf_index == n-1
-        let state_idx = diff.ilog(num_leaves_per_tree);
+        let state_idx = self.variable_level(leaf_index);
 
         let mut forest_proof = vec![];
         let mut idx = leaf_index;
 
         // only need to generate proof for state with index < state_idx
-        for i in 0..(state_idx as usize) {
+        for i in 0..state_idx as usize {
             let idx_within_tree = idx % num_leaves_per_tree;
             idx /= num_leaves_per_tree;
             let s = self.states[i]
                 .get(&idx)
                 .expect("state exists because leaf index is in bound");
-            let (siblings, _) = s.prove(idx_within_tree)?;
-            forest_proof.extend(siblings);
+            forest_proof.extend(s.prove(idx_within_tree)?.siblings);
         }
 
         Ok(MerkleForestVariableLengthProof {
             siblings: forest_proof,
             leaf_index,
             num_leaves_per_tree,
+            root: self.trees[state_idx as usize].leaf(idx % num_leaves_per_tree),
         })
     }
 
+    /// The node that the proof of `prove_variable(leaf_index)` goes up to: a
+    /// leaf of the live tree of the level that the proof stops at.
+    ///
+    /// The verifier has to hold these nodes as part of the public state it
+    /// already trusts, e.g. by carrying the live trees (or a commitment to
+    /// them) in the IVC state, as `verify_variable` only checks the leaf
+    /// against them.
+    pub fn variable_root(&self, leaf_index: usize) -> Result<Node<P>, MerkleForestError> {
+        if leaf_index >= self.size {
+            return Err(MerkleForestError::IndexOutOfBound);
+        }
+
+        let num_leaves_per_tree = self.num_leaves_per_tree() as usize;
+        let state_idx = self.variable_level(leaf_index);
+        let idx = leaf_index / num_leaves_per_tree.pow(state_idx);
+        Ok(self.trees[state_idx as usize].leaf(idx % num_leaves_per_tree))
+    }
+
+    /// The level of the live tree that the variable length proof of
+    /// `leaf_index` stops at, i.e. the number of trees the proof goes through.
+    fn variable_level(&self, leaf_index: usize) -> u32 {
+        let num_leaves_per_tree = self.num_leaves_per_tree() as usize;
+        let n = next_power_of_q(self.size, num_leaves_per_tree);
+        let diff = n - leaf_index - 1;
+        let diff = std::cmp::max(diff, 1); // handle the special case that leaf_index == n-1
+        diff.ilog(num_leaves_per_tree)
+    }
+
+    /// Verify a proof from `prove_variable` against `root`, the value of
+    /// `variable_root(proof.leaf_index)` that the verifier trusts.
+    ///
+    /// The root embedded in the proof is rejected unless it is `root`, so the
+    /// proof alone says nothing about the forest.
     pub fn verify_variable(
-        params: &PoseidonConfig<P::BasePrimeField>,
-        states: &[MerkleTree<P>],
-        n: usize,
-        num_leaves: u32,
-        leaf: Either<&P::BasePrimeField, &<Poseidon<P::BasePrimeField> as CRHScheme>::Input>,
+        params: &HashParams<P>,
+        root: &Node<P>,
+        leaf: Either<&Node<P>, &LeafInput<P>>,
         proof: MerkleForestVariableLengthProof<P>,
     ) -> Result<bool, MerkleForestError> {
-        let (root, adjusted_index) = {
-            let n = next_power_of_q(n, num_leaves as usize);
-            let diff = n - proof.leaf_index - 1;
-            let diff = std::cmp::max(diff, 1); // handle the special case that leaf_index == n-1
-            let state_idx = diff.ilog(num_leaves as usize);
-            let idx_within_tree = {
-                // calculate the position of the leaf at the (state_idx)-th level (indexed from 0)
-                proof.leaf_index / num_leaves.pow(state_idx) as usize % num_leaves as usize
-            };
+        let tree_height = tree_height::<P>(proof.num_leaves_per_tree)
+            .ok_or(MerkleForestError::InvalidNumLeavesPerTree)?;
+        let siblings_per_tree = tree_height * (arity::<P>() - 1);
+        if proof.siblings.len() % siblings_per_tree != 0 {
+            return Err(MerkleForestError::InvalidProofLength);
+        }
+        if proof.root != *root {
+            return Ok(false);
+        }
 
-            (
-                states[state_idx as usize].leaves()[idx_within_tree],
-                proof.leaf_index,
-            )
+        let hash = match leaf {
+            Either::Left(v) => v.clone(),
+            Either::Right(v) => P::LeafHash::evaluate(params, v).map_err(MerkleTreeError::from)?,
         };
 
+        // the leaf is a leaf of the live tree when the proof is empty
+        let num_tree = proof.siblings.len() / siblings_per_tree;
+        if num_tree == 0 {
+            return Ok(hash == proof.root);
+        }
+
+        // the siblings only cover the trees below the root, so the index is
+        // taken within them
+        let leaf_index = u32::try_from(num_tree)
+            .ok()
+            .and_then(|num_tree| proof.num_leaves_per_tree.checked_pow(num_tree))
+            .map_or(proof.leaf_index, |max_leaves| proof.leaf_index % max_leaves);
+
         Self::verify(
             params,
-            root,
-            leaf,
+            proof.root,
+            Either::Left(&hash),
             MerkleForestProof {
                 siblings: proof.siblings,
-                leaf_index: adjusted_index,
+                leaf_index,
                 num_leaves_per_tree: proof.num_leaves_per_tree,
             },
         )
     }
 
-    pub fn root(&self) -> P::BasePrimeField {
+    pub fn root(&self) -> Node<P> {
         self.trees
             .last()
             .expect("forest should not be empty")
             .root()
     }
 
-    pub fn states(&self) -> &[MerkleTree<P>] {
+    /// The live tree of each level, from the leaves up to the root.
+    pub fn trees(&self) -> &[SparseMerkleTree<P>] {
         &self.trees
     }
 
+    /// The snapshots of each level, keyed by the index of the tree within the
+    /// level. These are what `prove` and `prove_variable` read the siblings from.
+    pub fn states(&self) -> &[HashMap<usize, SparseMerkleTree<'a, P>>] {
+        &self.states
+    }
+
+    /// The leaf hashes in insertion order. Removed leaves are yielded as the
+    /// default hash.
+    pub fn leaves(&self) -> impl Iterator<Item = Node<P>> + '_ {
+        let num_leaves_per_tree = self.num_leaves_per_tree() as usize;
+        (0..self.size)
+            .map(move |i| self.snapshot_leaf(0, i / num_leaves_per_tree, i % num_leaves_per_tree))
+    }
+
+    /// The root of the forest when it held the first `size` leaves, or `None`
+    /// if `size` is 0 or larger than the current size.
+    ///
+    /// Only the last tree of each level was incomplete back then, so it is
+    /// rebuilt from the snapshots while the other trees are read as is.
+    /// Removed leaves count as the default hash, as in `remove`.
+    pub fn root_at(&self, size: usize) -> Option<Node<P>> {
+        if size == 0 || size > self.size {
+            return None;
+        }
+
+        let num_leaves_per_tree = self.num_leaves_per_tree() as usize;
+
+        // number of nodes at the current level and the root of the last tree
+        // of the level below
+        let mut count = size;
+        let mut last_root = None;
+        for i in 0..self.trees.len() {
+            let idx = (count - 1) / num_leaves_per_tree;
+            let last = (count - 1) % num_leaves_per_tree;
+
+            // Positions after `last` still hold the nodes of the previous tree,
+            // as the live tree is reused when moving to the next one.
+            let updates: Vec<_> = (0..num_leaves_per_tree)
+                .map(|pos| {
+                    let hash = match (pos.cmp(&last), &last_root) {
+                        (std::cmp::Ordering::Equal, Some(root)) => Node::<P>::clone(root),
+                        (std::cmp::Ordering::Greater, _) if idx == 0 => Node::<P>::default(),
+                        (std::cmp::Ordering::Greater, _) => self.snapshot_leaf(i, idx - 1, pos),
+                        _ => self.snapshot_leaf(i, idx, pos),
+                    };
+                    (pos, hash)
+                })
+                .collect();
+
+            let mut tree = self.trees[i].clone();
+            tree.update_batch_with_hash(&updates)
+                .expect("positions are within the tree");
+            last_root = Some(tree.root());
+            count = idx + 1;
+        }
+
+        last_root
+    }
+
+    /// The leaf at `pos` of the snapshot of the `idx`-th tree of `level`, where
+    /// a missing snapshot means that all its leaves have been removed.
+    fn snapshot_leaf(&self, level: usize, idx: usize, pos: usize) -> Node<P> {
+        self.states[level]
+            .get(&idx)
+            .map_or_else(Node::<P>::default, |tree| tree.leaf(pos))
+    }
+
+    /// Record that the leaves from `start` on hold `leaves`, skipping the
+    /// removed ones.
+    fn index_leaves(&mut self, start: usize, leaves: impl IntoIterator<Item = Node<P>>) {
+        for (i, leaf) in (start..).zip(leaves) {
+            if !self.removed.contains(&i) {
+                self.leaf_indices.entry(leaf).or_default().push(i);
+            }
+        }
+    }
+
     #[inline]
     pub fn max_leaves(&self) -> usize {
         // safe conversion as trees.len() is limited to be <= 2^32 - 1
@@ -291,11 +783,98 @@ impl<'a, P: MerkleConfig> LeveledMerkleForest<'a, P> {
         self.size
     }
 
+    /// The parameters `(capacity_per_tree, num_tree)` of a binary forest for
+    /// `n` leaves that minimize `objective`, see `optimal_forest_params_for`.
+    #[must_use]
+    pub fn optimal_params_for(objective: ForestObjective, n: usize) -> (u32, u32) {
+        optimal_forest_params_for(objective, n)
+    }
+
+    /// The size estimates of this forest, see `forest_stats`. Only binary
+    /// forests are supported.
+    #[must_use]
+    pub fn stats(&self) -> ForestStats {
+        assert_eq!(arity::<P>(), 2, "forest_stats assumes binary trees");
+        forest_stats(self.capacity_per_tree(), self.num_trees())
+    }
+
+    /// Append `vals` to the forest.
+    ///
+    /// This is equivalent to adding the values one by one, including the
+    /// snapshots kept for `prove_variable`, but every affected node of each
+    /// tree is hashed only once.
+    ///
+    /// With the `parallel` feature, the trees of a level that are filled
+    /// completely by the batch are built on several threads, as they do not
+    /// depend on each other.
+    pub fn add_batch(&mut self, vals: &[&LeafInput<P>]) -> Result<(), MerkleForestError> {
+        if vals.len() > self.max_leaves() - self.size {
+            return Err(MerkleForestError::ForestIsFull);
+        }
+        if vals.is_empty() {
+            return Ok(());
+        }
+
+        let num_leaves_per_tree = self.num_leaves_per_tree() as usize;
+
+        let leaves = hash_leaves::<P>(self.trees[0].params(), vals)?;
+        // (index at the current level, hash) for every updated node
+        let mut nodes: Vec<_> = (self.size..).zip(leaves.iter().cloned()).collect();
+
+        for i in 0..self.trees.len() {
+            let groups: Vec<_> = nodes
+                .chunk_by(|a, b| a.0 / num_leaves_per_tree == b.0 / num_leaves_per_tree)
+                .map(|group| {
+                    let idx = group[0].0 / num_leaves_per_tree;
+                    let updates: Vec<_> = group
+                        .iter()
+                        .map(|(j, hash)| (j % num_leaves_per_tree, hash.clone()))
+                        .collect();
+                    (idx, updates)
+                })
+                .collect();
+
+            // Only the first and the last tree can be partially filled: the
+            // first one continues from the live tree, and the last one keeps
+            // the nodes of the one before it.
+            let (first_idx, first_updates) = &groups[0];
+            self.trees[i].update_batch_with_hash(first_updates)?;
+            let mut trees = vec![(*first_idx, self.trees[i].clone())];
+
+            if let Some(((last_idx, last_updates), middle)) = groups[1..].split_last() {
+                let live = &self.trees[i];
+                let middle = cfg_iter!(middle)
+                    .map(|(idx, updates)| {
+                        let mut tree = live.clone();
+                        tree.update_batch_with_hash(updates)?;
+                        Ok((*idx, tree))
+                    })
+                    .collect::<Result<Vec<_>, MerkleTreeError>>()?;
+                trees.extend(middle);
+
+                let mut tree = trees.last().expect("first tree is present").1.clone();
+                tree.update_batch_with_hash(last_updates)?;
+                trees.push((*last_idx, tree));
+            }
+
+            self.trees[i] = trees.last().expect("first tree is present").1.clone();
+            nodes = trees
+                .into_iter()
+                .map(|(idx, tree)| {
+                    let root = tree.root();
+                    self.states[i].insert(idx, tree);
+                    (idx, root)
+                })
+                .collect();
+        }
+
+        self.index_leaves(self.size, leaves);
+        self.size += vals.len();
+        Ok(())
+    }
+
     // for the `Construct-Naive` algorithm in the thesis
-    fn seqadd(
-        &mut self,
-        val: &<Poseidon<P::BasePrimeField> as CRHScheme>::Input,
-    ) -> Result<(), MerkleForestError> {
+    fn seqadd(&mut self, val: &LeafInput<P>) -> Result<(), MerkleForestError> {
         if self.size == self.max_leaves() {
             return Err(MerkleForestError::ForestIsFull);
         }
@@ -303,6 +882,7 @@ impl<'a, P: MerkleConfig> LeveledMerkleForest<'a, P> {
         // update Merkle trees
         let num_leaves_per_tree = self.num_leaves_per_tree() as usize;
         self.trees[0].update(self.size % num_leaves_per_tree, val)?;
+        let leaf = self.trees[0].leaf(self.size % num_leaves_per_tree);
         let mut node = self.trees[0].root();
         let mut idx = self.size / num_leaves_per_tree;
         for i in 1..self.trees.len() {
@@ -318,6 +898,7 @@ impl<'a, P: MerkleConfig> LeveledMerkleForest<'a, P> {
             idx /= num_leaves_per_tree;
         }
 
+        self.index_leaves(self.size, [leaf]);
         self.size += 1;
         Ok(())
     }
@@ -325,27 +906,30 @@ impl<'a, P: MerkleConfig> LeveledMerkleForest<'a, P> {
     fn new(
         capacity_per_tree: u32,
         num_tree: u32,
-        params: &'a PoseidonConfig<P::BasePrimeField>,
+        params: &'a HashParams<P>,
     ) -> Result<Self, MerkleForestError> {
         if num_tree == 0 {
             return Err(MerkleForestError::InvalidNumTree);
         }
 
-        let trees = vec![MerkleTree::new(capacity_per_tree as usize, params)?; num_tree as usize];
+        let trees =
+            vec![SparseMerkleTree::new(capacity_per_tree as usize, params)?; num_tree as usize];
         let states = vec![HashMap::new(); num_tree as usize];
 
         Ok(Self {
             trees,
             states,
             size: 0,
+            removed: BTreeSet::new(),
+            leaf_indices: HashMap::new(),
         })
     }
 
     // the `Construct-Naive` algorithm in the thesis
     #[allow(dead_code)]
     fn new_with_data_naive(
-        data: &[&<Poseidon<P::BasePrimeField> as CRHScheme>::Input],
-        params: &'a PoseidonConfig<P::BasePrimeField>,
+        data: &[&LeafInput<P>],
+        params: &'a HashParams<P>,
     ) -> Result<Self, MerkleForestError> {
         let mut s = Self::new_optimal(data.len(), params)?;
 
@@ -357,9 +941,78 @@ impl<'a, P: MerkleConfig> LeveledMerkleForest<'a, P> {
     }
 }
 
+/// Only the trees, their snapshots, the size and the removed leaves are serialized, see
+/// `LeveledMerkleForest::deserialize_with_params`.
+impl<P: MerkleConfig> CanonicalSerialize for LeveledMerkleForest<'_, P> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.trees.serialize_with_mode(&mut writer, compress)?;
+        for states in &self.states {
+            // sort the snapshots to make the serialization deterministic
+            let mut states: Vec<_> = states.iter().collect();
+            states.sort_unstable_by_key(|(idx, _)| **idx);
+
+            states.len().serialize_with_mode(&mut writer, compress)?;
+            for (idx, tree) in states {
+                idx.serialize_with_mode(&mut writer, compress)?;
+                tree.serialize_with_mode(&mut writer, compress)?;
+            }
+        }
+        self.size.serialize_with_mode(&mut writer, compress)?;
+        self.removed.serialize_with_mode(writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        let states_size: usize = self
+            .states
+            .iter()
+            .map(|states| {
+                states.len().serialized_size(compress)
+                    + states
+                        .iter()
+                        .map(|(idx, tree)| {
+                            idx.serialized_size(compress) + tree.serialized_size(compress)
+                        })
+                        .sum::<usize>()
+            })
+            .sum();
+
+        self.trees.serialized_size(compress)
+            + states_size
+            + self.size.serialized_size(compress)
+            + self.removed.serialized_size(compress)
+    }
+}
+
+/// Size estimates of a forest, measured in the number of field elements.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ForestStats {
+    pub proof_size: u64,
+    pub forest_state_size: u64,
+    pub max_permanent_state_size: u128,
+    /// Size of a plain merkle tree holding the same number of leaves
+    pub plain_tree_size: u64,
+}
+
+impl std::fmt::Display for ForestStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "proof size: {}", self.proof_size)?;
+        writeln!(f, "forest state size: {}", self.forest_state_size)?;
+        writeln!(
+            f,
+            "max permanent state size: {}",
+            self.max_permanent_state_size
+        )?;
+        write!(f, "plain merkle tree size: {}", self.plain_tree_size)
+    }
+}
+
 #[allow(clippy::cast_precision_loss)]
 #[allow(clippy::cast_sign_loss)]
-pub fn forest_stats(capacity_per_tree: u32, num_tree: u32) -> (u64, u64, u128) {
+pub fn forest_stats(capacity_per_tree: u32, num_tree: u32) -> ForestStats {
     // reserve space for mul
     let capacity_per_tree = u64::from(capacity_per_tree);
     let num_tree = u64::from(num_tree);
@@ -386,14 +1039,6 @@ pub fn forest_stats(capacity_per_tree: u32, num_tree: u32) -> (u64, u64, u128) {
             / (1. - r)
             - 1.);
 
-    println!(
-        "proof size: {}",
-        u64::from(((capacity_per_tree + 1) / 2).ilog2()) * num_tree
-    );
-    println!("forest state size: {}", forest_state_size);
-    println!("max permanent state size: {}", max_permanent_state_size);
-    println!("plain merkle tree size: {}", 2 * n - 1);
-
     let max_permanent_state_size = max_permanent_state_size.ceil();
     #[allow(clippy::cast_possible_truncation)]
     let max_permanent_state_size_r = max_permanent_state_size.ceil() as u128;
@@ -402,23 +1047,104 @@ pub fn forest_stats(capacity_per_tree: u32, num_tree: u32) -> (u64, u64, u128) {
         "max_permanent_state_size is too large for u128"
     );
 
-    (proof_size, forest_state_size, max_permanent_state_size_r)
+    ForestStats {
+        proof_size,
+        forest_state_size,
+        max_permanent_state_size: max_permanent_state_size_r,
+        plain_tree_size: 2 * n - 1,
+    }
+}
+
+/// Height of the trees of a forest with `num_leaves_per_tree` leaves per
+/// tree, or `None` if it is not a power of `P::ARITY` that is at least `P::ARITY`.
+pub(crate) fn tree_height<P: MerkleConfig>(num_leaves_per_tree: usize) -> Option<usize> {
+    let bits_per_level = arity::<P>().ilog2();
+    (num_leaves_per_tree >= 2
+        && num_leaves_per_tree.is_power_of_two()
+        && num_leaves_per_tree.ilog2() % bits_per_level == 0)
+        .then(|| (num_leaves_per_tree.ilog2() / bits_per_level) as usize)
 }
 
 /// Find the optimal forest parameters for a given `n` with respect to the forest state size
 pub fn optimal_forest_params(n: usize) -> (u32, u32) {
+    optimal_forest_params_for_arity(n, 2)
+}
+
+/// What `optimal_forest_params_for` minimizes, using the sizes of `ForestStats`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ForestObjective {
+    /// The forest state size, as in `optimal_forest_params`.
+    StateSize,
+    /// The proof size, with ties broken by the forest state size.
+    ProofSize,
+    /// `weight * proof_size + (1 - weight) * forest_state_size`, where
+    /// `weight` is in `[0, 1]`.
+    Balanced { weight: f64 },
+}
+
+/// Find the binary forest parameters `(capacity_per_tree, num_tree)` for `n`
+/// leaves that minimize `objective`.
+///
+/// Except for `StateSize`, this searches the trees with `2^b` leaves for every
+/// `b` up to `ceil(log2(n))`, using `ceil(log2(n) / b)` of them.
+pub fn optimal_forest_params_for(objective: ForestObjective, n: usize) -> (u32, u32) {
+    let weight = match objective {
+        ForestObjective::StateSize => return optimal_forest_params(n),
+        ForestObjective::ProofSize => 1.,
+        ForestObjective::Balanced { weight } => {
+            assert!((0. ..=1.).contains(&weight), "weight should be in [0, 1]");
+            weight
+        }
+    };
+
+    let bits = n.next_power_of_two().ilog2();
+    let candidates = (1..=bits.clamp(1, 31)).map(|bits_per_tree| {
+        // 2^(bits_per_tree + 1) - 1 without overflowing for 31 bits
+        let capacity_per_tree = u32::MAX >> (31 - bits_per_tree);
+        let num_tree = std::cmp::max(1, bits.div_ceil(bits_per_tree));
+        let proof_size = u64::from(bits_per_tree * num_tree);
+        let forest_state_size = u64::from(capacity_per_tree) * u64::from(num_tree);
+        ((capacity_per_tree, num_tree), proof_size, forest_state_size)
+    });
+
+    #[allow(clippy::cast_precision_loss)]
+    let cost = |proof_size: u64, forest_state_size: u64| {
+        weight * proof_size as f64 + (1. - weight) * forest_state_size as f64
+    };
+    candidates
+        .min_by(|(_, p1, s1), (_, p2, s2)| {
+            cost(*p1, *s1)
+                .total_cmp(&cost(*p2, *s2))
+                .then_with(|| s1.cmp(s2))
+                .then_with(|| p1.cmp(p2))
+        })
+        .map(|(params, ..)| params)
+        .expect("there is at least one candidate")
+}
+
+/// Same as `optimal_forest_params`, but for trees whose nodes have `arity`
+/// children, which should be a power of 2.
+pub fn optimal_forest_params_for_arity(n: usize, arity: usize) -> (u32, u32) {
+    assert!(
+        arity >= 2 && arity.is_power_of_two(),
+        "arity should be a power of 2"
+    );
+    let arity = u32::try_from(arity).expect("arity is too large for u32");
     let n = int_to_safe_float(n as u64);
 
-    // minimize log2(N)/log2(q/2)*q with respect to q
-    let q = 2. * std::f64::consts::E;
-    // safe: as q = 2e
-    #[allow(clippy::cast_possible_truncation)]
-    #[allow(clippy::cast_sign_loss)]
-    let q = (q.ceil() as u32).next_power_of_two() - 1;
+    // A tree with `m` leaves has `q = (arity * m - 1) / (arity - 1)` nodes, so
+    // minimize log(N)/log(m)*q with respect to m, i.e. take the first power of
+    // `arity` that is >= e.
+    let mut num_leaves = arity;
+    while f64::from(num_leaves) < std::f64::consts::E {
+        num_leaves *= arity;
+    }
+    let q = (arity * num_leaves - 1) / (arity - 1);
 
     // safe: as n (float) >= n (uint)
+    // `q * (arity - 1) / arity` slightly underestimates the number of leaves
     #[allow(clippy::cast_precision_loss)]
-    let k = n.log(f64::from(q) / 2.);
+    let k = n.log(f64::from(q) * f64::from(arity - 1) / f64::from(arity));
 
     let k = k.ceil();
     #[allow(clippy::cast_possible_truncation)]
@@ -460,21 +1186,89 @@ const fn next_power_of_q(n: usize, q: usize) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::params::poseidon_canonical_config;
     use ark_bls12_381::Fr;
+    use ark_crypto_primitives::{
+        crh::poseidon::{TwoToOneCRH as PoseidonTwoToOne, CRH as Poseidon},
+        sponge::poseidon::PoseidonConfig,
+    };
     use ark_ff::UniformRand;
-    use folding_schemes::transcript::poseidon::poseidon_canonical_config;
-    use rand::thread_rng;
+    use rand::{rngs::StdRng, thread_rng, SeedableRng};
     use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
     struct TestConfig;
     impl MerkleConfig for TestConfig {
         type BasePrimeField = Fr;
+        type LeafHash = Poseidon<Fr>;
+        type TwoToOneHash = PoseidonTwoToOne<Fr>;
     }
 
     fn poseidon_params() -> PoseidonConfig<Fr> {
         poseidon_canonical_config::<Fr>()
     }
 
+    fn assert_same_forest(
+        a: &LeveledMerkleForest<TestConfig>,
+        b: &LeveledMerkleForest<TestConfig>,
+    ) {
+        assert_eq!(a.root(), b.root());
+        assert_eq!(a.size(), b.size());
+        for (states_a, states_b) in a.states.iter().zip(&b.states) {
+            assert_eq!(states_a.len(), states_b.len());
+            for (idx, tree) in states_a {
+                assert!(tree.leaves().eq(states_b[idx].leaves()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_add_batch() {
+        use crate::merkle::tree::NUM_NODE_HASHES;
+
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        for n in [1, 7, 64] {
+            let values: Vec<_> = (0..n).map(|_| [Fr::rand(&mut rng)]).collect();
+            let values_ref: Vec<_> = values.iter().map(|v| &v[..]).collect();
+
+            let mut sequential =
+                LeveledMerkleForest::<TestConfig>::new_optimal(n, &params).unwrap();
+            NUM_NODE_HASHES.with(|c| c.set(0));
+            for v in &values_ref {
+                sequential.seqadd(v).unwrap();
+            }
+            let sequential_hashes = NUM_NODE_HASHES.with(|c| c.get());
+
+            let mut batch = LeveledMerkleForest::<TestConfig>::new_optimal(n, &params).unwrap();
+            NUM_NODE_HASHES.with(|c| c.set(0));
+            batch.add_batch(&values_ref).unwrap();
+            let batch_hashes = NUM_NODE_HASHES.with(|c| c.get());
+
+            assert_same_forest(&batch, &sequential);
+            if n == 1 {
+                assert_eq!(batch_hashes, sequential_hashes);
+            } else {
+                assert!(batch_hashes < sequential_hashes);
+            }
+
+            // batches can start in the middle of a tree
+            let mut split = LeveledMerkleForest::<TestConfig>::new_optimal(n, &params).unwrap();
+            let (first, second) = values_ref.split_at(n / 2);
+            split.add_batch(first).unwrap();
+            split.add_batch(second).unwrap();
+            assert_same_forest(&split, &sequential);
+
+            // and the forest rejects a batch that does not fit
+            let remaining = batch.max_leaves() - batch.size();
+            let extra = vec![&values[0][..]; remaining + 1];
+            assert!(matches!(
+                batch.add_batch(&extra),
+                Err(MerkleForestError::ForestIsFull)
+            ));
+        }
+    }
+
     #[test]
     fn test_merkles_forest_creation_single_tree() {
         let params = poseidon_params(); // Use any appropriate field
@@ -663,9 +1457,7 @@ mod tests {
         // Verify the proof
         let verify_result = LeveledMerkleForest::<TestConfig>::verify_variable(
             &params,
-            forest.states(),
-            forest.size(),
-            forest.num_leaves_per_tree(),
+            &forest.variable_root(leaf_index).unwrap(),
             either::Right(&[values[leaf_index]]),
             proof,
         );
@@ -703,9 +1495,7 @@ mod tests {
         // Verify the proof
         let verify_result = LeveledMerkleForest::<TestConfig>::verify_variable(
             &params,
-            forest.states(),
-            forest.size(),
-            forest.num_leaves_per_tree(),
+            &forest.variable_root(leaf_index).unwrap(),
             either::Right(&[values[leaf_index]]),
             proof,
         );
@@ -713,6 +1503,192 @@ mod tests {
         assert_eq!(verify_result.unwrap(), true);
     }
 
+    #[test]
+    fn test_verify_variable_tampered_root() {
+        let params = poseidon_params();
+        let mut rng = StdRng::from_seed([42; 32]);
+        let mut forest = LeveledMerkleForest::<TestConfig>::new(7, 3, &params).unwrap();
+
+        let values: Vec<_> = (0..21).map(|_| [Fr::rand(&mut rng)]).collect();
+        for v in &values {
+            forest.seqadd(v).unwrap();
+        }
+
+        for i in [0, 10, 20] {
+            let root = forest.variable_root(i).unwrap();
+            let proof = forest.prove_variable(i).unwrap();
+            assert_eq!(proof.root, root);
+            assert!(LeveledMerkleForest::<TestConfig>::verify_variable(
+                &params,
+                &root,
+                either::Right(&values[i]),
+                proof,
+            )
+            .unwrap());
+
+            // a root that the verifier does not hold is rejected, even if the
+            // siblings hash up to it
+            let mut proof = forest.prove_variable(i).unwrap();
+            let fake_root = forest.variable_root(i).unwrap() + Fr::from(1);
+            proof.root = fake_root;
+            assert!(!LeveledMerkleForest::<TestConfig>::verify_variable(
+                &params,
+                &root,
+                either::Right(&values[i]),
+                proof,
+            )
+            .unwrap());
+
+            // and so is a proof that does not hash up to the trusted root
+            let mut proof = forest.prove_variable(i).unwrap();
+            proof.root = fake_root;
+            assert!(!LeveledMerkleForest::<TestConfig>::verify_variable(
+                &params,
+                &fake_root,
+                either::Right(&values[i]),
+                proof,
+            )
+            .unwrap());
+        }
+    }
+
+    #[test]
+    fn test_prove_and_verify_stale_root() {
+        let params = poseidon_params();
+        let mut rng = StdRng::from_seed([42; 32]);
+        let mut forest = LeveledMerkleForest::<TestConfig>::new(7, 3, &params).unwrap();
+
+        let values: Vec<_> = (0..10).map(|_| [Fr::rand(&mut rng)]).collect();
+        for v in &values[..9] {
+            forest.seqadd(v).unwrap();
+        }
+
+        // the proof is made of the proofs of every tree on the path
+        let proof = forest.prove(5).unwrap();
+        let first_tree = forest.states[0][&1].prove(1).unwrap();
+        assert_eq!(proof.siblings[..2], first_tree.siblings[..]);
+
+        let stale_root = forest.root();
+        assert!(LeveledMerkleForest::<TestConfig>::verify(
+            &params,
+            stale_root,
+            either::Right(&values[5]),
+            forest.prove(5).unwrap(),
+        )
+        .unwrap());
+
+        forest.seqadd(&values[9]).unwrap();
+        assert!(!LeveledMerkleForest::<TestConfig>::verify(
+            &params,
+            stale_root,
+            either::Right(&values[5]),
+            forest.prove(5).unwrap(),
+        )
+        .unwrap());
+        assert!(LeveledMerkleForest::<TestConfig>::verify(
+            &params,
+            forest.root(),
+            either::Right(&values[5]),
+            forest.prove(5).unwrap(),
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn test_remove() {
+        let params = poseidon_params();
+        let mut rng = StdRng::from_seed([42; 32]);
+
+        let n = 40;
+        let values: Vec<_> = (0..n).map(|_| [Fr::rand(&mut rng)]).collect();
+        let mut forest = LeveledMerkleForest::<TestConfig>::new_optimal(n, &params).unwrap();
+        let num_leaves_per_tree = forest.num_leaves_per_tree() as usize;
+
+        // interleave adds and removes, including leaves that are still
+        // referenced by the following snapshot and a whole first-level tree
+        let mut removed = vec![];
+        for (i, v) in values.iter().enumerate() {
+            forest.seqadd(v).unwrap();
+            if i % 3 == 1 {
+                forest.remove(i - 1).unwrap();
+                removed.push(i - 1);
+            }
+        }
+        for i in 0..num_leaves_per_tree {
+            if !removed.contains(&i) {
+                forest.remove(i).unwrap();
+                removed.push(i);
+            }
+        }
+        forest.remove(n - 1).unwrap();
+        removed.push(n - 1);
+        assert!(!forest.states[0].contains_key(&0));
+
+        let leaves: Vec<_> = values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                if removed.contains(&i) {
+                    Fr::default()
+                } else {
+                    Poseidon::evaluate(&params, &v[..]).unwrap()
+                }
+            })
+            .collect();
+        let expected =
+            LeveledMerkleForest::<TestConfig>::new_with_data(either::Left(&leaves), &params)
+                .unwrap();
+        assert_eq!(forest.root(), expected.root());
+        assert_eq!(forest.size(), n);
+
+        for i in 0..n {
+            if removed.contains(&i) {
+                assert!(matches!(
+                    forest.prove(i),
+                    Err(MerkleForestError::LeafRemoved)
+                ));
+                assert!(matches!(
+                    forest.prove_variable(i),
+                    Err(MerkleForestError::LeafRemoved)
+                ));
+            } else {
+                let proof = forest.prove(i).unwrap();
+                assert!(LeveledMerkleForest::<TestConfig>::verify(
+                    &params,
+                    forest.root(),
+                    either::Right(&values[i]),
+                    proof,
+                )
+                .unwrap());
+            }
+        }
+
+        assert!(matches!(
+            forest.remove(removed[0]),
+            Err(MerkleForestError::LeafRemoved)
+        ));
+        assert!(matches!(
+            forest.remove(n),
+            Err(MerkleForestError::IndexOutOfBound)
+        ));
+
+        // removed leaves survive serialization
+        let mut bytes = vec![];
+        forest.serialize_compressed(&mut bytes).unwrap();
+        let deserialized = LeveledMerkleForest::<TestConfig>::deserialize_with_params(
+            &*bytes,
+            Compress::Yes,
+            Validate::Yes,
+            &params,
+        )
+        .unwrap();
+        assert_eq!(deserialized.root(), forest.root());
+        assert!(matches!(
+            deserialized.prove(removed[0]),
+            Err(MerkleForestError::LeafRemoved)
+        ));
+    }
+
     #[test]
     fn test_prove_out_of_bound() {
         let params = poseidon_params();
@@ -738,6 +1714,171 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_find_and_prove() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        let [a, b] = [[Fr::rand(&mut rng)], [Fr::rand(&mut rng)]];
+        let values = [a, b, a, a, b, a];
+        let values_ref: Vec<_> = values.iter().map(|v| &v[..]).collect();
+        let hash = |v: &[Fr; 1]| Poseidon::<Fr>::evaluate(&params, &v[..]).unwrap();
+        let leaf_indices = |forest: &LeveledMerkleForest<TestConfig>, v| {
+            let proofs = forest.find_and_prove(&hash(v)).unwrap();
+            for proof in &proofs {
+                assert!(LeveledMerkleForest::verify(
+                    &params,
+                    forest.root(),
+                    either::Right(&v[..]),
+                    MerkleForestProof {
+                        siblings: proof.siblings.clone(),
+                        leaf_index: proof.leaf_index,
+                        num_leaves_per_tree: proof.num_leaves_per_tree,
+                    },
+                )
+                .unwrap());
+            }
+            proofs.iter().map(|p| p.leaf_index).collect::<Vec<_>>()
+        };
+
+        // the index is the same whether the leaves are added in one batch,
+        // in several, or one by one
+        let mut forest = LeveledMerkleForest::<TestConfig>::new_optimal(16, &params).unwrap();
+        forest.add_batch(&values_ref[..4]).unwrap();
+        forest.seqadd(&a).unwrap();
+        forest.add_batch(&values_ref[4..]).unwrap();
+        assert_eq!(leaf_indices(&forest, &a), vec![0, 2, 3, 4, 6]);
+        assert_eq!(leaf_indices(&forest, &b), vec![1, 5]);
+
+        let with_data =
+            LeveledMerkleForest::<TestConfig>::new_with_data(either::Right(&values_ref), &params)
+                .unwrap();
+        assert_eq!(leaf_indices(&with_data, &a), vec![0, 2, 3, 5]);
+
+        // absent values have no proof
+        assert!(forest
+            .find_and_prove(&hash(&[Fr::from(7)]))
+            .unwrap()
+            .is_empty());
+
+        // removed leaves are dropped from the index
+        forest.remove(2).unwrap();
+        forest.remove(1).unwrap();
+        forest.remove(5).unwrap();
+        assert_eq!(leaf_indices(&forest, &a), vec![0, 3, 4, 6]);
+        assert!(leaf_indices(&forest, &b).is_empty());
+
+        // and the index is rebuilt on deserialization
+        let mut bytes = vec![];
+        forest.serialize_compressed(&mut bytes).unwrap();
+        let deserialized = LeveledMerkleForest::<TestConfig>::deserialize_with_params(
+            &*bytes,
+            Compress::Yes,
+            Validate::Yes,
+            &params,
+        )
+        .unwrap();
+        assert_eq!(deserialized.leaf_indices, forest.leaf_indices);
+    }
+
+    #[test]
+    fn test_serialize() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        let values: Vec<_> = (0..40).map(|_| [Fr::rand(&mut rng)]).collect();
+        let values_ref: Vec<_> = values.iter().map(|v| &v[..]).collect();
+        let (first, second) = values_ref.split_at(25);
+
+        let mut forest =
+            LeveledMerkleForest::<TestConfig>::new_optimal(values.len(), &params).unwrap();
+        forest.add_batch(first).unwrap();
+
+        let mut bytes = vec![];
+        forest.serialize_compressed(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), forest.compressed_size());
+
+        let mut deserialized = LeveledMerkleForest::<TestConfig>::deserialize_with_params(
+            &*bytes,
+            Compress::Yes,
+            Validate::Yes,
+            &params,
+        )
+        .unwrap();
+        assert_same_forest(&deserialized, &forest);
+        for i in 0..forest.size() {
+            assert_eq!(
+                deserialized.prove(i).unwrap().siblings,
+                forest.prove(i).unwrap().siblings
+            );
+            assert_eq!(
+                deserialized.prove_variable(i).unwrap().siblings,
+                forest.prove_variable(i).unwrap().siblings
+            );
+        }
+
+        // adding continues from the persisted size
+        deserialized.add_batch(second).unwrap();
+        forest.add_batch(second).unwrap();
+        assert_same_forest(&deserialized, &forest);
+
+        // and the proofs roundtrip as well
+        let mut bytes = vec![];
+        deserialized
+            .prove(30)
+            .unwrap()
+            .serialize_compressed(&mut bytes)
+            .unwrap();
+        let proof = MerkleForestProof::<TestConfig>::deserialize_compressed(&*bytes).unwrap();
+        assert!(LeveledMerkleForest::verify(
+            &params,
+            deserialized.root(),
+            either::Right(values_ref[30]),
+            proof
+        )
+        .unwrap());
+
+        let mut bytes = vec![];
+        deserialized
+            .prove_variable(30)
+            .unwrap()
+            .serialize_compressed(&mut bytes)
+            .unwrap();
+        let proof =
+            MerkleForestVariableLengthProof::<TestConfig>::deserialize_compressed(&*bytes).unwrap();
+        assert!(LeveledMerkleForest::verify_variable(
+            &params,
+            &deserialized.variable_root(30).unwrap(),
+            either::Right(values_ref[30]),
+            proof
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn test_new_optimal_large() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        // trees are sparse, so only the added leaves cost memory and hashes
+        let mut forest = LeveledMerkleForest::<TestConfig>::new_optimal(1 << 25, &params).unwrap();
+        let values: Vec<_> = (0..4).map(|_| [Fr::rand(&mut rng)]).collect();
+        for v in &values {
+            forest.seqadd(v).unwrap();
+        }
+
+        for (i, v) in values.iter().enumerate() {
+            let proof = forest.prove(i).unwrap();
+            assert!(LeveledMerkleForest::verify(
+                &params,
+                forest.root(),
+                either::Right(&v[..]),
+                proof
+            )
+            .unwrap());
+        }
+    }
+
     #[test]
     fn test_invalid_num_tree() {
         let params = poseidon_params();
@@ -748,6 +1889,60 @@ mod tests {
         assert!(matches!(forest, Err(MerkleForestError::InvalidNumTree)));
     }
 
+    #[test]
+    fn test_tree_error_propagation() {
+        use std::error::Error;
+
+        let params = poseidon_params();
+
+        // the tree error is wrapped as is, and displayed after the forest's message
+        let err = LeveledMerkleForest::<TestConfig>::new(8, 2, &params).unwrap_err();
+        assert!(matches!(
+            err,
+            MerkleForestError::MerkleTreeError(MerkleTreeError::InvalidCapacity)
+        ));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Merkle tree error occurred: {}",
+                MerkleTreeError::InvalidCapacity
+            )
+        );
+        assert!(err.source().unwrap().is::<MerkleTreeError>());
+
+        // hash failures in `verify` keep the underlying CRH error
+        struct BrokenConfig;
+        impl MerkleConfig for BrokenConfig {
+            type BasePrimeField = Fr;
+            type LeafHash = Poseidon<Fr>;
+            type TwoToOneHash = PoseidonTwoToOne<Fr>;
+            const ARITY: usize = 4;
+        }
+        let proof = MerkleForestProof::<BrokenConfig> {
+            siblings: vec![Fr::from(0); 3],
+            leaf_index: 1,
+            num_leaves_per_tree: 4,
+        };
+        let err = LeveledMerkleForest::<BrokenConfig>::verify(
+            &params,
+            Fr::from(0),
+            Either::Left(&Fr::from(1)),
+            proof,
+        )
+        .unwrap_err();
+        let MerkleForestError::MerkleTreeError(MerkleTreeError::CRHError(source)) = &err else {
+            panic!("expected a CRH error, got {err:?}");
+        };
+        assert_eq!(
+            source.to_string(),
+            "TwoToOneHash only compresses 2 children"
+        );
+        assert_eq!(
+            err.source().unwrap().source().unwrap().to_string(),
+            source.to_string()
+        );
+    }
+
     #[test]
     fn test_forest_stats() {
         let params = poseidon_params();
@@ -758,7 +1953,11 @@ mod tests {
             LeveledMerkleForest::<TestConfig>::new(capacity_per_tree, num_tree, &params).unwrap();
         forest.seqadd(&[Fr::default()]).unwrap();
 
-        let (proof_size, _, max_permanent_state_size) = forest_stats(capacity_per_tree, num_tree);
+        let ForestStats {
+            proof_size,
+            max_permanent_state_size,
+            ..
+        } = forest_stats(capacity_per_tree, num_tree);
 
         let proof = forest.prove(0).unwrap();
         assert_eq!(proof_size as usize, proof.siblings.len());
@@ -831,9 +2030,7 @@ mod tests {
 
             let valid = LeveledMerkleForest::<TestConfig>::verify_variable(
                 &params,
-                forest.states(),
-                forest.size(),
-                forest.num_leaves_per_tree(),
+                &forest.variable_root(i).unwrap(),
                 either::Left(&values[i]),
                 proof,
             )
@@ -849,11 +2046,405 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_new_with_data_matches_seqadd() {
+        let params = poseidon_params();
+        let mut rng = StdRng::from_seed([42; 32]);
+
+        for n in [1, 2, 3, 5, 8, 13, 31, 64] {
+            let values: Vec<_> = (0..n).map(|_| [Fr::rand(&mut rng)]).collect();
+            let values_ref: Vec<_> = values.iter().map(|v| &v[..]).collect();
+            let leaves = hash_leaves::<TestConfig>(&params, &values_ref).unwrap();
+
+            let mut sequential =
+                LeveledMerkleForest::<TestConfig>::new_optimal(n, &params).unwrap();
+            for value in &values_ref {
+                sequential.seqadd(value).unwrap();
+            }
+
+            for forest in [
+                LeveledMerkleForest::new_with_data(either::Left(&leaves), &params).unwrap(),
+                LeveledMerkleForest::new_with_data(either::Right(&values_ref), &params).unwrap(),
+            ] {
+                assert_same_forest(&forest, &sequential);
+                for i in 0..n {
+                    assert_eq!(
+                        forest.variable_root(i).unwrap(),
+                        sequential.variable_root(i).unwrap()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_optimal_forest_params_for() {
+        let n = 1 << 20;
+        let stats = |objective| {
+            let (capacity_per_tree, num_tree) = optimal_forest_params_for(objective, n);
+            let num_leaves = u64::from((capacity_per_tree + 1) / 2);
+            assert!(num_leaves.pow(num_tree) >= n as u64);
+            forest_stats(capacity_per_tree, num_tree)
+        };
+
+        let state = stats(ForestObjective::StateSize);
+        let proof = stats(ForestObjective::ProofSize);
+        assert_eq!(
+            optimal_forest_params_for(ForestObjective::StateSize, n),
+            optimal_forest_params(n)
+        );
+        assert!(proof.proof_size < state.proof_size);
+        assert_eq!(proof.proof_size, 20);
+
+        // the weight moves the optimum between the two sizes
+        assert_eq!(stats(ForestObjective::Balanced { weight: 1. }), proof);
+        let balanced = stats(ForestObjective::Balanced { weight: 0.5 });
+        assert!(balanced.proof_size >= proof.proof_size);
+        assert!(balanced.forest_state_size <= proof.forest_state_size);
+
+        // the proofs of an actual forest have the predicted size
+        let params = poseidon_params();
+        for n in [1, 5, 100] {
+            let (capacity_per_tree, num_tree) =
+                optimal_forest_params_for(ForestObjective::ProofSize, n);
+            let mut forest =
+                LeveledMerkleForest::<TestConfig>::new(capacity_per_tree, num_tree, &params)
+                    .unwrap();
+            for _ in 0..n {
+                forest.seqadd(&[Fr::default()]).unwrap();
+            }
+            assert_eq!(
+                forest.prove(n - 1).unwrap().siblings.len() as u64,
+                forest_stats(capacity_per_tree, num_tree).proof_size
+            );
+        }
+    }
+
+    #[test]
+    fn test_optimal_params_for_brute_force() {
+        #[allow(clippy::cast_precision_loss)]
+        let cost = |weight: f64, stats: &ForestStats| {
+            weight * stats.proof_size as f64 + (1. - weight) * stats.forest_state_size as f64
+        };
+
+        for n in 1..=300usize {
+            let bits = n.next_power_of_two().ilog2().max(1);
+            for weight in [0., 0.1, 0.5, 0.9, 1.] {
+                let objective = if weight == 1. {
+                    ForestObjective::ProofSize
+                } else {
+                    ForestObjective::Balanced { weight }
+                };
+                let (capacity_per_tree, num_tree) =
+                    LeveledMerkleForest::<TestConfig>::optimal_params_for(objective, n);
+                let chosen = forest_stats(capacity_per_tree, num_tree);
+                assert!(chosen.plain_tree_size + 1 >= 2 * n as u64);
+
+                // every binary forest with enough leaves, ordered as
+                // `optimal_forest_params_for` breaks ties
+                let best = (1..=bits)
+                    .flat_map(|bits_per_tree| {
+                        (1..=bits).map(move |num_tree| ((2 << bits_per_tree) - 1, num_tree))
+                    })
+                    .map(|(capacity_per_tree, num_tree)| forest_stats(capacity_per_tree, num_tree))
+                    .filter(|stats| stats.plain_tree_size + 1 >= 2 * n as u64)
+                    .min_by(|a, b| {
+                        cost(weight, a)
+                            .total_cmp(&cost(weight, b))
+                            .then_with(|| a.forest_state_size.cmp(&b.forest_state_size))
+                            .then_with(|| a.proof_size.cmp(&b.proof_size))
+                    })
+                    .unwrap();
+                assert_eq!(
+                    (chosen.proof_size, chosen.forest_state_size),
+                    (best.proof_size, best.forest_state_size),
+                    "n = {n}, weight = {weight}"
+                );
+            }
+        }
+    }
+
     #[test]
     fn play_with_optimal_params() {
         let (capacity_per_tree, num_tree) = optimal_forest_params(1 << 25);
         println!("capacity_per_tree: {}", capacity_per_tree);
         println!("num_tree: {}", num_tree);
-        forest_stats(capacity_per_tree, num_tree);
+        println!("{}", forest_stats(capacity_per_tree, num_tree));
+    }
+
+    fn forest_with_proof(
+        params: &PoseidonConfig<Fr>,
+    ) -> (Fr, Vec<Fr>, MerkleForestProof<TestConfig>) {
+        let mut rng = thread_rng();
+        let values: Vec<_> = (0..6).map(|_| Fr::rand(&mut rng)).collect();
+        let forest =
+            LeveledMerkleForest::<TestConfig>::new_with_data(either::Left(&values), params)
+                .unwrap();
+        let proof = forest.prove(5).unwrap();
+        (forest.root(), values, proof)
+    }
+
+    #[test]
+    fn test_verify_truncated_proof() {
+        let params = poseidon_params();
+        let (root, values, mut proof) = forest_with_proof(&params);
+
+        proof.siblings.pop();
+        let result = LeveledMerkleForest::<TestConfig>::verify(
+            &params,
+            root,
+            either::Left(&values[5]),
+            proof,
+        );
+        assert!(matches!(result, Err(MerkleForestError::InvalidProofLength)));
+
+        let (root, values, mut proof) = forest_with_proof(&params);
+        proof.siblings.clear();
+        let result = LeveledMerkleForest::<TestConfig>::verify(
+            &params,
+            root,
+            either::Left(&values[5]),
+            proof,
+        );
+        assert!(matches!(result, Err(MerkleForestError::InvalidProofLength)));
+    }
+
+    #[test]
+    fn test_verify_oversized_proof() {
+        let params = poseidon_params();
+
+        // not a multiple of the tree height
+        let (root, values, mut proof) = forest_with_proof(&params);
+        proof.siblings.push(Fr::default());
+        let result = LeveledMerkleForest::<TestConfig>::verify(
+            &params,
+            root,
+            either::Left(&values[5]),
+            proof,
+        );
+        assert!(matches!(result, Err(MerkleForestError::InvalidProofLength)));
+
+        // a well-formed proof for a taller forest does not verify against this root
+        let (root, values, mut proof) = forest_with_proof(&params);
+        let tree_height = proof.num_leaves_per_tree.ilog2() as usize;
+        proof
+            .siblings
+            .extend(std::iter::repeat(Fr::default()).take(tree_height));
+        let result = LeveledMerkleForest::<TestConfig>::verify(
+            &params,
+            root,
+            either::Left(&values[5]),
+            proof,
+        );
+        assert!(!result.unwrap());
+    }
+
+    #[test]
+    fn test_verify_invalid_proof_params() {
+        let params = poseidon_params();
+
+        for num_leaves_per_tree in [0, 1, 3, 6] {
+            let (root, values, mut proof) = forest_with_proof(&params);
+            proof.num_leaves_per_tree = num_leaves_per_tree;
+            let result = LeveledMerkleForest::<TestConfig>::verify(
+                &params,
+                root,
+                either::Left(&values[5]),
+                proof,
+            );
+            assert!(matches!(
+                result,
+                Err(MerkleForestError::InvalidNumLeavesPerTree)
+            ));
+        }
+
+        let (root, values, mut proof) = forest_with_proof(&params);
+        let num_tree = proof.siblings.len() / proof.num_leaves_per_tree.ilog2() as usize;
+        proof.leaf_index = proof
+            .num_leaves_per_tree
+            .pow(u32::try_from(num_tree).unwrap());
+        let result = LeveledMerkleForest::<TestConfig>::verify(
+            &params,
+            root,
+            either::Left(&values[5]),
+            proof,
+        );
+        assert!(matches!(result, Err(MerkleForestError::IndexOutOfBound)));
+    }
+
+    #[test]
+    fn test_prove_and_verify_batch() {
+        use rand::{seq::SliceRandom, Rng};
+
+        let params = poseidon_params();
+        let mut rng = StdRng::from_seed([42; 32]);
+
+        let values: Vec<_> = (0..50).map(|_| Fr::rand(&mut rng)).collect();
+        let forest =
+            LeveledMerkleForest::<TestConfig>::new_with_data(either::Left(&values), &params)
+                .unwrap();
+        let root = forest.root();
+
+        for _ in 0..20 {
+            let num_indices = rng.gen_range(1..=values.len());
+            let mut indices: Vec<_> = (0..values.len()).collect();
+            indices.shuffle(&mut rng);
+            indices.truncate(num_indices);
+
+            let proof = forest.prove_batch(&indices).unwrap();
+
+            // optionally tamper with one of the leaves
+            let mut leaves: Vec<_> = proof.leaf_indices.iter().map(|i| values[*i]).collect();
+            if rng.gen_bool(0.5) {
+                let i = rng.gen_range(0..leaves.len());
+                leaves[i] = Fr::rand(&mut rng);
+            }
+
+            let individual_sibling_count: usize = proof
+                .leaf_indices
+                .iter()
+                .map(|i| forest.prove(*i).unwrap().siblings.len())
+                .sum();
+            assert!(proof.siblings.len() <= individual_sibling_count);
+
+            let individually_accepted = proof.leaf_indices.iter().zip(&leaves).all(|(i, leaf)| {
+                LeveledMerkleForest::<TestConfig>::verify(
+                    &params,
+                    root,
+                    either::Left(leaf),
+                    forest.prove(*i).unwrap(),
+                )
+                .unwrap()
+            });
+
+            let leaves: Vec<_> = leaves.iter().map(either::Left).collect();
+            let batch_accepted =
+                LeveledMerkleForest::<TestConfig>::verify_batch(&params, root, &leaves, proof)
+                    .unwrap();
+
+            assert_eq!(batch_accepted, individually_accepted);
+        }
+    }
+
+    #[test]
+    fn test_arity() {
+        type Wide = crate::merkle::WideConfig<Fr, 4>;
+
+        let params = poseidon_params();
+        let mut rng = StdRng::from_seed([42; 32]);
+
+        for n in [16, 64] {
+            let values: Vec<_> = (0..n).map(|_| [Fr::rand(&mut rng)]).collect();
+            let values_ref: Vec<_> = values.iter().map(|v| &v[..]).collect();
+            let forest =
+                LeveledMerkleForest::<Wide>::new_with_data(either::Right(&values_ref), &params)
+                    .unwrap();
+            let (capacity_per_tree, num_tree) = optimal_forest_params_for_arity(n, 4);
+            assert_eq!(forest.capacity_per_tree(), capacity_per_tree);
+            assert_eq!(forest.num_trees(), num_tree);
+            assert_eq!(forest.num_leaves_per_tree(), 4);
+
+            let naive =
+                LeveledMerkleForest::<Wide>::new_with_data_naive(&values_ref, &params).unwrap();
+            assert_eq!(naive.root(), forest.root());
+
+            for i in 0..n {
+                let proof = forest.prove(i).unwrap();
+                assert_eq!(proof.siblings.len(), 3 * num_tree as usize);
+                assert!(LeveledMerkleForest::<Wide>::verify(
+                    &params,
+                    forest.root(),
+                    either::Right(&values[i][..]),
+                    proof
+                )
+                .unwrap());
+            }
+
+            let indices = [0, 1, 2, 7, n - 1];
+            let leaves: Vec<_> = indices
+                .iter()
+                .map(|i| either::Right(&values[*i][..]))
+                .collect();
+            let proof = forest.prove_batch(&indices).unwrap();
+            assert!(LeveledMerkleForest::<Wide>::verify_batch(
+                &params,
+                forest.root(),
+                &leaves,
+                proof
+            )
+            .unwrap());
+        }
+
+        assert_eq!(
+            optimal_forest_params_for_arity(1 << 20, 2),
+            optimal_forest_params(1 << 20)
+        );
+    }
+
+    #[test]
+    fn test_verify_batch_malformed() {
+        let params = poseidon_params();
+        let mut rng = thread_rng();
+
+        let values: Vec<_> = (0..20).map(|_| Fr::rand(&mut rng)).collect();
+        let forest =
+            LeveledMerkleForest::<TestConfig>::new_with_data(either::Left(&values), &params)
+                .unwrap();
+        let root = forest.root();
+        let leaves: Vec<_> = [1, 2, 7]
+            .iter()
+            .map(|i| either::Left(&values[*i]))
+            .collect();
+
+        let mut proof = forest.prove_batch(&[7, 2, 1, 2]).unwrap();
+        assert_eq!(proof.leaf_indices, vec![1, 2, 7]);
+        proof.siblings.pop();
+        let result = LeveledMerkleForest::<TestConfig>::verify_batch(&params, root, &leaves, proof);
+        assert!(matches!(result, Err(MerkleForestError::InvalidProofLength)));
+
+        let mut proof = forest.prove_batch(&[1, 2, 7]).unwrap();
+        proof.siblings.push(Fr::default());
+        let result = LeveledMerkleForest::<TestConfig>::verify_batch(&params, root, &leaves, proof);
+        assert!(matches!(result, Err(MerkleForestError::InvalidProofLength)));
+
+        let proof = forest.prove_batch(&[1, 2, 7]).unwrap();
+        let result =
+            LeveledMerkleForest::<TestConfig>::verify_batch(&params, root, &leaves[1..], proof);
+        assert!(matches!(result, Err(MerkleForestError::LeafCountMismatch)));
+    }
+
+    #[test]
+    fn test_leaves_and_root_at() {
+        let params = poseidon_params();
+        let mut rng = StdRng::from_seed([42; 32]);
+
+        let capacity_per_tree = 4 - 1;
+        let num_tree = 4;
+        let mut forest =
+            LeveledMerkleForest::<TestConfig>::new(capacity_per_tree, num_tree, &params).unwrap();
+        assert_eq!(forest.root_at(0), None);
+
+        let values: Vec<_> = (0..10).map(|_| [Fr::rand(&mut rng)]).collect();
+        let mut roots = vec![];
+        for v in &values {
+            forest.seqadd(v).unwrap();
+            roots.push(forest.root());
+        }
+
+        for (i, root) in roots.iter().enumerate() {
+            assert_eq!(forest.root_at(i + 1), Some(*root));
+        }
+        assert_eq!(forest.root_at(values.len() + 1), None);
+
+        let leaves: Vec<_> = values
+            .iter()
+            .map(|v| Poseidon::<Fr>::evaluate(&params, &v[..]).unwrap())
+            .collect();
+        assert!(forest.leaves().eq(leaves.iter().copied()));
+
+        // removed leaves are read back as the default hash
+        forest.remove(3).unwrap();
+        assert_eq!(forest.leaves().nth(3), Some(Fr::default()));
+        assert_eq!(forest.root_at(values.len()), Some(forest.root()));
     }
 }
@@ -1,37 +1,258 @@
-use std::marker::PhantomData;
+use std::{borrow::Borrow, marker::PhantomData};
 
-use ark_crypto_primitives::sponge::Absorb;
+use ark_crypto_primitives::{
+    crh::{
+        poseidon::{TwoToOneCRH as PoseidonTwoToOne, CRH as Poseidon},
+        CRHScheme, TwoToOneCRHScheme,
+    },
+    sponge::{poseidon::PoseidonConfig, Absorb},
+    Error,
+};
 use ark_ff::PrimeField;
+use ark_std::cfg_iter;
+use rand::Rng;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 pub mod constraints;
 pub mod forest;
+pub mod sparse;
 pub mod tree;
 
+/// Hashes used by the native trees and forests.
+///
+/// The constraint gadgets in `constraints` hash with the in-circuit
+/// counterparts given by `constraints::MerkleConfigGadget`, which `Config`,
+/// `DomainSeparatedConfig` and `WideConfig` implement with Poseidon. Any other
+/// hash whose outputs are elements of `BasePrimeField` can be used in a
+/// circuit by implementing it as well.
 pub trait MerkleConfig {
     type BasePrimeField: PrimeField + Absorb;
+
+    /// Hash of the values stored in the leaves.
+    ///
+    /// The values are shared across threads with the `parallel` feature, so
+    /// they have to be `Sync`.
+    type LeafHash: CRHScheme<
+        Input: Sync,
+        Output = <Self::TwoToOneHash as TwoToOneCRHScheme>::Output,
+        Parameters = <Self::TwoToOneHash as TwoToOneCRHScheme>::Parameters,
+    >;
+
+    /// Hash of two children into their parent.
+    ///
+    /// The nodes and parameters are shared across threads with the `parallel`
+    /// feature, so they have to be `Send + Sync`.
+    type TwoToOneHash: TwoToOneCRHScheme<Output: Send + Sync, Parameters: Sync>;
+
+    /// Whether leaves and internal nodes are hashed under different domain
+    /// tags (`LEAF_DOMAIN` and `NODE_DOMAIN`), so that an internal node cannot
+    /// be opened as a leaf of a shorter path.
+    ///
+    /// The gadgets prepend the tags when this is set, so `LeafHash` and
+    /// `TwoToOneHash` have to do the same, as in `DomainSeparatedConfig`.
+    /// Enabling it changes every root.
+    const DOMAIN_SEPARATION: bool = false;
+
+    /// Number of children of every internal node, which should be a power of
+    /// 2 so that the position of a node among its siblings is a fixed number
+    /// of bits of its index.
+    ///
+    /// A wider tree is shallower, so a proof has fewer levels, but it carries
+    /// `ARITY - 1` siblings per level.
+    const ARITY: usize = 2;
+
+    /// Hash the `ARITY` children of a node into the node.
+    ///
+    /// The default compresses two children with `TwoToOneHash`, so configs
+    /// with a different `ARITY` have to override it. The gadgets hash more
+    /// than two children with `poseidon::CRH`, as `WideConfig` does.
+    fn compress(
+        params: &<Self::TwoToOneHash as TwoToOneCRHScheme>::Parameters,
+        children: &[<Self::TwoToOneHash as TwoToOneCRHScheme>::Output],
+    ) -> Result<<Self::TwoToOneHash as TwoToOneCRHScheme>::Output, Error> {
+        match children {
+            [left, right] => Self::TwoToOneHash::compress(params, left, right),
+            _ => Err("TwoToOneHash only compresses 2 children".into()),
+        }
+    }
 }
 
+/// A node of the tree, i.e. a leaf hash or an internal hash.
+pub type Node<P> = <<P as MerkleConfig>::TwoToOneHash as TwoToOneCRHScheme>::Output;
+
+/// Parameters shared by `LeafHash` and `TwoToOneHash`.
+pub type HashParams<P> = <<P as MerkleConfig>::TwoToOneHash as TwoToOneCRHScheme>::Parameters;
+
+/// A value that is hashed into a leaf.
+pub type LeafInput<P> = <<P as MerkleConfig>::LeafHash as CRHScheme>::Input;
+
 pub struct Config<CF>(PhantomData<CF>);
 impl<CF: PrimeField + Absorb> MerkleConfig for Config<CF> {
     type BasePrimeField = CF;
+    type LeafHash = Poseidon<CF>;
+    type TwoToOneHash = PoseidonTwoToOne<CF>;
+}
+
+/// Same as `Config`, but with `DOMAIN_SEPARATION` enabled.
+pub struct DomainSeparatedConfig<CF>(PhantomData<CF>);
+impl<CF: PrimeField + Absorb> MerkleConfig for DomainSeparatedConfig<CF> {
+    type BasePrimeField = CF;
+    type LeafHash = DomainSeparatedPoseidon<CF>;
+    type TwoToOneHash = DomainSeparatedPoseidon<CF>;
+    const DOMAIN_SEPARATION: bool = true;
+}
+
+/// Same as `Config`, but every internal node has `N` children that are hashed
+/// together with `poseidon::CRH`.
+pub struct WideConfig<CF, const N: usize>(PhantomData<CF>);
+impl<CF: PrimeField + Absorb, const N: usize> MerkleConfig for WideConfig<CF, N> {
+    type BasePrimeField = CF;
+    type LeafHash = Poseidon<CF>;
+    type TwoToOneHash = PoseidonTwoToOne<CF>;
+    const ARITY: usize = N;
+
+    fn compress(params: &PoseidonConfig<CF>, children: &[CF]) -> Result<CF, Error> {
+        if children.len() != N {
+            return Err(format!("expected {N} children, got {}", children.len()).into());
+        }
+        Poseidon::evaluate(params, children)
+    }
+}
+
+/// Domain tag prepended to the values of a leaf.
+pub const LEAF_DOMAIN: u64 = 0;
+
+/// Domain tag prepended to the children of an internal node.
+pub const NODE_DOMAIN: u64 = 1;
+
+/// Poseidon that hashes `LEAF_DOMAIN || input` for leaves and
+/// `NODE_DOMAIN || left || right` for internal nodes.
+pub struct DomainSeparatedPoseidon<CF>(PhantomData<CF>);
+
+impl<CF: PrimeField + Absorb> CRHScheme for DomainSeparatedPoseidon<CF> {
+    type Input = [CF];
+    type Output = CF;
+    type Parameters = PoseidonConfig<CF>;
+
+    fn setup<R: Rng>(rng: &mut R) -> Result<Self::Parameters, Error> {
+        Poseidon::<CF>::setup(rng)
+    }
+
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        input: T,
+    ) -> Result<Self::Output, Error> {
+        let input: Vec<_> = std::iter::once(CF::from(LEAF_DOMAIN))
+            .chain(input.borrow().iter().copied())
+            .collect();
+        Poseidon::evaluate(parameters, input)
+    }
+}
+
+impl<CF: PrimeField + Absorb> TwoToOneCRHScheme for DomainSeparatedPoseidon<CF> {
+    type Input = CF;
+    type Output = CF;
+    type Parameters = PoseidonConfig<CF>;
+
+    fn setup<R: Rng>(rng: &mut R) -> Result<Self::Parameters, Error> {
+        Poseidon::<CF>::setup(rng)
+    }
+
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        left_input: T,
+        right_input: T,
+    ) -> Result<Self::Output, Error> {
+        Self::compress(parameters, left_input, right_input)
+    }
+
+    fn compress<T: Borrow<Self::Output>>(
+        parameters: &Self::Parameters,
+        left_input: T,
+        right_input: T,
+    ) -> Result<Self::Output, Error> {
+        Poseidon::evaluate(
+            parameters,
+            [
+                CF::from(NODE_DOMAIN),
+                *left_input.borrow(),
+                *right_input.borrow(),
+            ],
+        )
+    }
+}
+
+/// Hash `values` into leaves, on several threads with the `parallel` feature.
+pub(crate) fn hash_leaves<P: MerkleConfig>(
+    params: &HashParams<P>,
+    values: &[&LeafInput<P>],
+) -> Result<Vec<Node<P>>, tree::MerkleTreeError> {
+    cfg_iter!(values)
+        .map(|v| P::LeafHash::evaluate(params, *v).map_err(tree::MerkleTreeError::from))
+        .collect()
+}
+
+/// `P::ARITY`, checked at compile time to be a power of 2 that is at least 2.
+#[inline]
+pub(crate) fn arity<P: MerkleConfig>() -> usize {
+    const {
+        assert!(
+            P::ARITY >= 2 && P::ARITY.is_power_of_two(),
+            "ARITY should be a power of 2"
+        );
+    }
+    P::ARITY
+}
+
+// Nodes are stored level by level from the root, so the children of node `i`
+// are `arity * i + 1..=arity * i + arity`.
+
+#[inline]
+pub(crate) const fn parent(index: usize, arity: usize) -> usize {
+    (index - 1) / arity
 }
 
 #[inline]
-pub(crate) const fn is_left_node(index: usize) -> bool {
-    index & 1 == 1
+pub(crate) const fn first_child(index: usize, arity: usize) -> usize {
+    arity * index + 1
 }
 
+/// Position of a non-root node among the children of its parent.
 #[inline]
-pub(crate) const fn parent(index: usize) -> usize {
-    (index - 1) / 2
+pub(crate) const fn child_position(index: usize, arity: usize) -> usize {
+    (index - 1) % arity
+}
+
+/// Number of edges from the root to `index`.
+#[inline]
+pub(crate) const fn depth(index: usize, arity: usize) -> usize {
+    // the nodes at depth `d` start at `(arity^d - 1) / (arity - 1)`
+    ((index * (arity - 1) + 1).ilog2() / arity.ilog2()) as usize
+}
+
+/// Whether a full tree of height `>= 1` has exactly `capacity` nodes.
+pub(crate) fn is_valid_capacity(capacity: usize, arity: usize) -> bool {
+    let mut level = 1usize;
+    let mut num_nodes = 1usize;
+    while num_nodes < capacity {
+        let Some(next) = level.checked_mul(arity) else {
+            return false;
+        };
+        level = next;
+        num_nodes = num_nodes.saturating_add(level);
+    }
+    num_nodes == capacity && level > 1
 }
 
+/// Number of leaves of a full tree with `capacity` nodes.
 #[inline]
-pub(crate) const fn left(index: usize) -> usize {
-    2 * index + 1
+pub(crate) const fn num_leaves(capacity: usize, arity: usize) -> usize {
+    (capacity * (arity - 1) + 1) / arity
 }
 
+/// Number of nodes of a full tree with `num_leaves` leaves.
 #[inline]
-pub(crate) const fn right(index: usize) -> usize {
-    2 * index + 2
+pub(crate) const fn capacity(num_leaves: usize, arity: usize) -> usize {
+    (num_leaves * arity - 1) / (arity - 1)
 }
@@ -0,0 +1,622 @@
+use std::collections::HashMap;
+
+use ark_crypto_primitives::crh::CRHScheme;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Validate, Write,
+};
+use ark_std::cfg_iter;
+use derivative::Derivative;
+use either::{for_both, Either};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use super::{
+    arity, capacity, child_position, depth, first_child, hash_leaves, is_valid_capacity,
+    num_leaves, parent,
+    tree::{MerkleTree, MerkleTreeError, MerkleTreeProof},
+    HashParams, LeafInput, MerkleConfig, Node,
+};
+
+/// A Merkle tree that only stores the nodes that differ from an empty tree.
+///
+/// The hash of an untouched subtree only depends on its height, so it is
+/// looked up in `defaults` instead. Construction is `O(height)` regardless of
+/// the capacity, and the memory grows with the number of updated leaves.
+///
+/// It behaves exactly like `MerkleTree`: the roots and proofs of both trees
+/// are the same after the same sequence of updates.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct SparseMerkleTree<'a, P: MerkleConfig> {
+    nodes: HashMap<usize, Node<P>>,
+
+    /// `defaults[l]` is the root of an empty subtree of height `l`, from the
+    /// leaves (`l = 0`) up to the root of the whole tree.
+    defaults: Vec<Node<P>>,
+    capacity: usize,
+
+    #[derivative(Debug = "ignore")]
+    params: &'a HashParams<P>,
+}
+
+impl<'a, P: MerkleConfig> Clone for SparseMerkleTree<'a, P> {
+    fn clone(&self) -> Self {
+        Self {
+            nodes: self.nodes.clone(),
+            defaults: self.defaults.clone(),
+            capacity: self.capacity,
+            params: self.params,
+        }
+    }
+}
+
+impl<'a, P: MerkleConfig> SparseMerkleTree<'a, P> {
+    pub fn new(capacity: usize, params: &'a HashParams<P>) -> Result<Self, MerkleTreeError> {
+        let arity = arity::<P>();
+        if !is_valid_capacity(capacity, arity) {
+            return Err(MerkleTreeError::InvalidCapacity);
+        }
+
+        // the last node is the last leaf
+        let height = depth(capacity - 1, arity);
+        let mut defaults = vec![Node::<P>::default()];
+        for l in 0..height {
+            defaults.push(
+                P::compress(params, &vec![defaults[l].clone(); arity])
+                    .map_err(MerkleTreeError::from)?,
+            );
+        }
+
+        Ok(Self {
+            nodes: HashMap::new(),
+            defaults,
+            capacity,
+            params,
+        })
+    }
+
+    /// Same as `MerkleTree::deserialize_with_params`.
+    pub fn deserialize_with_params(
+        mut reader: impl Read,
+        compress: Compress,
+        validate: Validate,
+        params: &'a HashParams<P>,
+    ) -> Result<Self, SerializationError> {
+        let capacity = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let nodes =
+            Vec::<(usize, Node<P>)>::deserialize_with_mode(&mut reader, compress, validate)?;
+
+        let mut s = Self::new(capacity, params).map_err(|_| SerializationError::InvalidData)?;
+        if nodes.iter().any(|(index, _)| *index >= capacity) {
+            return Err(SerializationError::InvalidData);
+        }
+        s.nodes = nodes.into_iter().collect();
+
+        if validate == Validate::Yes {
+            for index in s.nodes.keys().filter(|index| **index < s.leaf_start()) {
+                let hash = P::compress(params, &s.children(*index))
+                    .map_err(|_| SerializationError::InvalidData)?;
+                if hash != s.nodes[index] {
+                    return Err(SerializationError::InvalidData);
+                }
+            }
+        }
+
+        Ok(s)
+    }
+
+    /// Same as `MerkleTree::new_with_data`.
+    pub fn new_with_data(
+        data: Either<&[Node<P>], &[&LeafInput<P>]>,
+        params: &'a HashParams<P>,
+    ) -> Result<Self, MerkleTreeError> {
+        let len = for_both!(data, data => data.len());
+        if len == 0 {
+            return Err(MerkleTreeError::InvalidCapacity);
+        }
+        let capacity = capacity(len, arity::<P>());
+        match data {
+            Either::Left(leaves) => Self::from_leaves(leaves, capacity, params),
+            Either::Right(values) => Self::from_values(values, capacity, params),
+        }
+    }
+
+    /// Same as `MerkleTree::from_leaves`.
+    pub fn from_leaves(
+        leaves: &[Node<P>],
+        capacity: usize,
+        params: &'a HashParams<P>,
+    ) -> Result<Self, MerkleTreeError> {
+        let mut s = Self::new(capacity, params)?;
+        if leaves.len() > s.num_leaves() {
+            return Err(MerkleTreeError::TreeIsFull);
+        }
+
+        let updates: Vec<_> = leaves.iter().cloned().enumerate().collect();
+        s.update_batch_with_hash(&updates)?;
+
+        Ok(s)
+    }
+
+    /// Same as `MerkleTree::from_values`.
+    pub fn from_values(
+        values: &[&LeafInput<P>],
+        capacity: usize,
+        params: &'a HashParams<P>,
+    ) -> Result<Self, MerkleTreeError> {
+        let leaves = hash_leaves::<P>(params, values)?;
+        Self::from_leaves(&leaves, capacity, params)
+    }
+
+    pub fn prove(&self, leaf_index: usize) -> Result<MerkleTreeProof<P>, MerkleTreeError> {
+        if leaf_index >= self.num_leaves() {
+            return Err(MerkleTreeError::IndexOutOfBound);
+        }
+
+        let arity = arity::<P>();
+        let mut siblings = Vec::new();
+        let mut index = self.leaf_start() + leaf_index;
+        while index > 0 {
+            let first = index - child_position(index, arity);
+            siblings.extend(
+                (first..first + arity)
+                    .filter(|i| *i != index)
+                    .map(|i| self.node(i)),
+            );
+            index = parent(index, arity);
+        }
+        Ok(MerkleTreeProof {
+            siblings,
+            leaf_index,
+        })
+    }
+
+    pub fn update(&mut self, leaf_index: usize, val: &LeafInput<P>) -> Result<(), MerkleTreeError> {
+        if leaf_index >= self.num_leaves() {
+            return Err(MerkleTreeError::IndexOutOfBound);
+        }
+
+        self.update_with_hash(
+            leaf_index,
+            P::LeafHash::evaluate(self.params, val).map_err(MerkleTreeError::from)?,
+        )
+    }
+
+    /// Same as `MerkleTree::remove`. Nodes that are back to the hash of an
+    /// empty subtree are no longer stored.
+    pub fn remove(&mut self, leaf_index: usize) -> Result<(), MerkleTreeError> {
+        self.update_with_hash(leaf_index, self.defaults[0].clone())?;
+
+        let mut index = self.leaf_start() + leaf_index;
+        for default in &self.defaults {
+            if self.nodes.get(&index) != Some(default) {
+                break;
+            }
+            self.nodes.remove(&index);
+            if index == 0 {
+                break;
+            }
+            index = parent(index, arity::<P>());
+        }
+
+        Ok(())
+    }
+
+    /// Same as `MerkleTree::update_batch`.
+    pub fn update_batch(
+        &mut self,
+        updates: &[(usize, &LeafInput<P>)],
+    ) -> Result<(), MerkleTreeError> {
+        let values: Vec<_> = updates.iter().map(|(_, val)| *val).collect();
+        let updates: Vec<_> = updates
+            .iter()
+            .map(|(leaf_index, _)| *leaf_index)
+            .zip(hash_leaves::<P>(self.params, &values)?)
+            .collect();
+
+        self.update_batch_with_hash(&updates)
+    }
+
+    #[inline]
+    pub fn root(&self) -> Node<P> {
+        self.node(0)
+    }
+
+    #[inline]
+    pub fn leaf(&self, leaf_index: usize) -> Node<P> {
+        self.node(self.leaf_start() + leaf_index)
+    }
+
+    pub fn leaves(&self) -> impl Iterator<Item = Node<P>> + '_ {
+        (0..self.num_leaves()).map(|i| self.leaf(i))
+    }
+
+    /// Same as `MerkleTree::verify`.
+    #[inline]
+    pub fn verify(
+        params: &HashParams<P>,
+        root: Node<P>,
+        leaf: Either<&Node<P>, &LeafInput<P>>,
+        proof: &MerkleTreeProof<P>,
+    ) -> Result<bool, MerkleTreeError> {
+        MerkleTree::<P>::verify(params, root, leaf, proof)
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[inline]
+    pub fn num_leaves(&self) -> usize {
+        num_leaves(self.capacity(), arity::<P>())
+    }
+
+    #[inline]
+    pub(crate) fn params(&self) -> &'a HashParams<P> {
+        self.params
+    }
+
+    /// Number of nodes that are stored explicitly.
+    #[inline]
+    pub fn num_materialized(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub(crate) fn update_with_hash(
+        &mut self,
+        leaf_index: usize,
+        val: Node<P>,
+    ) -> Result<(), MerkleTreeError> {
+        if leaf_index >= self.num_leaves() {
+            return Err(MerkleTreeError::IndexOutOfBound);
+        }
+
+        let mut index = self.leaf_start() + leaf_index;
+        self.nodes.insert(index, val);
+        while index > 0 {
+            index = parent(index, arity::<P>());
+            self.update_state(index)?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn update_batch_with_hash(
+        &mut self,
+        updates: &[(usize, Node<P>)],
+    ) -> Result<(), MerkleTreeError> {
+        if updates
+            .iter()
+            .any(|(leaf_index, _)| *leaf_index >= self.num_leaves())
+        {
+            return Err(MerkleTreeError::IndexOutOfBound);
+        }
+
+        let leaf_start = self.leaf_start();
+        let mut dirty: Vec<_> = updates
+            .iter()
+            .map(|(leaf_index, val)| {
+                self.nodes.insert(leaf_start + leaf_index, val.clone());
+                leaf_start + leaf_index
+            })
+            .collect();
+
+        // all dirty nodes are on the same level, so we are done once we reach the root
+        while dirty.first().is_some_and(|index| *index > 0) {
+            dirty = dirty
+                .into_iter()
+                .map(|index| parent(index, arity::<P>()))
+                .collect();
+            dirty.sort_unstable();
+            dirty.dedup();
+
+            self.update_states(&dirty)?;
+        }
+
+        Ok(())
+    }
+
+    // `HashMap` iterates in arbitrary order, so sort the nodes to make the
+    // serialization deterministic
+    fn sorted_nodes(&self) -> Vec<(usize, Node<P>)> {
+        let mut nodes: Vec<_> = self
+            .nodes
+            .iter()
+            .map(|(i, hash)| (*i, hash.clone()))
+            .collect();
+        nodes.sort_unstable_by_key(|(i, _)| *i);
+        nodes
+    }
+
+    #[inline]
+    fn leaf_start(&self) -> usize {
+        self.capacity() - self.num_leaves()
+    }
+
+    fn node(&self, index: usize) -> Node<P> {
+        self.nodes.get(&index).cloned().unwrap_or_else(|| {
+            let depth = depth(index, arity::<P>());
+            self.defaults[self.defaults.len() - 1 - depth].clone()
+        })
+    }
+
+    fn children(&self, index: usize) -> Vec<Node<P>> {
+        let arity = arity::<P>();
+        let first = first_child(index, arity);
+        (first..first + arity).map(|i| self.node(i)).collect()
+    }
+
+    /// Same as `MerkleTree::update_states`.
+    fn update_states(&mut self, indices: &[usize]) -> Result<(), MerkleTreeError> {
+        let hashes = cfg_iter!(indices)
+            .map(|index| {
+                P::compress(self.params, &self.children(*index)).map_err(MerkleTreeError::from)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.nodes.extend(indices.iter().copied().zip(hashes));
+
+        #[cfg(test)]
+        super::tree::NUM_NODE_HASHES.with(|n| n.set(n.get() + indices.len()));
+
+        Ok(())
+    }
+
+    fn update_state(&mut self, index: usize) -> Result<(), MerkleTreeError> {
+        let hash =
+            P::compress(self.params, &self.children(index)).map_err(MerkleTreeError::from)?;
+        self.nodes.insert(index, hash);
+
+        #[cfg(test)]
+        super::tree::NUM_NODE_HASHES.with(|n| n.set(n.get() + 1));
+
+        Ok(())
+    }
+}
+
+/// Only the capacity and the materialized nodes are serialized, see
+/// `MerkleTree::deserialize_with_params`.
+impl<P: MerkleConfig> CanonicalSerialize for SparseMerkleTree<'_, P> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.capacity.serialize_with_mode(&mut writer, compress)?;
+        self.sorted_nodes().serialize_with_mode(writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.capacity.serialized_size(compress) + self.sorted_nodes().serialized_size(compress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::poseidon_canonical_config;
+    use ark_bls12_381::Fr;
+    use ark_crypto_primitives::{
+        crh::poseidon::{TwoToOneCRH as PoseidonTwoToOne, CRH as Poseidon},
+        sponge::poseidon::PoseidonConfig,
+    };
+    use ark_ff::UniformRand;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    struct TestConfig;
+    impl MerkleConfig for TestConfig {
+        type BasePrimeField = Fr;
+        type LeafHash = Poseidon<Fr>;
+        type TwoToOneHash = PoseidonTwoToOne<Fr>;
+    }
+
+    fn poseidon_params() -> PoseidonConfig<Fr> {
+        poseidon_canonical_config::<Fr>()
+    }
+
+    #[test]
+    fn test_sparse_matches_dense() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        for capacity in [3, 15, 255] {
+            let mut dense = MerkleTree::<TestConfig>::new(capacity, &params).unwrap();
+            let mut sparse = SparseMerkleTree::<TestConfig>::new(capacity, &params).unwrap();
+            assert_eq!(sparse.root(), dense.root());
+            assert_eq!(sparse.num_materialized(), 0);
+
+            let num_leaves = dense.num_leaves();
+            for _ in 0..32 {
+                let leaf_index = rng.gen_range(0..num_leaves);
+                let leaf = [Fr::rand(&mut rng)];
+                dense.update(leaf_index, &leaf).unwrap();
+                sparse.update(leaf_index, &leaf).unwrap();
+
+                let updates: Vec<_> = (0..rng.gen_range(1..=4))
+                    .map(|_| (rng.gen_range(0..num_leaves), [Fr::rand(&mut rng)]))
+                    .collect();
+                let updates: Vec<_> = updates.iter().map(|(i, v)| (*i, &v[..])).collect();
+                dense.update_batch(&updates).unwrap();
+                sparse.update_batch(&updates).unwrap();
+
+                assert_eq!(sparse.root(), dense.root());
+                assert!(sparse.leaves().eq(dense.leaves().iter().copied()));
+                for i in 0..num_leaves {
+                    assert_eq!(sparse.prove(i).unwrap(), dense.prove(i).unwrap());
+                }
+            }
+
+            assert!(matches!(
+                sparse.prove(num_leaves),
+                Err(MerkleTreeError::IndexOutOfBound)
+            ));
+            assert!(matches!(
+                sparse.update(num_leaves, &[Fr::from(1)]),
+                Err(MerkleTreeError::IndexOutOfBound)
+            ));
+        }
+
+        let leaves: Vec<_> = (0..8).map(|_| Fr::rand(&mut rng)).collect();
+        let dense = MerkleTree::<TestConfig>::from_leaves(&leaves, 31, &params).unwrap();
+        let sparse = SparseMerkleTree::<TestConfig>::from_leaves(&leaves, 31, &params).unwrap();
+        assert_eq!(sparse.root(), dense.root());
+
+        assert!(matches!(
+            SparseMerkleTree::<TestConfig>::new(6, &params),
+            Err(MerkleTreeError::InvalidCapacity)
+        ));
+    }
+
+    #[test]
+    fn test_sparse_matches_dense_arity() {
+        type Wide = crate::merkle::WideConfig<Fr, 4>;
+
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        for capacity in [5, 21, 85] {
+            let mut dense = MerkleTree::<Wide>::new(capacity, &params).unwrap();
+            let mut sparse = SparseMerkleTree::<Wide>::new(capacity, &params).unwrap();
+            assert_eq!(sparse.root(), dense.root());
+
+            let num_leaves = dense.num_leaves();
+            for _ in 0..16 {
+                let leaf_index = rng.gen_range(0..num_leaves);
+                let leaf = [Fr::rand(&mut rng)];
+                dense.update(leaf_index, &leaf).unwrap();
+                sparse.update(leaf_index, &leaf).unwrap();
+
+                assert_eq!(sparse.root(), dense.root());
+                for i in 0..num_leaves {
+                    assert_eq!(sparse.prove(i).unwrap(), dense.prove(i).unwrap());
+                }
+            }
+
+            for i in 0..num_leaves {
+                sparse.remove(i).unwrap();
+            }
+            assert_eq!(sparse.num_materialized(), 0);
+        }
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        let mut dense = MerkleTree::<TestConfig>::new(63, &params).unwrap();
+        let mut sparse = SparseMerkleTree::<TestConfig>::new(63, &params).unwrap();
+        let empty_root = sparse.root();
+
+        let indices = [0, 5, 6, 31];
+        for i in indices {
+            let leaf = [Fr::rand(&mut rng)];
+            dense.update(i, &leaf).unwrap();
+            sparse.update(i, &leaf).unwrap();
+        }
+
+        for i in [5, 31] {
+            dense.remove(i).unwrap();
+            sparse.remove(i).unwrap();
+            assert_eq!(sparse.root(), dense.root());
+            assert_eq!(sparse.leaf(i), Fr::default());
+        }
+
+        // removing every leaf gives back an empty tree
+        for i in [0, 6] {
+            sparse.remove(i).unwrap();
+        }
+        assert_eq!(sparse.root(), empty_root);
+        assert_eq!(sparse.num_materialized(), 0);
+    }
+
+    #[test]
+    fn test_sparse_large_capacity() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        // 2^20 leaves, which is impractical to allocate and hash densely
+        let capacity = (1 << 21) - 1;
+        let mut tree = SparseMerkleTree::<TestConfig>::new(capacity, &params).unwrap();
+        let num_leaves = tree.num_leaves();
+
+        let mut leaves = HashMap::new();
+        for _ in 0..16 {
+            let leaf_index = rng.gen_range(0..num_leaves);
+            let leaf = [Fr::rand(&mut rng)];
+            tree.update(leaf_index, &leaf).unwrap();
+            leaves.insert(leaf_index, leaf);
+        }
+
+        // only the updated paths are stored
+        assert!(tree.num_materialized() <= 16 * 21);
+
+        for (leaf_index, leaf) in &leaves {
+            let proof = tree.prove(*leaf_index).unwrap();
+            assert_eq!(proof.siblings.len(), 20);
+            assert!(SparseMerkleTree::<TestConfig>::verify(
+                &params,
+                tree.root(),
+                Either::Right(&leaf[..]),
+                &proof,
+            )
+            .unwrap());
+        }
+
+        // untouched leaves are proven against the default subtrees
+        let empty_index = (0..num_leaves).find(|i| !leaves.contains_key(i)).unwrap();
+        assert_eq!(tree.leaf(empty_index), Fr::default());
+        assert!(SparseMerkleTree::<TestConfig>::verify(
+            &params,
+            tree.root(),
+            Either::Left(&Fr::default()),
+            &tree.prove(empty_index).unwrap(),
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn test_serialize() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        let mut tree = SparseMerkleTree::<TestConfig>::new(255, &params).unwrap();
+        for _ in 0..8 {
+            tree.update(rng.gen_range(0..128), &[Fr::rand(&mut rng)])
+                .unwrap();
+        }
+
+        let mut bytes = vec![];
+        tree.serialize_compressed(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), tree.compressed_size());
+
+        let deserialized = SparseMerkleTree::<TestConfig>::deserialize_with_params(
+            &*bytes,
+            Compress::Yes,
+            Validate::Yes,
+            &params,
+        )
+        .unwrap();
+        assert_eq!(deserialized.root(), tree.root());
+        for i in 0..tree.num_leaves() {
+            assert_eq!(deserialized.prove(i).unwrap(), tree.prove(i).unwrap());
+        }
+
+        // the serialization does not depend on the iteration order of the nodes
+        let mut bytes_again = vec![];
+        deserialized.serialize_compressed(&mut bytes_again).unwrap();
+        assert_eq!(bytes, bytes_again);
+
+        let mut corrupted = tree.clone();
+        corrupted.nodes.insert(0, Fr::from(1));
+        let mut bytes = vec![];
+        corrupted.serialize_compressed(&mut bytes).unwrap();
+        assert!(SparseMerkleTree::<TestConfig>::deserialize_with_params(
+            &*bytes,
+            Compress::Yes,
+            Validate::Yes,
+            &params,
+        )
+        .is_err());
+    }
+}
@@ -1,23 +1,26 @@
-use ark_crypto_primitives::{
-    crh::{
-        poseidon::{TwoToOneCRH as PoseidonTwoToOne, CRH as Poseidon},
-        CRHScheme, TwoToOneCRHScheme,
-    },
-    sponge::poseidon::PoseidonConfig,
+use ark_crypto_primitives::crh::CRHScheme;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Validate, Write,
 };
+use ark_std::cfg_iter;
 use derivative::Derivative;
 use either::{for_both, Either};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use thiserror::Error;
 
-use super::{is_left_node, left, parent, right, MerkleConfig};
+use super::{
+    arity, capacity, child_position, first_child, hash_leaves, is_valid_capacity, num_leaves,
+    parent, HashParams, LeafInput, MerkleConfig, Node,
+};
 
 #[derive(Derivative)]
 #[derivative(Debug(bound = ""))]
 pub struct MerkleTree<'a, P: MerkleConfig> {
-    states: Vec<P::BasePrimeField>,
+    states: Vec<Node<P>>,
 
     #[derivative(Debug = "ignore")]
-    params: &'a PoseidonConfig<P::BasePrimeField>,
+    params: &'a HashParams<P>,
 }
 
 impl<'a, P: MerkleConfig> Clone for MerkleTree<'a, P> {
@@ -40,124 +43,264 @@ pub enum MerkleTreeError {
     #[error("Path length mismatches")]
     PathLenMismatch,
 
-    #[error("capacity != 2^k - 1 for k >= 2")]
+    #[error("capacity != (ARITY^(k+1) - 1) / (ARITY - 1) for k >= 1")]
     InvalidCapacity,
 
-    #[error("Poseidon CRH evaluation failed")]
-    CRHError,
+    #[error("CRH evaluation failed")]
+    CRHError(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl From<ark_crypto_primitives::Error> for MerkleTreeError {
+    /// `ark_crypto_primitives::Error` is not `Send`, so only its message is
+    /// kept, which lets the error cross the threads of the `parallel` feature.
+    fn from(e: ark_crypto_primitives::Error) -> Self {
+        Self::CRHError(e.to_string().into())
+    }
+}
+
+/// A proof that a leaf is included in a `MerkleTree`, ordered from the
+/// siblings of the leaf up to the children of the root. Each level holds the
+/// `ARITY - 1` siblings of the node on the path, ordered by their position.
+#[derive(Derivative, CanonicalSerialize, CanonicalDeserialize)]
+#[derivative(
+    Clone(bound = ""),
+    Debug(bound = ""),
+    PartialEq(bound = ""),
+    Eq(bound = "")
+)]
+pub struct MerkleTreeProof<P: MerkleConfig> {
+    pub siblings: Vec<Node<P>>,
+    pub leaf_index: usize,
+}
+
+/// The leaf at some index of a tree together with its siblings, ordered as
+/// in `MerkleTreeProof`. This lets `MerklePathUpdateVar` replace the leaf of
+/// a tree that the circuit only knows by its root.
+#[derive(Derivative, CanonicalSerialize, CanonicalDeserialize)]
+#[derivative(
+    Clone(bound = ""),
+    Debug(bound = ""),
+    PartialEq(bound = ""),
+    Eq(bound = "")
+)]
+pub struct MerklePathUpdate<P: MerkleConfig> {
+    pub old_leaf: Node<P>,
+    pub siblings: Vec<Node<P>>,
 }
 
-pub type MerkleProof<P> = (Vec<<P as MerkleConfig>::BasePrimeField>, usize);
+impl<P: MerkleConfig> MerkleTreeProof<P> {
+    /// Recompute the root of the tree from the hash of the leaf.
+    ///
+    /// Fails if `leaf_index` is not addressable by a tree of the proof's height.
+    pub fn compute_root(
+        &self,
+        params: &HashParams<P>,
+        leaf: Node<P>,
+    ) -> Result<Node<P>, MerkleTreeError> {
+        let arity = arity::<P>();
+        if self.siblings.is_empty() || self.siblings.len() % (arity - 1) != 0 {
+            return Err(MerkleTreeError::PathLenMismatch);
+        }
+
+        let num_leaves = u32::try_from(self.siblings.len() / (arity - 1))
+            .ok()
+            .and_then(|height| arity.checked_pow(height));
+        let Some(num_leaves) = num_leaves else {
+            return Err(MerkleTreeError::PathLenMismatch);
+        };
+        if self.leaf_index >= num_leaves {
+            return Err(MerkleTreeError::IndexOutOfBound);
+        }
+
+        // `hash_path` expects the index of the leaf among all nodes
+        let leaf_start = capacity(num_leaves, arity) - num_leaves;
+        MerkleTree::<P>::hash_path(params, leaf, leaf_start + self.leaf_index, &self.siblings)
+    }
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Number of internal nodes hashed on this thread, so tests can compare
+    /// the cost of different update strategies.
+    pub(crate) static NUM_NODE_HASHES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
 
 impl<'a, P: MerkleConfig> MerkleTree<'a, P> {
-    pub fn new(
-        capacity: usize,
-        params: &'a PoseidonConfig<P::BasePrimeField>,
-    ) -> Result<Self, MerkleTreeError> {
+    pub fn new(capacity: usize, params: &'a HashParams<P>) -> Result<Self, MerkleTreeError> {
         let mut s = Self::new_with_empty(capacity, params)?;
 
         // ensure the constructed merkle tree is valid
-        for i in (0..s.leaf_start()).rev() {
-            s.update_state(i)?;
+        s.update_all()?;
+
+        Ok(s)
+    }
+
+    /// Deserialize a tree written with `CanonicalSerialize`. The hash
+    /// parameters are not serialized, so they have to be supplied again.
+    ///
+    /// With `Validate::Yes`, every internal node is checked to be the hash of
+    /// its children.
+    pub fn deserialize_with_params(
+        reader: impl Read,
+        compress: Compress,
+        validate: Validate,
+        params: &'a HashParams<P>,
+    ) -> Result<Self, SerializationError> {
+        let states = Vec::deserialize_with_mode(reader, compress, validate)?;
+        let mut s = Self::new_with_empty(states.len(), params)
+            .map_err(|_| SerializationError::InvalidData)?;
+        s.states = states;
+
+        if validate == Validate::Yes {
+            let arity = arity::<P>();
+            for i in 0..s.leaf_start() {
+                let children = first_child(i, arity)..first_child(i, arity) + arity;
+                let hash = P::compress(params, &s.states[children])
+                    .map_err(|_| SerializationError::InvalidData)?;
+                if hash != s.states[i] {
+                    return Err(SerializationError::InvalidData);
+                }
+            }
         }
 
         Ok(s)
     }
 
+    /// Build a tree whose leaves are exactly `data`, i.e. of capacity
+    /// `(ARITY * data.len() - 1) / (ARITY - 1)`. `data` holds either the leaf
+    /// hashes or the values to be hashed into leaves, and its length should be
+    /// a power of `ARITY`.
     pub fn new_with_data(
-        data: Either<&[P::BasePrimeField], &[&<Poseidon<P::BasePrimeField> as CRHScheme>::Input]>,
-        params: &'a PoseidonConfig<P::BasePrimeField>,
+        data: Either<&[Node<P>], &[&LeafInput<P>]>,
+        params: &'a HashParams<P>,
     ) -> Result<Self, MerkleTreeError> {
         let len = for_both!(data, data => data.len());
-        let capacity = len * 2 - 1;
-        let mut s = Self::new_with_empty(capacity, params)?;
+        if len == 0 {
+            return Err(MerkleTreeError::InvalidCapacity);
+        }
+        let capacity = capacity(len, arity::<P>());
+        match data {
+            Either::Left(leaves) => Self::from_leaves(leaves, capacity, params),
+            Either::Right(values) => Self::from_values(values, capacity, params),
+        }
+    }
 
-        let mut data_holder = Vec::new();
-        let data = match data {
-            Either::Left(v) => v,
-            Either::Right(v) => {
-                data_holder.reserve_exact(v.len());
-                for d in v {
-                    data_holder.push(
-                        Poseidon::evaluate(s.params, *d).map_err(|_| MerkleTreeError::CRHError)?,
-                    );
-                }
-                &data_holder
-            }
-        };
+    /// Build a tree of `capacity` from the hashed `leaves`, padding the
+    /// remaining leaves with the default value.
+    ///
+    /// This is equivalent to updating the leaves of an empty tree one by one,
+    /// but every internal node is hashed only once.
+    pub fn from_leaves(
+        leaves: &[Node<P>],
+        capacity: usize,
+        params: &'a HashParams<P>,
+    ) -> Result<Self, MerkleTreeError> {
+        let mut s = Self::new_with_empty(capacity, params)?;
+        if leaves.len() > s.num_leaves() {
+            return Err(MerkleTreeError::TreeIsFull);
+        }
 
         let leaf_start = s.leaf_start();
-        for (h, v) in s.states[leaf_start..].iter_mut().zip(data) {
-            *h = *v;
-        }
+        s.states[leaf_start..leaf_start + leaves.len()].clone_from_slice(leaves);
 
         // O(N) construction
-        for i in (0..leaf_start).rev() {
-            s.update_state(i)?;
-        }
+        s.update_all()?;
 
         Ok(s)
     }
 
-    pub fn prove(&self, leaf_index: usize) -> Result<MerkleProof<P>, MerkleTreeError> {
+    /// Same as `from_leaves`, but hashes `values` into leaves first.
+    pub fn from_values(
+        values: &[&LeafInput<P>],
+        capacity: usize,
+        params: &'a HashParams<P>,
+    ) -> Result<Self, MerkleTreeError> {
+        let leaves = hash_leaves::<P>(params, values)?;
+        Self::from_leaves(&leaves, capacity, params)
+    }
+
+    pub fn prove(&self, leaf_index: usize) -> Result<MerkleTreeProof<P>, MerkleTreeError> {
         if leaf_index >= self.num_leaves() {
             return Err(MerkleTreeError::IndexOutOfBound);
         }
 
-        let mut proof = Vec::new();
+        let arity = arity::<P>();
+        let mut siblings = Vec::new();
         let mut index = self.leaf_start() + leaf_index;
         while index > 0 {
-            let sibling = Self::sibling(index);
-            proof.push(self.states[sibling]);
-            index = parent(index);
+            let first = index - child_position(index, arity);
+            siblings.extend(
+                (first..first + arity)
+                    .filter(|i| *i != index)
+                    .map(|i| self.states[i].clone()),
+            );
+            index = parent(index, arity);
         }
-        Ok((proof, self.leaf_start() + leaf_index))
+        Ok(MerkleTreeProof {
+            siblings,
+            leaf_index,
+        })
     }
 
-    pub fn update(
-        &mut self,
-        leaf_index: usize,
-        val: &<Poseidon<P::BasePrimeField> as CRHScheme>::Input,
-    ) -> Result<(), MerkleTreeError> {
+    pub fn update(&mut self, leaf_index: usize, val: &LeafInput<P>) -> Result<(), MerkleTreeError> {
         if leaf_index >= self.num_leaves() {
             return Err(MerkleTreeError::IndexOutOfBound);
         }
 
         self.update_with_hash(
             leaf_index,
-            Poseidon::evaluate(self.params, val).map_err(|_| MerkleTreeError::CRHError)?,
+            P::LeafHash::evaluate(self.params, val).map_err(MerkleTreeError::from)?,
         )
     }
 
+    /// Reset the leaf at `leaf_index` to the default hash, i.e. the value of
+    /// a leaf that was never updated.
+    pub fn remove(&mut self, leaf_index: usize) -> Result<(), MerkleTreeError> {
+        self.update_with_hash(leaf_index, Node::<P>::default())
+    }
+
+    /// Update several leaves at once.
+    ///
+    /// All leaves are written first, and then every affected internal node is
+    /// recomputed exactly once, level by level. If an index appears more than
+    /// once, the last value wins, as with sequential `update`s.
+    pub fn update_batch(
+        &mut self,
+        updates: &[(usize, &LeafInput<P>)],
+    ) -> Result<(), MerkleTreeError> {
+        let values: Vec<_> = updates.iter().map(|(_, val)| *val).collect();
+        let updates: Vec<_> = updates
+            .iter()
+            .map(|(leaf_index, _)| *leaf_index)
+            .zip(hash_leaves::<P>(self.params, &values)?)
+            .collect();
+
+        self.update_batch_with_hash(&updates)
+    }
+
     #[inline]
-    pub fn root(&self) -> P::BasePrimeField {
-        self.states[0]
+    pub fn root(&self) -> Node<P> {
+        self.states[0].clone()
     }
 
     #[inline]
-    pub fn leaves(&self) -> &[P::BasePrimeField] {
+    pub fn leaves(&self) -> &[Node<P>] {
         &self.states[self.leaf_start()..]
     }
 
+    /// Check `proof` against `root`. A well-formed proof for a different root,
+    /// e.g. one generated before an update, gives `Ok(false)`.
     pub fn verify(
-        params: &PoseidonConfig<P::BasePrimeField>,
-        root: P::BasePrimeField,
-        leaf: Either<&P::BasePrimeField, &<Poseidon<P::BasePrimeField> as CRHScheme>::Input>,
-        proof: MerkleProof<P>,
+        params: &HashParams<P>,
+        root: Node<P>,
+        leaf: Either<&Node<P>, &LeafInput<P>>,
+        proof: &MerkleTreeProof<P>,
     ) -> Result<bool, MerkleTreeError> {
-        let (siblings, leaf_index) = proof;
-        if (leaf_index + 1).ilog2() as usize != siblings.len() {
-            return Err(MerkleTreeError::PathLenMismatch);
-        }
-
         let hash = match leaf {
-            Either::Left(v) => *v,
-            Either::Right(v) => {
-                Poseidon::evaluate(params, v).map_err(|_| MerkleTreeError::CRHError)?
-            }
+            Either::Left(v) => v.clone(),
+            Either::Right(v) => P::LeafHash::evaluate(params, v).map_err(MerkleTreeError::from)?,
         };
-        let hash = Self::hash_path(params, hash, leaf_index, &siblings)?;
-        Ok(hash == root)
+        Ok(proof.compute_root(params, hash)? == root)
     }
 
     #[inline]
@@ -167,24 +310,32 @@ impl<'a, P: MerkleConfig> MerkleTree<'a, P> {
 
     #[inline]
     pub fn num_leaves(&self) -> usize {
-        (self.capacity() + 1) / 2
+        num_leaves(self.capacity(), arity::<P>())
     }
 
-    pub(crate) fn hash_path(
-        params: &PoseidonConfig<P::BasePrimeField>,
-        mut hash: P::BasePrimeField,
+    /// Hash `hash` up to the root along `siblings`, where `index` is the
+    /// position of the starting node among all nodes of the tree, i.e. the
+    /// leaf index offset by the number of internal nodes.
+    pub fn hash_path(
+        params: &HashParams<P>,
+        mut hash: Node<P>,
         mut index: usize,
-        siblings: &[P::BasePrimeField],
-    ) -> Result<P::BasePrimeField, MerkleTreeError> {
-        for sibling in siblings {
-            if is_left_node(index) {
-                hash = PoseidonTwoToOne::evaluate(params, hash, *sibling)
-                    .map_err(|_| MerkleTreeError::CRHError)?;
-            } else {
-                hash = PoseidonTwoToOne::evaluate(params, sibling, &hash)
-                    .map_err(|_| MerkleTreeError::CRHError)?;
-            }
-            index = parent(index);
+        siblings: &[Node<P>],
+    ) -> Result<Node<P>, MerkleTreeError> {
+        let arity = arity::<P>();
+        if siblings.len() % (arity - 1) != 0 {
+            return Err(MerkleTreeError::PathLenMismatch);
+        }
+
+        for siblings in siblings.chunks(arity - 1) {
+            let position = child_position(index, arity);
+            let mut children = Vec::with_capacity(arity);
+            children.extend_from_slice(&siblings[..position]);
+            children.push(hash);
+            children.extend_from_slice(&siblings[position..]);
+
+            hash = P::compress(params, &children).map_err(MerkleTreeError::from)?;
+            index = parent(index, arity);
         }
         Ok(hash)
     }
@@ -192,41 +343,65 @@ impl<'a, P: MerkleConfig> MerkleTree<'a, P> {
     pub(crate) fn update_with_hash(
         &mut self,
         leaf_index: usize,
-        val: P::BasePrimeField,
+        val: Node<P>,
     ) -> Result<(), MerkleTreeError> {
-        if leaf_index >= (self.capacity() + 1) / 2 {
+        if leaf_index >= self.num_leaves() {
             return Err(MerkleTreeError::IndexOutOfBound);
         }
 
         let mut index = self.leaf_start() + leaf_index;
         self.states[index] = val;
         while index > 0 {
-            index = parent(index);
+            index = parent(index, arity::<P>());
             self.update_state(index)?;
         }
 
         Ok(())
     }
 
-    const fn sibling(index: usize) -> usize {
-        if index % 2 == 0 {
-            index - 1
-        } else {
-            index + 1
+    pub(crate) fn update_batch_with_hash(
+        &mut self,
+        updates: &[(usize, Node<P>)],
+    ) -> Result<(), MerkleTreeError> {
+        if updates
+            .iter()
+            .any(|(leaf_index, _)| *leaf_index >= self.num_leaves())
+        {
+            return Err(MerkleTreeError::IndexOutOfBound);
         }
+
+        let leaf_start = self.leaf_start();
+        let mut dirty: Vec<_> = updates
+            .iter()
+            .map(|(leaf_index, val)| {
+                self.states[leaf_start + leaf_index] = val.clone();
+                leaf_start + leaf_index
+            })
+            .collect();
+
+        // all dirty nodes are on the same level, so we are done once we reach the root
+        while dirty.first().is_some_and(|index| *index > 0) {
+            dirty = dirty
+                .into_iter()
+                .map(|index| parent(index, arity::<P>()))
+                .collect();
+            dirty.sort_unstable();
+            dirty.dedup();
+
+            self.update_states(&dirty)?;
+        }
+
+        Ok(())
     }
 
     #[inline]
-    fn new_with_empty(
-        capacity: usize,
-        params: &'a PoseidonConfig<P::BasePrimeField>,
-    ) -> Result<Self, MerkleTreeError> {
-        if capacity < 3 || !(capacity + 1).is_power_of_two() {
+    fn new_with_empty(capacity: usize, params: &'a HashParams<P>) -> Result<Self, MerkleTreeError> {
+        if !is_valid_capacity(capacity, arity::<P>()) {
             return Err(MerkleTreeError::InvalidCapacity);
         }
 
         let s = Self {
-            states: vec![P::BasePrimeField::default(); capacity],
+            states: vec![Node::<P>::default(); capacity],
             params,
         };
 
@@ -235,30 +410,99 @@ impl<'a, P: MerkleConfig> MerkleTree<'a, P> {
 
     #[inline]
     fn leaf_start(&self) -> usize {
-        (self.capacity() + 1) / 2 - 1
+        self.capacity() - self.num_leaves()
+    }
+
+    /// Recompute every internal node, level by level from the leaves up.
+    fn update_all(&mut self) -> Result<(), MerkleTreeError> {
+        let mut end = self.leaf_start();
+        while end > 0 {
+            let start = parent(end, arity::<P>());
+            self.update_states(&(start..end).collect::<Vec<_>>())?;
+            end = start;
+        }
+
+        Ok(())
+    }
+
+    /// Recompute the nodes at `indices` from their children, which should be
+    /// up to date, e.g. because all the nodes are on the same level. The
+    /// nodes are hashed on several threads with the `parallel` feature.
+    fn update_states(&mut self, indices: &[usize]) -> Result<(), MerkleTreeError> {
+        let arity = arity::<P>();
+        let hashes = cfg_iter!(indices)
+            .map(|index| {
+                let first = first_child(*index, arity);
+                P::compress(self.params, &self.states[first..first + arity])
+                    .map_err(MerkleTreeError::from)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        for (index, hash) in indices.iter().zip(hashes) {
+            self.states[*index] = hash;
+        }
+
+        #[cfg(test)]
+        NUM_NODE_HASHES.with(|n| n.set(n.get() + indices.len()));
+
+        Ok(())
     }
 
     fn update_state(&mut self, index: usize) -> Result<(), MerkleTreeError> {
-        let left = left(index);
-        let right = right(index);
-        self.states[index] =
-            PoseidonTwoToOne::evaluate(self.params, self.states[left], self.states[right])
-                .map_err(|_| MerkleTreeError::CRHError)?;
+        let arity = arity::<P>();
+        let first = first_child(index, arity);
+        self.states[index] = P::compress(self.params, &self.states[first..first + arity])
+            .map_err(MerkleTreeError::from)?;
+
+        #[cfg(test)]
+        NUM_NODE_HASHES.with(|n| n.set(n.get() + 1));
+
         Ok(())
     }
 }
 
+/// Only the nodes are serialized, see `MerkleTree::deserialize_with_params`.
+impl<P: MerkleConfig> CanonicalSerialize for MerkleTree<'_, P> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.states.serialize_with_mode(writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.states.serialized_size(compress)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::params::poseidon_canonical_config;
     use ark_bls12_381::Fr;
+    use ark_crypto_primitives::{
+        crh::{
+            poseidon::{TwoToOneCRH as PoseidonTwoToOne, CRH as Poseidon},
+            sha256::Sha256,
+            TwoToOneCRHScheme,
+        },
+        sponge::poseidon::PoseidonConfig,
+    };
     use ark_ff::UniformRand;
-    use folding_schemes::transcript::poseidon::poseidon_canonical_config;
-    use rand::{rngs::StdRng, thread_rng, SeedableRng};
+    use rand::{rngs::StdRng, thread_rng, RngCore, SeedableRng};
 
     struct TestConfig;
     impl MerkleConfig for TestConfig {
         type BasePrimeField = Fr;
+        type LeafHash = Poseidon<Fr>;
+        type TwoToOneHash = PoseidonTwoToOne<Fr>;
+    }
+
+    struct Sha256Config;
+    impl MerkleConfig for Sha256Config {
+        type BasePrimeField = Fr;
+        type LeafHash = Sha256;
+        type TwoToOneHash = Sha256;
     }
 
     fn poseidon_params() -> PoseidonConfig<Fr> {
@@ -295,7 +539,7 @@ mod tests {
         let proof = proof.unwrap();
         let root = tree.root();
         let valid =
-            MerkleTree::<TestConfig>::verify(&params, root, either::Right(&[new_leaf]), proof);
+            MerkleTree::<TestConfig>::verify(&params, root, either::Right(&[new_leaf]), &proof);
         assert!(matches!(valid, Ok(true)));
     }
 
@@ -328,8 +572,12 @@ mod tests {
             let root = tree.root();
 
             // Verify the proof of each updated leaf
-            let valid =
-                MerkleTree::<TestConfig>::verify(&params, root, either::Right(&[leaves[i]]), proof);
+            let valid = MerkleTree::<TestConfig>::verify(
+                &params,
+                root,
+                either::Right(&[leaves[i]]),
+                &proof,
+            );
             assert!(matches!(valid, Ok(true)));
         }
 
@@ -347,8 +595,12 @@ mod tests {
             let root = tree.root();
 
             // Verify the proof of each updated leaf
-            let valid =
-                MerkleTree::<TestConfig>::verify(&params, root, either::Right(&[leaves[i]]), proof);
+            let valid = MerkleTree::<TestConfig>::verify(
+                &params,
+                root,
+                either::Right(&[leaves[i]]),
+                &proof,
+            );
             assert!(matches!(valid, Ok(true)));
         }
     }
@@ -379,10 +631,442 @@ mod tests {
                 &params,
                 merkle.root(),
                 either::Left(&leaves[i]),
-                p,
+                &p,
             )
             .unwrap();
             assert!(valid);
         }
     }
+
+    #[test]
+    fn test_update_batch() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        for n in [1, 7, 64] {
+            let leaves: Vec<_> = (0..n).map(|_| [Fr::rand(&mut rng)]).collect();
+
+            let mut sequential = MerkleTree::<TestConfig>::new(127, &params).unwrap();
+            NUM_NODE_HASHES.with(|c| c.set(0));
+            for (i, leaf) in leaves.iter().enumerate() {
+                sequential.update(i, leaf).unwrap();
+            }
+            let sequential_hashes = NUM_NODE_HASHES.with(|c| c.get());
+
+            let mut batch = MerkleTree::<TestConfig>::new(127, &params).unwrap();
+            let updates: Vec<_> = leaves
+                .iter()
+                .enumerate()
+                .map(|(i, leaf)| (i, &leaf[..]))
+                .collect();
+            NUM_NODE_HASHES.with(|c| c.set(0));
+            batch.update_batch(&updates).unwrap();
+            let batch_hashes = NUM_NODE_HASHES.with(|c| c.get());
+
+            assert_eq!(batch.root(), sequential.root());
+            assert_eq!(batch.leaves(), sequential.leaves());
+
+            // a single leaf has a single path to update either way
+            if n == 1 {
+                assert_eq!(batch_hashes, sequential_hashes);
+            } else {
+                assert!(batch_hashes < sequential_hashes);
+            }
+        }
+
+        let mut tree = MerkleTree::<TestConfig>::new(7, &params).unwrap();
+        assert!(matches!(
+            tree.update_batch(&[(0, &[Fr::from(1)][..]), (4, &[Fr::from(2)][..])]),
+            Err(MerkleTreeError::IndexOutOfBound)
+        ));
+    }
+
+    /// Compare against a root computed sequentially, so that running the
+    /// tests with and without the `parallel` feature checks that both paths
+    /// build the same tree.
+    #[test]
+    fn test_from_leaves_matches_sequential() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        let mut level: Vec<_> = (0..1 << 14).map(|_| Fr::rand(&mut rng)).collect();
+        let tree = MerkleTree::<TestConfig>::new_with_data(either::Left(&level), &params).unwrap();
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|children| TestConfig::compress(&params, children).unwrap())
+                .collect();
+        }
+        assert_eq!(tree.root(), level[0]);
+    }
+
+    #[test]
+    fn test_from_leaves() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        for (capacity, n) in [(3, 1), (3, 2), (15, 5), (31, 16)] {
+            let values: Vec<_> = (0..n).map(|_| [Fr::rand(&mut rng)]).collect();
+            let values_ref: Vec<_> = values.iter().map(|v| &v[..]).collect();
+            let leaves: Vec<_> = values_ref
+                .iter()
+                .map(|v| Poseidon::evaluate(&params, *v).unwrap())
+                .collect();
+
+            let mut incremental = MerkleTree::<TestConfig>::new(capacity, &params).unwrap();
+            for (i, leaf) in leaves.iter().enumerate() {
+                incremental.update_with_hash(i, *leaf).unwrap();
+            }
+
+            let from_leaves =
+                MerkleTree::<TestConfig>::from_leaves(&leaves, capacity, &params).unwrap();
+            let from_values =
+                MerkleTree::<TestConfig>::from_values(&values_ref, capacity, &params).unwrap();
+
+            assert_eq!(from_leaves.root(), incremental.root());
+            assert_eq!(from_values.root(), incremental.root());
+            assert_eq!(from_values.leaves(), incremental.leaves());
+        }
+
+        let leaves = vec![Fr::rand(&mut rng); 5];
+        assert!(matches!(
+            MerkleTree::<TestConfig>::from_leaves(&leaves, 7, &params),
+            Err(MerkleTreeError::TreeIsFull)
+        ));
+        assert!(matches!(
+            MerkleTree::<TestConfig>::from_leaves(&leaves, 6, &params),
+            Err(MerkleTreeError::InvalidCapacity)
+        ));
+    }
+
+    #[test]
+    fn test_proof() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        let leaves: Vec<_> = (0..8).map(|_| Fr::rand(&mut rng)).collect();
+        let mut tree = MerkleTree::<TestConfig>::from_leaves(&leaves, 15, &params).unwrap();
+
+        let proof = tree.prove(3).unwrap();
+        assert_eq!(proof.leaf_index, 3);
+        assert_eq!(proof.siblings.len(), 3);
+        assert!(MerkleTree::<TestConfig>::verify(
+            &params,
+            tree.root(),
+            Either::Left(&leaves[3]),
+            &proof
+        )
+        .unwrap());
+
+        // out-of-range indices
+        assert!(matches!(
+            tree.prove(8),
+            Err(MerkleTreeError::IndexOutOfBound)
+        ));
+        let out_of_range = MerkleTreeProof {
+            leaf_index: 8,
+            ..proof.clone()
+        };
+        assert!(matches!(
+            MerkleTree::<TestConfig>::verify(
+                &params,
+                tree.root(),
+                Either::Left(&leaves[3]),
+                &out_of_range
+            ),
+            Err(MerkleTreeError::IndexOutOfBound)
+        ));
+
+        // a proof against a stale root is rejected without an error
+        let stale_root = tree.root();
+        tree.update(3, &[Fr::from(1)]).unwrap();
+        let new_proof = tree.prove(3).unwrap();
+        assert!(!MerkleTree::<TestConfig>::verify(
+            &params,
+            stale_root,
+            Either::Right(&[Fr::from(1)]),
+            &new_proof
+        )
+        .unwrap());
+        assert!(!MerkleTree::<TestConfig>::verify(
+            &params,
+            tree.root(),
+            Either::Left(&leaves[3]),
+            &proof
+        )
+        .unwrap());
+    }
+
+    /// Open the parent of leaves 0 and 1 as if it were a leaf with value
+    /// `[hash(leaf 0), hash(leaf 1)]`, one level above the real leaves.
+    fn forge_internal_node_as_leaf<P>(params: &PoseidonConfig<Fr>) -> bool
+    where
+        P: MerkleConfig,
+        P::LeafHash: CRHScheme<Input = [Fr]>,
+        P::TwoToOneHash: TwoToOneCRHScheme<Output = Fr, Parameters = PoseidonConfig<Fr>>,
+    {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let values: Vec<_> = (0..4).map(|_| Fr::rand(&mut rng)).collect();
+
+        let mut tree = MerkleTree::<P>::new(7, params).unwrap();
+        for (i, v) in values.iter().enumerate() {
+            tree.update(i, &[*v]).unwrap();
+        }
+
+        let h0 = P::LeafHash::evaluate(params, &values[..1]).unwrap();
+        let h1 = P::LeafHash::evaluate(params, &values[1..2]).unwrap();
+        let proof = tree.prove(0).unwrap();
+        let forged = MerkleTreeProof::<P> {
+            siblings: proof.siblings[1..].to_vec(),
+            leaf_index: 0,
+        };
+        MerkleTree::<P>::verify(params, tree.root(), Either::Right(&[h0, h1][..]), &forged).unwrap()
+    }
+
+    #[test]
+    fn test_domain_separation() {
+        let params = poseidon_params();
+
+        // without separation, an internal node is a valid leaf of a shorter path
+        assert!(forge_internal_node_as_leaf::<TestConfig>(&params));
+        assert!(!forge_internal_node_as_leaf::<
+            crate::merkle::DomainSeparatedConfig<Fr>,
+        >(&params));
+    }
+
+    #[test]
+    fn test_arity() {
+        type Wide = crate::merkle::WideConfig<Fr, 4>;
+
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        for (num_leaves, height) in [(16, 2), (64, 3)] {
+            let values: Vec<_> = (0..num_leaves).map(|_| [Fr::rand(&mut rng)]).collect();
+            let values_ref: Vec<_> = values.iter().map(|v| &v[..]).collect();
+
+            let binary =
+                MerkleTree::<TestConfig>::new_with_data(Either::Right(&values_ref), &params)
+                    .unwrap();
+            let mut wide =
+                MerkleTree::<Wide>::new_with_data(Either::Right(&values_ref), &params).unwrap();
+            assert_eq!(wide.num_leaves(), num_leaves);
+            assert_eq!(wide.capacity(), (4 * num_leaves - 1) / 3);
+
+            // the same as updating the leaves of an empty tree one by one
+            let mut incremental = MerkleTree::<Wide>::new(wide.capacity(), &params).unwrap();
+            for (i, v) in values.iter().enumerate() {
+                incremental.update(i, v).unwrap();
+            }
+            assert_eq!(incremental.root(), wide.root());
+
+            for i in [0, 5, num_leaves - 1] {
+                // half as many levels, but 3 siblings per level
+                let proof = wide.prove(i).unwrap();
+                assert_eq!(proof.siblings.len(), 3 * height);
+                assert_eq!(binary.prove(i).unwrap().siblings.len(), 2 * height);
+
+                assert!(MerkleTree::<Wide>::verify(
+                    &params,
+                    wide.root(),
+                    Either::Right(&values[i]),
+                    &proof
+                )
+                .unwrap());
+                assert!(!MerkleTree::<Wide>::verify(
+                    &params,
+                    wide.root(),
+                    Either::Right(&values[(i + 1) % num_leaves]),
+                    &proof
+                )
+                .unwrap());
+
+                // a binary-length path is not a multiple of 3 siblings
+                let malformed = MerkleTreeProof::<Wide> {
+                    siblings: proof.siblings[1..].to_vec(),
+                    leaf_index: i,
+                };
+                assert!(matches!(
+                    malformed.compute_root(&params, Fr::default()),
+                    Err(MerkleTreeError::PathLenMismatch)
+                ));
+            }
+
+            wide.update(3, &[Fr::from(1)]).unwrap();
+            let proof = wide.prove(3).unwrap();
+            assert!(MerkleTree::<Wide>::verify(
+                &params,
+                wide.root(),
+                Either::Right(&[Fr::from(1)][..]),
+                &proof
+            )
+            .unwrap());
+        }
+
+        assert!(matches!(
+            MerkleTree::<Wide>::new(7, &params),
+            Err(MerkleTreeError::InvalidCapacity)
+        ));
+        assert!(matches!(
+            MerkleTree::<Wide>::new(4, &params),
+            Err(MerkleTreeError::InvalidCapacity)
+        ));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        let mut leaves: Vec<_> = (0..8).map(|_| Fr::rand(&mut rng)).collect();
+        let mut tree = MerkleTree::<TestConfig>::from_leaves(&leaves, 15, &params).unwrap();
+
+        for i in [2, 7, 2] {
+            tree.remove(i).unwrap();
+            leaves[i] = Fr::default();
+        }
+        tree.update(7, &[Fr::from(1)]).unwrap();
+        leaves[7] = Poseidon::evaluate(&params, [Fr::from(1)]).unwrap();
+
+        let expected = MerkleTree::<TestConfig>::from_leaves(&leaves, 15, &params).unwrap();
+        assert_eq!(tree.root(), expected.root());
+        assert_eq!(tree.leaves(), &leaves[..]);
+        assert!(matches!(
+            tree.remove(8),
+            Err(MerkleTreeError::IndexOutOfBound)
+        ));
+    }
+
+    #[test]
+    fn test_serialize() {
+        let mut rng = StdRng::from_seed([42; 32]);
+        let params = poseidon_params();
+
+        let leaves: Vec<_> = (0..5).map(|_| Fr::rand(&mut rng)).collect();
+        let tree = MerkleTree::<TestConfig>::from_leaves(&leaves, 15, &params).unwrap();
+
+        let mut bytes = vec![];
+        tree.serialize_compressed(&mut bytes).unwrap();
+        let mut deserialized = MerkleTree::<TestConfig>::deserialize_with_params(
+            &*bytes,
+            Compress::Yes,
+            Validate::Yes,
+            &params,
+        )
+        .unwrap();
+        assert_eq!(deserialized.root(), tree.root());
+        assert_eq!(deserialized.leaves(), tree.leaves());
+
+        // the deserialized tree can still be updated
+        deserialized.update(5, &[Fr::from(1)]).unwrap();
+        let mut expected = tree.clone();
+        expected.update(5, &[Fr::from(1)]).unwrap();
+        assert_eq!(deserialized.root(), expected.root());
+
+        // a corrupted node is rejected
+        let mut corrupted = tree.clone();
+        corrupted.states[0] = Fr::from(1);
+        let mut bytes = vec![];
+        corrupted.serialize_compressed(&mut bytes).unwrap();
+        assert!(MerkleTree::<TestConfig>::deserialize_with_params(
+            &*bytes,
+            Compress::Yes,
+            Validate::Yes,
+            &params,
+        )
+        .is_err());
+    }
+
+    /// A 4-ary config that keeps the default `compress`, which only hashes
+    /// 2 children, so that every internal node fails to hash.
+    struct BrokenConfig;
+    impl MerkleConfig for BrokenConfig {
+        type BasePrimeField = Fr;
+        type LeafHash = Poseidon<Fr>;
+        type TwoToOneHash = PoseidonTwoToOne<Fr>;
+        const ARITY: usize = 4;
+    }
+
+    #[test]
+    fn test_error_variants() {
+        use std::error::Error;
+
+        let params = poseidon_params();
+
+        assert!(matches!(
+            MerkleTree::<TestConfig>::new(8, &params),
+            Err(MerkleTreeError::InvalidCapacity)
+        ));
+
+        let mut tree = MerkleTree::<TestConfig>::new(7, &params).unwrap();
+        assert!(matches!(
+            tree.prove(4),
+            Err(MerkleTreeError::IndexOutOfBound)
+        ));
+        assert!(matches!(
+            tree.update(4, &[Fr::from(1)]),
+            Err(MerkleTreeError::IndexOutOfBound)
+        ));
+
+        let err = MerkleTree::<BrokenConfig>::new(5, &params).unwrap_err();
+        assert!(matches!(err, MerkleTreeError::CRHError(_)));
+        assert_eq!(err.to_string(), "CRH evaluation failed");
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            "TwoToOneHash only compresses 2 children"
+        );
+    }
+
+    #[test]
+    fn test_sha256_tree() {
+        let mut rng = StdRng::from_seed([42; 32]);
+
+        let values: Vec<_> = (0..5)
+            .map(|_| {
+                let mut v = [0u8; 32];
+                rng.fill_bytes(&mut v);
+                v
+            })
+            .collect();
+        let values_ref: Vec<_> = values.iter().map(|v| &v[..]).collect();
+        let mut tree = MerkleTree::<Sha256Config>::from_values(&values_ref, 15, &()).unwrap();
+
+        for (i, v) in values.iter().enumerate() {
+            let proof = tree.prove(i).unwrap();
+            assert!(MerkleTree::<Sha256Config>::verify(
+                &(),
+                tree.root(),
+                Either::Right(&v[..]),
+                &proof
+            )
+            .unwrap());
+        }
+
+        tree.update(1, &[1u8; 32]).unwrap();
+        let proof = tree.prove(1).unwrap();
+        assert!(MerkleTree::<Sha256Config>::verify(
+            &(),
+            tree.root(),
+            Either::Right(&[1u8; 32][..]),
+            &proof
+        )
+        .unwrap());
+        assert!(!MerkleTree::<Sha256Config>::verify(
+            &(),
+            tree.root(),
+            Either::Right(&values[1][..]),
+            &proof
+        )
+        .unwrap());
+
+        // the root of a tree with two leaves is `H(H(v0) || H(v1))`
+        let tree = MerkleTree::<Sha256Config>::from_values(&values_ref[..2], 3, &()).unwrap();
+        let leaves: Vec<_> = values_ref[..2]
+            .iter()
+            .map(|v| <Sha256 as CRHScheme>::evaluate(&(), *v).unwrap())
+            .collect();
+        let root =
+            <Sha256 as TwoToOneCRHScheme>::evaluate(&(), &leaves[0][..], &leaves[1][..]).unwrap();
+        assert_eq!(tree.root(), root);
+    }
 }
@@ -3,3 +3,10 @@ use ark_ec::bls12::Bls12Config;
 pub type BlsSigField<SigCurveConfig> = <SigCurveConfig as Bls12Config>::Fp;
 
 pub type BlsSigConfig = ark_bls12_381::Config;
+
+/// The Poseidon parameters of the Merkle trees and forests, which the folding
+/// circuits also use to hash their state.
+///
+/// Re-exported so that the Merkle APIs can be used without importing
+/// `folding_schemes`.
+pub use folding_schemes::transcript::poseidon::poseidon_canonical_config;
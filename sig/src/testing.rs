@@ -0,0 +1,40 @@
+//! Helpers for tests and benchmarks that measure circuit sizes.
+
+use ark_ff::Field;
+use ark_relations::r1cs::{ConstraintSystem, ConstraintSystemRef, SynthesisError};
+
+/// Run `f` on a fresh constraint system and return the number of constraints
+/// it generated.
+///
+/// Panics if `f` fails or leaves the constraint system unsatisfied, naming the
+/// first unsatisfied constraint.
+#[track_caller]
+pub fn count_constraints<F: Field, T>(
+    f: impl FnOnce(ConstraintSystemRef<F>) -> Result<T, SynthesisError>,
+) -> usize {
+    let cs = ConstraintSystem::<F>::new_ref();
+    f(cs.clone()).expect("circuit synthesis should succeed");
+
+    let unsatisfied = cs
+        .which_is_unsatisfied()
+        .expect("constraint system should be in prove mode");
+    assert!(
+        unsatisfied.is_none(),
+        "constraint system is not satisfied: {}",
+        unsatisfied.unwrap_or_default()
+    );
+
+    cs.num_constraints()
+}
+
+/// Assert that `actual` is within `tolerance` constraints of `expected`.
+///
+/// A tolerance of `0` pins the exact count, so that any change to the circuit
+/// size has to update the expectation.
+#[track_caller]
+pub fn assert_constraints_within(actual: usize, expected: usize, tolerance: usize) {
+    assert!(
+        actual.abs_diff(expected) <= tolerance,
+        "expected {expected} ± {tolerance} constraints, got {actual}"
+    );
+}
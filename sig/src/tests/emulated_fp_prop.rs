@@ -0,0 +1,298 @@
+#[cfg(test)]
+mod test {
+    use std::fmt;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use ark_ec::bls12::Bls12Config;
+    use ark_ec::pairing::Pairing;
+    use ark_ff::{One, PrimeField, UniformRand, Zero};
+    use ark_r1cs_std::{
+        alloc::AllocVar, fields::emulated_fp::EmulatedFpVar, fields::FieldVar, R1CSVar,
+    };
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::rand::{rngs::StdRng, Rng, SeedableRng};
+
+    type TargetF = <ark_bls12_381::Config as Bls12Config>::Fp;
+    type BaseF = <ark_bls12_377::Bls12_377 as Pairing>::ScalarField;
+
+    /// Fixed seed so that CI always explores the same cases.
+    const SEED: u64 = 0x6d69_6d5f_656d_7570;
+    const NUM_CASES: usize = 32;
+    const MAX_OPS: usize = 12;
+
+    // ================================================================================
+    // ==========Randomized add/sub/mul sequences on `EmulatedFpVar`===================
+    // ================================================================================
+    /*
+        Each case starts from a witness accumulator and applies a random sequence of
+        add/sub/mul operations, tracking the expected `TargetF` value natively. After
+        every operation the value of the accumulator must match the native value, and
+        the constraint system must be satisfied at the end.
+
+        A failing case is shrunk greedily (dropping operations, then simplifying the
+        operands) before being reported, so that the printed sequence is a small MRE.
+    */
+
+    #[derive(Clone, Copy, Debug)]
+    enum OpKind {
+        Add,
+        Sub,
+        Mul,
+    }
+
+    #[derive(Clone, Copy)]
+    struct Op {
+        kind: OpKind,
+        operand: TargetF,
+        /// Whether the operand is allocated as a witness or used as a constant.
+        witness: bool,
+    }
+
+    impl fmt::Debug for Op {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let mode = if self.witness { "witness" } else { "constant" };
+            write!(f, "{:?}({mode} {})", self.kind, self.operand)
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    struct Case {
+        initial: TargetF,
+        ops: Vec<Op>,
+    }
+
+    fn interesting_value<R: Rng>(rng: &mut R) -> TargetF {
+        // bias towards edge values, as those are where the limb bounds are tight
+        match rng.gen_range(0..6) {
+            0 => TargetF::zero(),
+            1 => TargetF::one(),
+            2 => -TargetF::one(),
+            _ => TargetF::rand(rng),
+        }
+    }
+
+    fn gen_case<R: Rng>(rng: &mut R) -> Case {
+        let len = rng.gen_range(1..=MAX_OPS);
+        let ops = (0..len)
+            .map(|_| Op {
+                kind: match rng.gen_range(0..3) {
+                    0 => OpKind::Add,
+                    1 => OpKind::Sub,
+                    _ => OpKind::Mul,
+                },
+                operand: interesting_value(rng),
+                witness: rng.gen_bool(0.75),
+            })
+            .collect();
+        Case {
+            initial: interesting_value(rng),
+            ops,
+        }
+    }
+
+    fn run_case(case: &Case) -> Result<(), String> {
+        let cs = ConstraintSystem::<BaseF>::new_ref();
+        let mut expected = case.initial;
+        let mut acc = EmulatedFpVar::<TargetF, BaseF>::new_witness(cs.clone(), || Ok(case.initial))
+            .map_err(|e| format!("failed to allocate initial value: {e}"))?;
+
+        for (i, op) in case.ops.iter().enumerate() {
+            let operand = if op.witness {
+                EmulatedFpVar::new_witness(cs.clone(), || Ok(op.operand))
+                    .map_err(|e| format!("op {i}: failed to allocate operand: {e}"))?
+            } else {
+                EmulatedFpVar::constant(op.operand)
+            };
+
+            (acc, expected) = match op.kind {
+                OpKind::Add => (acc + operand, expected + op.operand),
+                OpKind::Sub => (acc - operand, expected - op.operand),
+                OpKind::Mul => (acc * operand, expected * op.operand),
+            };
+
+            let actual = acc
+                .value()
+                .map_err(|e| format!("op {i}: failed to read value: {e}"))?;
+            if actual != expected {
+                return Err(format!("op {i}: expected {expected}, got {actual}"));
+            }
+        }
+
+        match cs.which_is_unsatisfied() {
+            Ok(None) => Ok(()),
+            Ok(Some(name)) => Err(format!("unsatisfied constraint: {name}")),
+            Err(e) => Err(format!("failed to check satisfiability: {e}")),
+        }
+    }
+
+    /// Like `run_case`, but turns panics inside the gadgets into failures so
+    /// that they can be shrunk as well.
+    fn check_case(case: &Case) -> Result<(), String> {
+        catch_unwind(AssertUnwindSafe(|| run_case(case)))
+            .unwrap_or_else(|_| Err("gadget panicked".to_string()))
+    }
+
+    /// Greedily shrink a failing case: drop operations while it still fails,
+    /// then replace operands with simpler values.
+    fn shrink(mut case: Case) -> (Case, String) {
+        let mut error = check_case(&case).expect_err("only failing cases are shrunk");
+
+        let mut progress = true;
+        while progress {
+            progress = false;
+
+            let mut i = 0;
+            while i < case.ops.len() {
+                let mut candidate = case.clone();
+                candidate.ops.remove(i);
+                match check_case(&candidate) {
+                    Err(e) => {
+                        (case, error) = (candidate, e);
+                        progress = true;
+                    }
+                    Ok(()) => i += 1,
+                }
+            }
+
+            for i in 0..case.ops.len() {
+                for simpler in [TargetF::zero(), TargetF::one()] {
+                    if case.ops[i].operand == simpler {
+                        continue;
+                    }
+                    let mut candidate = case.clone();
+                    candidate.ops[i].operand = simpler;
+                    if let Err(e) = check_case(&candidate) {
+                        (case, error) = (candidate, e);
+                        progress = true;
+                        break;
+                    }
+                }
+            }
+
+            if !case.initial.is_zero() && !case.initial.is_one() {
+                let mut candidate = case.clone();
+                candidate.initial = TargetF::one();
+                if let Err(e) = check_case(&candidate) {
+                    (case, error) = (candidate, e);
+                    progress = true;
+                }
+            }
+        }
+
+        (case, error)
+    }
+
+    fn check_random_cases(seed: u64, generate: impl Fn(&mut StdRng) -> Case) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        for n in 0..NUM_CASES {
+            let case = generate(&mut rng);
+            if check_case(&case).is_err() {
+                let (minimal, error) = shrink(case);
+                panic!("case {n} (seed {seed:#x}) failed: {error}\nminimal case: {minimal:#?}");
+            }
+        }
+    }
+
+    #[test]
+    fn emulated_fpvar_random_op_sequences() {
+        check_random_cases(SEED, gen_case);
+    }
+
+    /// Long runs of additions/subtractions push `num_of_additions_over_normal_form`
+    /// up before a multiplication has to reduce, which is where the subtraction and
+    /// multiplication bugs documented in `ark_r1cs_std_test` used to surface.
+    #[test]
+    fn emulated_fpvar_random_add_sub_then_mul() {
+        check_random_cases(SEED.wrapping_add(1), |rng| {
+            let mut case = gen_case(rng);
+            for op in case.ops.iter_mut() {
+                if matches!(op.kind, OpKind::Mul) {
+                    op.kind = OpKind::Sub;
+                }
+            }
+            case.ops.push(Op {
+                kind: OpKind::Mul,
+                operand: interesting_value(rng),
+                witness: true,
+            });
+            case
+        });
+    }
+
+    // ================================================================================
+    // ===================`is_zero`, `negate` and `x - x` on `EmulatedFpVar`==========
+    // ================================================================================
+    /*
+        `swu` picks its branch with `ta.is_zero()?.select(...)`, so a wrong `is_zero`
+        silently corrupts hash-to-curve. Every value is checked both in the normal form
+        and after an unreduced `(x + y) - y`, as the subtraction bug documented in
+        `ark_r1cs_std_test` only shows up on non-normal forms.
+    */
+
+    /// Values next to the modulus and to the largest power of two below it, the
+    /// latter being the value used in `mre_emulated_fpvar_mul`.
+    fn near_modulus_values() -> Vec<TargetF> {
+        let half = TargetF::from(TargetF::one().into_bigint() << (TargetF::MODULUS_BIT_SIZE - 1));
+        vec![
+            TargetF::zero(),
+            TargetF::one(),
+            -TargetF::one(),
+            -TargetF::from(2u64),
+            half - TargetF::one(),
+            half,
+            half + TargetF::one(),
+        ]
+    }
+
+    fn check_zero_and_negate(x: TargetF, y: TargetF) {
+        let cs = ConstraintSystem::<BaseF>::new_ref();
+        let x_var = EmulatedFpVar::<TargetF, BaseF>::new_witness(cs.clone(), || Ok(x)).unwrap();
+        let y_var = EmulatedFpVar::new_witness(cs.clone(), || Ok(y)).unwrap();
+        let unreduced = &(&x_var + &y_var) - &y_var;
+        assert_eq!(unreduced.value().unwrap(), x, "x = {x}, y = {y}");
+
+        for (form, v) in [("normal", &x_var), ("unreduced", &unreduced)] {
+            let is_zero = v.is_zero().unwrap();
+            assert_eq!(is_zero.value().unwrap(), x.is_zero(), "{form} is_zero({x})");
+
+            let zero = v - v;
+            assert!(zero.value().unwrap().is_zero(), "{form} {x} - {x}");
+            assert!(
+                zero.is_zero().unwrap().value().unwrap(),
+                "{form} is_zero({x} - {x})"
+            );
+
+            let neg = v.negate().unwrap();
+            assert_eq!(neg.value().unwrap(), -x, "{form} -{x}");
+            assert!(
+                (&neg + v).is_zero().unwrap().value().unwrap(),
+                "{form} -{x} + {x}"
+            );
+
+            // the pattern used by `swu`
+            let selected = is_zero.select(&y_var, v).unwrap();
+            let expected = if x.is_zero() { y } else { x };
+            assert_eq!(selected.value().unwrap(), expected, "{form} select on {x}");
+        }
+
+        assert!(cs.is_satisfied().unwrap(), "x = {x}, y = {y}");
+    }
+
+    #[test]
+    fn emulated_fpvar_is_zero_and_negate_near_modulus() {
+        let values = near_modulus_values();
+        for x in &values {
+            for y in &values {
+                check_zero_and_negate(*x, *y);
+            }
+        }
+    }
+
+    #[test]
+    fn emulated_fpvar_is_zero_and_negate_random() {
+        let mut rng = StdRng::seed_from_u64(SEED.wrapping_add(2));
+        for _ in 0..NUM_CASES {
+            check_zero_and_negate(TargetF::rand(&mut rng), interesting_value(&mut rng));
+        }
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod test {
+    use ark_ff::{
+        fields::{Field, Fp12, Fp12Config, Fp2Config, Fp6Config},
+        PrimeField, UniformRand,
+    };
+    use ark_r1cs_std::{
+        alloc::AllocVar,
+        fields::{emulated_fp::EmulatedFpVar, fp::FpVar, fp12::Fp12Var, FieldOpsBounds, FieldVar},
+        R1CSVar,
+    };
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::test_rng;
+
+    // ================================================================================
+    // ==========Constraints of the Frobenius map on `Fp12Var`=========================
+    // ================================================================================
+    /*
+        `final_exponentiation` applies the Frobenius map several times. The map is a
+        set of conjugations and multiplications by the constant Frobenius
+        coefficients, so over `FpVar` it is linear and emits no constraints at all.
+
+        Over `EmulatedFpVar`, a multiplication by a constant reduces the product and
+        costs constraints, except for the coefficients 0 and +-1, which are skipped.
+        All coefficients of `frobenius_map(6)` are +-1, so it is free as well.
+    */
+
+    type Fp2C<P> = <<P as Fp12Config>::Fp6Config as Fp6Config>::Fp2Config;
+    type BaseField<P> = <Fp2C<P> as Fp2Config>::Fp;
+
+    /// Apply `frobenius_map(power)` to a witness for every `power`, check its
+    /// value, and return the number of constraints each power adds.
+    fn frobenius_constraints<P, F, CF>() -> Vec<usize>
+    where
+        P: Fp12Config,
+        F: FieldVar<BaseField<P>, CF>,
+        for<'a> &'a F: FieldOpsBounds<'a, BaseField<P>, F>,
+        CF: PrimeField,
+    {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::<CF>::new_ref();
+
+        let f = Fp12::<P>::rand(&mut rng);
+        let f_var = Fp12Var::<P, F, CF>::new_witness(cs.clone(), || Ok(f)).unwrap();
+
+        let num_constraints = (0..12)
+            .map(|power| {
+                let before = cs.num_constraints();
+                let frob = f_var.frobenius_map(power).unwrap();
+                let num_constraints = cs.num_constraints() - before;
+
+                let mut expected = f;
+                expected.frobenius_map_in_place(power);
+                assert_eq!(frob.value().unwrap(), expected, "power {power}");
+                num_constraints
+            })
+            .collect();
+
+        assert!(cs.is_satisfied().unwrap());
+        num_constraints
+    }
+
+    #[test]
+    fn fp12_frobenius_native_bls12_381() {
+        type P = ark_bls12_381::Fq12Config;
+        let num_constraints = frobenius_constraints::<P, FpVar<BaseField<P>>, BaseField<P>>();
+        assert!(
+            num_constraints.iter().all(|n| *n == 0),
+            "{num_constraints:?}"
+        );
+    }
+
+    #[test]
+    fn fp12_frobenius_native_bls12_377() {
+        type P = ark_bls12_377::Fq12Config;
+        let num_constraints = frobenius_constraints::<P, FpVar<BaseField<P>>, BaseField<P>>();
+        assert!(
+            num_constraints.iter().all(|n| *n == 0),
+            "{num_constraints:?}"
+        );
+    }
+
+    #[test]
+    fn fp12_frobenius_emulated_bls12_381() {
+        type P = ark_bls12_381::Fq12Config;
+        type CF = ark_bls12_377::Fr;
+        let num_constraints = frobenius_constraints::<P, EmulatedFpVar<BaseField<P>, CF>, CF>();
+        assert_eq!(num_constraints[0], 0);
+        assert_eq!(num_constraints[6], 0);
+    }
+}
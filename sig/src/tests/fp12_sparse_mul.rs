@@ -0,0 +1,76 @@
+#[cfg(test)]
+mod test {
+    use ark_ff::{
+        fields::{Fp12, Fp12Config, Fp2, Fp2Config, Fp6, Fp6Config},
+        UniformRand, Zero,
+    };
+    use ark_r1cs_std::{
+        alloc::AllocVar,
+        fields::{fp::FpVar, fp12::Fp12Var, fp2::Fp2Var},
+        R1CSVar,
+    };
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::test_rng;
+
+    // ================================================================================
+    // ==========Sparse multiplications used by the `ell` step of the pairing==========
+    // ================================================================================
+    /*
+        The Miller loop multiplies by the line evaluations, which are sparse `Fp12`
+        elements. Depending on the twist of the curve, `ell` calls either
+        `mul_by_014` (M-type twist, e.g. BLS12-381) or `mul_by_034` (D-type twist,
+        e.g. BLS12-377). Both are checked against a full `Fp12` multiplication by
+        the sparse element they stand for, on both curves.
+    */
+
+    type Fp2C<P> = <<P as Fp12Config>::Fp6Config as Fp6Config>::Fp2Config;
+    type BaseField<P> = <Fp2C<P> as Fp2Config>::Fp;
+    type Fp2VarOf<P> = Fp2Var<Fp2C<P>, FpVar<BaseField<P>>, BaseField<P>>;
+
+    const NUM_CASES: usize = 8;
+
+    fn check_sparse_mul<P: Fp12Config>() {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::<BaseField<P>>::new_ref();
+        let zero = Fp2::<Fp2C<P>>::zero();
+
+        for _ in 0..NUM_CASES {
+            let f = Fp12::<P>::rand(&mut rng);
+            let [c0, c1, c2] = [(); 3].map(|_| Fp2::<Fp2C<P>>::rand(&mut rng));
+
+            let f_var =
+                Fp12Var::<P, FpVar<BaseField<P>>, BaseField<P>>::new_witness(cs.clone(), || Ok(f))
+                    .unwrap();
+            let [c0_var, c1_var, c2_var] =
+                [c0, c1, c2].map(|c| Fp2VarOf::<P>::new_witness(cs.clone(), || Ok(c)).unwrap());
+
+            // `(c0 = (c0, c1, 0), c1 = (0, c2, 0))`
+            let sparse_014 = Fp12::<P>::new(
+                Fp6::<P::Fp6Config>::new(c0, c1, zero),
+                Fp6::<P::Fp6Config>::new(zero, c2, zero),
+            );
+            let product = f_var.mul_by_014(&c0_var, &c1_var, &c2_var).unwrap();
+            assert_eq!(product.value().unwrap(), f * sparse_014);
+
+            // `(c0 = (c0, 0, 0), c1 = (c1, c2, 0))`
+            let sparse_034 = Fp12::<P>::new(
+                Fp6::<P::Fp6Config>::new(c0, zero, zero),
+                Fp6::<P::Fp6Config>::new(c1, c2, zero),
+            );
+            let product = f_var.mul_by_034(&c0_var, &c1_var, &c2_var).unwrap();
+            assert_eq!(product.value().unwrap(), f * sparse_034);
+        }
+
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn fp12_sparse_mul_bls12_381() {
+        check_sparse_mul::<ark_bls12_381::Fq12Config>();
+    }
+
+    #[test]
+    fn fp12_sparse_mul_bls12_377() {
+        check_sparse_mul::<ark_bls12_377::Fq12Config>();
+    }
+}
@@ -1,2 +1,5 @@
 mod ark_r1cs_std_test;
+mod emulated_fp_prop;
+mod fp12_frobenius;
+mod fp12_sparse_mul;
 mod limb_sizes;
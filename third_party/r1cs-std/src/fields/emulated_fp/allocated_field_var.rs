@@ -534,6 +534,27 @@ impl<TargetF: PrimeField, BaseF: PrimeField> AllocatedEmulatedFpVar<TargetF, Bas
         Ok(())
     }
 
+    /// Enforce that `self` is equal to the constant `c`.
+    ///
+    /// The limbs of an element in the normal form are the canonical
+    /// decomposition of its value, so they are compared directly against the
+    /// limbs of `c`, which costs one linear constraint per limb. Other
+    /// elements go through `conditional_enforce_equal`.
+    #[tracing::instrument(target = "r1cs")]
+    pub(crate) fn enforce_equal_constant(&self, c: &TargetF) -> R1CSResult<()> {
+        if !self.is_in_the_normal_form {
+            let c = Self::constant(self.cs(), *c)?;
+            return self.conditional_enforce_equal(&c, &Boolean::TRUE);
+        }
+
+        let c_limbs = Self::get_limbs_representations(c, self.get_optimization_type())?;
+        for (limb, c_limb) in self.limbs.iter().zip(c_limbs.iter()) {
+            limb.enforce_equal(&FpVar::Constant(*c_limb))?;
+        }
+
+        Ok(())
+    }
+
     #[tracing::instrument(target = "r1cs")]
     pub(crate) fn conditional_enforce_not_equal(
         &self,
@@ -720,6 +741,9 @@ impl<TargetF: PrimeField, BaseF: PrimeField> ToBytesGadget<BaseF>
     }
 }
 
+/// Selects every limb on its own. The limbs of the result are those of one of
+/// the inputs, so its bounds are the loosest of both: the maximum number of
+/// additions over the normal form, and the normal form only if both are.
 impl<TargetF: PrimeField, BaseF: PrimeField> CondSelectGadget<BaseF>
     for AllocatedEmulatedFpVar<TargetF, BaseF>
 {
@@ -239,7 +239,7 @@ impl<TargetF: PrimeField, BaseF: PrimeField> AllocatedMulResultVar<TargetF, Base
             new_limbs.push(new_limb);
         }
 
-        // BUG: this can overflow
+        // BUG: this can overflow, see `add_or_reduce`
         Ok(Self {
             cs: self.cs(),
             limbs: new_limbs,
@@ -278,6 +278,59 @@ impl<TargetF: PrimeField, BaseF: PrimeField> AllocatedMulResultVar<TargetF, Base
         })
     }
 
+    /// Add unreduced elements like `add`, but reduce the operand with more
+    /// additions first (and then the other one) if the limbs of the sum could
+    /// exceed the bound that `reduce` relies on.
+    #[tracing::instrument(target = "r1cs")]
+    pub fn add_or_reduce(&self, other: &Self) -> R1CSResult<Self> {
+        if self.can_add(other.prod_of_num_of_additions) {
+            return self.add(other);
+        }
+
+        let (larger, smaller) = if self.prod_of_num_of_additions >= other.prod_of_num_of_additions {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let larger = Self::from(&larger.reduce()?);
+        if larger.can_add(smaller.prod_of_num_of_additions) {
+            return larger.add(smaller);
+        }
+
+        larger.add(&Self::from(&smaller.reduce()?))
+    }
+
+    /// Add native constant elem like `add_constant`, but reduce `self` first
+    /// if the limbs of the sum could exceed the bound that `reduce` relies on.
+    #[tracing::instrument(target = "r1cs")]
+    pub fn add_constant_or_reduce(&self, other: &TargetF) -> R1CSResult<Self> {
+        if self.can_add(BaseF::one()) {
+            return self.add_constant(other);
+        }
+
+        Self::from(&self.reduce()?).add_constant(other)
+    }
+
+    /// Whether the limbs stay within the bound that `reduce` relies on after
+    /// adding an element with `prod_of_num_of_additions`.
+    ///
+    /// This is the same bound that `Reducer::pre_mul_reduce` enforces on the
+    /// result of `mul_without_reduce`.
+    fn can_add(&self, prod_of_num_of_additions: BaseF) -> bool {
+        let params = get_params(
+            TargetF::MODULUS_BIT_SIZE as usize,
+            BaseF::MODULUS_BIT_SIZE as usize,
+            self.get_optimization_type(),
+        );
+
+        let prod_of_num_of_additions = self.prod_of_num_of_additions + prod_of_num_of_additions;
+        let overhead_limb = overhead!(
+            BaseF::one() + prod_of_num_of_additions * BaseF::from(params.num_limbs as u64)
+        );
+
+        2 * params.bits_per_limb + overhead_limb < (BaseF::MODULUS_BIT_SIZE - 3) as usize
+    }
+
     // Debug: pub(crate) is removed for debug `EmulatedFpVar`
     // This is not revered back to pub(crate) because
     // of the MRE example in `sig/src/lib.rs`.
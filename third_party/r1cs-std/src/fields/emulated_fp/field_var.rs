@@ -194,8 +194,15 @@ impl_bounded_ops!(
         }
     },
     |this: &'a EmulatedFpVar<TargetF, BaseF>, other: TargetF| {
+        // Multiplying by a constant still reduces the product, which costs
+        // constraints. Multiplying by 0 or +-1 (e.g. the Frobenius
+        // coefficients) is linear, so it skips the reduction.
         if other.is_zero() {
             EmulatedFpVar::zero()
+        } else if other.is_one() {
+            this.clone()
+        } else if (-other).is_one() {
+            this.negate().unwrap()
         } else {
             this * &EmulatedFpVar::Constant(other)
         }
@@ -318,6 +325,9 @@ impl<TargetF: PrimeField, BaseF: PrimeField> ToBytesGadget<BaseF>
     }
 }
 
+/// Selects limb by limb, i.e. one constraint per limb instead of an emulated
+/// multiplication, see `AllocatedEmulatedFpVar::conditionally_select`. Two
+/// equal constants are selected for free.
 impl<TargetF: PrimeField, BaseF: PrimeField> CondSelectGadget<BaseF>
     for EmulatedFpVar<TargetF, BaseF>
 {
@@ -327,9 +337,10 @@ impl<TargetF: PrimeField, BaseF: PrimeField> CondSelectGadget<BaseF>
         true_value: &Self,
         false_value: &Self,
     ) -> R1CSResult<Self> {
-        match cond {
-            &Boolean::Constant(true) => Ok(true_value.clone()),
-            &Boolean::Constant(false) => Ok(false_value.clone()),
+        match (cond, true_value, false_value) {
+            (&Boolean::Constant(true), ..) => Ok(true_value.clone()),
+            (&Boolean::Constant(false), ..) => Ok(false_value.clone()),
+            (_, Self::Constant(t), Self::Constant(f)) if t == f => Ok(Self::Constant(*t)),
             _ => {
                 let cs = cond.cs();
                 let true_value = match true_value {
@@ -453,6 +464,25 @@ impl<TargetF: PrimeField, BaseF: PrimeField> ToConstraintFieldGadget<BaseF>
 }
 
 impl<TargetF: PrimeField, BaseF: PrimeField> EmulatedFpVar<TargetF, BaseF> {
+    /// Enforce that `self` is equal to the constant `c`, without allocating
+    /// `c` as an emulated element.
+    ///
+    /// When `self` is in the normal form (e.g. a public input), its limbs are
+    /// checked against the limbs of `c` directly. Otherwise this is as costly
+    /// as `self.enforce_equal(&Self::Constant(c))`.
+    #[tracing::instrument(target = "r1cs")]
+    pub fn enforce_equal_constant(&self, c: TargetF) -> R1CSResult<()> {
+        match self {
+            Self::Constant(v) => {
+                if *v != c {
+                    return Err(SynthesisError::Unsatisfiable);
+                }
+                Ok(())
+            },
+            Self::Var(v) => v.enforce_equal_constant(&c),
+        }
+    }
+
     /// The `mul_without_reduce` for `EmulatedFpVar`
     #[tracing::instrument(target = "r1cs")]
     pub fn mul_without_reduce(&self, other: &Self) -> R1CSResult<MulResultVar<TargetF, BaseF>> {
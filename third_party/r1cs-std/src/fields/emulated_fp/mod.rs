@@ -15,6 +15,12 @@
 //! of optimizations. (Nevertheless, the overhead is still substantial, and
 //! native fields should be used where possible.)
 //!
+//! The module only needs `alloc`, so it is available when the crate is built
+//! with `--no-default-features`. `num-bigint` is still used (without `std`)
+//! for witness generation that needs integers wider than the base field, but
+//! the carry bookkeeping in `group_and_check_equality` works on
+//! `BaseF::BigInt` directly.
+//!
 //! ## Usage
 //!
 //! Because [`EmulatedFpVar`] implements the [`FieldVar`] trait in arkworks,
@@ -94,6 +100,18 @@
 //! It performs only one *reduce* operation and is roughly 2x faster than the
 //! first implementation.
 //!
+//! For inner products with many terms, [`MulResultVar::add_many`] sums the
+//! unreduced products and only reduces early when the limbs could overflow:
+//!
+//! ```ignore
+//! let products = a
+//!     .iter()
+//!     .zip(&b)
+//!     .map(|(a, b)| a.mul_without_reduce(b))
+//!     .collect::<Result<Vec<_>, _>>()?;
+//! let res = MulResultVar::add_many(&products)?.reduce()?;
+//! ```
+//!
 //! ## Inspiration and basic design
 //!
 //! This implementation employs the standard idea of using multiple **limbs** to
@@ -124,6 +142,7 @@
 //!
 //! [`EmulatedFpVar`]: crate::fields::emulated_fp::EmulatedFpVar
 //! [`MulResultVar`]: crate::fields::emulated_fp::MulResultVar
+//! [`MulResultVar::add_many`]: crate::fields::emulated_fp::MulResultVar::add_many
 //! [`FpVar`]: crate::fields::fp::FpVar
 
 #![allow(
@@ -38,6 +38,31 @@ impl<TargetF: PrimeField, BaseF: PrimeField> MulResultVar<TargetF, BaseF> {
             Self::Var(v) => Ok(EmulatedFpVar::Var(v.reduce()?)),
         }
     }
+
+    /// Sum many `MulResultVar`s, e.g. the terms of an inner product computed
+    /// with `mul_without_reduce`, so that a single `reduce` is needed at the
+    /// end.
+    ///
+    /// Unlike `+`, the running sum is reduced early whenever adding the next
+    /// term could overflow its limbs, so any number of terms can be added.
+    #[tracing::instrument(target = "r1cs", skip(terms))]
+    pub fn add_many<'a>(terms: impl IntoIterator<Item = &'a Self>) -> R1CSResult<Self>
+    where
+        Self: 'a,
+    {
+        use MulResultVar::*;
+
+        let mut sum = Self::zero();
+        for term in terms {
+            sum = match (&sum, term) {
+                (Constant(c1), Constant(c2)) => Constant(*c1 + c2),
+                (Constant(c), Var(v)) | (Var(v), Constant(c)) => Var(v.add_constant_or_reduce(c)?),
+                (Var(v1), Var(v2)) => Var(v1.add_or_reduce(v2)?),
+            };
+        }
+
+        Ok(sum)
+    }
 }
 
 impl<TargetF: PrimeField, BaseF: PrimeField> From<&EmulatedFpVar<TargetF, BaseF>>
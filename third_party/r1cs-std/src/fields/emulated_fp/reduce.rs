@@ -9,11 +9,10 @@ use crate::{
 use ark_ff::{biginteger::BigInteger, BitIteratorBE, One, PrimeField, Zero};
 use ark_relations::{
     ns,
-    r1cs::{ConstraintSystemRef, Result as R1CSResult},
+    r1cs::{ConstraintSystemRef, Result as R1CSResult, SynthesisError},
 };
 use ark_std::{cmp::min, marker::PhantomData, vec, vec::Vec};
 use num_bigint::BigUint;
-use num_integer::Integer;
 
 pub fn limbs_to_bigint<BaseF: PrimeField>(bits_per_limb: usize, limbs: &[BaseF]) -> BigUint {
     let mut val = BigUint::zero();
@@ -339,7 +338,10 @@ impl<TargetF: PrimeField, BaseF: PrimeField> Reducer<TargetF, BaseF> {
         // The following code is adapted from https://github.com/alex-ozdemir/bellman-bignat/blob/master/src/mp/bignat.rs#L567
         let mut carry_in = zero;
         let mut carry_in_value = BaseF::zero();
-        let mut accumulated_extra = BigUint::zero();
+        // `accumulated_extra` is the quotient carried over from the previous group plus a pad
+        // below `2^(MODULUS_BIT_SIZE - 1)`, so it always fits in `BaseF::BigInt`. Keeping it there
+        // avoids `BigUint` arithmetic on this hot path.
+        let mut accumulated_extra = BaseF::BigInt::from(0u64);
         for (group_id, (left_total_limb, right_total_limb, num_limb_in_this_group)) in
             groupped_limb_pairs.iter().enumerate()
         {
@@ -404,12 +406,18 @@ impl<TargetF: PrimeField, BaseF: PrimeField> Reducer<TargetF, BaseF> {
 
             let carry = FpVar::new_witness(cs.clone(), || Ok(carry_value))?;
 
-            accumulated_extra += limbs_to_bigint(bits_per_limb, &[pad_limb]);
+            // a wrapped `accumulated_extra` would bound the last carry with a wrong value,
+            // so the reduction would not be sound anymore
+            if accumulated_extra.add_with_carry(&pad_limb.into_bigint()) {
+                return Err(SynthesisError::Unsatisfiable);
+            }
 
-            let (new_accumulated_extra, remainder) = accumulated_extra.div_rem(
-                &BigUint::from(2u64).pow((shift_per_limb * num_limb_in_this_group) as u32),
-            );
-            let remainder_limb = bigint_to_basefield::<BaseF>(&remainder);
+            let shift = (shift_per_limb * num_limb_in_this_group) as u32;
+            let new_accumulated_extra = accumulated_extra >> shift;
+            let mut remainder = accumulated_extra;
+            remainder.sub_with_borrow(&(new_accumulated_extra << shift));
+            // remainder < 2^shift, which is below the modulus of BaseF
+            let remainder_limb = BaseF::from_bigint(remainder).unwrap();
 
             // println!("");
             // dbg!(accumulated_extra);
@@ -454,8 +462,8 @@ impl<TargetF: PrimeField, BaseF: PrimeField> Reducer<TargetF, BaseF> {
             carry_in_value = carry_value;
 
             if group_id == groupped_limb_pairs.len() - 1 {
-                carry.enforce_equal(&FpVar::<BaseF>::Constant(bigint_to_basefield(
-                    &accumulated_extra,
+                carry.enforce_equal(&FpVar::<BaseF>::Constant(BaseF::from_le_bytes_mod_order(
+                    &accumulated_extra.to_bytes_le(),
                 )))?;
             } else {
                 // enforce carry's bits length
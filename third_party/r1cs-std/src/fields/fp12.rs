@@ -195,4 +195,26 @@ where
 
         Ok(res)
     }
+
+    /// Like `Self::optimized_cyclotomic_exp`, but follows a precomputed
+    /// square-and-multiply chain that starts from `self` instead of `one`.
+    ///
+    /// For every `(num_squarings, multiply)` in `chain`, the accumulator is
+    /// cyclotomically squared `num_squarings` times and then, if `multiply` is
+    /// set, multiplied by `self`.
+    pub fn cyclotomic_exp_by_chain(&self, chain: &[(usize, bool)]) -> Result<Self, SynthesisError> {
+        let mut res = self.clone();
+
+        for &(num_squarings, multiply) in chain {
+            for _ in 0..num_squarings {
+                res = res.cyclotomic_square()?;
+            }
+
+            if multiply {
+                res *= self;
+            }
+        }
+
+        Ok(res)
+    }
 }
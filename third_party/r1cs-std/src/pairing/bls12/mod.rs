@@ -9,7 +9,7 @@ use crate::{
 };
 use ark_ec::bls12::{Bls12, Bls12Config, TwistType};
 use ark_ff::{BitIteratorBE, PrimeField};
-use ark_std::marker::PhantomData;
+use ark_std::{marker::PhantomData, vec::Vec};
 
 /// Specifies the constraints for computing a pairing in a BLS12 bilinear group.
 pub struct PairingVar<P: Bls12Config, F, CF>(PhantomData<P>, PhantomData<F>, PhantomData<CF>);
@@ -56,11 +56,15 @@ where
         }
     }
 
+    // The BLS12 `X` parameters have a low Hamming weight and (almost) no runs of
+    // ones, so plain square-and-multiply from the top bit is already the shortest
+    // chain. Compared to the NAF, it avoids the multiplication by `one` and, when
+    // the top bits are `11` (as in BLS12-381), one cyclotomic squaring.
     #[tracing::instrument(target = "r1cs", skip_all)]
     fn exp_by_x(
         f: &Fp12Var<P::Fp12Config, F, CF>,
     ) -> Result<Fp12Var<P::Fp12Config, F, CF>, SynthesisError> {
-        let mut result = f.optimized_cyclotomic_exp(P::X)?;
+        let mut result = f.cyclotomic_exp_by_chain(&exp_chain(P::X))?;
         if P::X_IS_NEGATIVE {
             result = result.unitary_inverse()?;
         }
@@ -68,6 +72,28 @@ where
     }
 }
 
+/// Compute the square-and-multiply chain of `exponent` for
+/// `Fp12Var::cyclotomic_exp_by_chain`.
+fn exp_chain(exponent: &[u64]) -> Vec<(usize, bool)> {
+    let mut bits = BitIteratorBE::without_leading_zeros(exponent);
+    assert_eq!(bits.next(), Some(true), "exponent should be non-zero");
+
+    let mut chain = vec![];
+    let mut num_squarings = 0;
+    for bit in bits {
+        num_squarings += 1;
+        if bit {
+            chain.push((num_squarings, true));
+            num_squarings = 0;
+        }
+    }
+    if num_squarings > 0 {
+        chain.push((num_squarings, false));
+    }
+
+    chain
+}
+
 impl<P: Bls12Config, F: FieldVar<P::Fp, CF>, CF: PrimeField> PG<Bls12<P>, CF>
     for PairingVar<P, F, CF>
 where
@@ -184,3 +210,87 @@ where
         Self::G2PreparedVar::from_group_var(q)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use ark_bls12_381::{Bls12_381, Config, Fq, Fq12};
+    use ark_ec::{
+        bls12::Bls12Config,
+        pairing::{MillerLoopOutput, Pairing},
+    };
+    use ark_ff::{CyclotomicMultSubgroup, UniformRand};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    use super::{exp_chain, PairingVar};
+    use crate::{
+        alloc::AllocVar,
+        fields::{fp::FpVar, fp12::Fp12Var},
+        pairing::PairingVar as _,
+        R1CSVar,
+    };
+
+    type PV = PairingVar<Config, FpVar<Fq>, Fq>;
+    type Fp12V = Fp12Var<<Config as Bls12Config>::Fp12Config, FpVar<Fq>, Fq>;
+
+    #[test]
+    fn test_exp_chain() {
+        // 0b1101 = ((1 * 2 + 1) * 2) * 2 + 1
+        assert_eq!(exp_chain(&[0b1101]), vec![(1, true), (2, true)]);
+        assert_eq!(exp_chain(&[0b1000]), vec![(3, false)]);
+        assert_eq!(exp_chain(&[1]), vec![]);
+    }
+
+    #[test]
+    fn test_exp_by_x() {
+        let mut rng = ark_std::test_rng();
+
+        // an element of the cyclotomic subgroup
+        let f = Bls12_381::final_exponentiation(MillerLoopOutput(Fq12::rand(&mut rng)))
+            .unwrap()
+            .0;
+        let mut expected = f.cyclotomic_exp(Config::X);
+        if Config::X_IS_NEGATIVE {
+            expected.cyclotomic_inverse_in_place();
+        }
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let f_var = Fp12V::new_witness(cs.clone(), || Ok(f)).unwrap();
+
+        let start = cs.num_constraints();
+        let result = PV::exp_by_x(&f_var).unwrap();
+        let chain_constraints = cs.num_constraints() - start;
+
+        let start = cs.num_constraints();
+        let mut naf_result = f_var.optimized_cyclotomic_exp(Config::X).unwrap();
+        if Config::X_IS_NEGATIVE {
+            naf_result = naf_result.unitary_inverse().unwrap();
+        }
+        let naf_constraints = cs.num_constraints() - start;
+
+        assert_eq!(result.value().unwrap(), expected);
+        assert_eq!(naf_result.value().unwrap(), expected);
+        assert!(cs.is_satisfied().unwrap());
+
+        // BLS12-381's `X` starts with `11`, so its NAF needs an extra squaring
+        assert!(
+            chain_constraints < naf_constraints,
+            "chain: {chain_constraints}, naf: {naf_constraints}"
+        );
+    }
+
+    #[test]
+    fn test_final_exponentiation() {
+        let mut rng = ark_std::test_rng();
+        let f = Fq12::rand(&mut rng);
+        let expected = Bls12_381::final_exponentiation(MillerLoopOutput(f))
+            .unwrap()
+            .0;
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let f_var = Fp12V::new_witness(cs.clone(), || Ok(f)).unwrap();
+        let result = PV::final_exponentiation(&f_var).unwrap();
+
+        assert_eq!(result.value().unwrap(), expected);
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
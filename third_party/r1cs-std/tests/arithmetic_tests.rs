@@ -715,3 +715,183 @@ nonnative_test!(
 );
 nonnative_test!(PallasFqFr, ark_pallas::Fq, ark_pallas::Fr);
 nonnative_test!(PallasFrFq, ark_pallas::Fr, ark_pallas::Fq);
+
+#[test]
+fn enforce_equal_constant_test() {
+    use ark_std::UniformRand;
+
+    type TargetF = <<Bls12_381 as Pairing>::G1 as CurveGroup>::BaseField;
+    type BaseField = <Bls12_381 as Pairing>::ScalarField;
+
+    let rng = &mut ark_std::test_rng();
+    let c = TargetF::rand(rng);
+    let d = TargetF::rand(rng);
+    assert_ne!(c, d);
+
+    // public inputs are in the normal form, so the limbs are compared directly
+    let cs = ConstraintSystem::<BaseField>::new_ref();
+    let a = EmulatedFpVar::<TargetF, BaseField>::new_input(cs.clone(), || Ok(c)).unwrap();
+    a.enforce_equal_constant(c).unwrap();
+    assert!(cs.is_satisfied().unwrap());
+    let num_constraints_fast = cs.num_constraints();
+
+    let cs = ConstraintSystem::<BaseField>::new_ref();
+    let a = EmulatedFpVar::<TargetF, BaseField>::new_input(cs.clone(), || Ok(c)).unwrap();
+    a.enforce_equal(&EmulatedFpVar::Constant(c)).unwrap();
+    assert!(cs.is_satisfied().unwrap());
+    let num_constraints_general = cs.num_constraints();
+    assert!(num_constraints_fast < num_constraints_general);
+
+    let cs = ConstraintSystem::<BaseField>::new_ref();
+    let a = EmulatedFpVar::<TargetF, BaseField>::new_input(cs.clone(), || Ok(c)).unwrap();
+    a.enforce_equal_constant(d).unwrap();
+    assert!(!cs.is_satisfied().unwrap());
+
+    // other elements fall back to the general path
+    let cs = ConstraintSystem::<BaseField>::new_ref();
+    let a = EmulatedFpVar::<TargetF, BaseField>::new_witness(cs.clone(), || Ok(c)).unwrap();
+    let b = EmulatedFpVar::<TargetF, BaseField>::new_witness(cs.clone(), || Ok(d)).unwrap();
+    let a_times_b = &a * &b;
+    a_times_b.enforce_equal_constant(c * d).unwrap();
+    (&a + &b).enforce_equal_constant(c + d).unwrap();
+    assert!(cs.is_satisfied().unwrap());
+
+    let cs = ConstraintSystem::<BaseField>::new_ref();
+    let a = EmulatedFpVar::<TargetF, BaseField>::new_witness(cs.clone(), || Ok(c)).unwrap();
+    a.enforce_equal_constant(d).unwrap();
+    assert!(!cs.is_satisfied().unwrap());
+
+    // constants are compared natively
+    assert!(EmulatedFpVar::<TargetF, BaseField>::Constant(c)
+        .enforce_equal_constant(c)
+        .is_ok());
+    assert!(EmulatedFpVar::<TargetF, BaseField>::Constant(c)
+        .enforce_equal_constant(d)
+        .is_err());
+}
+
+#[test]
+fn mul_result_add_many_test() {
+    use ark_r1cs_std::fields::emulated_fp::MulResultVar;
+    use ark_std::UniformRand;
+
+    type TargetF = <<Bls12_381 as Pairing>::G1 as CurveGroup>::BaseField;
+    type BaseField = <Bls12_381 as Pairing>::ScalarField;
+
+    let rng = &mut ark_std::test_rng();
+
+    // a 5-term inner product with a single reduce
+    let a_native: Vec<_> = (0..5).map(|_| TargetF::rand(rng)).collect();
+    let b_native: Vec<_> = (0..5).map(|_| TargetF::rand(rng)).collect();
+    let expected: TargetF = a_native.iter().zip(&b_native).map(|(a, b)| *a * b).sum();
+
+    let cs = ConstraintSystem::<BaseField>::new_ref();
+    let a: Vec<_> = a_native
+        .iter()
+        .map(|v| EmulatedFpVar::<TargetF, BaseField>::new_witness(cs.clone(), || Ok(*v)).unwrap())
+        .collect();
+    let b: Vec<_> = b_native
+        .iter()
+        .map(|v| EmulatedFpVar::<TargetF, BaseField>::new_witness(cs.clone(), || Ok(*v)).unwrap())
+        .collect();
+    let num_constraints_before = cs.num_constraints();
+
+    let products: Vec<_> = a
+        .iter()
+        .zip(&b)
+        .map(|(a, b)| a.mul_without_reduce(b).unwrap())
+        .collect();
+    let res = MulResultVar::add_many(&products).unwrap().reduce().unwrap();
+    assert_eq!(res.value().unwrap(), expected);
+    assert!(cs.is_satisfied().unwrap());
+    let num_constraints_fused = cs.num_constraints() - num_constraints_before;
+
+    // the same inner product with one reduce per term
+    let num_constraints_before = cs.num_constraints();
+    let res = a
+        .iter()
+        .zip(&b)
+        .fold(EmulatedFpVar::zero(), |acc, (a, b)| acc + a * b);
+    assert_eq!(res.value().unwrap(), expected);
+    assert!(cs.is_satisfied().unwrap());
+    let num_constraints_naive = cs.num_constraints() - num_constraints_before;
+    assert!(num_constraints_fused < num_constraints_naive);
+
+    // constants are folded into the sum
+    let mut terms = products;
+    terms.push(MulResultVar::constant(TargetF::from(7u64)));
+    let res = MulResultVar::add_many(&terms).unwrap().reduce().unwrap();
+    assert_eq!(res.value().unwrap(), expected + TargetF::from(7u64));
+    assert!(cs.is_satisfied().unwrap());
+
+    // the running sum is reduced early instead of overflowing its limbs
+    let cs = ConstraintSystem::<BaseField>::new_ref();
+    let c =
+        EmulatedFpVar::<TargetF, BaseField>::new_witness(cs.clone(), || Ok(a_native[0])).unwrap();
+    let d =
+        EmulatedFpVar::<TargetF, BaseField>::new_witness(cs.clone(), || Ok(b_native[0])).unwrap();
+    let product = c.mul_without_reduce(&d).unwrap();
+    let num_terms = 1 << 12;
+    let res = MulResultVar::add_many(std::iter::repeat(&product).take(num_terms))
+        .unwrap()
+        .reduce()
+        .unwrap();
+    assert_eq!(
+        res.value().unwrap(),
+        a_native[0] * b_native[0] * TargetF::from(num_terms as u64)
+    );
+    assert!(cs.is_satisfied().unwrap());
+}
+
+#[test]
+fn conditionally_select_test() {
+    use ark_r1cs_std::{prelude::Boolean, select::CondSelectGadget};
+    use ark_std::UniformRand;
+
+    type TargetF = <<Bls12_381 as Pairing>::G1 as CurveGroup>::BaseField;
+    type BaseField = <Bls12_381 as Pairing>::ScalarField;
+
+    let rng = &mut ark_std::test_rng();
+    let c = TargetF::rand(rng);
+    let d = TargetF::rand(rng);
+
+    for cond_value in [true, false] {
+        let cs = ConstraintSystem::<BaseField>::new_ref();
+        let cond = Boolean::new_witness(cs.clone(), || Ok(cond_value)).unwrap();
+        let a = AllocatedEmulatedFpVar::<TargetF, BaseField>::new_witness(cs.clone(), || Ok(c))
+            .unwrap();
+        let b = AllocatedEmulatedFpVar::<TargetF, BaseField>::new_witness(cs.clone(), || Ok(d))
+            .unwrap();
+        // `a + a + a` is two additions over the normal form
+        let a = a.add(&a).unwrap().add(&a).unwrap();
+
+        // one constraint per limb
+        let before = cs.num_constraints();
+        let selected = AllocatedEmulatedFpVar::conditionally_select(&cond, &a, &b).unwrap();
+        assert_eq!(cs.num_constraints() - before, selected.limbs.len());
+
+        let expected = if cond_value { c + c + c } else { d };
+        assert_eq!(selected.value().unwrap(), expected);
+        assert_eq!(
+            selected.num_of_additions_over_normal_form,
+            a.num_of_additions_over_normal_form
+        );
+        assert!(!selected.is_in_the_normal_form);
+        assert!(cs.is_satisfied().unwrap());
+
+        // selecting with arithmetic takes an emulated multiplication
+        let (a, b) = (EmulatedFpVar::Var(a), EmulatedFpVar::Var(b));
+        let before = cs.num_constraints();
+        let arithmetic = &b + &(&(&a - &b) * &EmulatedFpVar::from(cond.clone()));
+        assert!(selected.limbs.len() < cs.num_constraints() - before);
+        assert_eq!(arithmetic.value().unwrap(), expected);
+
+        // equal constants need no constraint
+        let before = cs.num_constraints();
+        let constant = EmulatedFpVar::<TargetF, BaseField>::Constant(c);
+        let selected = EmulatedFpVar::conditionally_select(&cond, &constant, &constant).unwrap();
+        assert!(selected.is_constant());
+        assert_eq!(cs.num_constraints(), before);
+        assert!(cs.is_satisfied().unwrap());
+    }
+}